@@ -4,6 +4,9 @@ defmodule Powex do
 
   This module provides functions to compute and validate Proof of Work using SHA-256 hashing.
   The implementation uses Rust for performance-critical operations.
+
+  An unexpected panic inside the NIF is caught and returned as
+  `{:error, :internal, message}` by any function, rather than aborting the VM.
   """
 
   use Rustler,
@@ -11,12 +14,85 @@ defmodule Powex do
     crate: "powex_nif",
     path: "native/powex_nif"
 
+  @typedoc """
+  Exact number of leading hex zeros (at most 64), a minimum number of leading
+  zero bits as `{:zero_bits, n}` (at most 256), a minimum number of trailing hex
+  zeros as `{:trailing_zeros, n}`, `n` hex zeros starting at hex digit `offset`
+  as `{:zeros_at, offset, n}`, a 64-character hex target the hash must not
+  exceed, or a `{mask, value}` pair of binaries.
+
+  A float zero count such as `4.5` is a precise target met on average once per
+  `16^4.5` hashes, like a pool share difficulty, rather than an exact zero count:
+  `4.5` costs 4x as much as `4.0`, which accepts any hash with at least 4 zeros.
+
+  Hex targets are compared against the hash as big-endian integers, so any
+  hash at or below the target qualifies. A `{mask, value}` pair of equal-length
+  binaries (at most 32 bytes) accepts a raw hash when `hash &&& mask == value`
+  for each of its leading bytes, e.g. `{<<0xF0, 0, 0xFF>>, <<0, 0, 0xAB>>}` for
+  "top 4 bits zero and third byte 0xAB". Malformed targets and masks raise `ArgumentError`.
+  Out-of-range difficulties and the all-zero target return `{:error, reason}`
+  naming the limit of their mode.
+  """
+  @type difficulty() ::
+    non_neg_integer()
+    | float()
+    | {:zero_bits, non_neg_integer()}
+    | {:trailing_zeros, non_neg_integer()}
+    | {:zeros_at, non_neg_integer(), non_neg_integer()}
+    | String.t()
+    | {binary(), binary()}
+
+  @typedoc """
+  Hash algorithm of the data-and-nonce functions that take an `:algorithm`
+  option: `:sha256`, `:blake3`, `:sha512_256`, `:sha384`, `:poseidon`, `:hash160`,
+  `:k12`, `:turboshake128`, `:yespower`, BLAKE2b or a chain of them.
+
+  `:blake2b` is unkeyed BLAKE2b-512; `{:blake2b, size}` selects a digest of
+  `size` bytes (1-64) and `{:blake2b, size, key}` adds a key of up to 64 bytes.
+  The NIF zeroizes its own copy of the key once a call is done with it, and
+  never includes keys in logs or error messages; the copies held by the BLAKE2b
+  hasher's internal state are not zeroized.
+  Each hashes `data <> <<nonce::64-little>>`; see `encoding_spec/1` for Poseidon.
+
+  `:yespower` is yespower 1.0 with `N = 2048` and `r = 8` (2 MiB per hash);
+  `{:yespower, n, r}` sets `n` (a power of two from 1024 to 524288) and `r`
+  (8-32), and `{:yespower, n, r, personalization}` adds a personalization
+  string of up to 256 bytes. It requires the NIF to be built with the
+  `yespower` cargo feature (`config :powex, Powex, features: ["yespower"]`).
+
+  `{:chain, algorithms}` applies up to 16 algorithms in order, X11-style: the
+  first hashes `data <> <<nonce::64-little>>` and each later one hashes the
+  previous digest, so `{:chain, [:sha256, :sha256]}` is double SHA-256. Only
+  the first link may be `:poseidon`, and chains don't nest.
+  """
+  @type algorithm() ::
+    :sha256
+    | :blake3
+    | :sha512_256
+    | :sha384
+    | :poseidon
+    | :hash160
+    | :k12
+    | :turboshake128
+    | :yespower
+    | {:yespower, pos_integer(), 8..32}
+    | {:yespower, pos_integer(), 8..32, binary()}
+    | :blake2b
+    | {:blake2b, 1..64}
+    | {:blake2b, 1..64, binary()}
+    | {:chain, [algorithm()]}
+
   @doc """
   Computes a Proof of Work nonce for the given data and difficulty.
 
   ## Parameters
   - `data`: The input data (string or binary) to hash
-  - `difficulty`: Number of leading zeros required in the hash (integer)
+  - `difficulty`: Number of leading zeros required in the hash (integer),
+    `{:zero_bits, n}`, or a 64-character hex target that the hash must not exceed
+  - `opts`: Keyword list of options
+    - `:schedule` - `:auto` (default) to search on a dirty CPU scheduler only when
+      `scheduler_for/3` expects the search to take over a millisecond, or
+      `:normal` / `:dirty` to force one
 
   ## Returns
   - `{:ok, nonce}` when a valid nonce is found
@@ -29,22 +105,136 @@ defmodule Powex do
 
       iex> Powex.compute("", 0)
       {:ok, 0}
+
+      iex> target = "0fff" <> String.duplicate("f", 60)
+      iex> {:ok, nonce} = Powex.compute("hello world", target)
+      iex> Powex.valid?("hello world", nonce, target)
+      true
+  """
+  @spec compute(binary(), difficulty(), keyword()) :: {:ok, non_neg_integer()} | {:error, String.t()}
+  def compute(data, difficulty, opts \\ []) do
+    case scheduler(byte_size(data), difficulty, false, opts) do
+      :normal -> compute_normal(data, difficulty)
+      :dirty -> compute_dirty(data, difficulty)
+    end
+  end
+
+  @doc false
+  def compute_normal(_data, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def compute_dirty(_data, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Computes a Proof of Work like `compute/2`, returning a self-describing map.
+
+  ## Parameters
+  - `data`: The input data (string or binary) to hash
+  - `difficulty`: Number of leading zeros, or a 64-character hex target
+  - `opts`: Keyword list of options
+    - `:encoding` - Encoding of the returned hash, as in `get_hash/3` (default: `:hex`)
+    - `:timeout` - Stop searching after this many milliseconds (default: no deadline)
+    - `:best_so_far` - When the search stops without a solution, return the
+      lowest hash seen instead of an error, so partial work keeps its value (default: `false`)
+
+  ## Returns
+  - `{:ok, %{nonce: nonce, hash: hash, attempts: attempts, algorithm: :sha256, difficulty: difficulty}}`
+  - `{:best_so_far, result}` with the same map for the lowest hash seen, when
+    `:best_so_far` is set and the search stopped early
+  - `{:error, :timeout}` when the deadline passed
+  - `{:error, reason}` if computation fails
+
+  ## Examples
+      iex> {:ok, %{nonce: nonce, hash: hash, attempts: attempts}} = Powex.compute_detailed("hello world", 2)
+      iex> {:ok, hash} == Powex.get_hash("hello world", nonce)
+      true
+      iex> attempts == nonce + 1
+      true
+  """
+  @spec compute_detailed(binary(), difficulty(), keyword()) ::
+    {:ok | :best_so_far, %{nonce: non_neg_integer(), hash: binary(), attempts: pos_integer(), algorithm: atom(), difficulty: difficulty()}} |
+    {:error, :timeout | String.t()}
+  def compute_detailed(_data, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Searches for a single nonce that satisfies any of several targets in one pass.
+
+  Each candidate nonce is tested against every target, and targets sharing the
+  same data share one hash computation per nonce.
+
+  ## Parameters
+  - `targets`: A non-empty list of `{data, difficulty}` tuples
+
+  ## Returns
+  - `{:ok, {nonce, index}}` where `index` is the position of the matched target
+  - `{:error, reason}` if computation fails
+
+  ## Examples
+      iex> {:ok, {nonce, index}} = Powex.compute_multi([{"challenge a", 3}, {"challenge b", 3}])
+      iex> {data, difficulty} = Enum.at([{"challenge a", 3}, {"challenge b", 3}], index)
+      iex> Powex.valid?(data, nonce, difficulty)
+      true
+  """
+  @spec compute_multi([{binary(), non_neg_integer()}]) ::
+    {:ok, {non_neg_integer(), non_neg_integer()}} | {:error, String.t()}
+  def compute_multi(_targets), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Searches for a nonce matching any data in a list, or any difficulty in a list.
+
+  ## Parameters
+  - `data_or_list`: A single binary, or a list of binaries (e.g. rotating challenges)
+  - `difficulty_or_list`: A single difficulty, or a list of difficulties for the same data
+
+  ## Returns
+  - `{:ok, {nonce, index}}` where `index` is the position of the matched entry in the list
+  - `{:error, reason}` if computation fails
+
+  ## Examples
+      iex> {:ok, {nonce, index}} = Powex.compute_multi(["epoch 1", "epoch 2"], 2)
+      iex> Powex.valid?(Enum.at(["epoch 1", "epoch 2"], index), nonce, 2)
+      true
+
+      iex> {:ok, {nonce, index}} = Powex.compute_multi("shared data", [3, 2])
+      iex> Powex.valid?("shared data", nonce, Enum.at([3, 2], index))
+      true
   """
-  @spec compute(binary(), non_neg_integer()) :: {:ok, non_neg_integer()} | {:error, String.t()}
-  def compute(_data, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+  @spec compute_multi(binary() | [binary()], non_neg_integer() | [non_neg_integer()]) ::
+    {:ok, {non_neg_integer(), non_neg_integer()}} | {:error, String.t()}
+  def compute_multi(data_list, difficulty) when is_list(data_list) and is_integer(difficulty) do
+    data_list |> Enum.map(&{&1, difficulty}) |> compute_multi()
+  end
+
+  def compute_multi(data, difficulties) when is_binary(data) and is_list(difficulties) do
+    difficulties |> Enum.map(&{data, &1}) |> compute_multi()
+  end
 
   @doc """
   Validates if a nonce produces a valid Proof of Work for the given data and difficulty.
 
+  Results are kept in a bounded in-NIF LRU cache keyed by the BLAKE3 digest of
+  the data, the nonce and the difficulty, so re-verifying the same proof skips
+  rehashing. See `cache_stats/0` and `clear_cache/0`.
+
   ## Parameters
   - `data`: The input data (string or binary) that was hashed
   - `nonce`: The nonce value to validate (integer)
-  - `difficulty`: Number of leading zeros required in the hash (integer)
+  - `difficulty`: Number of leading zeros required in the hash (integer), or a
+    64-character hex target that the hash must not exceed
+  - `opts`: Keyword list of options
+    - `:schedule` - `:auto` (default) to verify large data on a dirty CPU
+      scheduler, as picked by `scheduler_for/3`, or `:normal` / `:dirty` to force one
 
   ## Returns
   - `true` if the nonce is valid for the given difficulty
   - `false` if the nonce is invalid
 
+  On a normal scheduler, data is hashed in 1 MiB chunks, each reported to the
+  scheduler as a used time slice; once the slice runs out, the verification
+  moves to a dirty CPU scheduler. Data larger than the `:max_data_size` of
+  `configure/1` raises `ErlangError` with `{:data_too_large, limit}`, as in
+  every function taking data.
+
   ## Examples
       iex> {:ok, nonce} = Powex.compute("test data", 3)
       iex> Powex.valid?("test data", nonce, 3)
@@ -53,46 +243,3044 @@ defmodule Powex do
       iex> Powex.valid?("test data", 12345, 3)
       false
   """
-  @spec valid?(binary(), non_neg_integer(), non_neg_integer()) :: boolean()
-  def valid?(_data, _nonce, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+  @spec valid?(binary(), non_neg_integer(), difficulty(), keyword()) :: boolean()
+  def valid?(data, nonce, difficulty, opts \\ []) do
+    case scheduler(byte_size(data), difficulty, true, opts) do
+      :normal ->
+        case valid_normal?(data, nonce, difficulty) do
+          :reschedule -> valid_dirty?(data, nonce, difficulty)
+          valid -> valid
+        end
+
+      :dirty ->
+        valid_dirty?(data, nonce, difficulty)
+    end
+  end
+
+  @doc false
+  def valid_normal?(_data, _nonce, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def valid_dirty?(_data, _nonce, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
 
   @doc """
-  Computes a Proof of Work nonce using parallel processing for improved performance.
+  Picks the scheduler `compute/3` and `valid?/4` run on for `:schedule` `:auto`.
+
+  The cost is estimated from the data size, the expected hashes of the
+  difficulty and the speed of the algorithm; calls expected to take more than
+  a millisecond go to a dirty CPU scheduler, the rest stay on the calling
+  scheduler and avoid the dirty-scheduler handoff.
+
+  ## Parameters
+  - `data_size`: Bytes of data to hash
+  - `difficulty`: The difficulty to search for or verify
+  - `opts`: Keyword list of options
+    - `:algorithm` - See `t:algorithm/0` (default: `:sha256`)
+    - `:verify` - Estimate a single verification instead of a search (default: `false`)
+
+  ## Returns
+  - `:normal` or `:dirty`
+
+  ## Examples
+      iex> Powex.scheduler_for(64, 2)
+      :normal
+      iex> Powex.scheduler_for(64, 6)
+      :dirty
+      iex> Powex.scheduler_for(64, 6, verify: true)
+      :normal
+  """
+  @spec scheduler_for(non_neg_integer(), difficulty(), keyword()) :: :normal | :dirty
+  def scheduler_for(_data_size, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  defp scheduler(data_size, difficulty, verify, opts) do
+    case Keyword.get(opts, :schedule, :auto) do
+      :auto -> scheduler_for(data_size, difficulty, verify: verify)
+      scheduler when scheduler in [:normal, :dirty] -> scheduler
+    end
+  end
+
+  # Functions hashing their data once pick the scheduler of a single verification
+  defp hash_scheduler(data), do: scheduler_for(byte_size(data), 0, verify: true)
+
+  @doc """
+  Gets statistics of the `valid?/3` verification cache.
+
+  ## Returns
+  - `%{hits: hits, misses: misses, size: entries, capacity: max_entries}`
+  """
+  @spec cache_stats() :: %{hits: non_neg_integer(), misses: non_neg_integer(), size: non_neg_integer(), capacity: pos_integer()}
+  def cache_stats(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Empties the `valid?/3` verification cache and resets its counters.
+  """
+  @spec clear_cache() :: :ok
+  def clear_cache(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Classifies a pool submission against share and network difficulty in one hash evaluation.
+
+  Unlike `valid?/3`, hashes with more leading zeros than required still qualify,
+  since a submission meeting the network difficulty is also a valid share.
+
+  ## Parameters
+  - `data`: The input data (string or binary) that was hashed
+  - `nonce`: The submitted nonce (integer)
+  - `share_difficulty`: Minimum leading zeros for a share (integer)
+  - `network_difficulty`: Minimum leading zeros for a block (integer)
+
+  ## Returns
+  - `:block` if the hash meets the network difficulty
+  - `:share` if it only meets the share difficulty
+  - `:invalid` otherwise
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute("pool share", 2)
+      iex> Powex.validate_share("pool share", nonce, 2, 6)
+      :share
+  """
+  @spec validate_share(binary(), non_neg_integer(), non_neg_integer(), non_neg_integer()) ::
+    :block | :share | :invalid
+  def validate_share(data, nonce, share_difficulty, network_difficulty) do
+    case hash_scheduler(data) do
+      :normal -> validate_share_normal(data, nonce, share_difficulty, network_difficulty)
+      :dirty -> validate_share_dirty(data, nonce, share_difficulty, network_difficulty)
+    end
+  end
+
+  @doc false
+  def validate_share_normal(_data, _nonce, _share_difficulty, _network_difficulty),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def validate_share_dirty(_data, _nonce, _share_difficulty, _network_difficulty),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Solves a chain of `rounds` proofs, where each round's data is the previous
+  round's data followed by its 32-byte SHA-256 hash.
+
+  Chained rounds at a lower difficulty have far less variance in solve time
+  than a single proof of the same total work.
+
+  ## Parameters
+  - `data`: The challenge data (string or binary)
+  - `difficulty`: Difficulty of every round, as in `compute/2`
+  - `rounds`: Number of chained proofs
+  - `opts`: Keyword list of options
+    - `:threads` - Threads used to search each round (default: `1`)
+
+  ## Returns
+  - `{:ok, nonces}` with one nonce per round
+  - `{:error, reason}` if a round fails
+
+  ## Examples
+      iex> {:ok, nonces} = Powex.compute_rounds("challenge", 2, 3)
+      iex> Powex.valid_rounds?("challenge", nonces, 2, 3)
+      true
+  """
+  @spec compute_rounds(binary(), difficulty(), non_neg_integer(), keyword()) ::
+    {:ok, [non_neg_integer()]} | {:error, String.t()}
+  def compute_rounds(_data, _difficulty, _rounds, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Validates a chain of proofs produced by `compute_rounds/4`.
+
+  ## Parameters
+  - `data`: The challenge data (string or binary)
+  - `nonces`: One nonce per round
+  - `difficulty`: Difficulty of every round
+  - `rounds`: Required number of rounds; shorter or longer chains are rejected
+
+  ## Returns
+  - `true` if every round meets the difficulty
+  - `false` otherwise
+
+  ## Examples
+      iex> Powex.valid_rounds?("challenge", [], 2, 0)
+      true
+
+      iex> Powex.valid_rounds?("challenge", [1, 2], 2, 3)
+      false
+  """
+  @spec valid_rounds?(binary(), [non_neg_integer()], difficulty(), non_neg_integer()) :: boolean()
+  def valid_rounds?(_data, _nonces, _difficulty, _rounds), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Computes a memory-hard Proof of Work using Argon2id.
+
+  Each candidate hashes `data <> <<nonce::little-64>>` with Argon2id, salted with
+  the first 16 bytes of `SHA-256(data)`. The Argon2 memory is allocated once per
+  call and reused for every nonce.
 
   ## Parameters
   - `data`: The input data (string or binary) to hash
-  - `difficulty`: Number of leading zeros required in the hash (integer)
-  - `threads`: Number of threads to use for parallel computation (default: number of CPU cores)
+  - `difficulty`: Difficulty as in `compute/2`
+  - `opts`: Keyword list of options
+    - `:m_cost` - Memory in KiB (default: `19456`)
+    - `:t_cost` - Number of passes (default: `2`)
+    - `:p_cost` - Degree of parallelism (default: `1`)
+    - `:max_attempts` - Nonces to try before giving up (default: `1_000_000`)
+    - `:max_memory` - Maximum bytes of Argon2 memory to allocate (default: unlimited)
+    - `:huge_pages` - Map the Argon2 memory on 2 MiB-aligned huge pages where the
+      OS provides them (Linux only; default: `false`). Reserved huge pages are
+      tried first, then transparent huge pages; elsewhere the heap is used
 
   ## Returns
   - `{:ok, nonce}` when a valid nonce is found
-  - `{:error, reason}` if computation fails
+  - `{:ok, %{nonce: nonce, huge_pages: :explicit | :transparent | :none}}` with
+    `huge_pages: true`, telling which kind of pages backed the memory
+  - `{:error, :memory_limit}` if `:m_cost` needs more than `:max_memory`
+  - `{:error, reason}` for invalid parameters or if no nonce was found
 
   ## Examples
-      iex> {:ok, nonce} = Powex.compute_parallel("hello world", 4, 4)
-      iex> is_integer(nonce)
+      iex> {:ok, nonce} = Powex.compute_memory_hard("memory", 1, m_cost: 64, t_cost: 1)
+      iex> Powex.valid_memory_hard?("memory", nonce, 1, m_cost: 64, t_cost: 1)
+      true
+  """
+  @spec compute_memory_hard(binary(), difficulty(), keyword()) ::
+    {:ok, non_neg_integer() | %{nonce: non_neg_integer(), huge_pages: :explicit | :transparent | :none}}
+    | {:error, String.t()}
+  def compute_memory_hard(_data, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Validates a memory-hard proof produced by `compute_memory_hard/3`.
+
+  Takes the same `:m_cost`, `:t_cost` and `:p_cost` options, which must match
+  the ones used to compute the proof. Invalid parameters raise `ArgumentError`.
+
+  When the parameters come from an untrusted proof, pass `:max_memory` (bytes)
+  so hostile parameters are rejected before any memory is allocated.
+
+  ## Returns
+  - `true` if the nonce is valid for the given difficulty
+  - `false` if the nonce is invalid
+  - `{:error, :memory_limit}` if `:m_cost` needs more than `:max_memory`
+  - `{:error, :over_budget}` if the verification exceeds the `:max_verify_bytes`
+    or `:max_verify_memory` budget of `configure/1`
+
+  ## Examples
+      iex> Powex.valid_memory_hard?("memory", 0, 1, m_cost: 4_194_304, max_memory: 64 * 1024 * 1024)
+      {:error, :memory_limit}
+  """
+  @spec valid_memory_hard?(binary(), non_neg_integer(), difficulty(), keyword()) ::
+    boolean() | {:error, :memory_limit | :over_budget}
+  def valid_memory_hard?(_data, _nonce, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Prepares a mining session for the data.
+
+  The setup that would otherwise be repeated on every call is done once: the
+  data is absorbed into the hasher state, or the Argon2id memory is allocated
+  and the salt derived. `session_mine/3` and `session_verify/3` then only hash
+  nonces. Calls on an Argon2id session share its memory and take turns.
+
+  ## Parameters
+  - `data`: The data to search and verify proofs for
+  - `opts`: Keyword list of options
+    - `:algorithm` - See `t:algorithm/0` (default: `:sha256`), or `:argon2id`
+    - `:m_cost`, `:t_cost`, `:p_cost` - Argon2id parameters, as in `compute_memory_hard/3`
+    - `:huge_pages` - Map the Argon2id memory on huge pages, as in `compute_memory_hard/3`
+
+  ## Returns
+  - `{:ok, session}`
+  - `{:error, reason}` if the Argon2id parameters are invalid or exceed the
+    `:max_verify_memory` budget of `configure/1`
+
+  ## Examples
+      iex> {:ok, session} = Powex.session_new("hello world")
+      iex> {:ok, nonce} = Powex.session_mine(session, 2)
+      iex> Powex.session_verify(session, nonce, 2)
       true
+      iex> Powex.compute("hello world", 2)
+      {:ok, nonce}
+  """
+  @spec session_new(binary(), keyword()) :: {:ok, reference()} | {:error, String.t()}
+  def session_new(_data, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Searches a session's data for a nonce meeting the difficulty.
+
+  ## Options
+  - `:start` - First nonce to try (default: `0`)
+  - `:max_attempts` - Nonces to try before giving up (default: unbounded, or
+    `1_000_000` for Argon2id sessions)
+
+  ## Returns
+  - `{:ok, nonce}`
+  - `{:error, reason}` if the difficulty is invalid or no nonce was found
   """
-  @spec compute_parallel(binary(), non_neg_integer(), pos_integer()) ::
+  @spec session_mine(reference(), difficulty(), keyword()) ::
     {:ok, non_neg_integer()} | {:error, String.t()}
-  def compute_parallel(_data, _difficulty, _threads), do: :erlang.nif_error(:nif_not_loaded)
+  def session_mine(_session, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
 
   @doc """
-  Gets the hash for given data and nonce combination.
+  Checks a nonce against a session's data with a single hash.
+  """
+  @spec session_verify(reference(), non_neg_integer(), difficulty()) :: boolean()
+  def session_verify(_session, _nonce, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Finds Argon2id parameters for `compute_memory_hard/3` whose single hash
+  evaluation (one verification) takes about `target_ms` on this machine.
+
+  Memory is preferred over passes: the tuner starts at the full `max_memory`
+  budget, halves it until one pass fits the target, then adds passes to use
+  the remaining time.
 
   ## Parameters
-  - `data`: The input data (string or binary)
-  - `nonce`: The nonce value (integer)
+  - `target_ms`: Target verification time in milliseconds
+  - `max_memory`: Memory budget in bytes
 
   ## Returns
-  - `{:ok, hash}` where hash is the SHA-256 hash as a hex string
-  - `{:error, reason}` if hashing fails
+  - `{:ok, %{m_cost: kib, t_cost: passes, p_cost: 1, verify_ms: measured}}`
+  - `{:error, reason}` if the budget is below the Argon2 minimum of 8 KiB
 
   ## Examples
-      iex> {:ok, hash} = Powex.get_hash("test", 123)
-      iex> String.length(hash)
-      64
+      iex> {:ok, %{m_cost: m_cost}} = Powex.tune_memory_hard(5, 1024 * 1024)
+      iex> m_cost <= 1024
+      true
+  """
+  @spec tune_memory_hard(non_neg_integer(), pos_integer()) ::
+    {:ok, %{m_cost: pos_integer(), t_cost: pos_integer(), p_cost: pos_integer(), verify_ms: float()}} |
+    {:error, String.t()}
+  def tune_memory_hard(_target_ms, _max_memory), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Encodes a proof in a self-describing binary format for `decode_and_verify/2`.
+
+  The layout is `<<1, algorithm, difficulty, nonce_width, nonce::little-size(nonce_width * 8)>>`,
+  followed by `<<m_cost::little-32, t_cost::little-32, p_cost::little-32>>`
+  for Argon2id, then the data. The narrowest nonce width is used, and
+  `decode_and_verify/2` refuses any other, so each proof has one encoding.
+
+  ## Parameters
+  - `data`: The data the proof was computed over
+  - `nonce`: The proof nonce
+  - `difficulty`: Leading zeros the proof claims (0-255)
+  - `opts`: Keyword list of options
+    - `:algorithm` - `:sha256` (default), `:blake3`, `:poseidon` or `:argon2id`
+    - `:m_cost`, `:t_cost`, `:p_cost` - Argon2id parameters, as in `compute_memory_hard/3`
+
+  ## Examples
+      iex> Powex.encode_proof("data", 300, 2)
+      <<1, 0, 2, 2, 44, 1, "data">>
+  """
+  @spec encode_proof(binary(), non_neg_integer(), non_neg_integer(), keyword()) :: binary()
+  def encode_proof(_data, _nonce, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Creates a policy restricting which self-describing proofs `decode_and_verify/2` will verify.
+
+  ## Options
+  - `:algorithms` - Allowed algorithms (default: `[:sha256, :blake3]`; Poseidon and Argon2id must be opted in)
+  - `:min_difficulty` / `:max_difficulty` - Allowed difficulty range (default: `0..64`)
+  - `:max_nonce_width` - Maximum nonce width in bytes (default: `8`)
+  - `:max_m_cost` - Maximum Argon2id memory in KiB (default: `65536`)
+  - `:max_t_cost` - Maximum Argon2id passes (default: `4`)
+  - `:max_p_cost` - Maximum Argon2id parallelism (default: `1`)
+
+  ## Returns
+  - A policy reference
+  """
+  @spec policy_new(keyword()) :: reference()
+  def policy_new(_opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Decodes a proof from `encode_proof/4`, enforces the policy on its declared
+  parameters, and only then verifies it.
+
+  Servers consuming proofs from clients should always verify through a policy,
+  so attacker-chosen parameters can't trigger expensive verification.
+
+  ## Returns
+  - `{:ok, %{algorithm: algorithm, difficulty: difficulty, nonce: nonce, data: data}}`
+  - `{:error, :malformed}` if the proof cannot be decoded
+  - `{:error, :algorithm_not_allowed | :difficulty_out_of_range | :nonce_too_wide | :memory_params_not_allowed}`
+    if the declared parameters violate the policy
+  - `{:error, :over_budget}` if verifying would exceed the `:max_verify_bytes` or
+    `:max_verify_memory` budget of `configure/1`
+  - `{:error, :invalid}` if the proof does not meet its declared difficulty
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute("data", 2)
+      iex> proof = Powex.encode_proof("data", nonce, 2)
+      iex> {:ok, %{data: "data", difficulty: 2}} = Powex.decode_and_verify(proof, Powex.policy_new())
+      iex> Powex.decode_and_verify(proof, Powex.policy_new(min_difficulty: 4))
+      {:error, :difficulty_out_of_range}
+  """
+  @spec decode_and_verify(binary(), reference()) :: {:ok, map()} | {:error, atom()}
+  def decode_and_verify(_proof, _policy), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Decodes a proof from `encode_proof/4` without verifying it.
+
+  The decoder is pure: nothing is hashed, logged or cached, no memory is
+  allocated beyond the result, and the data is a sub-binary of the input. Any
+  binary, including truncated or random network bytes, either decodes or
+  returns `{:error, :malformed}`, so it is safe to call on untrusted input
+  and to fuzz. Use `decode_and_verify/2` to actually accept a proof.
+
+  ## Returns
+  - `{:ok, %{algorithm: algorithm, difficulty: difficulty, nonce_width: width, nonce: nonce, memory: params, data: data}}`,
+    where `params` is `%{m_cost: m, t_cost: t, p_cost: p}` for Argon2id and `nil` otherwise
+  - `{:error, :malformed}` if the proof cannot be decoded
+
+  ## Examples
+      iex> Powex.decode_proof(<<1, 0, 2, 2, 44, 1, "data">>)
+      {:ok, %{algorithm: :sha256, difficulty: 2, nonce_width: 2, nonce: 300, memory: nil, data: "data"}}
+      iex> Powex.decode_proof(<<1, 0, 2, 2, 44>>)
+      {:error, :malformed}
+  """
+  @spec decode_proof(binary()) :: {:ok, map()} | {:error, :malformed}
+  def decode_proof(_proof), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Compresses a batch of proofs from `encode_proof/4` for transport.
+
+  Proofs for the same challenge and parameters share everything but their
+  nonce, so the common part is stored once and each proof is reduced to the
+  difference from the previous nonce, as a varint; the result is then
+  deflated. A batch of thousands of proofs for one challenge shrinks to a few
+  bytes per proof. Order is preserved, and proofs of different challenges may
+  be mixed.
+
+  ## Returns
+  - `{:ok, batch}`
+  - `{:error, :malformed}` if a proof cannot be decoded
+
+  ## Examples
+      iex> proofs = for nonce <- 1..100, do: Powex.encode_proof("challenge", nonce, 2)
+      iex> {:ok, batch} = Powex.compress_proofs(proofs)
+      iex> byte_size(batch) < div(byte_size(Enum.join(proofs)), 4)
+      true
+      iex> Powex.decompress_proofs(batch) == {:ok, proofs}
+      true
+  """
+  @spec compress_proofs([binary()]) :: {:ok, binary()} | {:error, :malformed}
+  def compress_proofs(_proofs), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Restores the proofs of a batch from `compress_proofs/1`, in their original order.
+
+  Batches are untrusted input: expansion stops at the `:max_data_size` of
+  `configure/1`, so a small upload can't decompress into gigabytes. The
+  proofs still need verifying, e.g. with `decode_and_verify/2`.
+
+  ## Returns
+  - `{:ok, proofs}`
+  - `{:error, :malformed}` if the batch cannot be decoded
+  - `{:error, :data_too_large}` if the proofs would exceed `:max_data_size`
+  """
+  @spec decompress_proofs(binary()) :: {:ok, [binary()]} | {:error, :malformed | :data_too_large}
+  def decompress_proofs(_batch), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Encodes a proof as a compact token for an HTTP header such as `X-PoW`.
+
+  Tokens use a versioned envelope modeled on PASETO, `v1.local.<payload>` or
+  `v1.public.<payload>`, where the payload is unpadded base64url and the whole
+  token is at most 8191 characters. The version names the cryptographic suite,
+  so a future suite gets a new version instead of changing how deployed
+  clients parse v1:
+
+  - `v1.local` tokens carry a 32-byte keyed BLAKE3 MAC under a key shared by
+    the servers that issue and verify them
+  - `v1.public` tokens carry a 64-byte Ed25519 signature, so verifiers only
+    need the public key from `token_keypair/0`
+
+  The payload holds the proof of `encode_proof/4`, a timestamp and the tag.
+  The tag covers everything but the nonce, so a server can hand out a signed
+  challenge token with nonce 0 and stay stateless: the client solves it,
+  re-encodes the token with its nonce and the server's `:tag`, and
+  `token_verify/3` accepts it.
+
+  ## Parameters
+  - `fields`: Keyword list or map of fields
+    - `:data` - Challenge data (required)
+    - `:difficulty` - Declared difficulty, 0-255 (required)
+    - `:purpose` - `:local` (default) or `:public`
+    - `:algorithm` - `:sha256` (default), `:blake3` or `:argon2id`, with
+      `:m_cost`, `:t_cost` and `:p_cost` as in `encode_proof/4`
+    - `:nonce` - Solution nonce (default: `0`)
+    - `:timestamp` - Issue time in Unix seconds (default: now)
+    - `:key` - Shared key of a `:local` token, or
+    - `:secret_key` - Ed25519 secret key of a `:public` token, or
+    - `:tag` - Tag of a token decoded with `token_decode/1`
+
+  ## Returns
+  - `{:ok, token}`
+  - `{:error, reason}` if a field is missing or the token exceeds 8191 characters
+
+  ## Examples
+      iex> {:ok, token} = Powex.token_encode(data: "challenge", difficulty: 1, key: "server key")
+      iex> {:ok, %{purpose: :local, data: "challenge", difficulty: 1, nonce: 0}} = Powex.token_decode(token)
+      iex> token =~ ~r/^v1\.local\.[A-Za-z0-9_-]+$/
+      true
+  """
+  @spec token_encode(keyword() | map()) :: {:ok, String.t()} | {:error, String.t()}
+  def token_encode(_fields), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Decodes a token from `token_encode/1` without checking its tag, timestamp or proof.
+
+  ## Returns
+  - `{:ok, %{version: :v1, purpose: purpose, algorithm: algorithm, difficulty: difficulty, nonce: nonce, memory: params, data: data, timestamp: timestamp, tag: tag}}`
+  - `{:error, :unsupported_version}` if the token is of an unknown version or purpose
+  - `{:error, :malformed}` if the token cannot be decoded
+  """
+  @spec token_decode(String.t()) :: {:ok, map()} | {:error, :malformed | :unsupported_version}
+  def token_decode(_token), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies a token from `token_encode/1`, as a Plug would for an `X-PoW` header.
+
+  The tag is checked first, then the timestamp, and only then the proof,
+  through the policy as in `decode_and_verify/2`. The token's purpose selects
+  the key, so a `v1.public` token is never checked against a shared key or the
+  other way around. Replay protection is up to the caller, e.g. with
+  `consume_proof/1`.
+
+  ## Parameters
+  - `token`: Token text
+  - `policy`: Policy from `policy_new/1` the proof must satisfy
+  - `opts`: Keyword list of options, with at least one key
+    - `:key` - Shared key of `v1.local` tokens
+    - `:public_key` - Ed25519 public key of `v1.public` tokens
+    - `:max_age` - Oldest accepted timestamp, in seconds before `:now` (default: `300`)
+    - `:max_future_drift` - Allowed clock skew, in seconds after `:now` (default: `60`)
+    - `:now` - Current Unix time in seconds (default: `clock_now/0`)
+
+  ## Returns
+  - `{:ok, fields}`, as from `token_decode/1`
+  - `{:error, :malformed | :unsupported_version}` if the token cannot be decoded
+  - `{:error, :wrong_purpose}` if no key was given for the token's purpose
+  - `{:error, :bad_signature}` if the tag does not match the key
+  - `{:error, :too_old | :too_far_in_future}` if the timestamp is out of range
+  - `{:error, reason}` if the proof fails, as from `decode_and_verify/2`
+  """
+  @spec token_verify(String.t(), reference(), keyword()) :: {:ok, map()} | {:error, atom()}
+  def token_verify(_token, _policy, _opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Generates an Ed25519 key pair for `v1.public` tokens.
+
+  Issuers sign with the 32-byte secret key; verifiers only need the 32-byte
+  public key.
+
+  ## Examples
+      iex> %{secret_key: secret_key, public_key: public_key} = Powex.token_keypair()
+      iex> {byte_size(secret_key), byte_size(public_key)}
+      {32, 32}
+  """
+  @spec token_keypair() :: %{secret_key: binary(), public_key: binary()}
+  def token_keypair(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Issues a challenge token for a client, with 16 bytes of fresh random data at
+  the client's `personal_difficulty/5`.
+
+  The difficulty is covered by the token's tag, so a client can't lower it,
+  and the server keeps no state: `token_verify/3` checks the returned token
+  like any other.
+
+  ## Parameters
+  - `opts`: Keyword list of options
+    - `:client_id` - Client identifier (required)
+    - `:difficulty` - Difficulty for a neutral client (required)
+    - `:reputation` - Reputation score from 0 to 1 (default: `0.5`)
+    - `:max_adjustment` - As in `personal_difficulty/5` (default: `2.0`)
+    - `:secret` - Personalization secret (default: the `:key` or `:secret_key`)
+    - `:key`, `:secret_key`, `:purpose`, `:algorithm` and `:timestamp` - As
+      in `token_encode/1`
+
+  ## Returns
+  - `{:ok, token}`
+  - `{:error, reason}` if an option is missing or invalid
+
+  ## Examples
+      iex> {:ok, token} = Powex.issue_challenge(client_id: "client", difficulty: 5, reputation: 1.0, key: "k")
+      iex> {:ok, %{difficulty: difficulty, nonce: 0}} = Powex.token_decode(token)
+      iex> Powex.personal_difficulty("k", "client", 5, 1.0)
+      {:ok, difficulty}
+  """
+  @spec issue_challenge(keyword()) :: {:ok, String.t()} | {:error, String.t()}
+  def issue_challenge(_opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Generates random challenge bytes from the source set with the `:entropy`
+  option of `configure/1`, recording the source in the result.
+
+  Caller-provided `:entropy`, e.g. a drand beacon round, is mixed into the
+  bytes with a keyed BLAKE3 hash, so the challenge is unpredictable as long as
+  either the source or the caller entropy is, and anyone holding the entropy
+  can check it was used.
+
+  ## Parameters
+  - `opts`: Keyword list of options
+    - `:size` - Challenge size in bytes, 1 to 1024 (default: `32`)
+    - `:entropy` - Binary to mix in (default: none)
+
+  ## Returns
+  - `{:ok, %{challenge: binary, source: :os | :seeded, entropy: hash | nil}}`,
+    where `hash` is the BLAKE3 hash of the mixed-in entropy
+  - `{:error, reason}` if the size is invalid
+
+  ## Examples
+      iex> {:ok, %{challenge: challenge, source: :os, entropy: nil}} = Powex.generate_challenge(size: 16)
+      iex> byte_size(challenge)
+      16
+  """
+  @spec generate_challenge(keyword()) ::
+          {:ok, %{challenge: binary(), source: :os | :seeded, entropy: binary() | nil}}
+          | {:error, String.t()}
+  def generate_challenge(_opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Derives a 32-byte challenge from a public randomness beacon value, e.g. the
+  randomness of a drand round, and a `context` naming the protocol and purpose.
+
+  The challenge is a BLAKE3 hash of the two, so anyone holding the beacon
+  value can recompute it and check the issuer had no way to grind it, while
+  the context keeps challenges for one protocol from being reused in another.
+
+  ## Parameters
+  - `beacon_round_bytes`: Beacon value, not empty
+  - `context`: Application context string, not empty, e.g. `"myapp 2026 login"`
+
+  ## Returns
+  - `{:ok, challenge}`
+  - `{:error, reason}` if either argument is empty
+
+  ## Examples
+      iex> {:ok, challenge} = Powex.challenge_from_beacon(<<1::256>>, "myapp login")
+      iex> {:ok, ^challenge} = Powex.challenge_from_beacon(<<1::256>>, "myapp login")
+      iex> {:ok, other} = Powex.challenge_from_beacon(<<1::256>>, "myapp signup")
+      iex> other != challenge
+      true
+  """
+  @spec challenge_from_beacon(binary(), binary()) :: {:ok, binary()} | {:error, String.t()}
+  def challenge_from_beacon(_beacon_round_bytes, _context), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Commits to a contribution to a jointly derived challenge, the first step of
+  a commit-reveal exchange between parties that don't trust each other, e.g.
+  BEAM nodes ordering work fairly.
+
+  Every party publishes its `commitment`, and reveals its `opening` only once
+  it holds everyone else's commitment; `open_challenge/2` then derives the
+  challenge. The commitment hides the contribution under a random salt and
+  binds the party to it, so no party can pick its contribution after seeing
+  the others'. A party can still refuse to reveal, so treat a missing opening
+  as a failure of that party rather than retrying with fresh commitments.
+
+  ## Parameters
+  - `opts`: Keyword list of options
+    - `:contribution` - Bytes to contribute (default: 32 fresh random bytes
+      from the source of `generate_challenge/1`)
+
+  ## Returns
+  - `{:ok, %{commitment: commitment, opening: opening}}`
+  - `{:error, reason}` if the contribution is empty
+
+  ## Examples
+      iex> {:ok, %{commitment: commitment, opening: opening}} = Powex.commit_challenge()
+      iex> Powex.verify_opening(commitment, opening)
+      true
+  """
+  @spec commit_challenge(keyword()) ::
+          {:ok, %{commitment: binary(), opening: binary()}} | {:error, String.t()}
+  def commit_challenge(_opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks that `opening` opens `commitment`, as returned by `commit_challenge/1`.
+
+  ## Examples
+      iex> {:ok, %{commitment: commitment}} = Powex.commit_challenge()
+      iex> {:ok, %{opening: other}} = Powex.commit_challenge()
+      iex> Powex.verify_opening(commitment, other)
+      false
+  """
+  @spec verify_opening(binary(), binary()) :: boolean()
+  def verify_opening(_commitment, _opening), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Derives the joint 32-byte challenge of a commit-reveal exchange from every
+  party's commitment and opening, given in the same order.
+
+  Each opening is checked against its commitment first. The challenge depends
+  on the contributions ordered by commitment, not on the order of the lists,
+  so all parties derive the same challenge, and it is unpredictable as long as
+  one contribution is.
+
+  ## Returns
+  - `{:ok, challenge}`
+  - `{:error, :bad_opening}` if an opening doesn't match its commitment
+  - `{:error, :duplicate}` if a commitment appears twice
+  - `{:error, :malformed}` if the lists are empty or of different lengths
+
+  ## Examples
+      iex> {:ok, a} = Powex.commit_challenge()
+      iex> {:ok, b} = Powex.commit_challenge()
+      iex> {:ok, challenge} = Powex.open_challenge([a.commitment, b.commitment], [a.opening, b.opening])
+      iex> Powex.open_challenge([b.commitment, a.commitment], [b.opening, a.opening])
+      {:ok, challenge}
+  """
+  @spec open_challenge([binary()], [binary()]) ::
+          {:ok, binary()} | {:error, :bad_opening | :duplicate | :malformed}
+  def open_challenge(_commitments, _openings), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Issues a greylisting-style sequence of challenges for a client with
+  `failures` prior failures, e.g. failed logins.
+
+  The sequence has `failures + 1` steps, at most `:max_steps`, and step `i`
+  has difficulty `base + i * step`, so the total work grows exponentially with
+  failures: with the default step of one leading hex zero, each step takes 16
+  times as long as the previous one. The data of each step is derived from the
+  solution of the previous one, so the steps can only be solved in order. The
+  whole sequence is one signed token, and the server keeps no state.
+
+  ## Parameters
+  - `failures`: Prior failures of the client
+  - `opts`: Keyword list of options
+    - `:difficulty` - Difficulty of the first step (required)
+    - `:max_steps` - Most steps, 1-255 (default: `4`)
+    - `:step` - Difficulty added per step (default: `1`)
+    - `:key`, `:secret_key`, `:purpose`, `:algorithm` and `:timestamp` - As
+      in `token_encode/1`
+
+  ## Returns
+  - `{:ok, token}`
+  - `{:error, reason}` if an option is missing or invalid
+
+  ## Examples
+      iex> {:ok, token} = Powex.escalating_challenge(2, difficulty: 1, key: "k")
+      iex> {:ok, nonces} = Powex.escalating_solve(token)
+      iex> length(nonces)
+      3
+      iex> Powex.escalating_verify(token, nonces, Powex.policy_new(), key: "k")
+      {:ok, 3}
+  """
+  @spec escalating_challenge(non_neg_integer(), keyword()) :: {:ok, String.t()} | {:error, String.t()}
+  def escalating_challenge(_failures, _opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Solves the steps of a sequence from `escalating_challenge/2` in order, on
+  the calling dirty scheduler.
+
+  ## Parameters
+  - `token`: Sequence token
+  - `opts`: Keyword list of options
+    - `:timeout` - Milliseconds after which to give up (default: none)
+
+  ## Returns
+  - `{:ok, nonces}`, one per step
+  - `{:error, :malformed}` if the token is not a sequence
+  - `{:error, :timeout}` if the timeout passed first
+  """
+  @spec escalating_solve(String.t(), keyword()) :: {:ok, [non_neg_integer()]} | {:error, atom()}
+  def escalating_solve(_token, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies that `nonces` solve the steps of a sequence from
+  `escalating_challenge/2`, in order.
+
+  The token's tag and timestamp are checked first, with the options of
+  `token_verify/3`, then every step through the policy, whose
+  `:max_difficulty` must allow the last step.
+
+  ## Returns
+  - `{:ok, steps}`
+  - `{:error, :incomplete}` if there isn't exactly one nonce per step
+  - `{:error, reason}` as from `token_verify/3`, for the token or the first
+    step that fails
+  """
+  @spec escalating_verify(String.t(), [non_neg_integer()], reference(), keyword()) ::
+          {:ok, pos_integer()} | {:error, atom()}
+  def escalating_verify(_token, _nonces, _policy, _opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Generates an RSA key pair for minting anonymous tokens with
+  `blind_mint/4`.
+
+  Use the key for nothing else: the server signs blinded values it cannot
+  see, which is only safe because each one is the full-domain hash of a token.
+
+  ## Parameters
+  - `opts`: Keyword list of options
+    - `:bits` - Modulus size, even, 1024-4096 (default: `2048`)
+
+  ## Returns
+  - `{:ok, %{public_key: %{n: n, e: e}, secret_key: %{n: n, e: e, d: d}}}`, with
+    big-endian binaries
+  - `{:error, reason}` if the size is invalid
+  """
+  @spec blind_keypair(keyword()) :: {:ok, map()} | {:error, String.t()}
+  def blind_keypair(_opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Client side of minting: draws a random token, blinds it for the server's
+  public key and solves a proof of work over the blinded value.
+
+  Send `proof` to the server, keep the rest for `blind_finalize/3`. The proof's
+  data is the blinded value, so each proof pays for exactly one signature, and
+  the server never sees the token.
+
+  ## Parameters
+  - `public_key`: Public key from `blind_keypair/1`
+  - `difficulty`: Difficulty of the proof, as in `encode_proof/4`
+  - `opts`: Keyword list of options
+    - `:algorithm` - `:sha256` (default), `:blake3` or `:argon2id`, with
+      `:m_cost`, `:t_cost` and `:p_cost` as in `encode_proof/4`
+    - `:timeout` - Milliseconds after which to give up (default: none)
+
+  ## Returns
+  - `{:ok, %{proof: proof, token: token, blind: blind}}`
+  - `{:error, :malformed}` if the public key is invalid
+  - `{:error, :timeout}` if the timeout passed first
+  """
+  @spec blind_request(map(), non_neg_integer(), keyword()) :: {:ok, map()} | {:error, atom()}
+  def blind_request(_public_key, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Server side of minting: verifies the proof of `blind_request/3` through the
+  policy, then signs the blinded value it carries.
+
+  ## Parameters
+  - `proof`: Proof sent by the client
+  - `secret_key`: Secret key from `blind_keypair/1`
+  - `policy`: Policy from `policy_new/1` the proof must satisfy
+  - `opts`: Keyword list of options
+    - `:consume` - Also spend the work in the store of `proof_store_open/1`,
+      like `issue_receipt/4`, so it mints only once however its proof is
+      encoded (default: `false`)
+
+  ## Returns
+  - `{:ok, blind_signature}`
+  - `{:error, reason}` if the proof fails, as from `decode_and_verify/2`, or
+    was already consumed
+  - `{:error, :malformed}` if the key is invalid or the proof carries no
+    blinded value
+  """
+  @spec blind_mint(binary(), map(), reference(), keyword()) :: {:ok, binary()} | {:error, atom()}
+  def blind_mint(_proof, _secret_key, _policy, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Removes the blinding from the server's signature, leaving a signature on the
+  client's token that the server cannot link to the request it signed.
+
+  ## Returns
+  - `{:ok, signature}`
+  - `{:error, :bad_signature}` if the server's signature is not valid
+  - `{:error, :malformed}` if an argument is malformed
+
+  ## Examples
+      iex> {:ok, %{public_key: public_key, secret_key: secret_key}} = Powex.blind_keypair(bits: 1024)
+      iex> {:ok, request} = Powex.blind_request(public_key, 1)
+      iex> {:ok, blind_signature} = Powex.blind_mint(request.proof, secret_key, Powex.policy_new())
+      iex> {:ok, signature} = Powex.blind_finalize(public_key, request, blind_signature)
+      iex> Powex.blind_redeem(public_key, request.token, signature)
+      :ok
+  """
+  @spec blind_finalize(map(), map(), binary()) :: {:ok, binary()} | {:error, atom()}
+  def blind_finalize(_public_key, _request, _blind_signature), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks the signature of a token minted with `blind_mint/4`.
+
+  ## Parameters
+  - `public_key`: Public key from `blind_keypair/1`
+  - `token`: Token from `blind_request/3`
+  - `signature`: Signature from `blind_finalize/3`
+  - `opts`: Keyword list of options
+    - `:consume` - Also spend the token with `consume_proof/1`, so it redeems
+      only once (default: `false`)
+
+  ## Returns
+  - `:ok`
+  - `{:error, :bad_signature}` if the signature is not valid for the token
+  - `{:error, :already_consumed | :no_store | :io_error}` as from `consume_proof/1`
+  """
+  @spec blind_redeem(map(), binary(), binary(), keyword()) :: :ok | {:error, atom()}
+  def blind_redeem(_public_key, _token, _signature, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts verified work into a signed, single-use receipt worth `value`,
+  redeemable later with `redeem_receipt/3`.
+
+  The proof is verified through the policy and spent in the store of
+  `proof_store_open/1` under its algorithm, parameters, data and nonce, so the
+  same work never buys two receipts, however its proof is encoded. Receipts
+  are 65-byte binaries authenticated with a keyed BLAKE3 MAC under `secret`,
+  carrying their value and issue time, so the server keeps nothing until
+  they are redeemed.
+
+  ## Parameters
+  - `proof`: Proof from `encode_proof/4`
+  - `secret`: Server secret
+  - `value`: Credit the receipt is worth, a non-negative integer
+  - `opts`: Keyword list of options
+    - `:policy` - Policy from `policy_new/1` the proof must satisfy
+      (default: `policy_new()`)
+
+  ## Returns
+  - `{:ok, receipt}`
+  - `{:error, reason}` if the proof fails, as from `decode_and_verify/2`
+  - `{:error, :already_consumed | :no_store | :io_error}` as from `consume_proof/1`
+  """
+  @spec issue_receipt(binary(), binary(), non_neg_integer(), keyword()) ::
+          {:ok, binary()} | {:error, atom()}
+  def issue_receipt(proof, secret, value, opts \\ []) do
+    policy = Keyword.get_lazy(opts, :policy, &policy_new/0)
+    receipt_issue(proof, secret, value, policy)
+  end
+
+  @doc false
+  def receipt_issue(_proof, _secret, _value, _policy), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Redeems a receipt from `issue_receipt/4`, spending it in the store of
+  `proof_store_open/1` so it redeems only once.
+
+  ## Parameters
+  - `receipt`: Receipt
+  - `secret`: Secret the receipt was issued with
+  - `opts`: Keyword list of options
+    - `:max_age` - Refuse receipts issued more than this many seconds before
+      `clock_now/0` (default: no limit)
+
+  ## Returns
+  - `{:ok, %{value: value, issued_at: issued_at}}`
+  - `{:error, :malformed}` if the receipt cannot be decoded
+  - `{:error, :bad_signature}` if it was not issued with `secret`
+  - `{:error, :too_old}` if it is older than `:max_age`
+  - `{:error, :already_consumed | :no_store | :io_error}` as from `consume_proof/1`
+  """
+  @spec redeem_receipt(binary(), binary(), keyword()) :: {:ok, map()} | {:error, atom()}
+  def redeem_receipt(_receipt, _secret, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Signs a proof from `encode_proof/4` as an entry of a proof log.
+
+  An entry is `<<byte_size(proof)::32-little, proof::binary, tag::binary-32>>`,
+  where the tag is a keyed BLAKE3 hash of the proof under a key derived from
+  `key`. The tag is a MAC: verifying needs the same key. Logs are plain
+  concatenations of entries, verified with `verify_proof_stream/3`.
+
+  ## Returns
+  - `{:ok, entry}`
+  - `{:error, reason}` if the key is empty
+
+  ## Examples
+      iex> {:ok, entry} = Powex.sign_proof(Powex.encode_proof("data", 0, 0), "log key")
+      iex> byte_size(entry)
+      45
+  """
+  @spec sign_proof(binary(), binary()) :: {:ok, binary()} | {:error, String.t()}
+  def sign_proof(_proof, _key), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies a log of entries from `sign_proof/2` in constant memory, as a lazy
+  stream of per-entry results.
+
+  A background worker reads one entry at a time and verifies it like
+  `decode_and_verify/2`, recording it in the audit log if enabled. Results
+  are sent to the consuming process in batches, each only once the stream
+  asks for it, so memory stays flat over logs of millions of proofs. Entries
+  larger than the `:max_verify_bytes` budget of `configure/1` are skipped
+  without being read.
+
+  ## Parameters
+  - `path`: Path of the log
+  - `policy`: Policy from `policy_new/1` the proofs must satisfy
+  - `opts`: Keyword list of options
+    - `:key` - Key the entries were signed with (required)
+    - `:batch` - Entries verified per batch, 1-100000 (default: `1000`)
+
+  ## Returns
+  A stream of `{index, offset, status}` per entry, where `offset` is the
+  entry's byte offset in the log and `status` is `:ok` or `{:error, reason}`:
+  `:bad_signature` if the tag doesn't match, any error of
+  `decode_and_verify/2`, or, for the last element, `:truncated` if the log
+  ends mid-entry or `:io_error` if it can't be read further. Raises
+  `ArgumentError` if the log can't be opened or the options are invalid.
+
+  ## Examples
+      Powex.verify_proof_stream("proofs.log", Powex.policy_new(), key: key)
+      |> Stream.reject(&match?({_index, _offset, :ok}, &1))
+      |> Enum.each(&IO.inspect/1)
+  """
+  @spec verify_proof_stream(Path.t(), reference(), keyword()) :: Enumerable.t()
+  def verify_proof_stream(path, policy, opts) do
+    Stream.resource(
+      fn ->
+        case proof_stream_open(to_string(path), policy, opts) do
+          {:ok, {stream, job}} -> {stream, job}
+          {:error, reason} -> raise ArgumentError, reason
+        end
+      end,
+      fn {stream, job} ->
+        :ok = proof_stream_next(stream)
+
+        receive do
+          {:powex_proof_stream, ^job, {:entries, entries}} -> {entries, {stream, job}}
+          {:powex_proof_stream, ^job, :done} -> {:halt, {stream, job}}
+        end
+      end,
+      fn {stream, _job} -> proof_stream_close(stream) end
+    )
+  end
+
+  @doc false
+  def proof_stream_open(_path, _policy, _opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def proof_stream_next(_stream), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def proof_stream_close(_stream), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Issues a bundle of `n` random sub-challenges, any `k` of which must be solved.
+
+  Each sub-challenge gets a target `k` times easier than `difficulty`, so
+  solving `k` of them costs the same on average as one solution at `difficulty`
+  while varying much less. Keep the issued bundle server-side and check
+  solutions against it with `verify_bundle/2`.
+
+  ## Parameters
+  - `n`: Number of sub-challenges (1-1024)
+  - `difficulty`: Difficulty the whole bundle should cost, see `t:difficulty/0`
+  - `opts`: Keyword list of options
+    - `:k` - Sub-challenges that must be solved (1 to `n`, default: 1)
+
+  ## Returns
+  - `{:ok, bundle}` with the bundle as a binary
+  - `{:error, reason}` if `n`, `k` or the difficulty is out of range
+  """
+  @spec issue_bundle(pos_integer(), difficulty(), keyword()) :: {:ok, binary()} | {:error, String.t()}
+  def issue_bundle(_n, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Splits a bundle from `issue_bundle/3` into its hex target and sub-challenges.
+
+  Sub-challenge `i` is solved by a nonce whose hash, as computed by `compute/2`,
+  is at or below the target, so `Powex.compute(challenge, target)` solves it.
+  Raises `ArgumentError` for malformed bundles.
+
+  ## Examples
+      iex> {:ok, bundle} = Powex.issue_bundle(4, 2, k: 2)
+      iex> %{target: target, challenges: challenges} = Powex.decode_bundle(bundle)
+      iex> {String.length(target), length(challenges)}
+      {64, 4}
+  """
+  @spec decode_bundle(binary()) :: %{target: String.t(), challenges: [binary()]}
+  def decode_bundle(_bundle), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks that at least `k` distinct sub-challenges of a bundle are solved.
+
+  `solutions` is `{bundle, [{index, nonce}]}`, indexes counting from 0 in the
+  order of `decode_bundle/1`. Invalid nonces and repeated indexes don't count.
+  Raises `ArgumentError` for malformed bundles.
+  """
+  @spec verify_bundle({binary(), [{non_neg_integer(), non_neg_integer()}]}, pos_integer()) :: boolean()
+  def verify_bundle(_solutions, _k), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Derives the challenge parameters of the current time epoch from a shared secret.
+
+  Every node configured with the same secret and options computes the same
+  algorithm, difficulty and seed for an epoch, so a cluster rotates parameters
+  in lockstep without exchanging messages. Nodes should accept solutions for
+  the previous epoch too, to cover clock skew and challenges issued just before
+  a rotation.
+
+  ## Parameters
+  - `opts`: Keyword list of options
+    - `:secret` - Shared secret binary (required)
+    - `:difficulties` - Difficulties to rotate between (required), see `t:difficulty/0`
+    - `:algorithms` - Algorithms to rotate between (default: `[:sha256, :blake3]`)
+    - `:epoch_seconds` - Length of an epoch (default: `3600`)
+    - `:now` - Unix time in seconds to derive the epoch from (default: `clock_now/0`)
+
+  ## Returns
+  - `{:ok, %{epoch: epoch, algorithm: algorithm, difficulty: difficulty, seed: seed, starts_at: starts_at, expires_at: expires_at}}`,
+    `seed` being a 32-character hex string unique to the epoch
+  - `{:error, reason}` if an option is missing or invalid
+
+  ## Examples
+      iex> opts = [secret: "cluster secret", difficulties: [3, 4], now: 7_200]
+      iex> {:ok, params} = Powex.current_challenge_params(opts)
+      iex> {params.epoch, params.starts_at, params.expires_at}
+      {2, 7200, 10800}
+  """
+  @spec current_challenge_params(keyword()) :: {:ok, map()} | {:error, String.t()}
+  def current_challenge_params(_opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Derives a per-client difficulty from a server secret, without storing
+  anything per client.
+
+  The base difficulty moves by up to `:max_adjustment` levels with the
+  reputation score: down toward a score of 1 for trusted clients, up toward 0
+  for suspicious ones, and unchanged at 0.5. A jitter in `[-0.5, 0.5)`, drawn
+  from a keyed BLAKE3 hash of the client id under the secret (a PRF like HMAC),
+  is added before rounding: a client always gets the same difficulty for the
+  same score, clients can't predict each other's, and across many clients the
+  mean difficulty follows the score exactly rather than in whole steps. The
+  result is clamped to 0-64.
+
+  ## Parameters
+  - `secret`: Server secret
+  - `client_id`: Client identifier, e.g. an account id or an IP address
+  - `base`: Difficulty for a neutral client
+  - `reputation_score`: Number from 0 to 1
+  - `opts`: Keyword list of options
+    - `:max_adjustment` - Levels added at a score of 0 and removed at 1 (default: `2.0`)
+
+  ## Returns
+  - `{:ok, difficulty}`
+  - `{:error, reason}` if the secret is empty or `:max_adjustment` is negative
+
+  ## Examples
+      iex> {:ok, trusted} = Powex.personal_difficulty("secret", "client", 5, 1.0)
+      iex> {:ok, suspicious} = Powex.personal_difficulty("secret", "client", 5, 0.0)
+      iex> trusted < suspicious
+      true
+      iex> Powex.personal_difficulty("secret", "client", 5, 1.0)
+      {:ok, trusted}
+  """
+  @spec personal_difficulty(binary(), binary(), non_neg_integer(), number(), keyword()) ::
+          {:ok, non_neg_integer()} | {:error, String.t()}
+  def personal_difficulty(_secret, _client_id, _base, _reputation_score, _opts \\ []),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Measures the exact difficulty achieved by a proof, in leading zero bits of the hash.
+
+  Useful for granting proportional credit to stronger proofs instead of a
+  pass/fail answer. A proof for `n` leading hex zeros achieves at least `4 * n` bits.
+
+  ## Parameters
+  - `data`: The input data (string or binary) that was hashed
+  - `nonce`: The nonce to measure (integer)
+
+  ## Returns
+  - The number of leading zero bits (0-256)
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute("credit", 3)
+      iex> Powex.measure_difficulty("credit", nonce) in 12..15
+      true
+  """
+  @spec measure_difficulty(binary(), non_neg_integer()) :: non_neg_integer()
+  def measure_difficulty(data, nonce) do
+    case hash_scheduler(data) do
+      :normal -> measure_difficulty_normal(data, nonce)
+      :dirty -> measure_difficulty_dirty(data, nonce)
+    end
+  end
+
+  @doc false
+  def measure_difficulty_normal(_data, _nonce), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def measure_difficulty_dirty(_data, _nonce), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Maps a proof to a lottery draw, a float uniform in `[0, 1)`.
+
+  The draw comes from a BLAKE3 hash of the proof's SHA-256 hash, so it stays
+  uniform although valid hashes start with zeros. Check the proof with
+  `valid?/3` first: the draw doesn't depend on the difficulty.
+
+  ## Parameters
+  - `data`: The input data (string or binary) that was hashed
+  - `nonce`: The proof's nonce (integer)
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute("lottery", 1)
+      iex> ticket = Powex.lottery_ticket("lottery", nonce)
+      iex> ticket >= 0.0 and ticket < 1.0
+      true
+  """
+  @spec lottery_ticket(binary(), non_neg_integer()) :: float()
+  def lottery_ticket(data, nonce) do
+    case hash_scheduler(data) do
+      :normal -> lottery_ticket_normal(data, nonce)
+      :dirty -> lottery_ticket_dirty(data, nonce)
+    end
+  end
+
+  @doc false
+  def lottery_ticket_normal(_data, _nonce), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def lottery_ticket_dirty(_data, _nonce), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Picks the winner of a proof-of-work lottery: the proof with the lowest
+  `lottery_ticket/2` draw.
+
+  Each valid proof is one ticket, so a node's chance of winning is
+  proportional to the proofs it submits, e.g. for leader election among
+  cluster nodes. Ties are broken on the full 256-bit draw and then on position,
+  so every node holding the same proofs picks the same winner. Proofs are not
+  checked against a difficulty; validate them with `valid?/3` first.
+
+  ## Parameters
+  - `proofs`: List of `{data, nonce}` tuples
+
+  ## Returns
+  - `{:ok, %{index: index, ticket: ticket}}`, with the winner's position in
+    the list and its draw
+  - `{:error, reason}` if the list is empty
+
+  ## Examples
+      iex> {:ok, a} = Powex.compute("node a", 1)
+      iex> {:ok, b} = Powex.compute("node b", 1)
+      iex> {:ok, %{index: index, ticket: ticket}} = Powex.select_winner([{"node a", a}, {"node b", b}])
+      iex> ticket == min(Powex.lottery_ticket("node a", a), Powex.lottery_ticket("node b", b))
+      true
+      iex> index in [0, 1]
+      true
+  """
+  @spec select_winner([{binary(), non_neg_integer()}]) ::
+          {:ok, %{index: non_neg_integer(), ticket: float()}} | {:error, String.t()}
+  def select_winner(_proofs), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns how many times the required work a proof represents.
+
+  The hash is compared to the difficulty's numeric target instead of counting
+  zeros, and the multiple is `(target + 1) / (hash + 1)`: a proof that just
+  meets the target is worth about 1x, and one with a much lower hash a
+  fractional multiple such as 8.3x, for granting proportional credit. Integer
+  difficulties count as a minimum number of hex zeros here.
+
+  ## Parameters
+  - `data`: The input data (string or binary) that was hashed
+  - `nonce`: The submitted nonce (integer)
+  - `difficulty`: The required difficulty: hex zeros, `{:zero_bits, n}` or a
+    64-character hex target
+
+  ## Returns
+  - `{:ok, multiple}` with a multiple of at least 1.0 if the proof meets the target
+  - `{:error, :invalid}` if it doesn't
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute("credit", {:zero_bits, 8})
+      iex> {:ok, multiple} = Powex.work_multiple("credit", nonce, {:zero_bits, 8})
+      iex> multiple >= 1.0
+      true
+  """
+  @spec work_multiple(binary(), non_neg_integer(), difficulty()) :: {:ok, float()} | {:error, :invalid}
+  def work_multiple(_data, _nonce, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Hashes `n` random nonces with `data` and returns a histogram of the number of
+  leading zero bits of each hash.
+
+  Operators can compare the counts to what an ideal hash predicts, `n / 2^(k + 1)`
+  hashes with exactly `k` leading zero bits, to check empirically that
+  difficulty settings behave as expected on their own payloads.
+
+  ## Options
+  - `:algorithm` - The algorithm to sample (default: `:sha256`)
+  - `:deterministic_seed` - Integer seed drawing the same nonces on every run
+
+  ## Returns
+  - `{:ok, %{counts: counts, expected: expected, mean_bits: mean}}` where
+    `Enum.at(counts, k)` hashes had exactly `k` leading zero bits, `expected`
+    holds the ideal counts and `mean` (about 1.0 for an ideal hash) is the mean
+    number of leading zero bits
+  - `{:error, reason}` if `n` is outside 1-100000000
+
+  ## Examples
+      iex> {:ok, %{counts: counts}} = Powex.sample_distribution("payload", 1000)
+      iex> Enum.sum(counts)
+      1000
+  """
+  @spec sample_distribution(binary(), pos_integer(), keyword()) ::
+          {:ok, %{counts: [non_neg_integer()], expected: [float()], mean_bits: float()}}
+          | {:error, String.t()}
+  def sample_distribution(_data, _n, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Computes a Proof of Work nonce using parallel processing for improved performance.
+
+  ## Parameters
+  - `data`: The input data (string or binary) to hash
+  - `difficulty`: Number of leading zeros required in the hash (integer), or a
+    64-character hex target that the hash must not exceed
+  - `threads`: Number of threads to use for parallel computation (default: number of CPU cores)
+  - `opts`: Keyword list of options
+    - `:diagnostics` - Return per-thread statistics along with the nonce (default: `false`)
+    - `:nonce_range` - Inclusive range of nonces to split between the threads,
+      such as `0..999_999` (default: the whole 64-bit space)
+    - `:exclude` - Inclusive ranges of nonces to skip, such as ranges already
+      searched by a failed worker; the rest is split evenly between the threads
+
+  ## Returns
+  - `{:ok, nonce}` when a valid nonce is found
+  - `{:ok, diagnostics}` with `diagnostics: true`, a map with `:nonce`,
+    `:found_by` (thread index), `:elapsed_ms` and `:threads`, a list of
+    `%{thread, start, end, attempts, found, elapsed_ms}` per thread. Each thread
+    searched `start..(start + attempts - 1)` of its assigned range `start..end`,
+    less any excluded nonces.
+  - `{:error, :nonce_space_exhausted, searched_range}` if every nonce of the
+    range was tried without a solution
+  - `{:error, reason}` if the arguments are invalid or the search gave up
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute_parallel("hello world", 4, 4)
+      iex> is_integer(nonce)
+      true
+
+      iex> {:ok, %{nonce: nonce, found_by: thread, threads: reports}} =
+      ...>   Powex.compute_parallel("hello world", 4, 4, diagnostics: true)
+      iex> %{start: start, found: true} = Enum.at(reports, thread)
+      iex> nonce >= start
+      true
+  """
+  @spec compute_parallel(binary(), difficulty(), pos_integer(), keyword()) ::
+    {:ok, non_neg_integer() | map()}
+    | {:error, :nonce_space_exhausted, Range.t()}
+    | {:error, String.t()}
+  def compute_parallel(_data, _difficulty, _threads, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Starts a Proof of Work search in the background and returns immediately.
+
+  The result is sent to the subscriber as `{:powex_job, job, result}`, where
+  `result` is what `compute/2` would have returned. Jobs started for the same
+  algorithm, data and difficulty while a search is in flight share that search,
+  and each of them receives the single result.
+
+  ## Parameters
+  - `data`: The input data (string or binary) to hash
+  - `difficulty`: Number of leading zeros required in the hash (integer), or a
+    64-character hex target that the hash must not exceed
+  - `opts`: Keyword list of options
+    - `:algorithm` - See `t:algorithm/0` (default: `:sha256`)
+    - `:pid` - Process receiving the result (default: the caller)
+
+  ## Returns
+  - `{:ok, job}`, where `job` is an opaque handle to match the result message on
+
+  ## Examples
+      iex> {:ok, job} = Powex.compute_async("hello world", 2)
+      iex> receive do
+      ...>   {:powex_job, ^job, {:ok, nonce}} -> Powex.valid?("hello world", nonce, 2)
+      ...> end
+      true
+  """
+  @spec compute_async(binary(), difficulty(), keyword()) :: {:ok, reference()}
+  def compute_async(_data, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies a proof on a background worker and returns immediately.
+
+  Use it when the data is huge or the algorithm memory-hard, so that even a
+  single verification would stall a scheduler. The result is sent to the
+  subscriber as `{:powex_job, job, {:ok, valid}}`, or `{:powex_job, job, {:error, reason}}`
+  with `:over_budget` past the verification budget of `configure/1`, or
+  `:invalid` for unusable Argon2id parameters. Data past its `:max_data_size`
+  raises `{:data_too_large, limit}` before any work is started.
+  SHA-256 results share the `valid?/3` cache, and every result is audited.
+
+  ## Parameters
+  - `data`: The data the proof was computed over
+  - `nonce`: The nonce to verify
+  - `difficulty`: The required difficulty, as in `valid?/3`
+  - `opts`: Keyword list of options
+    - `:algorithm` - See `t:algorithm/0` (default: `:sha256`), or `:argon2id`
+      to verify like `valid_memory_hard?/4`
+    - `:m_cost`, `:t_cost`, `:p_cost` - Argon2id parameters
+    - `:pid` - Process receiving the result (default: the caller)
+
+  ## Returns
+  - `{:ok, job}`, where `job` is an opaque handle to match the result message on
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute("hello world", 2)
+      iex> {:ok, job} = Powex.valid_async?("hello world", nonce, 2)
+      iex> receive do
+      ...>   {:powex_job, ^job, result} -> result
+      ...> end
+      {:ok, true}
+  """
+  @spec valid_async?(binary(), non_neg_integer(), difficulty(), keyword()) :: {:ok, reference()}
+  def valid_async?(_data, _nonce, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Gets statistics of async jobs.
+
+  ## Returns
+  - `%{searches: searches, jobs: jobs, coalesced: coalesced}`: the searches in
+    flight, the jobs waiting on them, and the number of jobs that joined an
+    identical search instead of starting their own
+  """
+  @spec job_stats() :: %{searches: non_neg_integer(), jobs: non_neg_integer(), coalesced: non_neg_integer()}
+  def job_stats(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Gets the internal counters, grouped by subsystem.
+
+  ## Returns
+  - `%{hashes: hashes, cache: cache, jobs: jobs}`, where
+    - `hashes` - Hashes computed by searches
+    - `cache` - As returned by `cache_stats/0`
+    - `jobs` - As returned by `job_stats/0`
+  """
+  @spec stats() :: %{
+          hashes: non_neg_integer(),
+          cache: %{hits: non_neg_integer(), misses: non_neg_integer(), size: non_neg_integer(), capacity: pos_integer()},
+          jobs: %{searches: non_neg_integer(), jobs: non_neg_integer(), coalesced: non_neg_integer()}
+        }
+  def stats(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Lists the NUMA nodes of the machine with their CPUs, as read from sysfs.
+
+  The list is empty where the topology isn't available (outside Linux). See
+  the `:numa` option of `configure/1`.
+
+  ## Returns
+  - A list of `%{node: index, cpus: [cpu]}` maps
+  """
+  @spec numa_topology() :: [%{node: non_neg_integer(), cpus: [non_neg_integer()]}]
+  def numa_topology(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Starts a mining daemon: a persistent pool of worker threads fed by an internal priority queue.
+
+  The daemon stops when `daemon_stop/1` is called or when its handle is garbage collected.
+
+  ## Parameters
+  - `opts`: Keyword list of options
+    - `:threads` - Number of worker threads, 1-64 (default: number of CPU cores)
+    - `:reserve_cores` - Cores to leave free for the BEAM; the worker count is
+      capped to the remaining cores, keeping at least one worker (default: `0`)
+    - `:max_hashes_per_second` - Hashrate budget shared by all workers, enforced
+      by token-bucket pacing so background minting uses a predictable slice of
+      compute (default: unlimited)
+    - `:power_policy` - What workers do on battery power: `:ignore` (default),
+      `:pause_on_battery` to stop hashing until AC power returns, or
+      `:throttle_on_battery` to hash a quarter of the time. See `power_source/0`.
+    - `:max_temperature` - CPU temperature in degrees Celsius above which
+      workers back off: their duty cycle halves on every check (once per
+      second) above the threshold and recovers gradually below it. See
+      `cpu_temperature/0` (default: no thermal limit)
+    - `:os_limit` - When any of the options above throttles the daemon, also
+      have the OS enforce it rather than only the workers' cooperative sleeping:
+      on macOS workers run at the utility QoS class, dropping to background
+      while throttled; on Windows the VM joins a job object whose CPU rate cap
+      leaves the throttled share of the workers' cores idle, which caps the
+      whole VM since job objects hold processes. No effect elsewhere (default: `true`)
+
+  ## Returns
+  - `{:ok, daemon}` with an opaque daemon handle
+  - `{:error, reason}` if the options are invalid
+
+  ## Examples
+      iex> {:ok, daemon} = Powex.daemon_start(threads: 2)
+      iex> {:ok, id} = Powex.enqueue(daemon, "queued data", 2, self())
+      iex> receive do
+      ...>   {:powex_daemon, ^id, {:ok, nonce}} -> Powex.valid?("queued data", nonce, 2)
+      ...> end
+      true
+  """
+  @spec daemon_start(keyword()) :: {:ok, reference()} | {:error, String.t()}
+  def daemon_start(_opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Generates deterministic known-answer test vectors for cross-implementation checks.
+
+  Vector `i` hashes `data` made of `sha256(seed <> <<i::64-big>>)` repeated and
+  cut to `rem(i * 7, 160)` bytes, requires exactly `rem(i, 4)` leading hex zeros
+  (the `valid?/3` semantics) and carries the lowest nonce meeting it. Hashes are
+  `algorithm(data <> <<nonce::64-little>>)` as lowercase hex. The same seed always
+  yields the same vectors, on any machine.
+
+  ## Parameters
+  - `algorithm`: See `t:algorithm/0`
+  - `count`: Number of vectors, at most 10000
+  - `opts`: Keyword list of options
+    - `:seed` - Binary seed (default: `"powex test vectors"`)
+
+  ## Returns
+  - `{:ok, [%{data: data, nonce: nonce, hash: hash, difficulty: difficulty}]}`
+  - `{:error, reason}` if `count` is too large
+
+  ## Examples
+      iex> {:ok, [first | _]} = Powex.test_vectors(:sha256, 4)
+      iex> first
+      %{data: "", nonce: 0, difficulty: 0, hash: "af5570f5a1810b7af78caf4bc70a660f0df51e42baf91d4de5b2328de0e83dfc"}
+  """
+  @spec test_vectors(algorithm(), non_neg_integer(), keyword()) ::
+    {:ok, [%{data: binary(), nonce: non_neg_integer(), hash: String.t(), difficulty: non_neg_integer()}]}
+    | {:error, String.t()}
+  def test_vectors(_algorithm, _count, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Finds a nonce accepted by a custom predicate compiled to WebAssembly.
+
+  The module must export `accept(w0, w1, w2, w3) -> i32` taking the digest as
+  four big-endian signed 64-bit words, and returning non-zero to accept it. It runs
+  in a sandboxed interpreter with no imports, at most 1 MiB of memory and a fresh
+  fuel budget per candidate. Requires the NIF to be built with the `wasm` cargo
+  feature (`config :powex, Powex, features: ["wasm"]`).
+
+  ## Parameters
+  - `data`: The input data (string or binary) to hash
+  - `wasm`: The compiled WebAssembly module
+  - `opts`: Keyword list of options
+    - `:algorithm` - See `t:algorithm/0` (default: `:sha256`)
+    - `:fuel` - Fuel each `accept` call may burn (default: `10_000`)
+    - `:max_attempts` - Nonces to try before giving up (default: `1_000_000`)
+
+  ## Returns
+  - `{:ok, nonce}` for the first accepted nonce
+  - `{:error, reason}` if the module is invalid, traps, runs out of fuel, or
+    accepts no nonce within `:max_attempts`
+  """
+  @spec compute_with_predicate(binary(), binary(), keyword()) ::
+    {:ok, non_neg_integer()} | {:error, String.t()}
+  def compute_with_predicate(_data, _wasm, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks a nonce against a WebAssembly predicate, as `compute_with_predicate/3` does.
+
+  An invalid module, a trap or running out of fuel counts as rejection.
+
+  ## Parameters
+  - `data`: The input data that was hashed
+  - `nonce`: The nonce to check
+  - `wasm`: The compiled WebAssembly module
+  - `opts`: `:algorithm` and `:fuel`, as in `compute_with_predicate/3`
+
+  ## Examples
+      iex> Powex.valid_predicate?("data", 0, "not wasm")
+      false
+  """
+  @spec valid_predicate?(binary(), non_neg_integer(), binary(), keyword()) :: boolean()
+  def valid_predicate?(_data, _nonce, _wasm, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Finds every EquiX solution for a challenge.
+
+  EquiX is the asymmetric puzzle of Tor's onion service DoS defense: solving
+  takes about 2 MiB of memory and a few milliseconds, and verifying a solution
+  takes microseconds. A challenge has about two solutions on average, and
+  sometimes none.
+
+  ## Parameters
+  - `challenge`: The challenge bytes
+
+  ## Returns
+  - `{:ok, solutions}` with each solution as a 16-byte binary
+  - `{:error, reason}` for the rare challenges whose HashX program is unusable
+  """
+  @spec equix_solve(binary()) :: {:ok, [binary()]} | {:error, String.t()}
+  def equix_solve(_challenge), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks a 16-byte EquiX solution against its challenge.
+
+  ## Examples
+      iex> Powex.equix_verify?("challenge", <<0::128>>)
+      false
+  """
+  @spec equix_verify?(binary(), binary()) :: boolean()
+  def equix_verify?(_challenge, _solution), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Solves Tor's onion service proof-of-work (v1) for an introduction request.
+
+  Starting from a random 16-byte nonce, solves EquiX for the challenge
+  `"Tor hs intro v1\\0" <> blinded_id <> seed <> nonce <> <<effort::32>>` and
+  increments the nonce until a solution's 32-bit BLAKE2b hash `r` with the
+  challenge satisfies `r * effort <= 0xFFFFFFFF`, so the expected work grows
+  linearly with `effort`.
+
+  ## Parameters
+  - `blinded_id`: The service's 32-byte blinded ed25519 key
+  - `seed`: The 32-byte seed from the service's `pow-params`
+  - `effort`: The effort to prove (32-bit)
+  - `opts`: Keyword list of options
+    - `:max_attempts` - Nonces to try before giving up (default: `1_000_000`)
+    - `:deterministic_seed` - Integer seed for the starting nonce, making the
+      result reproducible (default: a random start)
+
+  ## Returns
+  - `{:ok, %{nonce: nonce, effort: effort, seed_head: seed_head, solution: solution}}`
+    with the fields of the INTRODUCE2 proof-of-work extension; `seed_head` is
+    the first 4 bytes of the seed
+  - `{:error, reason}` if the inputs are malformed or no solution meets the
+    effort within `:max_attempts`
+  """
+  @spec onion_pow_solve(binary(), binary(), non_neg_integer(), keyword()) ::
+    {:ok, %{nonce: binary(), effort: non_neg_integer(), seed_head: binary(), solution: binary()}}
+    | {:error, String.t()}
+  def onion_pow_solve(_blinded_id, _seed, _effort, _opts \\ []),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies an onion service proof-of-work (v1) as a service would.
+
+  Checks that the EquiX solution is valid for the challenge and meets the
+  claimed effort. Picking the seed that matches `seed_head`, enforcing the
+  service's suggested effort and rejecting replayed nonces are up to the caller.
+
+  ## Parameters
+  - `blinded_id`: The service's 32-byte blinded ed25519 key
+  - `seed`: The 32-byte seed the proof names
+  - `nonce`: The client's 16-byte nonce
+  - `effort`: The claimed effort
+  - `solution`: The 16-byte EquiX solution
+
+  ## Examples
+      iex> Powex.onion_pow_valid?(<<0::256>>, <<0::256>>, <<0::128>>, 1, <<0::128>>)
+      false
+  """
+  @spec onion_pow_valid?(binary(), binary(), binary(), non_neg_integer(), binary()) :: boolean()
+  def onion_pow_valid?(_blinded_id, _seed, _nonce, _effort, _solution),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Generates Nano-compatible work for a block root.
+
+  Nano's work value is the 8-byte BLAKE2b of `<<work::64-little>> <> root`,
+  read as a little-endian integer; work is valid when the value is at least the
+  threshold. Each thread starts at a random work value, as the reference node does.
+
+  ## Parameters
+  - `root`: The 32-byte block root (previous block hash, or the account public
+    key for an account's first block)
+  - `threshold`: A 64-bit minimum work value, or `:send` (`0xfffffff800000000`,
+    send and change blocks), `:receive` (`0xfffffe0000000000`) or `:epoch_1`
+    (`0xffffffc000000000`) (default: `:send`)
+  - `opts`: Keyword list of options
+    - `:threads` - Worker threads, 1-64 (default: one per core)
+    - `:timeout` - Milliseconds to search before giving up (default: none)
+    - `:deterministic_seed` - Integer seed for a shared starting point; the
+      threads split the nonces after it and the lowest one reaching the
+      threshold is returned, the same on every run and platform whatever the
+      thread count
+
+  ## Returns
+  - `{:ok, work}` with `work` as 16 lowercase hex digits, as in blocks and RPC
+  - `{:error, reason}` if the root is malformed or the timeout passed
+  """
+  @spec nano_work_generate(binary(), non_neg_integer() | :send | :receive | :epoch_1, keyword()) ::
+    {:ok, String.t()} | {:error, String.t()}
+  def nano_work_generate(_root, _threshold \\ :send, _opts \\ []),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks Nano work for a block root against a threshold (default: `:send`).
+
+  ## Examples
+      iex> Powex.nano_work_valid?(<<0::256>>, "0000000000000000", 0)
+      true
+      iex> Powex.nano_work_valid?(<<0::256>>, "not work", 0)
+      false
+  """
+  @spec nano_work_valid?(binary(), String.t(), non_neg_integer() | :send | :receive | :epoch_1) ::
+    boolean()
+  def nano_work_valid?(_root, _work, _threshold \\ :send), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns the work value of Nano work for a block root, the `difficulty` that
+  the node's `work_validate` RPC reports. Raises `ArgumentError` for a
+  malformed root or work.
+  """
+  @spec nano_work_value(binary(), String.t()) :: non_neg_integer()
+  def nano_work_value(_root, _work), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies an Ethash seal the way a light client does, from the epoch's light
+  cache instead of the full dataset.
+
+  Recomputes hashimoto for the header and nonce, deriving each dataset item it
+  reads from the cache, and checks the header's mix hash. The cache of an epoch
+  (16 MiB and up) is generated the first time it is needed, which takes a
+  second or more; caches are kept within the byte cap of
+  `ethash_cache_configure/1`, and each verification prebuilds the next epoch's
+  cache in the background so the epoch boundary doesn't stall.
+
+  ## Parameters
+  - `header_hash`: The 32-byte Keccak-256 of the RLP header without nonce and mix hash
+  - `nonce`: The header nonce as an integer (its 8 bytes read big-endian)
+  - `mix_hash`: The 32-byte mix hash from the header
+  - `epoch`: `div(block_number, 30_000)`, below 2048
+  - `opts`: Keyword list of options
+    - `:difficulty` - Also require the result to be at most `2^256 / difficulty`
+
+  ## Examples
+      iex> Powex.ethash_verify(<<0::256>>, 0, "short", 0)
+      false
+  """
+  @spec ethash_verify(binary(), non_neg_integer(), binary(), non_neg_integer(), keyword()) ::
+    boolean()
+  def ethash_verify(_header_hash, _nonce, _mix_hash, _epoch, _opts \\ []),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Generates an epoch's Ethash light cache ahead of time, e.g. at startup.
+
+  Concurrent requests for an epoch being generated wait for it instead of
+  generating it again.
+
+  ## Returns
+  - `{:ok, bytes}` with the size of the cache
+  - `{:error, reason}` for an epoch of 2048 or more
+  """
+  @spec ethash_cache_build(non_neg_integer()) :: {:ok, pos_integer()} | {:error, String.t()}
+  def ethash_cache_build(_epoch), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Reports the Ethash light caches.
+
+  ## Returns
+  - `%{epochs: epochs, building: building, bytes: bytes, max_bytes: max_bytes, prebuild: prebuild}`,
+    with the cached epochs most recently used first and the epochs being generated
+
+  ## Examples
+      iex> %{max_bytes: max_bytes} = Powex.ethash_cache_info()
+      iex> max_bytes > 0
+      true
+  """
+  @spec ethash_cache_info() :: %{
+    epochs: [non_neg_integer()],
+    building: [non_neg_integer()],
+    bytes: non_neg_integer(),
+    max_bytes: non_neg_integer(),
+    prebuild: boolean()
+  }
+  def ethash_cache_info(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Configures the Ethash light caches.
+
+  ## Options
+  - `:max_bytes` - Byte cap of the caches (default: 256 MiB). Least recently
+    used epochs are evicted beyond it, though the newest cache is always kept
+  - `:prebuild` - Whether verifications generate the next epoch's cache in
+    the background (default: `true`)
+
+  ## Returns
+  - `:ok`
+  """
+  @spec ethash_cache_configure(keyword()) :: :ok
+  def ethash_cache_configure(_opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies a Primecoin-style prime-chain proof (experimental).
+
+  The chain origin is `header_hash * multiplier`. A Cunningham chain of the
+  first kind starts at `origin - 1` and continues with `2p + 1`, one of the
+  second kind starts at `origin + 1` and continues with `2p - 1`, and a bi-twin
+  chain alternates between the two. Primality uses base-2 Fermat tests, and
+  lengths have a fractional part that measures how close the first composite
+  came to passing, as in Primecoin.
+
+  ## Parameters
+  - `header_hash`: The 32-byte header hash in internal byte order (a
+    little-endian integer), at least `2^255`
+  - `multiplier`: The positive chain multiplier; an even multiplier whose
+    halved origin gives a longer chain is rejected as not normalized
+  - `chain_type`: `:cunningham1`, `:cunningham2` or `:bi_twin`
+  - `target_length`: The length to reach, from 2 to 99, as an integer or a
+    float with a fractional part
+
+  ## Examples
+      iex> Powex.prime_chain_verify(<<0::256>>, 1, :cunningham1, 2)
+      false
+  """
+  @spec prime_chain_verify(binary(), pos_integer(), :cunningham1 | :cunningham2 | :bi_twin, number()) ::
+    boolean()
+  def prime_chain_verify(_header_hash, _multiplier, _chain_type, _target_length),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Solves a discrete-log style puzzle on the BN254 G1 curve.
+
+  The nonce's scalar is `sha256(data <> <<nonce::64-little>>)` read as a
+  little-endian integer modulo the group order, and the nonce is accepted when
+  the x-coordinate of `scalar * G` has `zero_bits` leading zero bits out of 254.
+  Every candidate costs a scalar multiplication, hundreds of times a SHA-256
+  hash, while verification is that single multiplication.
+
+  ## Parameters
+  - `data`: The input data (string or binary)
+  - `zero_bits`: Leading zero bits required of the x-coordinate (0-64)
+
+  ## Returns
+  - `{:ok, nonce}` for the first accepted nonce
+  - `{:error, reason}` if the difficulty or data is out of range
+  """
+  @spec compute_curve(binary(), non_neg_integer()) :: {:ok, non_neg_integer()} | {:error, String.t()}
+  def compute_curve(_data, _zero_bits), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks a curve puzzle nonce from `compute_curve/2` with one scalar multiplication.
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute_curve("curve", 2)
+      iex> Powex.valid_curve?("curve", nonce, 2)
+      true
+  """
+  @spec valid_curve?(binary(), non_neg_integer(), non_neg_integer()) :: boolean()
+  def valid_curve?(_data, _nonce, _zero_bits), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Opens the file-backed store of consumed proofs used by `consume_proof/1`.
+
+  The file is an append-only log of 32-byte BLAKE3 digests of consumed proofs,
+  created if missing and loaded into memory, so "each proof spendable once"
+  holds across restarts. A partial record left by a crash is discarded. Opening
+  another file replaces the current store.
+
+  ## Returns
+  - `{:ok, count}` with the number of proofs already consumed
+  - `{:error, reason}` if the file can't be opened
+  """
+  @spec proof_store_open(String.t()) :: {:ok, non_neg_integer()} | {:error, String.t()}
+  def proof_store_open(_path), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Atomically checks that a proof was never consumed and records it.
+
+  The proof is any binary identifying the spent work, typically the output of
+  `encode_proof/4` after `decode_and_verify/2` accepted it. The record is
+  synced to disk before `:ok` is returned.
+
+  ## Returns
+  - `:ok` if the proof was unspent and is now consumed
+  - `{:error, :already_consumed}` if it was consumed before
+  - `{:error, :no_store}` if `proof_store_open/1` wasn't called
+  - `{:error, :io_error}` if the record couldn't be written
+  """
+  @spec consume_proof(binary()) :: :ok | {:error, :already_consumed | :no_store | :io_error}
+  def consume_proof(_proof), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns whether a proof was consumed, without consuming it.
+  """
+  @spec proof_consumed?(binary()) :: boolean()
+  def proof_consumed?(_proof), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Creates a per-client token bucket for `price_request/3`.
+
+  The bucket starts full; requests spend tokens, which refill over time or are
+  bought with proofs of work. The difficulty of a proof rises from
+  `:min_difficulty` when the bucket is full to `:max_difficulty` when empty, so
+  clients that stay within their budget never hash, and bursts get more
+  expensive as they go on.
+
+  ## Options
+  - `:capacity` - Most tokens the bucket holds (default: 10)
+  - `:refill_per_second` - Tokens earned back per second (default: 1)
+  - `:credit` - Tokens an accepted proof buys (default: the capacity)
+  - `:min_difficulty` - Difficulty when the bucket is full (default: 2)
+  - `:max_difficulty` - Difficulty when the bucket is empty (default: 6)
+
+  ## Returns
+  - `{:ok, bucket}` with the bucket resource
+  - `{:error, reason}` if the options are inconsistent
+  """
+  @spec pricing_bucket_new(keyword()) :: {:ok, reference()} | {:error, String.t()}
+  def pricing_bucket_new(_opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Atomically applies an optional proof to a bucket and charges `cost` tokens.
+
+  A proof is a nonce for the bucket's current challenge at its current
+  difficulty, as found by `compute(quote.challenge, quote.difficulty)`. An
+  accepted proof credits tokens and rotates the challenge, so it can't be
+  replayed; an invalid one denies the request without charging it.
+
+  ## Returns
+  `{decision, quote}` where `decision` is `:allow` or `:deny` and `quote` holds
+  the remaining `:tokens`, the `:difficulty` now required and the `:challenge`
+  the next proof must solve.
+
+  ## Examples
+      iex> {:ok, bucket} = Powex.pricing_bucket_new(capacity: 2, refill_per_second: 0, max_difficulty: 3)
+      iex> {:allow, _} = Powex.price_request(bucket, 2)
+      iex> {:deny, quote} = Powex.price_request(bucket, 1)
+      iex> {:ok, nonce} = Powex.compute(quote.challenge, quote.difficulty)
+      iex> {decision, _} = Powex.price_request(bucket, 1, nonce)
+      iex> decision
+      :allow
+  """
+  @spec price_request(reference(), number(), non_neg_integer() | nil) ::
+          {:allow | :deny,
+           %{tokens: float(), difficulty: non_neg_integer(), challenge: binary()}}
+  def price_request(_bucket, _cost, _proof \\ nil), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Starts recording verifications by `valid?/3` and `decode_and_verify/2` in an
+  in-memory ring buffer, for export with `audit_export/1`.
+
+  Each entry holds the BLAKE3 digest of the proof (of `data <> <<nonce::64-little>>`
+  for `valid?/3`), its difficulty, a millisecond Unix timestamp and the outcome:
+  `:valid`, `:invalid`, or `:rejected` for malformed proofs and policy
+  violations. Proof data itself is never kept. Once full, the oldest entries are
+  dropped. Recording is off by default and costs nothing while off.
+
+  ## Options
+  - `:capacity` - Entries kept (1-1000000, default: 10000)
+  """
+  @spec audit_enable(keyword()) :: :ok | {:error, String.t()}
+  def audit_enable(_opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Stops recording verifications and discards the recorded entries.
+  """
+  @spec audit_disable() :: :ok
+  def audit_disable(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Exports the recorded verifications, oldest first.
+
+  As CSV, the output starts with a `hash,difficulty,timestamp_ms,outcome` header
+  and hashes are lowercase hex. As a binary, each entry is a 45-byte record
+  `<<hash::binary-32, difficulty::32, timestamp_ms::64, outcome::8>>` with
+  outcome 0 for `:valid`, 1 for `:invalid` and 2 for `:rejected`.
+
+  ## Options
+  - `:format` - `:csv` (default) or `:binary`
+  - `:limit` - Most entries to export
+  - `:max_bytes` - Largest output; the CSV header always counts toward it
+  - `:redact` - Fields to zero out: any of `:hash`, `:difficulty` and `:timestamp`
+
+  When a limit applies, the oldest entries are left out.
+  """
+  @spec audit_export(keyword()) :: binary()
+  def audit_export(_opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Records a solve time measured outside the NIF, such as the time between
+  issuing a challenge and receiving its proof, for `solve_time_stats/1`.
+
+  `compute/2` and `compute_parallel/4` record their own successful searches.
+  """
+  @spec solve_time_record(difficulty(), number()) :: :ok
+  def solve_time_record(_difficulty, _solve_ms), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Summarizes the recent solve times of a difficulty.
+
+  The NIF keeps the last 512 solves of each of the 64 most recently solved
+  difficulties, measured with a monotonic clock. Percentiles use the nearest
+  rank.
+
+  ## Returns
+  A map with `:count`, `:min_ms`, `:max_ms`, `:mean_ms`, `:p50_ms`, `:p90_ms`
+  and `:p99_ms`, or `nil` if no solve of the difficulty was recorded.
+  """
+  @spec solve_time_stats(difficulty()) ::
+          %{
+            count: pos_integer(),
+            min_ms: float(),
+            max_ms: float(),
+            mean_ms: float(),
+            p50_ms: float(),
+            p90_ms: float(),
+            p99_ms: float()
+          }
+          | nil
+  def solve_time_stats(_difficulty), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Creates a closed-loop difficulty controller steering solve times toward
+  `target_solve_ms`, with a difficulty between `min_bits` and `max_bits` leading
+  zero bits.
+
+  Observed solve times are smoothed with an exponentially weighted moving
+  average. Each observation moves the difficulty by `:gain` bits per doubling of
+  the ratio between the target and the average, so it settles where the average
+  solve time meets the target and follows solvers as they get faster or slower.
+
+  ## Options
+  - `:initial` - Starting difficulty in zero bits (default: `min_bits`)
+  - `:alpha` - Weight of the newest observation in the average, in (0, 1] (default: 0.2)
+  - `:gain` - Bits moved per doubling of the error (default: 0.5)
+
+  ## Returns
+  - `{:ok, controller}` with the controller resource
+  - `{:error, reason}` for invalid bounds or options
+  """
+  @spec controller_new(number(), {non_neg_integer(), non_neg_integer()}, keyword()) ::
+          {:ok, reference()} | {:error, String.t()}
+  def controller_new(_target_solve_ms, _bounds, _opts \\ []),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Feeds an observed solve time, in milliseconds, to a controller and returns the
+  updated difficulty as `{:zero_bits, n}`.
+  """
+  @spec controller_observe(reference(), number()) :: {:zero_bits, non_neg_integer()}
+  def controller_observe(_controller, _solve_ms), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns the difficulty a controller currently asks for, as `{:zero_bits, n}`,
+  ready for `compute/2` and `valid?/3`.
+
+  ## Examples
+      iex> {:ok, controller} = Powex.controller_new(1_000, {8, 24})
+      iex> Powex.controller_difficulty(controller)
+      {:zero_bits, 8}
+  """
+  @spec controller_difficulty(reference()) :: {:zero_bits, non_neg_integer()}
+  def controller_difficulty(_controller), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts a difficulty from one algorithm to another so the expected solve
+  time stays the same.
+
+  Each algorithm's single-thread hashrate is measured on this machine the first
+  time it's converted (about 50ms) and remembered. The expected number of hashes
+  of `difficulty` is scaled by the ratio of the hashrates and rounded to the
+  nearest difficulty: integer hex-zero difficulties convert to hex zeros, and
+  every other form to `{:zero_bits, n}`.
+
+  ## Examples
+      iex> Powex.equivalent_difficulty(:sha256, 4, :sha256)
+      4
+  """
+  @spec equivalent_difficulty(algorithm(), difficulty(), algorithm()) ::
+          non_neg_integer() | {:zero_bits, non_neg_integer()}
+  def equivalent_difficulty(_from, _difficulty, _to), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns the measured single-thread hashrate of an algorithm in hashes per
+  second, as used by `equivalent_difficulty/3`.
+  """
+  @spec algorithm_hashrate(algorithm()) :: float()
+  def algorithm_hashrate(_algorithm), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Describes exactly how a mode hashes its input, as a map other implementations
+  can follow: which byte strings are concatenated in which order, the nonce width
+  and byte order, the digest function and encoding, and how difficulties compare.
+
+  ## Parameters
+  - `mode`: `:sha256`, `:blake3`, `:sha512_256`, `:sha384`, `:poseidon`, `:hash160`,
+    `:k12`, `:turboshake128`, `:yespower`, `:blake2b`, `:argon2id`, `:header` or `:proof`
+
+  ## Returns
+  - A map with `:input`, `:nonce`, `:digest`, `:difficulty` and `:parameters` keys
+    (`:proof` describes its binary `:layout` instead)
+
+  ## Examples
+      iex> spec = Powex.encoding_spec(:sha256)
+      iex> {spec.input, spec.nonce.width, spec.nonce.byte_order, spec.digest.encoding}
+      {[:data, :nonce], 8, :little, :hex_lowercase}
+  """
+  @spec encoding_spec(:sha256 | :blake3 | :sha512_256 | :sha384 | :poseidon | :hash160 | :k12 |
+    :turboshake128 | :yespower | :blake2b | :argon2id | :header | :proof) :: map()
+  def encoding_spec(_mode), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Renders the internal counters in the Prometheus text exposition format.
+
+  The output can be served as-is by a metrics endpoint with content type
+  `text/plain; version=0.0.4`.
+
+  ## Metrics
+  - `powex_hashes_total` - Hashes computed by searches
+  - `powex_hashrate_ema` - Hashes per second, exponential moving average over one minute
+  - `powex_jobs_started_total` / `powex_jobs_completed_total` - Async and daemon jobs
+  - `powex_jobs_coalesced_total` - Async jobs that joined an identical search
+  - `powex_searches_in_flight` - Async searches currently running
+  - `powex_queue_depth` - Work waiting in daemon queues
+  - `powex_cache_hits_total` / `powex_cache_misses_total` / `powex_cache_entries` - The `valid?/3` cache
+  """
+  @spec metrics_prometheus() :: String.t()
+  def metrics_prometheus(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Registers the process receiving structured log events from the NIF.
+
+  Events arrive as `{:powex_log, level, event, metadata}`, where `level` is a
+  `Logger` level, `event` an atom such as `:job_started`, `:work_finished`,
+  `:daemon_started`, `:power_source_changed` or `:thermal_backoff`, and
+  `metadata` a map. Failed searches are reported at
+  `:warning` level with their `:reason`. `Powex.LogSink` forwards them to `Logger`.
+
+  ## Parameters
+  - `pid`: Process receiving the events, or `nil` to stop emitting them
+  - `level`: Lowest level emitted: `:debug`, `:info`, `:warning` or `:error`
+
+  ## Returns
+  - `:ok`
+  """
+  @spec set_log_sink(pid() | nil, :debug | :info | :warning | :error) :: :ok
+  def set_log_sink(_pid, _level), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Applies global settings.
+
+  ## Parameters
+  - `opts`: Keyword list of options
+    - `:profile` - `:default`, or `:low_memory` for Raspberry Pi-class devices:
+      the `valid?/3` cache keeps 1024 entries instead of 16384, and background
+      workers run on 256 KiB stacks
+      `:mobile` is for phones, e.g. apps built with Elixir Desktop: it has the
+      sizes of `:low_memory`, caps `compute_parallel/4` and daemons at two
+      threads, and holds daemon workers and `compute_async/3` jobs to half the
+      time. See also `pause_background/0`
+    - `:max_data_size` - Largest data, in bytes, accepted by the search and
+      validation functions (default: 64 MiB). Larger data raises `ErlangError`
+      with `{:data_too_large, limit}`
+    - `:max_verify_bytes` - Most bytes a single `decode_and_verify/2` or
+      `valid_memory_hard?/4` call may hash (default: 64 MiB)
+    - `:max_verify_memory` - Most memory, in bytes, a single verification may
+      allocate (default: 256 MiB). Both are checked against the declared
+      parameters before any work, and exceeding them returns `{:error, :over_budget}`
+    - `:numa` - Pins background workers (daemon workers and async jobs) to the
+      NUMA nodes of `numa_topology/0` in turn, so each worker's scratch memory is
+      allocated on its local node and workers are balanced across nodes. Applies
+      to workers started afterwards, and does nothing on single-node machines
+      (default: `false`)
+    - `:clock` - Time used by expiry checks without a `:now` option of their
+      own (`token_verify/3`, `validate_timestamp/3`, `current_challenge_params/1`)
+      and by `token_encode/1` timestamps: `:system` (the default), or a Unix
+      time in seconds that stays fixed until set again, for deterministic tests
+    - `:clock_offset` - Seconds added to that time, possibly negative, e.g. to
+      correct a known skew while NTP disciplines the host clock (default: `0`)
+    - `:entropy` - Source of the random bytes of `generate_challenge/1` and
+      the other challenge issuers: `:os` for the operating system's CSPRNG (the
+      default), or `{:seeded, seed}` for a deterministic stream of `seed`, for
+      reproducible tests only. Setting a seed again restarts its stream
+
+  ## Returns
+  - `:ok`
+
+  ## Examples
+      iex> Powex.configure(profile: :low_memory)
+      :ok
+      iex> Powex.configure(profile: :default)
+      :ok
+  """
+  @spec configure(keyword()) :: :ok
+  def configure(_opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns the Unix time in seconds that expiry checks currently use: the
+  `:clock` of `configure/1` plus its `:clock_offset`.
+
+  ## Examples
+      iex> Powex.configure(clock: 1_700_000_000, clock_offset: 60)
+      :ok
+      iex> Powex.clock_now()
+      1_700_000_060
+      iex> Powex.configure(clock: :system, clock_offset: 0)
+      :ok
+  """
+  @spec clock_now() :: non_neg_integer()
+  def clock_now(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Pauses background work: daemon workers and `compute_async/3` jobs stop at
+  their next batch of nonces until `resume_background/0`.
+
+  Meant for the lifecycle hooks of mobile apps, which shouldn't hash while in
+  the background. Stopping a daemon while paused fails its queued work, but
+  searches already running finish after resuming.
+
+  ## Returns
+  - `:ok`
+  """
+  @spec pause_background() :: :ok
+  def pause_background(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Resumes background work paused by `pause_background/0`.
+
+  ## Returns
+  - `:ok`
+  """
+  @spec resume_background() :: :ok
+  def resume_background(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Tells whether background work is paused by `pause_background/0`.
+
+  ## Examples
+      iex> Powex.background_paused?()
+      false
+  """
+  @spec background_paused?() :: boolean()
+  def background_paused?(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Reports where the machine draws its power from.
+
+  The power source is read from `/sys/class/power_supply` on Linux. Machines
+  without that information (desktops, servers and other operating systems)
+  report `:unknown`, which power policies treat like AC power.
+
+  ## Returns
+  - `:ac`, `:battery` or `:unknown`
+  """
+  @spec power_source() :: :ac | :battery | :unknown
+  def power_source(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Reports the hottest CPU temperature sensor in degrees Celsius.
+
+  Temperatures are read from `/sys/class/thermal` on Linux, including Nerves
+  targets. Returns `nil` where no sensor is readable, in which case
+  `:max_temperature` never throttles.
+  """
+  @spec cpu_temperature() :: float() | nil
+  def cpu_temperature(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Queues a search on a daemon; the result is sent to `pid` as `{:powex_daemon, id, result}`.
+
+  `result` is what `compute/2` would have returned, or `{:error, :stopped}` if
+  the daemon stopped before the work started.
+
+  ## Parameters
+  - `daemon`: Handle returned by `daemon_start/1`
+  - `data`: The input data (string or binary) to hash
+  - `difficulty`: Number of leading zeros required in the hash (integer), or a
+    64-character hex target that the hash must not exceed
+  - `pid`: Process receiving the result
+  - `opts`: Keyword list of options
+    - `:algorithm` - See `t:algorithm/0` (default: `:sha256`)
+    - `:priority` - Integer priority; higher runs first, equal priorities in submission order (default: `0`)
+
+  ## Returns
+  - `{:ok, id}` with the id tagging the result message
+  - `{:error, :stopped}` if the daemon was stopped
+  """
+  @spec enqueue(reference(), binary(), difficulty(), pid(), keyword()) ::
+    {:ok, non_neg_integer()} | {:error, :stopped}
+  def enqueue(_daemon, _data, _difficulty, _pid, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Stops a daemon. Queued work fails with `{:error, :stopped}`; searches already running finish first.
+  """
+  @spec daemon_stop(reference()) :: :ok
+  def daemon_stop(_daemon), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Gets the state of a daemon.
+
+  ## Returns
+  - `%{workers: workers, queued: queued, running: running, completed: completed, stopped: stopped}`
+  """
+  @spec daemon_stats(reference()) :: %{
+    workers: pos_integer(),
+    queued: non_neg_integer(),
+    running: non_neg_integer(),
+    completed: non_neg_integer(),
+    stopped: boolean()
+  }
+  def daemon_stats(_daemon), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Computes a nonce whose hash meets the difficulty under two hash algorithms simultaneously.
+
+  The same `(data, nonce)` pair must satisfy the difficulty under both algorithms,
+  which raises the bar for attackers with single-algorithm hardware.
+
+  ## Parameters
+  - `data`: The input data (string or binary) to hash
+  - `difficulty`: Number of leading zeros required in each hash (integer)
+  - `algorithms`: Tuple of two distinct algorithms, see `t:algorithm/0` (default: `{:sha256, :blake3}`)
+
+  ## Returns
+  - `{:ok, nonce}` when a valid nonce is found
+  - `{:error, reason}` if computation fails
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute_dual("dual data", 1)
+      iex> Powex.valid_dual?("dual data", nonce, 1)
+      true
+  """
+  @spec compute_dual(binary(), non_neg_integer(), {atom(), atom()}) ::
+    {:ok, non_neg_integer()} | {:error, String.t()}
+  def compute_dual(_data, _difficulty, _algorithms \\ {:sha256, :blake3}),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Validates that a nonce meets the difficulty under both hash algorithms.
+
+  ## Parameters
+  - `data`: The input data (string or binary) that was hashed
+  - `nonce`: The nonce value to validate (integer)
+  - `difficulty`: Number of leading zeros required in each hash (integer)
+  - `algorithms`: Tuple of two distinct algorithms (default: `{:sha256, :blake3}`)
+
+  ## Returns
+  - `true` if the nonce is valid under both algorithms
+  - `false` otherwise
+
+  ## Examples
+      iex> Powex.valid_dual?("dual data", 0, 10)
+      false
+  """
+  @spec valid_dual?(binary(), non_neg_integer(), non_neg_integer(), {atom(), atom()}) :: boolean()
+  def valid_dual?(data, nonce, difficulty, algorithms \\ {:sha256, :blake3}) do
+    case hash_scheduler(data) do
+      :normal -> valid_dual_normal?(data, nonce, difficulty, algorithms)
+      :dirty -> valid_dual_dirty?(data, nonce, difficulty, algorithms)
+    end
+  end
+
+  @doc false
+  def valid_dual_normal?(_data, _nonce, _difficulty, _algorithms),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def valid_dual_dirty?(_data, _nonce, _difficulty, _algorithms),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Creates a sequential time-lock witness for the given data (sloth-style).
+
+  Each iteration is a modular square root that depends on the previous one, so
+  the work cannot be parallelized: the time taken is roughly the same on a
+  laptop and on a datacenter. Verification only needs one squaring per iteration.
+
+  ## Parameters
+  - `data`: The input data (string or binary) seeding the chain
+  - `iterations`: Number of sequential steps (positive integer)
+
+  ## Returns
+  - `{:ok, witness}` where `witness` is a 32-byte binary
+  - `{:error, reason}` if the parameters are invalid
+
+  ## Examples
+      iex> {:ok, witness} = Powex.timelock_create("fair launch", 100)
+      iex> Powex.timelock_verify("fair launch", 100, witness)
+      true
+  """
+  @spec timelock_create(binary(), pos_integer()) :: {:ok, binary()} | {:error, String.t()}
+  def timelock_create(_data, _iterations), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies a sequential time-lock witness created by `timelock_create/2`.
+
+  ## Parameters
+  - `data`: The input data (string or binary) seeding the chain
+  - `iterations`: Number of sequential steps the witness claims
+  - `witness`: The 32-byte witness binary
+
+  ## Returns
+  - `true` if the witness is valid for the data and iterations
+  - `false` otherwise
+
+  ## Examples
+      iex> Powex.timelock_verify("fair launch", 100, <<0::256>>)
+      false
+  """
+  @spec timelock_verify(binary(), pos_integer(), binary()) :: boolean()
+  def timelock_verify(_data, _iterations, _witness), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Evaluates a Wesolowski verifiable delay function over the RSA-2048 group.
+
+  Computes `y = g^(2^iterations)` where `g` is derived from the data, together
+  with a proof that lets anyone check the result without repeating the squarings.
+
+  ## Parameters
+  - `data`: The input data (string or binary) the evaluation is bound to
+  - `iterations`: Number of sequential squarings (positive integer)
+
+  ## Returns
+  - `{:ok, {output, proof}}` where both are 256-byte binaries
+  - `{:error, reason}` if the parameters are invalid
+
+  ## Examples
+      iex> {:ok, {output, proof}} = Powex.vdf_eval("leader election", 1000)
+      iex> Powex.vdf_verify("leader election", 1000, {output, proof})
+      true
+  """
+  @spec vdf_eval(binary(), pos_integer()) :: {:ok, {binary(), binary()}} | {:error, String.t()}
+  def vdf_eval(_data, _iterations), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Verifies a VDF output and proof produced by `vdf_eval/2`.
+
+  Verification costs two exponentiations by ~128-bit exponents, independent of `iterations`.
+
+  ## Parameters
+  - `data`: The input data (string or binary) the evaluation is bound to
+  - `iterations`: Number of sequential squarings claimed
+  - `output`: The `{output, proof}` tuple returned by `vdf_eval/2`
+
+  ## Returns
+  - `true` if the output and proof are valid
+  - `false` otherwise
+  """
+  @spec vdf_verify(binary(), pos_integer(), {binary(), binary()}) :: boolean()
+  def vdf_verify(_data, _iterations, _output), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Seals a secret in a Rivest-Shamir-Wagner time-lock puzzle.
+
+  Recovering the secret requires `squarings` sequential modular squarings, so it
+  stays sealed until roughly that much sequential work has been spent. Creating
+  the puzzle is fast because the creator knows the factorization of the modulus.
+
+  ## Parameters
+  - `secret`: The binary to seal
+  - `squarings`: Number of sequential squarings required to solve (positive integer)
+  - `modulus_bits`: RSA modulus size, an even number between 512 and 4096 (default: 2048)
+
+  ## Returns
+  - `{:ok, puzzle}` where `puzzle` is a map with `:modulus`, `:base`, `:squarings`, `:ciphertext` and `:tag`
+  - `{:error, reason}` if the parameters are invalid
+
+  ## Examples
+      iex> {:ok, puzzle} = Powex.timelock_encrypt("sealed", 1000, 512)
+      iex> Powex.timelock_solve(puzzle)
+      {:ok, "sealed"}
+  """
+  @spec timelock_encrypt(binary(), pos_integer(), pos_integer()) :: {:ok, map()} | {:error, String.t()}
+  def timelock_encrypt(_secret, _squarings, _modulus_bits \\ 2048),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Solves a time-lock puzzle created by `timelock_encrypt/3`.
+
+  When a `progress` pid is given, it receives roughly one hundred
+  `{:powex_timelock_progress, done, total}` messages during the solve.
+
+  ## Parameters
+  - `puzzle`: The puzzle map returned by `timelock_encrypt/3`
+  - `progress`: Optional pid receiving progress messages (default: `nil`)
+
+  ## Returns
+  - `{:ok, secret}` with the recovered secret
+  - `{:error, reason}` if the puzzle is malformed or was tampered with
+  """
+  @spec timelock_solve(map(), pid() | nil) :: {:ok, binary()} | {:error, String.t()}
+  def timelock_solve(_puzzle, _progress \\ nil), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Computes the Merkle root of a list of leaves.
+
+  Leaves are hashed as `SHA-256(0x00 || leaf)` and nodes as `SHA-256(0x01 || left || right)`;
+  an odd node at the end of a level is paired with itself.
+
+  ## Parameters
+  - `leaves`: A non-empty list of binaries
+
+  ## Returns
+  - `{:ok, root}` where `root` is a 32-byte binary
+  - `{:error, reason}` if the list is empty
+
+  ## Examples
+      iex> {:ok, root} = Powex.merkle_root(["a", "b", "c"])
+      iex> byte_size(root)
+      32
+  """
+  @spec merkle_root([binary()]) :: {:ok, binary()} | {:error, String.t()}
+  def merkle_root(_leaves), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Computes a Proof of Work over any leaf of a Merkle tree.
+
+  The proof hash is `SHA-256(root || leaf || nonce)`, and the solver may pick
+  whichever leaf reaches the difficulty first, so work can target any shard.
+
+  ## Parameters
+  - `leaves`: A non-empty list of binaries forming the tree
+  - `difficulty`: Number of leading zeros required in the hash (integer)
+
+  ## Returns
+  - `{:ok, {leaf_index, nonce, path}}` where `path` is a list of `{:left | :right, sibling}` tuples
+  - `{:error, reason}` if computation fails
+
+  ## Examples
+      iex> leaves = ["shard 0", "shard 1", "shard 2"]
+      iex> {:ok, root} = Powex.merkle_root(leaves)
+      iex> {:ok, {index, nonce, path}} = Powex.compute_merkle(leaves, 2)
+      iex> Powex.valid_merkle?(root, Enum.at(leaves, index), nonce, path, 2)
+      true
+  """
+  @spec compute_merkle([binary()], non_neg_integer()) ::
+    {:ok, {non_neg_integer(), non_neg_integer(), [{:left | :right, binary()}]}} | {:error, String.t()}
+  def compute_merkle(_leaves, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Validates a Merkle leaf Proof of Work against the tree root.
+
+  Checks both that the path links the leaf to the root and that the proof hash meets the difficulty.
+
+  ## Parameters
+  - `root`: The 32-byte Merkle root (the challenge)
+  - `leaf`: The leaf the proof was computed for
+  - `nonce`: The nonce value to validate (integer)
+  - `path`: The Merkle path returned by `compute_merkle/2`
+  - `difficulty`: Number of leading zeros required in the hash (integer)
+
+  ## Returns
+  - `true` if both the path and the hash are valid
+  - `false` otherwise
+  """
+  @spec valid_merkle?(binary(), binary(), non_neg_integer(), [{:left | :right, binary()}], non_neg_integer()) ::
+    boolean()
+  def valid_merkle?(_root, _leaf, _nonce, _path, _difficulty), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Commits to many proofs with a single Merkle root.
+
+  The root is the same as `merkle_root/1` of the proofs, so one 32-byte value
+  stored on-chain or in a log summarizes thousands of per-request proofs. Each
+  proof's path lets anyone check its membership with `verify_aggregate/3`.
+
+  ## Returns
+  - `{:ok, {root, paths}}` with one `{:left | :right, sibling}` path per proof,
+    in the order given
+  - `{:error, reason}` if the list is empty
+
+  ## Examples
+      iex> proofs = ["proof 1", "proof 2", "proof 3"]
+      iex> {:ok, {root, paths}} = Powex.aggregate_proofs(proofs)
+      iex> Powex.verify_aggregate("proof 2", Enum.at(paths, 1), root)
+      true
+  """
+  @spec aggregate_proofs([binary()]) ::
+    {:ok, {binary(), [[{:left | :right, binary()}]]}} | {:error, String.t()}
+  def aggregate_proofs(_proofs), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks that a proof is a member of an aggregate root, given its path from
+  `aggregate_proofs/1`.
+  """
+  @spec verify_aggregate(binary(), [{:left | :right, binary()}], binary()) :: boolean()
+  def verify_aggregate(_member_proof, _path, _root), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Accepts a set of proofs whose combined work reaches a threshold.
+
+  Each `{data, nonce}` proof is hashed as by `compute/2` and counts for
+  `2^n` expected hashes, where `n` is the number of leading zero bits of its
+  hash. The proofs pass if the sum reaches `2^min_total_bits`, so a policy can
+  ask for "2^24 hashes of work" however the client splits it between puzzles.
+  Repeated proofs count once; binding each `data` to an issued challenge is up
+  to the caller.
+
+  ## Examples
+      iex> {:ok, nonce} = Powex.compute("total work", {:zero_bits, 8})
+      iex> Powex.verify_total_work([{"total work", nonce}], 8)
+      true
+      iex> Powex.verify_total_work([{"total work", nonce}, {"total work", nonce}], 64)
+      false
+  """
+  @spec verify_total_work([{binary(), non_neg_integer()}], number()) :: boolean()
+  def verify_total_work(_proofs, _min_total_bits), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Computes a deterministic, non-overlapping share of the nonce space for one worker.
+
+  ## Parameters
+  - `num_workers`: Total number of workers (positive integer)
+  - `worker_index`: Index of this worker, from `0` to `num_workers - 1`
+  - `opts`: Keyword list of options
+    - `:mode` - `:contiguous` (one block per worker, default) or `:strided` (interleaved nonces)
+    - `:start` - First nonce of the space to split (default: `0`)
+    - `:end` - Exclusive end of the space to split (default: `2^64 - 1`)
+
+  ## Returns
+  - `{:ok, %{start: start, end: end, stride: stride}}` describing the nonces `start`, `start + stride`, ... below `end`
+  - `{:error, reason}` if the parameters are invalid
+
+  ## Examples
+      iex> Powex.partition_space(4, 1, start: 0, end: 1000)
+      {:ok, %{start: 250, end: 500, stride: 1}}
+
+      iex> Powex.partition_space(4, 1, mode: :strided, end: 1000)
+      {:ok, %{start: 1, end: 1000, stride: 4}}
+  """
+  @spec partition_space(pos_integer(), non_neg_integer(), keyword()) ::
+    {:ok, %{start: non_neg_integer(), end: non_neg_integer(), stride: pos_integer()}} | {:error, String.t()}
+  def partition_space(_num_workers, _worker_index, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Assigns a node a strided share of the nonce space from its identifier alone.
+
+  The offset is a jump consistent hash of the node id into `cluster_size`
+  slots: every node computes the same assignment without a coordinator, and
+  growing the cluster by one node moves only about `1 / cluster_size` of the
+  nodes to a new offset. Distinct ids may still share an offset.
+
+  ## Parameters
+  - `node_id`: Any term identifying the node
+  - `cluster_size`: Number of slots to split the nonce space into
+
+  ## Returns
+  - `{:ok, %{start: offset, end: end, stride: cluster_size}}` describing the
+    nonces `offset`, `offset + cluster_size`, ... below `end`
+  - `{:error, reason}` if the cluster size is invalid
+
+  ## Examples
+      iex> {:ok, %{start: offset, stride: 8}} = Powex.assigned_stride(:"node@host", 8)
+      iex> offset in 0..7
+      true
+  """
+  @spec assigned_stride(term(), pos_integer()) ::
+    {:ok, %{start: non_neg_integer(), end: non_neg_integer(), stride: pos_integer()}} | {:error, String.t()}
+  def assigned_stride(_node_id, _cluster_size), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Creates a shared pool handing out chunks of the nonce space to coordinating workers.
+
+  The pool is a resource that can be shared between processes: workers claim
+  ranges with `range_claim/1` and hand unfinished ones back with `range_release/2`.
+
+  ## Parameters
+  - `start`: First nonce of the pool
+  - `end`: Exclusive end of the pool
+  - `chunk_size`: Number of nonces per claimed range
+
+  ## Returns
+  - `{:ok, pool}` with the pool resource
+  - `{:error, reason}` if the parameters are invalid
+
+  ## Examples
+      iex> {:ok, pool} = Powex.range_pool_new(0, 250, 100)
+      iex> Powex.range_claim(pool)
+      {:ok, {0, 100}}
+  """
+  @spec range_pool_new(non_neg_integer(), non_neg_integer(), pos_integer()) ::
+    {:ok, reference()} | {:error, String.t()}
+  def range_pool_new(_start, _end, _chunk_size), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Claims the next free range from a pool, preferring previously released ranges.
+
+  ## Returns
+  - `{:ok, {start, end}}` with the claimed half-open range
+  - `{:error, :exhausted}` when the whole space has been handed out
+  """
+  @spec range_claim(reference()) :: {:ok, {non_neg_integer(), non_neg_integer()}} | {:error, :exhausted}
+  def range_claim(_pool), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns an unfinished range to the pool so another worker can claim it.
+
+  ## Returns
+  - `:ok`
+  """
+  @spec range_release(reference(), {non_neg_integer(), non_neg_integer()}) :: :ok
+  def range_release(_pool, _range), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Publishes a job to the in-NIF work ledger, replacing the current one.
+
+  Together with `get_work/1` and `submit_work/2` this is the backend of a
+  minimal mining pool: workers lease nonce ranges and submit solutions.
+
+  ## Parameters
+  - `challenge`: The data workers hash
+  - `difficulty`: Number of leading zeros required in the hash (integer)
+  - `opts`: Keyword list of options
+    - `:chunk_size` - Number of nonces per lease (default: `1_000_000`)
+    - `:lease_ms` - Lease lifetime in milliseconds before the range returns to the pool,
+      renewed by `heartbeat_work/2` (default: `30_000`)
+
+  ## Returns
+  - `{:ok, job_id}` with the new job's id
+  - `{:error, reason}` if the parameters are invalid
+  """
+  @spec put_work(binary(), non_neg_integer(), keyword()) :: {:ok, pos_integer()} | {:error, String.t()}
+  def put_work(_challenge, _difficulty, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Leases the next nonce range of the current job to a worker.
+
+  A worker calling again is assumed to have finished its previous range.
+  Ranges of expired leases are handed out again before fresh ones.
+
+  ## Parameters
+  - `worker_id`: Any term identifying the worker
+
+  ## Returns
+  - `{:ok, %{job_id: id, challenge: data, difficulty: d, start: start, end: end}}`
+  - `{:error, :no_work}` if no job was published
+  - `{:error, :exhausted}` if the whole nonce space was handed out
+  """
+  @spec get_work(term()) :: {:ok, map()} | {:error, :no_work | :exhausted}
+  def get_work(_worker_id), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Renews a worker's lease for another `:lease_ms`.
+
+  Workers that stop sending heartbeats lose their lease once it expires, and
+  its range is handed out again. With `progress`, the nonces of the range below
+  it count as searched, so only the rest returns to the pool.
+
+  ## Parameters
+  - `worker_id`: The term identifying the worker
+  - `progress`: Optional first nonce of the range not searched yet
+
+  ## Returns
+  - `:ok` if the lease was renewed
+  - `{:error, :no_lease}` if the worker holds no lease, or it already expired
+  - `{:error, :no_work}` if no job was published
+  """
+  @spec heartbeat_work(term(), non_neg_integer() | nil) :: :ok | {:error, :no_lease | :no_work}
+  def heartbeat_work(_worker_id, _progress \\ nil), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Validates a worker's solution for the current job and records it.
+
+  ## Parameters
+  - `worker_id`: The term identifying the worker
+  - `nonce`: The nonce found by the worker
+
+  ## Returns
+  - `:ok` if the solution is valid and was recorded
+  - `{:error, :invalid}` if the nonce does not meet the difficulty
+  - `{:error, :duplicate}` if the nonce was already submitted
+  - `{:error, :no_work}` if no job was published
+  """
+  @spec submit_work(term(), non_neg_integer()) :: :ok | {:error, :invalid | :duplicate | :no_work}
+  def submit_work(_worker_id, _nonce), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Reports the current job of the work ledger.
+
+  ## Returns
+  - `{:ok, %{job_id: id, difficulty: d, leases: count, solutions: [{worker_id, nonce}]}}`
+  - `{:error, :no_work}` if no job was published
+  """
+  @spec work_status() :: {:ok, map()} | {:error, :no_work}
+  def work_status(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Assembles an 80-byte Bitcoin-style block header from its fields.
+
+  Hashes are given in internal (serialized) byte order, i.e. reversed compared
+  to the usual hex display.
+
+  ## Parameters
+  - `fields`: Map or keyword list with `:version`, `:prev_hash` (32 bytes),
+    `:merkle_root` (32 bytes), `:timestamp`, `:bits` and optionally `:nonce` (default: `0`)
+
+  ## Returns
+  - `{:ok, header}` with the 80-byte header
+  - `{:error, reason}` if fields are missing or invalid
+
+  ## Examples
+      iex> {:ok, header} = Powex.header_assemble(version: 1, prev_hash: <<0::256>>, merkle_root: <<0::256>>, timestamp: 0, bits: 0x1D00FFFF)
+      iex> byte_size(header)
+      80
+  """
+  @spec header_assemble(map() | keyword()) :: {:ok, binary()} | {:error, String.t()}
+  def header_assemble(_fields), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Decodes an 80-byte header into a map of its fields.
+
+  ## Returns
+  - `{:ok, %{version: v, prev_hash: h, merkle_root: r, timestamp: t, bits: b, nonce: n}}`
+  - `{:error, reason}` if the header is not 80 bytes
+  """
+  @spec header_parse(binary()) :: {:ok, map()} | {:error, String.t()}
+  def header_parse(_header), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns a copy of the header with the given fields replaced.
+
+  ## Parameters
+  - `header`: The 80-byte header
+  - `fields`: Map or keyword list with any of the fields accepted by `header_assemble/1`
+
+  ## Returns
+  - `{:ok, header}` with the updated header
+  - `{:error, reason}` if the header or fields are invalid
+  """
+  @spec header_update(binary(), map() | keyword()) :: {:ok, binary()} | {:error, String.t()}
+  def header_update(_header, _fields), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Computes the double SHA-256 hash of a header, in internal byte order.
+
+  ## Returns
+  - `{:ok, hash}` with the 32-byte hash
+  - `{:error, reason}` if the header is not 80 bytes
+  """
+  @spec header_hash(binary()) :: {:ok, binary()} | {:error, String.t()}
+  def header_hash(_header), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Rolls the header timestamp forward and resets the nonce, for when the 32-bit nonce space is exhausted.
+
+  ## Parameters
+  - `header`: The 80-byte header
+  - `seconds`: Number of seconds to add to the timestamp (default: `1`)
+
+  ## Returns
+  - `{:ok, header}` with the updated header
+  - `{:error, reason}` if the header is invalid or the timestamp overflows
+  """
+  @spec header_roll_time(binary(), non_neg_integer()) :: {:ok, binary()} | {:error, String.t()}
+  def header_roll_time(_header, _seconds \\ 1), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Increments the extranonce, recomputes the merkle root and resets the nonce.
+
+  The coinbase transaction is `coinbase1 <> extranonce <> coinbase2` and the
+  extranonce is incremented as a little-endian counter of the same width.
+
+  ## Parameters
+  - `header`: The 80-byte header
+  - `coinbase1`: Coinbase bytes before the extranonce
+  - `extranonce`: The current extranonce
+  - `coinbase2`: Coinbase bytes after the extranonce
+  - `branch`: Merkle branch hashes (32 bytes each) from the coinbase to the root
+
+  ## Returns
+  - `{:ok, {header, extranonce}}` with the updated header and the new extranonce
+  - `{:error, reason}` if the inputs are invalid or the extranonce space is exhausted
+  """
+  @spec header_roll_extranonce(binary(), binary(), binary(), binary(), [binary()]) ::
+    {:ok, {binary(), binary()}} | {:error, String.t()}
+  def header_roll_extranonce(_header, _coinbase1, _extranonce, _coinbase2, _branch),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Mines a Bitcoin-style header against the target encoded in its `bits` field.
+
+  The double SHA-256 hash, read as a little-endian integer, must not exceed the target.
+  When the nonce space is exhausted and an `:extranonce` context is given, the
+  extranonce is incremented and the merkle root recomputed from the coinbase and
+  branch, and the search continues without returning to Elixir.
+
+  ## Parameters
+  - `header`: The 80-byte header template
+  - `opts`: Keyword list of options
+    - `:extranonce` - `{coinbase1, extranonce, coinbase2, branch}` as accepted by `header_roll_extranonce/5`
+    - `:max_rolls` - Maximum number of extranonce increments (default: `1_000`)
+    - `:nonce_end` - Exclusive end of the nonce range searched per template (default: `2^32`)
+
+  ## Returns
+  - `{:ok, %{header: header, nonce: nonce, extranonce: extranonce | nil, hash: hash}}`
+  - `{:error, reason}` if the space is exhausted or the header is invalid
+
+  ## Examples
+      iex> {:ok, header} = Powex.header_assemble(version: 1, prev_hash: <<0::256>>, merkle_root: <<0::256>>, timestamp: 0, bits: 0x207FFFFF)
+      iex> {:ok, %{header: solved, hash: hash}} = Powex.compute_header(header)
+      iex> Powex.header_hash(solved)
+      {:ok, hash}
+  """
+  @spec compute_header(binary(), keyword()) :: {:ok, map()} | {:error, String.t()}
+  def compute_header(_header, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Validates a block timestamp with the median-time-past and max-future-drift rules.
+
+  The candidate must be strictly greater than the median of the last `:window`
+  timestamps, and at most `:max_future_drift` seconds ahead of the current time.
+
+  ## Parameters
+  - `timestamps`: Timestamps (Unix seconds) of the previous blocks, oldest first
+  - `candidate`: The timestamp to validate
+  - `opts`: Keyword list of options
+    - `:window` - Number of previous blocks in the median (default: `11`)
+    - `:max_future_drift` - Maximum seconds ahead of `:now` (default: `7200`)
+    - `:now` - Current Unix time in seconds (default: `clock_now/0`)
+
+  ## Returns
+  - `:ok` if the timestamp is acceptable
+  - `{:error, :too_old}` if it does not exceed the median time past
+  - `{:error, :too_far_in_future}` if it exceeds the allowed drift
+
+  ## Examples
+      iex> Powex.validate_timestamp([100, 200, 300], 250, now: 1_000)
+      :ok
+
+      iex> Powex.validate_timestamp([100, 200, 300], 200, now: 1_000)
+      {:error, :too_old}
+  """
+  @spec validate_timestamp([non_neg_integer()], non_neg_integer(), keyword()) ::
+    :ok | {:error, :too_old | :too_far_in_future}
+  def validate_timestamp(_timestamps, _candidate, _opts \\ []), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Estimates the aggregate network hashrate from recent blocks.
+
+  Each block's expected work is derived from its nBits target and summed over
+  the time span covered by the blocks. Block arrivals are treated as a Poisson
+  process to produce a 95% confidence interval.
+
+  ## Parameters
+  - `targets_and_timestamps`: List of `{bits, timestamp}` tuples, oldest first.
+    The first entry only anchors the start of the time span.
+
+  ## Returns
+  - `{:ok, %{hashrate: float, lower: float, upper: float, blocks: integer}}` in hashes per second
+  - `{:error, :insufficient_data}` if fewer than two entries or no time elapsed
+  - `{:error, :invalid}` if an nBits value is negative, overflowing or zero
+
+  ## Examples
+      iex> blocks = for i <- 0..10, do: {0x1d00ffff, i * 600}
+      iex> {:ok, %{hashrate: rate, blocks: 10}} = Powex.estimate_hashrate(blocks)
+      iex> round(rate)
+      7158388
+  """
+  @spec estimate_hashrate([{non_neg_integer(), non_neg_integer()}]) ::
+    {:ok, %{hashrate: float(), lower: float(), upper: float(), blocks: non_neg_integer()}} |
+    {:error, :insufficient_data | :invalid}
+  def estimate_hashrate(_targets_and_timestamps), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts a proof-of-work difficulty between representations.
+
+  Every conversion goes through a 256-bit target, so any pair of representations
+  can be used. `:target`, `:hex` and `:zero_bits` round-trip exactly, `:bits`
+  round-trips exactly for canonical compact values, and `:pool` and `:hashes`
+  round-trip within floating-point precision.
+
+  ## Representations
+  - `:target` - 32-byte big-endian binary
+  - `:hex` - 64-character hex string
+  - `:bits` - Compact nBits integer
+  - `:zero_bits` - Leading zero bits of the target (a hash meets the target
+    `2^(256 - n) - 1` exactly when it has at least `n` leading zero bits)
+  - `:pool` - Pool difficulty relative to the difficulty 1 target `0x1d00ffff`
+  - `:hashes` - Expected number of hashes to find a solution, `2^256 / (target + 1)`
+
+  ## Returns
+  - `{:ok, value}` in the requested representation
+  - `{:error, :invalid}` if the value is malformed or out of range
+
+  ## Examples
+      iex> Powex.convert_difficulty(0x1d00ffff, :bits, :hex)
+      {:ok, "00000000ffff0000000000000000000000000000000000000000000000000000"}
+
+      iex> Powex.convert_difficulty(0x1d00ffff, :bits, :pool)
+      {:ok, 1.0}
+
+      iex> Powex.convert_difficulty(20, :zero_bits, :bits)
+      {:ok, 0x1e0fffff}
+  """
+  @spec convert_difficulty(term(), atom(), atom()) :: {:ok, term()} | {:error, :invalid}
+  def convert_difficulty(_value, _from, _to), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Gets the hash for given data and nonce combination.
+
+  ## Parameters
+  - `data`: The input data (string or binary)
+  - `nonce`: The nonce value (integer)
+  - `opts`: Keyword list of options
+    - `:encoding` - `:hex` (default), `:hex_upper`, `:raw` (32-byte binary),
+      `:base64` or `:base58`
+    - `:multihash` - Prefix the digest with its multihash header (`0x12 0x20`
+      for SHA-256) before encoding, for content-addressed storage (default: `false`)
+
+  ## Returns
+  - `{:ok, hash}` where hash is the SHA-256 hash in the requested encoding
+  - `{:error, reason}` if hashing fails
+
+  ## Examples
+      iex> {:ok, hash} = Powex.get_hash("test", 123)
+      iex> String.length(hash)
+      64
+
+      iex> {:ok, hash} = Powex.get_hash("test", 123, encoding: :raw)
+      iex> byte_size(hash)
+      32
+
+      iex> {:ok, <<0x12, 0x20, digest::binary-size(32)>>} = Powex.get_hash("test", 123, encoding: :raw, multihash: true)
+      iex> {:ok, digest} == Powex.get_hash("test", 123, encoding: :raw)
+      true
+  """
+  @spec get_hash(binary(), non_neg_integer(), keyword()) :: {:ok, binary()} | {:error, String.t()}
+  def get_hash(data, nonce, opts \\ []) do
+    case hash_scheduler(data) do
+      :normal -> get_hash_normal(data, nonce, opts)
+      :dirty -> get_hash_dirty(data, nonce, opts)
+    end
+  end
+
+  @doc false
+  def get_hash_normal(_data, _nonce, _opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc false
+  def get_hash_dirty(_data, _nonce, _opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Gets the SHA-256 implementation used by `compute/2` and `compute_parallel/3`.
+
+  There is nothing to tune: `sha2` uses SHA-NI or the ARMv8 SHA-2 instructions
+  when the CPU has them, selected at runtime (see `cpu_features/0`), and falls
+  back to portable code otherwise. There is no GPU backend.
+
+  ## Returns
+  - `:sha_ni`, `:armv8` or `:soft`
+
+  ## Examples
+      iex> Powex.backend() in [:sha_ni, :armv8, :soft]
+      true
+  """
+  @spec backend() :: :sha_ni | :armv8 | :soft
+  def backend(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Reports the SIMD features the hashing code selects at runtime.
+
+  Every SIMD path (SHA-NI, SSE4.1, AVX2 and AVX-512 on x86; the SHA-2 and SHA-3
+  instructions on AArch64) is compiled into the NIF and chosen per CPU, so one
+  build made without `target-cpu` flags runs at full speed across a
+  heterogeneous fleet. A build that does require features, e.g. through
+  `RUSTFLAGS="-C target-cpu=native"`, refuses to run on a CPU lacking them
+  instead of crashing with an illegal instruction: every other function returns
+  `{:error, :unsupported_cpu}`, and `Powex.LogSink` logs the missing features
+  when it starts.
+
+  ## Returns
+  - `%{arch: arch, detected: [feature], required: [feature], missing: [feature]}`
+    with the architecture name, the features of this CPU, the features the build
+    requires and those of them the CPU lacks
+
+  ## Examples
+      iex> %{detected: detected, required: required, missing: missing} = Powex.cpu_features()
+      iex> {Enum.all?(required, &(&1 in detected)), missing}
+      {true, []}
+  """
+  @spec cpu_features() :: %{arch: String.t(), detected: [atom()], required: [atom()], missing: [atom()]}
+  def cpu_features(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Measures the hashrate with 1 up to `max_threads` threads to find the point of
+  diminishing returns for `compute_parallel/4`.
+
+  Each thread count is measured for `duration_ms`, so the call takes roughly
+  `max_threads * duration_ms` milliseconds.
+
+  ## Parameters
+  - `max_threads`: Largest thread count to measure (1-64)
+  - `duration_ms`: Measurement time per thread count in milliseconds
+
+  ## Returns
+  - `{:ok, points}` with one `%{threads, hashrate, speedup, efficiency}` map per
+    thread count, where `speedup` is relative to one thread and `efficiency`
+    is `speedup / threads`
+  - `{:error, reason}` for an invalid thread count
+
+  ## Examples
+      iex> {:ok, [%{threads: 1, speedup: 1.0}, %{threads: 2}]} = Powex.bench_scaling(2, 10)
   """
-  @spec get_hash(binary(), non_neg_integer()) :: {:ok, String.t()} | {:error, String.t()}
-  def get_hash(_data, _nonce), do: :erlang.nif_error(:nif_not_loaded)
+  @spec bench_scaling(pos_integer(), non_neg_integer()) ::
+    {:ok, [%{threads: pos_integer(), hashrate: float(), speedup: float(), efficiency: float()}]} |
+    {:error, String.t()}
+  def bench_scaling(_max_threads, _duration_ms), do: :erlang.nif_error(:nif_not_loaded)
 end
@@ -0,0 +1,68 @@
+defmodule Powex.LogSink do
+  @moduledoc """
+  Forwards the structured log events emitted by the NIF to `Logger`.
+
+  Events cover job and daemon lifecycles and throttling.
+  Each one is logged at its own level with its metadata, plus `:powex_event`
+  naming the event, so it can be filtered and formatted like any other log.
+
+  Add it to a supervision tree, optionally with the lowest level to forward:
+
+      children = [
+        {Powex.LogSink, level: :info}
+      ]
+
+  Only one process receives events at a time; see `Powex.set_log_sink/2`.
+
+  On a CPU lacking features the NIF was built to require, the sink logs them
+  as an error instead, since every other NIF call returns
+  `{:error, :unsupported_cpu}`; see `Powex.cpu_features/0`.
+  """
+
+  use GenServer
+  require Logger
+
+  @doc """
+  Starts the sink and registers it with the NIF.
+
+  ## Options
+  - `:level` - Lowest level forwarded: `:debug`, `:info` (default), `:warning` or `:error`
+  - `:name` - Process name (default: `Powex.LogSink`)
+  """
+  def start_link(opts \\ []) do
+    GenServer.start_link(__MODULE__, opts, name: Keyword.get(opts, :name, __MODULE__))
+  end
+
+  @impl true
+  def init(opts) do
+    Process.flag(:trap_exit, true)
+
+    case Powex.cpu_features() do
+      %{missing: []} ->
+        :ok = Powex.set_log_sink(self(), Keyword.get(opts, :level, :info))
+
+      %{missing: missing} ->
+        Logger.error("powex: the NIF was built for CPU features this machine lacks: #{inspect(missing)}",
+          powex_event: :unsupported_cpu
+        )
+    end
+
+    {:ok, nil}
+  end
+
+  @impl true
+  def handle_info({:powex_log, level, event, metadata}, state) do
+    Logger.log(level, fn -> format(event, metadata) end, [powex_event: event] ++ Map.to_list(metadata))
+    {:noreply, state}
+  end
+
+  @impl true
+  def terminate(_reason, _state) do
+    Powex.set_log_sink(nil, :info)
+  end
+
+  defp format(event, metadata) do
+    fields = Enum.map(metadata, fn {key, value} -> "#{key}=#{inspect(value)}" end)
+    Enum.join(["powex #{event}" | fields], " ")
+  end
+end
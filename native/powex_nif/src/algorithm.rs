@@ -0,0 +1,467 @@
+use ark_bn254::Fr;
+use ripemd::Ripemd160;
+use rustler::{Atom, Binary, Decoder, Encoder, Env, Error, NewBinary, NifResult, Term};
+use sha2::{Digest, Sha256, Sha384, Sha512_256};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{TurboShake128, TurboShake128Core};
+use std::cell::RefCell;
+use std::fmt;
+use zeroize::Zeroize;
+
+use crate::atoms;
+use crate::poseidon;
+#[cfg(feature = "yespower")]
+use crate::yespower;
+
+/// Digest length and optional key of a BLAKE2b hash. The key is zeroized on drop and
+/// left out of `Debug` output. `blake2b_simd` copies it into its parameter block and keeps
+/// keyed chaining values in its state; neither zeroizes itself, and those copies can't be
+/// wiped from here
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Blake2bParams {
+    size: u8,
+    key: [u8; 64],
+    key_len: u8,
+}
+
+impl Blake2bParams {
+    /// Unkeyed BLAKE2b-512
+    pub const DEFAULT: Blake2bParams = Blake2bParams {
+        size: 64,
+        key: [0; 64],
+        key_len: 0,
+    };
+
+    /// Checks the digest length (1-64 bytes) and key length (at most 64 bytes)
+    pub fn new(size: usize, key: &[u8]) -> Option<Blake2bParams> {
+        if !(1..=64).contains(&size) || key.len() > 64 {
+            return None;
+        }
+
+        let mut params = Blake2bParams {
+            size: size as u8,
+            key: [0; 64],
+            key_len: key.len() as u8,
+        };
+        params.key[..key.len()].copy_from_slice(key);
+        Some(params)
+    }
+
+    fn key(&self) -> &[u8] {
+        &self.key[..self.key_len as usize]
+    }
+
+    fn state(&self) -> blake2b_simd::State {
+        blake2b_simd::Params::new()
+            .hash_length(self.size as usize)
+            .key(self.key())
+            .to_state()
+    }
+
+    /// Same digest length and key length, with a zero key: hashes at the same speed
+    fn redacted(&self) -> Blake2bParams {
+        Blake2bParams {
+            size: self.size,
+            key: [0; 64],
+            key_len: self.key_len,
+        }
+    }
+}
+
+impl Drop for Blake2bParams {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl fmt::Debug for Blake2bParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blake2bParams")
+            .field("size", &self.size)
+            .field("key_len", &self.key_len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// TurboSHAKE domain separation byte for plain hashing, as in the specification
+pub const TURBOSHAKE_DOMAIN: u8 = 0x1f;
+
+/// Most links a `{:chain, algorithms}` may have
+const MAX_CHAIN_LINKS: usize = 16;
+
+/// Hash algorithms selectable per call, given as atoms except for parameterized BLAKE2b and chains
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Sha256,
+    Blake3,
+    /// SHA-512 truncated to 32 bytes with its own initial values, fast on 64-bit CPUs
+    Sha512_256,
+    /// 48-byte SHA-384
+    Sha384,
+    /// Circom-compatible Poseidon over BN254, for proofs checked inside ZK circuits
+    Poseidon,
+    /// RIPEMD-160 of SHA-256, the 20-byte HASH160 of legacy commitment schemes
+    Hash160,
+    /// KangarooTwelve with an empty customization string, 32 bytes of output
+    K12,
+    /// TurboSHAKE128 with domain byte 0x1F, 32 bytes of output
+    TurboShake128,
+    /// `:yespower` for yespower 1.0 with `N = 2048` and `r = 8`, or `{:yespower, n, r}` /
+    /// `{:yespower, n, r, personalization}`
+    #[cfg(feature = "yespower")]
+    Yespower(yespower::Params),
+    /// `:blake2b` for BLAKE2b-512, or `{:blake2b, size}` / `{:blake2b, size, key}`
+    Blake2b(Blake2bParams),
+    /// `{:chain, algorithms}`: the first algorithm hashes `data || nonce` and each later one
+    /// hashes the previous digest, like X11
+    Chain(Vec<Algorithm>),
+}
+
+impl<'a> Decoder<'a> for Algorithm {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if let Ok(name) = term.decode::<Atom>() {
+            return match name {
+                name if name == atoms::sha256() => Ok(Algorithm::Sha256),
+                name if name == atoms::blake3() => Ok(Algorithm::Blake3),
+                name if name == atoms::sha512_256() => Ok(Algorithm::Sha512_256),
+                name if name == atoms::sha384() => Ok(Algorithm::Sha384),
+                name if name == atoms::poseidon() => Ok(Algorithm::Poseidon),
+                name if name == atoms::hash160() => Ok(Algorithm::Hash160),
+                name if name == atoms::k12() => Ok(Algorithm::K12),
+                name if name == atoms::turboshake128() => Ok(Algorithm::TurboShake128),
+                name if name == atoms::blake2b() => Ok(Algorithm::Blake2b(Blake2bParams::DEFAULT)),
+                #[cfg(feature = "yespower")]
+                name if name == atoms::yespower() => {
+                    Ok(Algorithm::Yespower(yespower::Params::DEFAULT))
+                }
+                _ => Err(Error::BadArg),
+            };
+        }
+
+        if let Ok((name, links)) = term.decode::<(Atom, Vec<Algorithm>)>() {
+            let chainable =
+                |link: &Algorithm| !matches!(link, Algorithm::Poseidon | Algorithm::Chain(_));
+            let valid = (1..=MAX_CHAIN_LINKS).contains(&links.len())
+                && !matches!(links[0], Algorithm::Chain(_))
+                && links[1..].iter().all(chainable);
+            if name != atoms::chain() || !valid {
+                return Err(Error::BadArg);
+            }
+            return Ok(Algorithm::Chain(links));
+        }
+
+        #[cfg(feature = "yespower")]
+        if let Some(algorithm) = decode_yespower(term) {
+            return algorithm;
+        }
+
+        let (size, key) = match term.decode::<(Atom, usize)>() {
+            Ok((name, size)) if name == atoms::blake2b() => (size, None),
+            Ok(_) => return Err(Error::BadArg),
+            Err(_) => match term.decode::<(Atom, usize, Binary)>()? {
+                (name, size, key) if name == atoms::blake2b() => (size, Some(key)),
+                _ => return Err(Error::BadArg),
+            },
+        };
+
+        let key = key.as_ref().map_or(&[][..], |key| key.as_slice());
+        Blake2bParams::new(size, key)
+            .map(Algorithm::Blake2b)
+            .ok_or(Error::BadArg)
+    }
+}
+
+impl Encoder for Algorithm {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            Algorithm::Sha256 => atoms::sha256().encode(env),
+            Algorithm::Blake3 => atoms::blake3().encode(env),
+            Algorithm::Sha512_256 => atoms::sha512_256().encode(env),
+            Algorithm::Sha384 => atoms::sha384().encode(env),
+            Algorithm::Poseidon => atoms::poseidon().encode(env),
+            Algorithm::Hash160 => atoms::hash160().encode(env),
+            Algorithm::K12 => atoms::k12().encode(env),
+            Algorithm::TurboShake128 => atoms::turboshake128().encode(env),
+            #[cfg(feature = "yespower")]
+            Algorithm::Yespower(params) if *params == yespower::Params::DEFAULT => {
+                atoms::yespower().encode(env)
+            }
+            #[cfg(feature = "yespower")]
+            Algorithm::Yespower(params) if params.pers().is_empty() => {
+                (atoms::yespower(), params.n(), params.r()).encode(env)
+            }
+            #[cfg(feature = "yespower")]
+            Algorithm::Yespower(params) => {
+                let mut pers = NewBinary::new(env, params.pers().len());
+                pers.as_mut_slice().copy_from_slice(params.pers());
+                (atoms::yespower(), params.n(), params.r(), Binary::from(pers)).encode(env)
+            }
+            Algorithm::Blake2b(params) if *params == Blake2bParams::DEFAULT => {
+                atoms::blake2b().encode(env)
+            }
+            Algorithm::Blake2b(params) if params.key_len == 0 => {
+                (atoms::blake2b(), params.size).encode(env)
+            }
+            Algorithm::Blake2b(params) => {
+                let mut key = NewBinary::new(env, params.key().len());
+                key.as_mut_slice().copy_from_slice(params.key());
+                (atoms::blake2b(), params.size, Binary::from(key)).encode(env)
+            }
+            Algorithm::Chain(links) => (atoms::chain(), links).encode(env),
+        }
+    }
+}
+
+impl Algorithm {
+    /// The same algorithm with any BLAKE2b key replaced by zeros, for tables that outlive
+    /// the call and only depend on the cost of hashing
+    pub fn redacted(&self) -> Algorithm {
+        match self {
+            Algorithm::Blake2b(params) => Algorithm::Blake2b(params.redacted()),
+            Algorithm::Chain(links) => Algorithm::Chain(links.iter().map(Self::redacted).collect()),
+            algorithm => algorithm.clone(),
+        }
+    }
+
+    /// Name used in log metadata; never includes a BLAKE2b key
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Blake3 => "blake3",
+            Algorithm::Sha512_256 => "sha512_256",
+            Algorithm::Sha384 => "sha384",
+            Algorithm::Poseidon => "poseidon",
+            Algorithm::Hash160 => "hash160",
+            Algorithm::K12 => "k12",
+            Algorithm::TurboShake128 => "turboshake128",
+            #[cfg(feature = "yespower")]
+            Algorithm::Yespower(_) => "yespower",
+            Algorithm::Blake2b(_) => "blake2b",
+            Algorithm::Chain(_) => "chain",
+        }
+    }
+
+    /// Absorbs the data so that only the nonce has to be hashed per candidate
+    pub fn prepare(&self, data: &[u8]) -> State {
+        match self {
+            Algorithm::Sha256 => State::Sha256(Sha256::new().chain_update(data)),
+            Algorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                hasher.update(data);
+                State::Blake3(Box::new(hasher))
+            }
+            Algorithm::Sha512_256 => State::Sha512_256(Sha512_256::new().chain_update(data)),
+            Algorithm::Sha384 => State::Sha384(Sha384::new().chain_update(data)),
+            Algorithm::Poseidon => State::Poseidon(poseidon::absorb(data)),
+            Algorithm::Hash160 => State::Hash160(Sha256::new().chain_update(data)),
+            Algorithm::K12 => State::K12(Box::new(k12::KangarooTwelve::default().chain(data))),
+            Algorithm::TurboShake128 => State::TurboShake128(turboshake128().chain(data)),
+            #[cfg(feature = "yespower")]
+            Algorithm::Yespower(params) => {
+                State::Yespower(Sha256::new().chain_update(data), params.clone())
+            }
+            Algorithm::Blake2b(params) => {
+                let mut state = params.state();
+                state.update(data);
+                State::Blake2b(Box::new(state))
+            }
+            Algorithm::Chain(links) => {
+                State::Chain(Box::new(links[0].prepare(data)), links[1..].to_vec())
+            }
+        }
+    }
+
+    /// One-shot digest of `bytes`, as applied by the later links of a chain
+    fn hash(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut digest = Vec::new();
+        self.hash_into(bytes, &mut digest);
+        digest
+    }
+
+    /// Like `hash`, but overwrites `out`
+    fn hash_into(&self, bytes: &[u8], out: &mut Vec<u8>) {
+        out.clear();
+        match self {
+            Algorithm::Sha256 => out.extend_from_slice(&Sha256::digest(bytes)),
+            Algorithm::Blake3 => out.extend_from_slice(blake3::hash(bytes).as_bytes()),
+            Algorithm::Sha512_256 => out.extend_from_slice(&Sha512_256::digest(bytes)),
+            Algorithm::Sha384 => out.extend_from_slice(&Sha384::digest(bytes)),
+            Algorithm::Hash160 => out.extend_from_slice(&Ripemd160::digest(Sha256::digest(bytes))),
+            Algorithm::K12 => squeeze_into(k12::KangarooTwelve::default().chain(bytes), out),
+            Algorithm::TurboShake128 => squeeze_into(turboshake128().chain(bytes), out),
+            #[cfg(feature = "yespower")]
+            Algorithm::Yespower(params) => {
+                out.extend_from_slice(&params.hash(&Sha256::digest(bytes)))
+            }
+            Algorithm::Blake2b(params) => {
+                let mut state = params.state();
+                state.update(bytes);
+                out.extend_from_slice(state.finalize().as_bytes())
+            }
+            // Not reached from Elixir: decoding keeps Poseidon and nested chains out of later links
+            Algorithm::Poseidon => {
+                out.extend_from_slice(&poseidon::digest(poseidon::absorb(bytes), 0))
+            }
+            Algorithm::Chain(links) => {
+                let digest = links.iter().fold(bytes.to_vec(), |digest, link| link.hash(&digest));
+                out.extend_from_slice(&digest);
+            }
+        }
+    }
+
+    /// Multicodec identifier used as the multihash prefix
+    pub fn multicodec(&self) -> u64 {
+        match self {
+            Algorithm::Sha256 => 0x12,
+            Algorithm::Blake3 => 0x1e,
+            Algorithm::Sha512_256 => 0x1014,
+            Algorithm::Sha384 => 0x20,
+            // No multicodec is registered for Poseidon over BN254; first private-use code
+            Algorithm::Poseidon => 0x30_0000,
+            // HASH160 has no multicodec of its own either
+            Algorithm::Hash160 => 0x30_0001,
+            // kangarootwelve
+            Algorithm::K12 => 0x1d01,
+            // TurboSHAKE isn't registered yet
+            Algorithm::TurboShake128 => 0x30_0003,
+            // Nor is yespower
+            #[cfg(feature = "yespower")]
+            Algorithm::Yespower(_) => 0x30_0004,
+            // blake2b-8 through blake2b-512 are 0xb201 to 0xb240
+            Algorithm::Blake2b(params) => 0xb200 + params.size as u64,
+            Algorithm::Chain(_) => 0x30_0002,
+        }
+    }
+
+    /// Wraps a digest as a self-describing multihash: `varint(code) || varint(length) || digest`
+    pub fn multihash(&self, digest: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(digest.len() + 4);
+        write_varint(&mut output, self.multicodec());
+        write_varint(&mut output, digest.len() as u64);
+        output.extend_from_slice(digest);
+        output
+    }
+}
+
+/// Decodes `{:yespower, n, r}` and `{:yespower, n, r, personalization}`, or returns `None`
+/// for terms of another shape
+#[cfg(feature = "yespower")]
+fn decode_yespower(term: Term) -> Option<NifResult<Algorithm>> {
+    let (name, n, r, pers) = match term.decode::<(Atom, u32, u32)>() {
+        Ok((name, n, r)) => (name, n, r, None),
+        Err(_) => {
+            let (name, n, r, pers) = term.decode::<(Atom, u32, u32, Binary)>().ok()?;
+            (name, n, r, Some(pers))
+        }
+    };
+
+    let pers = pers.as_ref().map_or(&[][..], |pers| pers.as_slice());
+    let params = yespower::Params::new(n, r, pers).filter(|_| name == atoms::yespower());
+    Some(params.map(Algorithm::Yespower).ok_or(Error::BadArg))
+}
+
+/// TurboSHAKE128 with the default domain byte
+fn turboshake128() -> TurboShake128 {
+    TurboShake128::from_core(TurboShake128Core::new(TURBOSHAKE_DOMAIN))
+}
+
+/// Reads the first 32 bytes of an extendable-output hash
+fn squeeze_into(hasher: impl ExtendableOutput, out: &mut Vec<u8>) {
+    out.resize(32, 0);
+    hasher.finalize_xof().read(out);
+}
+
+/// Appends an unsigned LEB128 varint
+pub fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+/// Hasher state with the data already absorbed
+#[derive(Clone)]
+pub enum State {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Sha512_256(Sha512_256),
+    Sha384(Sha384),
+    Poseidon(Fr),
+    Hash160(Sha256),
+    K12(Box<k12::KangarooTwelve<'static>>),
+    TurboShake128(TurboShake128),
+    /// SHA-256 of the data so far, finished per nonce before the yespower core runs
+    #[cfg(feature = "yespower")]
+    Yespower(Sha256, yespower::Params),
+    Blake2b(Box<blake2b_simd::State>),
+    /// The first link's state and the links applied to its digest
+    Chain(Box<State>, Vec<Algorithm>),
+}
+
+impl State {
+    /// Finishes the hash for the given nonce without consuming the state
+    pub fn digest(&self, nonce: u64) -> Vec<u8> {
+        let mut digest = Vec::new();
+        self.digest_into(nonce, &mut digest);
+        digest
+    }
+
+    /// Like `digest`, but overwrites `out`, so search loops can reuse one buffer
+    pub fn digest_into(&self, nonce: u64, out: &mut Vec<u8>) {
+        out.clear();
+        match self {
+            State::Sha256(state) => {
+                out.extend_from_slice(&state.clone().chain_update(nonce.to_le_bytes()).finalize())
+            }
+            State::Blake3(state) => {
+                let mut hasher = state.as_ref().clone();
+                hasher.update(&nonce.to_le_bytes());
+                out.extend_from_slice(hasher.finalize().as_bytes());
+            }
+            State::Sha512_256(state) => {
+                out.extend_from_slice(&state.clone().chain_update(nonce.to_le_bytes()).finalize())
+            }
+            State::Sha384(state) => {
+                out.extend_from_slice(&state.clone().chain_update(nonce.to_le_bytes()).finalize())
+            }
+            State::Poseidon(state) => out.extend_from_slice(&poseidon::digest(*state, nonce)),
+            State::Hash160(state) => {
+                let sha256 = state.clone().chain_update(nonce.to_le_bytes()).finalize();
+                out.extend_from_slice(&Ripemd160::digest(sha256));
+            }
+            State::K12(state) => {
+                squeeze_into(state.as_ref().clone().chain(nonce.to_le_bytes()), out)
+            }
+            State::TurboShake128(state) => {
+                squeeze_into(state.clone().chain(nonce.to_le_bytes()), out)
+            }
+            #[cfg(feature = "yespower")]
+            State::Yespower(state, params) => {
+                let sha256 = state.clone().chain_update(nonce.to_le_bytes()).finalize();
+                out.extend_from_slice(&params.hash(&sha256));
+            }
+            State::Blake2b(state) => {
+                let mut state = state.as_ref().clone();
+                state.update(&nonce.to_le_bytes());
+                out.extend_from_slice(state.finalize().as_bytes());
+            }
+            State::Chain(first, links) => {
+                first.digest_into(nonce, out);
+                // Decoding keeps chains out of later links, so the scratch is never reentered
+                CHAIN_SCRATCH.with(|scratch| {
+                    let mut scratch = scratch.borrow_mut();
+                    for link in links {
+                        link.hash_into(out, &mut scratch);
+                        std::mem::swap(out, &mut *scratch);
+                    }
+                });
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// Second buffer of chain digests, which hash each link's digest into the other buffer
+    static CHAIN_SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
@@ -0,0 +1,85 @@
+use std::time::Instant;
+
+use crate::backend;
+use crate::difficulty::Difficulty;
+use crate::metrics;
+
+/// Nonces hashed between deadline checks
+const DEADLINE_CHECK_INTERVAL: u64 = 4_096;
+
+/// Why a search stopped without finding a solution
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// The deadline passed
+    Timeout,
+    /// The give-up heuristic for very high difficulties triggered
+    Aborted,
+}
+
+/// State of a search that stopped early
+pub struct Stopped {
+    pub reason: Stop,
+    pub attempts: u64,
+    /// Nonce and digest of the lowest hash seen, if tracked
+    pub best: Option<(u64, [u8; 32])>,
+}
+
+/// Scans nonces from zero until one meets the difficulty or the deadline passes,
+/// optionally remembering the lowest hash so partial work can be returned
+pub fn search(
+    data: &[u8],
+    difficulty: &Difficulty,
+    deadline: Option<Instant>,
+    track_best: bool,
+) -> Result<u64, Stopped> {
+    let past_deadline = || deadline.is_some_and(|d| Instant::now() >= d);
+    let Some(prepared) = backend::prepare_until(data, || !past_deadline()) else {
+        return Err(Stopped {
+            reason: Stop::Timeout,
+            attempts: 0,
+            best: None,
+        });
+    };
+    let mut best: Option<(u64, [u8; 32])> = None;
+    let mut meter = metrics::Meter::new();
+
+    for nonce in 0..u64::MAX {
+        meter.tick();
+        let digest = prepared.digest(nonce);
+        if difficulty.is_met_by(&digest) {
+            return Ok(nonce);
+        }
+
+        let improved = match best {
+            Some((_, lowest)) => digest < lowest,
+            None => true,
+        };
+        if track_best && improved {
+            best = Some((nonce, digest));
+        }
+
+        let stop = if deadline
+            .is_some_and(|d| nonce % DEADLINE_CHECK_INTERVAL == 0 && Instant::now() >= d)
+        {
+            Some(Stop::Timeout)
+        } else if nonce > 100_000_000 && difficulty.zeros() > 20 {
+            Some(Stop::Aborted)
+        } else {
+            None
+        };
+
+        if let Some(reason) = stop {
+            return Err(Stopped {
+                reason,
+                attempts: nonce + 1,
+                best,
+            });
+        }
+    }
+
+    Err(Stopped {
+        reason: Stop::Aborted,
+        attempts: u64::MAX,
+        best,
+    })
+}
@@ -0,0 +1,206 @@
+use rustler::{Atom, Binary, Env, NewBinary, NifResult, NifUnitEnum};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+
+const DEFAULT_CAPACITY: usize = 10_000;
+const MAX_CAPACITY: usize = 1_000_000;
+
+/// Size of an entry in the binary export: hash, difficulty, timestamp and outcome
+const RECORD_SIZE: usize = 32 + 4 + 8 + 1;
+
+const CSV_HEADER: &str = "hash,difficulty,timestamp_ms,outcome\n";
+
+/// How a verification ended
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The proof met its difficulty
+    Valid,
+    /// The proof was well formed but didn't meet its difficulty
+    Invalid,
+    /// The proof was malformed or broke the verification policy
+    Rejected,
+}
+
+impl Outcome {
+    fn code(self) -> u8 {
+        match self {
+            Outcome::Valid => 0,
+            Outcome::Invalid => 1,
+            Outcome::Rejected => 2,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::Valid => "valid",
+            Outcome::Invalid => "invalid",
+            Outcome::Rejected => "rejected",
+        }
+    }
+}
+
+/// A verified proof, identified by the BLAKE3 digest of its bytes, never the bytes themselves
+struct Entry {
+    hash: [u8; 32],
+    difficulty: u32,
+    timestamp_ms: u64,
+    outcome: Outcome,
+}
+
+/// Checked before hashing anything, so verification costs nothing extra while disabled
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static LOG: Mutex<VecDeque<Entry>> = Mutex::new(VecDeque::new());
+static CAPACITY: Mutex<usize> = Mutex::new(DEFAULT_CAPACITY);
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Records a verification if the audit log is enabled, dropping the oldest entry when full.
+/// `parts` are hashed in order to identify the proof
+pub fn record(parts: &[&[u8]], difficulty: u32, outcome: Outcome) {
+    if !enabled() {
+        return;
+    }
+
+    let mut hasher = blake3::Hasher::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    record_hash(*hasher.finalize().as_bytes(), difficulty, outcome);
+}
+
+/// Whether verifications are being recorded
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Records a verification by the BLAKE3 digest of its parts, for callers that hash them
+/// alongside the proof itself
+pub fn record_hash(hash: [u8; 32], difficulty: u32, outcome: Outcome) {
+    let entry = Entry {
+        hash,
+        difficulty,
+        timestamp_ms: now_ms(),
+        outcome,
+    };
+
+    let capacity = *CAPACITY.lock().unwrap();
+    let mut log = LOG.lock().unwrap();
+    while log.len() >= capacity {
+        log.pop_front();
+    }
+    log.push_back(entry);
+}
+
+/// Starts recording verifications in a ring buffer of `:capacity` entries, keeping the
+/// entries recorded so far up to the new capacity
+#[rustler::nif]
+fn audit_enable(opts: Options) -> NifResult<GuardedResult<Atom>> {
+    guard::run_result(|| {
+        let capacity = opts.get_or(atoms::capacity(), DEFAULT_CAPACITY)?;
+        if capacity == 0 || capacity > MAX_CAPACITY {
+            return Ok(Err((atoms::error(), "Invalid capacity (1-1000000)")));
+        }
+
+        *CAPACITY.lock().unwrap() = capacity;
+        let mut log = LOG.lock().unwrap();
+        while log.len() > capacity {
+            log.pop_front();
+        }
+        ENABLED.store(true, Ordering::Relaxed);
+        Ok(Ok(atoms::ok()))
+    })
+}
+
+/// Stops recording and discards the recorded entries
+#[rustler::nif]
+fn audit_disable() -> Guarded<Atom> {
+    guard::run(|| {
+        ENABLED.store(false, Ordering::Relaxed);
+        LOG.lock().unwrap().clear();
+        atoms::ok()
+    })
+}
+
+/// Fields an export can blank out
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Hash,
+    Difficulty,
+    Timestamp,
+}
+
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Binary,
+}
+
+/// Exports the most recent entries, oldest first, as CSV or as fixed-size binary records.
+/// `:limit` caps the entry count and `:max_bytes` the output size, dropping the oldest
+/// entries first; `:redact` zeroes the listed fields
+#[rustler::nif]
+fn audit_export<'a>(env: Env<'a>, opts: Options<'a>) -> NifResult<Guarded<Binary<'a>>> {
+    guard::run_result(|| {
+        let format = opts.get_or(atoms::format(), Format::Csv)?;
+        let limit = opts.get_or(atoms::limit(), usize::MAX)?;
+        let max_bytes = opts.get_or(atoms::max_bytes(), usize::MAX)?;
+        let redact: Vec<Field> = opts.get_or(atoms::redact(), vec![])?;
+        let redacted = |field| redact.contains(&field);
+
+        let log = LOG.lock().unwrap();
+        let mut records = Vec::new();
+        let mut size = if format == Format::Csv { CSV_HEADER.len() } else { 0 };
+        for entry in log.iter().rev().take(limit) {
+            let hash = if redacted(Field::Hash) { [0; 32] } else { entry.hash };
+            let difficulty = if redacted(Field::Difficulty) { 0 } else { entry.difficulty };
+            let timestamp_ms = if redacted(Field::Timestamp) { 0 } else { entry.timestamp_ms };
+
+            let mut record = Vec::with_capacity(RECORD_SIZE);
+            match format {
+                Format::Binary => {
+                    record.extend_from_slice(&hash);
+                    record.extend_from_slice(&difficulty.to_be_bytes());
+                    record.extend_from_slice(&timestamp_ms.to_be_bytes());
+                    record.push(entry.outcome.code());
+                }
+                Format::Csv => {
+                    let mut line = hex::encode(hash);
+                    let _ = writeln!(line, ",{difficulty},{timestamp_ms},{}", entry.outcome.name());
+                    record = line.into_bytes();
+                }
+            }
+
+            if size + record.len() > max_bytes {
+                break;
+            }
+            size += record.len();
+            records.push(record);
+        }
+        drop(log);
+
+        let mut output = NewBinary::new(env, size);
+        let mut offset = 0;
+        if format == Format::Csv {
+            output.as_mut_slice()[..CSV_HEADER.len()].copy_from_slice(CSV_HEADER.as_bytes());
+            offset = CSV_HEADER.len();
+        }
+        for record in records.iter().rev() {
+            output.as_mut_slice()[offset..offset + record.len()].copy_from_slice(record);
+            offset += record.len();
+        }
+        Ok(output.into())
+    })
+}
@@ -0,0 +1,161 @@
+use rustler::{Atom, Env, NifMap, NifResult};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+use crate::atoms;
+use crate::cpu;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::input;
+
+/// Prepares SHA-256 for repeated nonce checks against the same data by absorbing the data
+/// once. `sha2` picks SHA-NI or the ARMv8 SHA-2 instructions at runtime when the CPU has them,
+/// so there is no backend to choose here
+pub fn prepare(data: &[u8]) -> Prepared {
+    let mut midstate = Sha256::new();
+    midstate.update(data);
+    Prepared { midstate }
+}
+
+/// Like `prepare`, but absorbs the data in chunks and gives up once `keep_going` returns false
+pub fn prepare_until(data: &[u8], keep_going: impl FnMut() -> bool) -> Option<Prepared> {
+    let mut midstate = Sha256::new();
+    if !input::absorb(&mut midstate, data, keep_going) {
+        return None;
+    }
+    Some(Prepared { midstate })
+}
+
+/// Hasher state cached after absorbing a piece of data
+pub struct Prepared {
+    midstate: Sha256,
+}
+
+impl Prepared {
+    /// Checks if the nonce meets the difficulty requirement for the bound data
+    pub fn check(&self, nonce: u64, difficulty: u32) -> bool {
+        self.check_any(nonce, &[difficulty]).is_some()
+    }
+
+    /// Checks if the nonce meets a difficulty, using the fast nibble check for zero counts
+    pub fn meets(&self, nonce: u64, difficulty: &Difficulty) -> bool {
+        match difficulty {
+            Difficulty::Zeros(zeros) => self.check(nonce, *zeros),
+            Difficulty::ZeroBits(bits) => leading_zero_bits(&self.digest(nonce)) >= *bits,
+            Difficulty::Target(target) => self.digest(nonce).as_slice() <= target.as_slice(),
+            Difficulty::TrailingZeros(_) | Difficulty::ZerosAt { .. } | Difficulty::Mask { .. } => {
+                difficulty.is_met_by(&self.digest(nonce))
+            }
+        }
+    }
+
+    /// Computes the digest of the bound data and nonce
+    pub fn digest(&self, nonce: u64) -> [u8; 32] {
+        let mut hasher = self.midstate.clone();
+        hasher.update(nonce.to_le_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Hashes the nonce once and returns the index of the first difficulty it meets
+    pub fn check_any(&self, nonce: u64, difficulties: &[u32]) -> Option<usize> {
+        let digest = self.digest(nonce);
+        difficulties.iter().position(|&d| meets_difficulty_bytes(&digest, d))
+    }
+}
+
+/// Byte-level equivalent of `meets_difficulty`, avoiding the hex round trip
+pub fn meets_difficulty_bytes(digest: &[u8], difficulty: u32) -> bool {
+    let nibbles = digest.len() * 2;
+    let required = difficulty as usize;
+    let nibble = |i: usize| {
+        let byte = digest[i / 2];
+        if i & 1 == 0 { byte >> 4 } else { byte & 0x0f }
+    };
+
+    if required > nibbles {
+        return false;
+    }
+
+    (0..required).all(|i| nibble(i) == 0) && (required == nibbles || nibble(required) != 0)
+}
+
+/// Counts the leading zero bits of a digest
+pub fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut bits = 0;
+    for &byte in digest {
+        bits += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// Measures the aggregate hashrate of `threads` threads hashing for `duration`
+fn benchmark_threads(threads: u32, duration: Duration) -> f64 {
+    let start = Instant::now();
+    let hashes: u64 = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                scope.spawn(move || {
+                    let data = format!("powex scaling benchmark {thread}");
+                    let prepared = prepare(data.as_bytes());
+                    let mut hashes = 0u64;
+                    while start.elapsed() < duration {
+                        for nonce in hashes..hashes + 1_000 {
+                            std::hint::black_box(prepared.check(nonce, 64));
+                        }
+                        hashes += 1_000;
+                    }
+                    hashes
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|handle| handle.join().unwrap_or(0)).sum()
+    });
+
+    hashes as f64 / start.elapsed().as_secs_f64()
+}
+
+#[derive(NifMap)]
+struct ScalingPoint {
+    threads: u32,
+    hashrate: f64,
+    speedup: f64,
+    efficiency: f64,
+}
+
+/// Measures the hashrate at 1..=max_threads threads to expose the scaling curve
+#[rustler::nif(schedule = "DirtyCpu")]
+fn bench_scaling(max_threads: u32, duration_ms: u64) -> GuardedResult<Vec<ScalingPoint>> {
+    guard::run(|| {
+        if max_threads == 0 || max_threads > 64 {
+            return Err((atoms::error(), "Invalid number of threads (1-64)"));
+        }
+
+        let duration = Duration::from_millis(duration_ms);
+        let mut points: Vec<ScalingPoint> = Vec::with_capacity(max_threads as usize);
+
+        for threads in 1..=max_threads {
+            let hashrate = benchmark_threads(threads, duration);
+            let baseline = points.first().map_or(hashrate, |point| point.hashrate);
+            let speedup = if baseline > 0.0 { hashrate / baseline } else { 0.0 };
+
+            points.push(ScalingPoint {
+                threads,
+                hashrate,
+                speedup,
+                efficiency: speedup / threads as f64,
+            });
+        }
+
+        Ok(points)
+    })
+}
+
+/// Gets the SHA-256 implementation `sha2` selected for this CPU
+#[rustler::nif(name = "backend")]
+fn backend_nif(env: Env) -> NifResult<Guarded<Atom>> {
+    guard::run_result(|| Atom::from_str(env, cpu::sha256_backend()))
+}
@@ -0,0 +1,289 @@
+//! Anonymous tokens minted for proof of work, with Chaum's RSA blind signatures over a
+//! full-domain hash (RSA-FDH).
+//!
+//! The client draws a random token, blinds its hash `m` as `m * r^e mod n` and solves a
+//! proof whose data is the blinded value, so the proof pays for exactly one signature. The
+//! server checks the proof and signs the blinded value without learning `m`; the client
+//! divides `r` out and holds a signature on its token that the server cannot link to the
+//! minting request. Hashing the token to the full modulus keeps RSA's multiplicativity from
+//! combining signatures into new ones.
+
+use num_bigint::{BigUint, RandBigInt};
+use rand::RngCore;
+use rustler::{Atom, Binary, Env, NewBinary, NifMap, NifResult, ResourceArc};
+use std::time::{Duration, Instant};
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::memory_hard::MemoryHardParams;
+use crate::options::Options;
+use crate::primes::random_prime;
+use crate::proof::{self, Policy, Proof, ProofAlgorithm};
+use crate::proof_store;
+use crate::Status;
+
+/// Public exponent of generated keys
+const PUBLIC_EXPONENT: u32 = 65_537;
+
+/// Default modulus size of `blind_keypair`
+const DEFAULT_MODULUS_BITS: u64 = 2048;
+
+/// Accepted modulus sizes
+const MODULUS_BITS: std::ops::RangeInclusive<u64> = 1024..=4096;
+
+/// Size of the random tokens signed blindly
+const TOKEN_LEN: usize = 32;
+
+/// BLAKE3 key derivation context of the full-domain hash
+const FDH_CONTEXT: &str = "powex 2026-10-15 blind token full-domain hash";
+
+#[derive(NifMap)]
+struct PublicKey<'a> {
+    n: Binary<'a>,
+    e: Binary<'a>,
+}
+
+#[derive(NifMap)]
+struct SecretKey<'a> {
+    n: Binary<'a>,
+    e: Binary<'a>,
+    d: Binary<'a>,
+}
+
+#[derive(NifMap)]
+struct Keypair<'a> {
+    public_key: PublicKey<'a>,
+    secret_key: SecretKey<'a>,
+}
+
+#[derive(NifMap)]
+struct BlindRequest<'a> {
+    /// Proof of work over the blinded token, sent to the server
+    proof: Binary<'a>,
+    /// The token itself, kept by the client
+    token: Binary<'a>,
+    /// The blinding factor `r`, kept by the client until the signature arrives
+    blind: Binary<'a>,
+}
+
+/// An RSA public key, checked once on the way in
+struct Rsa {
+    n: BigUint,
+    e: BigUint,
+    /// Size of the modulus in bytes, the width of blinded values and signatures
+    len: usize,
+}
+
+impl Rsa {
+    fn new(n: &[u8], e: &[u8]) -> Option<Rsa> {
+        let n = BigUint::from_bytes_be(n);
+        let e = BigUint::from_bytes_be(e);
+        if !MODULUS_BITS.contains(&n.bits()) || !n.bit(0) || e <= BigUint::from(1u8) || !e.bit(0)
+        {
+            return None;
+        }
+        let len = n.bits().div_ceil(8) as usize;
+        Some(Rsa { n, e, len })
+    }
+
+    /// Hashes a token to an integer below the modulus
+    fn full_domain_hash(&self, token: &[u8]) -> BigUint {
+        let mut bytes = vec![0u8; self.len];
+        blake3::Hasher::new_derive_key(FDH_CONTEXT)
+            .update(token)
+            .finalize_xof()
+            .fill(&mut bytes);
+        BigUint::from_bytes_be(&bytes) % &self.n
+    }
+
+    /// An integer below the modulus given as exactly `len` big-endian bytes
+    fn element(&self, bytes: &[u8]) -> Option<BigUint> {
+        let value = BigUint::from_bytes_be(bytes);
+        (bytes.len() == self.len && value < self.n).then_some(value)
+    }
+
+    /// `value` as exactly `len` big-endian bytes
+    fn to_bytes(&self, value: &BigUint) -> Vec<u8> {
+        let bytes = value.to_bytes_be();
+        let mut padded = vec![0u8; self.len - bytes.len()];
+        padded.extend_from_slice(&bytes);
+        padded
+    }
+
+    fn verify(&self, token: &[u8], signature: &BigUint) -> bool {
+        signature.modpow(&self.e, &self.n) == self.full_domain_hash(token)
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.into()
+}
+
+/// Generates an RSA key pair for blind signatures, with `:bits` of modulus (default 2048)
+#[rustler::nif(schedule = "DirtyCpu")]
+fn blind_keypair<'a>(env: Env<'a>, opts: Options) -> NifResult<GuardedResult<Keypair<'a>>> {
+    guard::run_result(|| {
+        let bits = opts.get_or(atoms::bits(), DEFAULT_MODULUS_BITS)?;
+        if !MODULUS_BITS.contains(&bits) || bits & 1 == 1 {
+            return Ok(Err((atoms::error(), "Invalid modulus size (even, 1024-4096 bits)")));
+        }
+
+        let mut rng = rand::thread_rng();
+        let e = BigUint::from(PUBLIC_EXPONENT);
+        let (n, d) = loop {
+            let p = random_prime(&mut rng, bits / 2);
+            let q = random_prime(&mut rng, bits / 2);
+            let phi = (&p - 1u8) * (&q - 1u8);
+            if let (false, Some(d)) = (p == q, e.modinv(&phi)) {
+                break (p * q, d);
+            }
+        };
+
+        let (n, e) = (n.to_bytes_be(), e.to_bytes_be());
+        Ok(Ok(Keypair {
+            public_key: PublicKey { n: to_binary(env, &n), e: to_binary(env, &e) },
+            secret_key: SecretKey {
+                n: to_binary(env, &n),
+                e: to_binary(env, &e),
+                d: to_binary(env, &d.to_bytes_be()),
+            },
+        }))
+    })
+}
+
+/// Draws a token, blinds it for the server's public key and solves a proof of work over
+/// the blinded value at `difficulty`, with `:algorithm` and Argon2id parameters as in
+/// `encode_proof`. Returns `{:error, :timeout}` once `:timeout` milliseconds have passed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn blind_request<'a>(
+    env: Env<'a>,
+    public_key: PublicKey,
+    difficulty: u8,
+    opts: Options,
+) -> NifResult<Guarded<Result<BlindRequest<'a>, Atom>>> {
+    guard::run_result(|| {
+        let algorithm = opts.get_or(atoms::algorithm(), ProofAlgorithm::Sha256)?;
+        let memory = match algorithm {
+            ProofAlgorithm::Argon2id => Some(MemoryHardParams::from_options(&opts)?),
+            _ => None,
+        };
+        let timeout: Option<u64> = opts.get(atoms::timeout())?;
+        let deadline = timeout.map(|ms| Instant::now() + Duration::from_millis(ms));
+
+        let Some(rsa) = Rsa::new(&public_key.n, &public_key.e) else {
+            return Ok(Err(atoms::malformed()));
+        };
+
+        let mut rng = rand::thread_rng();
+        let mut token = [0u8; TOKEN_LEN];
+        rng.fill_bytes(&mut token);
+        let blind = loop {
+            let r = rng.gen_biguint_range(&BigUint::from(2u8), &rsa.n);
+            if r.modinv(&rsa.n).is_some() {
+                break r;
+            }
+        };
+        let blinded = rsa.full_domain_hash(&token) * blind.modpow(&rsa.e, &rsa.n) % &rsa.n;
+        let blinded = rsa.to_bytes(&blinded);
+
+        let unsolved = Proof::new(algorithm, difficulty, 0, memory, &blinded);
+        let Some(nonce) = unsolved.solve(deadline) else {
+            return Ok(Err(atoms::timeout()));
+        };
+        let proof = Proof::new(algorithm, difficulty, nonce, memory, &blinded).encode();
+
+        Ok(Ok(BlindRequest {
+            proof: to_binary(env, &proof),
+            token: to_binary(env, &token),
+            blind: to_binary(env, &rsa.to_bytes(&blind)),
+        }))
+    })
+}
+
+/// Verifies a request's proof under the policy, consumes it with `consume: true`, then
+/// signs the blinded value it carries
+#[rustler::nif(schedule = "DirtyCpu")]
+fn blind_mint<'a>(
+    env: Env<'a>,
+    proof: Binary,
+    secret_key: SecretKey,
+    policy: ResourceArc<Policy>,
+    opts: Options,
+) -> NifResult<Guarded<Result<Binary<'a>, Atom>>> {
+    guard::run_result(|| {
+        let consume = opts.get_or(atoms::consume(), false)?;
+
+        let Some(rsa) = Rsa::new(&secret_key.n, &secret_key.e) else {
+            return Ok(Err(atoms::malformed()));
+        };
+        let decoded = match proof::check(&proof, &policy) {
+            Ok(decoded) => decoded,
+            Err(reason) => return Ok(Err(reason)),
+        };
+        let Some(blinded) = rsa.element(decoded.data) else {
+            return Ok(Err(atoms::malformed()));
+        };
+        if consume {
+            if let Status::Error(reason) = proof_store::consume_key(decoded.identity()) {
+                return Ok(Err(reason));
+            }
+        }
+
+        let d = BigUint::from_bytes_be(&secret_key.d);
+        Ok(Ok(to_binary(env, &rsa.to_bytes(&blinded.modpow(&d, &rsa.n)))))
+    })
+}
+
+/// Unblinds the server's signature on a request and checks it against the token
+#[rustler::nif(schedule = "DirtyCpu")]
+fn blind_finalize<'a>(
+    env: Env<'a>,
+    public_key: PublicKey,
+    request: BlindRequest,
+    blind_signature: Binary,
+) -> Guarded<Result<Binary<'a>, Atom>> {
+    guard::run(|| {
+        let rsa = Rsa::new(&public_key.n, &public_key.e).ok_or(atoms::malformed())?;
+        let blinded_signature = rsa.element(&blind_signature).ok_or(atoms::malformed())?;
+        let inverse = rsa
+            .element(&request.blind)
+            .and_then(|blind| blind.modinv(&rsa.n))
+            .ok_or(atoms::malformed())?;
+
+        let signature = blinded_signature * inverse % &rsa.n;
+        if !rsa.verify(&request.token, &signature) {
+            return Err(atoms::bad_signature());
+        }
+        Ok(to_binary(env, &rsa.to_bytes(&signature)))
+    })
+}
+
+/// Checks a token's signature and, with `consume: true`, spends the token in the proof
+/// store so it can only be redeemed once
+#[rustler::nif(schedule = "DirtyCpu")]
+fn blind_redeem(
+    public_key: PublicKey,
+    token: Binary,
+    signature: Binary,
+    opts: Options,
+) -> NifResult<Guarded<Status>> {
+    guard::run_result(|| {
+        let consume = opts.get_or(atoms::consume(), false)?;
+
+        let Some(rsa) = Rsa::new(&public_key.n, &public_key.e) else {
+            return Ok(Status::Error(atoms::malformed()));
+        };
+        let valid = rsa
+            .element(&signature)
+            .is_some_and(|signature| rsa.verify(&token, &signature));
+        if !valid {
+            return Ok(Status::Error(atoms::bad_signature()));
+        }
+        if consume {
+            return Ok(proof_store::consume(&token));
+        }
+        Ok(Status::Ok)
+    })
+}
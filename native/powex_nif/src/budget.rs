@@ -0,0 +1,36 @@
+use rustler::Atom;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::atoms;
+
+/// Default `:max_verify_bytes` of `configure/1`
+pub const DEFAULT_MAX_VERIFY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Default `:max_verify_memory` of `configure/1`: four times the Argon2 recommendation
+pub const DEFAULT_MAX_VERIFY_MEMORY: u64 = 256 * 1024 * 1024;
+
+static MAX_VERIFY_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_MAX_VERIFY_BYTES);
+static MAX_VERIFY_MEMORY: AtomicU64 = AtomicU64::new(DEFAULT_MAX_VERIFY_MEMORY);
+
+pub fn set_max_verify_bytes(bytes: u64) {
+    MAX_VERIFY_BYTES.store(bytes, Ordering::Relaxed);
+}
+
+pub fn set_max_verify_memory(bytes: u64) {
+    MAX_VERIFY_MEMORY.store(bytes, Ordering::Relaxed);
+}
+
+pub fn max_verify_bytes() -> u64 {
+    MAX_VERIFY_BYTES.load(Ordering::Relaxed)
+}
+
+/// Rejects a verification that would hash more than `:max_verify_bytes` or allocate more
+/// than `:max_verify_memory`, judged from the proof's declared parameters before any work
+pub fn check(hashed_bytes: u64, memory_bytes: u64) -> Result<(), Atom> {
+    if hashed_bytes > MAX_VERIFY_BYTES.load(Ordering::Relaxed)
+        || memory_bytes > MAX_VERIFY_MEMORY.load(Ordering::Relaxed)
+    {
+        return Err(atoms::over_budget());
+    }
+    Ok(())
+}
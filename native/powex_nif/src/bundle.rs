@@ -0,0 +1,154 @@
+use rustler::{Binary, Env, Error, NewBinary, NifMap, NifResult};
+use sha2::{Digest, Sha256};
+
+use crate::atoms;
+use crate::difficulty::{hashes_to_target, Difficulty};
+use crate::entropy;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+
+/// Version byte of the bundle encoding
+const BUNDLE_VERSION: u8 = 1;
+
+/// Size of the fixed bundle header: version, sub-challenge count and target
+const HEADER_LEN: usize = 1 + 2 + 32;
+
+/// Size of each random sub-challenge
+const CHALLENGE_LEN: usize = 16;
+
+/// Most sub-challenges a bundle may hold
+const MAX_CHALLENGES: usize = 1024;
+
+/// A K-of-N bundle decoded from its binary form
+///
+/// Layout: `version || n (u16 LE) || target (32 bytes, big-endian) || n 16-byte challenges`.
+/// Sub-challenge `i` is solved by a nonce with `SHA-256(challenge_i || nonce_le) <= target`.
+struct Bundle<'a> {
+    target: [u8; 32],
+    challenges: Vec<&'a [u8]>,
+}
+
+impl<'a> Bundle<'a> {
+    fn decode(bytes: &'a [u8]) -> Option<Bundle<'a>> {
+        if bytes.len() < HEADER_LEN || bytes[0] != BUNDLE_VERSION {
+            return None;
+        }
+
+        let n = u16::from_le_bytes([bytes[1], bytes[2]]) as usize;
+        let challenges = &bytes[HEADER_LEN..];
+        if n == 0 || n > MAX_CHALLENGES || challenges.len() != n * CHALLENGE_LEN {
+            return None;
+        }
+
+        Some(Bundle {
+            target: bytes[3..HEADER_LEN].try_into().ok()?,
+            challenges: challenges.chunks(CHALLENGE_LEN).collect(),
+        })
+    }
+
+    fn encode(target: &[u8; 32], challenges: &[u8]) -> Vec<u8> {
+        let n = (challenges.len() / CHALLENGE_LEN) as u16;
+        let mut bytes = vec![BUNDLE_VERSION];
+        bytes.extend_from_slice(&n.to_le_bytes());
+        bytes.extend_from_slice(target);
+        bytes.extend_from_slice(challenges);
+        bytes
+    }
+
+    fn solves(&self, index: usize, nonce: u64) -> bool {
+        let Some(challenge) = self.challenges.get(index) else {
+            return false;
+        };
+
+        let mut hasher = Sha256::new();
+        hasher.update(challenge);
+        hasher.update(nonce.to_le_bytes());
+        let digest: [u8; 32] = hasher.finalize().into();
+        digest <= self.target
+    }
+}
+
+/// Issues `n` random sub-challenges, any `:k` of which (default 1) together cost
+/// as much on average as one solution at `difficulty`
+#[rustler::nif]
+fn issue_bundle<'a>(
+    env: Env<'a>,
+    n: usize,
+    difficulty: Difficulty,
+    opts: Options,
+) -> NifResult<GuardedResult<Binary<'a>>> {
+    guard::run_result(|| {
+        let k = opts.get_or(atoms::k(), 1usize)?;
+
+        if let Err(reason) = difficulty.validate() {
+            return Ok(Err(reason));
+        }
+
+        if !(1..=MAX_CHALLENGES).contains(&n) {
+            return Ok(Err((
+                atoms::error(),
+                "Bundle size must be between 1 and 1024",
+            )));
+        }
+
+        if !(1..=n).contains(&k) {
+            return Ok(Err((atoms::error(), "k must be between 1 and n")));
+        }
+
+        let Some(target) = hashes_to_target(difficulty.expected_hashes() / k as f64) else {
+            return Ok(Err((atoms::error(), "Difficulty too low to split k ways")));
+        };
+
+        let mut challenges = vec![0u8; n * CHALLENGE_LEN];
+        entropy::fill(&mut challenges, None);
+
+        let bytes = Bundle::encode(&target, &challenges);
+        let mut binary = NewBinary::new(env, bytes.len());
+        binary.as_mut_slice().copy_from_slice(&bytes);
+        Ok(Ok(binary.into()))
+    })
+}
+
+#[derive(NifMap)]
+struct DecodedBundle<'a> {
+    target: String,
+    challenges: Vec<Binary<'a>>,
+}
+
+/// Splits a bundle into its hex target and sub-challenges, for solvers
+#[rustler::nif]
+fn decode_bundle<'a>(bundle: Binary<'a>) -> NifResult<Guarded<DecodedBundle<'a>>> {
+    guard::run_result(|| {
+        let decoded = Bundle::decode(bundle.as_slice()).ok_or(Error::BadArg)?;
+        let challenges = (0..decoded.challenges.len())
+            .map(|i| {
+                bundle
+                    .make_subbinary(HEADER_LEN + i * CHALLENGE_LEN, CHALLENGE_LEN)
+                    .map_err(|_| Error::BadArg)
+            })
+            .collect::<NifResult<_>>()?;
+
+        Ok(DecodedBundle {
+            target: hex::encode(decoded.target),
+            challenges,
+        })
+    })
+}
+
+/// Checks that at least `k` distinct sub-challenges of the bundle are solved
+#[rustler::nif]
+fn verify_bundle(solutions: (Binary, Vec<(usize, u64)>), k: usize) -> NifResult<Guarded<bool>> {
+    guard::run_result(|| {
+        let (bundle, nonces) = solutions;
+        let bundle = Bundle::decode(bundle.as_slice()).ok_or(Error::BadArg)?;
+
+        let mut solved = vec![false; bundle.challenges.len()];
+        for (index, nonce) in nonces {
+            if index < solved.len() && !solved[index] && bundle.solves(index, nonce) {
+                solved[index] = true;
+            }
+        }
+
+        Ok(k > 0 && solved.iter().filter(|&&solved| solved).count() >= k)
+    })
+}
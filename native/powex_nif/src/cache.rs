@@ -0,0 +1,130 @@
+use lru::LruCache;
+use rustler::{Atom, NifMap};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::atoms;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded};
+
+/// Independently locked shards, so concurrent verifications rarely contend
+const SHARDS: usize = 16;
+
+/// Entries kept per shard by default
+pub const SHARD_CAPACITY: usize = 1_024;
+
+/// Cache key: a collision-resistant digest of the data, never the data itself
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    data: [u8; 32],
+    nonce: u64,
+    difficulty: Difficulty,
+}
+
+struct VerificationCache {
+    shards: Vec<Mutex<LruCache<Key, bool>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+fn cache() -> &'static VerificationCache {
+    static CACHE: OnceLock<VerificationCache> = OnceLock::new();
+    CACHE.get_or_init(|| VerificationCache {
+        shards: (0..SHARDS)
+            .map(|_| Mutex::new(LruCache::new(NonZeroUsize::new(SHARD_CAPACITY).unwrap())))
+            .collect(),
+        hits: AtomicU64::new(0),
+        misses: AtomicU64::new(0),
+    })
+}
+
+fn shard(key: &Key) -> &'static Mutex<LruCache<Key, bool>> {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    &cache().shards[hasher.finish() as usize % SHARDS]
+}
+
+/// Changes the number of entries kept per shard, evicting the oldest ones if shrinking
+pub fn resize(shard_capacity: usize) {
+    let capacity = NonZeroUsize::new(shard_capacity.max(1)).unwrap();
+    for shard in &cache().shards {
+        shard.lock().unwrap().resize(capacity);
+    }
+}
+
+/// Returns the cached verification result, or computes and caches it
+///
+/// The data is keyed by its BLAKE3 digest, so a false hit would need a BLAKE3 collision.
+pub fn verify_cached(
+    data: &[u8],
+    nonce: u64,
+    difficulty: Difficulty,
+    verify: impl FnOnce() -> bool,
+) -> bool {
+    let key = Key {
+        data: *blake3::hash(data).as_bytes(),
+        nonce,
+        difficulty,
+    };
+    let shard = shard(&key);
+
+    if let Some(&valid) = shard.lock().unwrap().get(&key) {
+        cache().hits.fetch_add(1, Ordering::Relaxed);
+        return valid;
+    }
+
+    cache().misses.fetch_add(1, Ordering::Relaxed);
+    let valid = verify();
+    shard.lock().unwrap().put(key, valid);
+    valid
+}
+
+#[derive(NifMap)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub size: usize,
+    pub capacity: usize,
+}
+
+/// Exposes `stats` to Elixir
+#[rustler::nif]
+fn cache_stats() -> Guarded<CacheStats> {
+    guard::run(stats)
+}
+
+/// Gets hit/miss counters and the occupancy of the verification cache
+pub fn stats() -> CacheStats {
+    let cache = cache();
+    CacheStats {
+        hits: cache.hits.load(Ordering::Relaxed),
+        misses: cache.misses.load(Ordering::Relaxed),
+        size: cache
+            .shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum(),
+        capacity: cache
+            .shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().cap().get())
+            .sum(),
+    }
+}
+
+/// Empties the verification cache and resets its counters
+#[rustler::nif]
+fn clear_cache() -> Guarded<Atom> {
+    guard::run(|| {
+        let cache = cache();
+        for shard in &cache.shards {
+            shard.lock().unwrap().clear();
+        }
+        cache.hits.store(0, Ordering::Relaxed);
+        cache.misses.store(0, Ordering::Relaxed);
+        atoms::ok()
+    })
+}
@@ -0,0 +1,110 @@
+use rustler::{Atom, NifMap, NifResult};
+
+use crate::clock;
+use crate::difficulty::{bits_to_target, expected_hashes};
+use crate::guard::{self, Guarded};
+use crate::options::Options;
+use crate::{atoms, Status};
+
+/// Number of previous blocks considered by the median-time-past rule
+const DEFAULT_MTP_WINDOW: usize = 11;
+
+/// Maximum seconds a timestamp may be ahead of the local clock
+const DEFAULT_MAX_FUTURE_DRIFT: u64 = 2 * 60 * 60;
+
+/// Median of the most recent `window` timestamps (the last elements of the slice)
+pub fn median_time_past(timestamps: &[u64], window: usize) -> Option<u64> {
+    let recent = &timestamps[timestamps.len().saturating_sub(window)..];
+    if recent.is_empty() {
+        return None;
+    }
+
+    let mut sorted = recent.to_vec();
+    sorted.sort_unstable();
+    Some(sorted[sorted.len() / 2])
+}
+
+/// Checks a candidate block timestamp against median-time-past and max-future-drift rules
+#[rustler::nif]
+fn validate_timestamp(
+    timestamps: Vec<u64>,
+    candidate: u64,
+    opts: Options,
+) -> NifResult<Guarded<Status>> {
+    guard::run_result(|| {
+        let window = opts.get_or(atoms::window(), DEFAULT_MTP_WINDOW)?;
+        let max_drift = opts.get_or(atoms::max_future_drift(), DEFAULT_MAX_FUTURE_DRIFT)?;
+        let now = opts.get::<u64>(atoms::now())?.unwrap_or_else(clock::now);
+
+        if let Some(median) = median_time_past(&timestamps, window) {
+            if candidate <= median {
+                return Ok(Status::Error(atoms::too_old()));
+            }
+        }
+
+        if candidate > now.saturating_add(max_drift) {
+            return Ok(Status::Error(atoms::too_far_in_future()));
+        }
+
+        Ok(Status::Ok)
+    })
+}
+
+/// Two-sided 95% normal quantile
+const Z_95: f64 = 1.959_963_985;
+
+#[derive(NifMap)]
+struct HashrateEstimate {
+    hashrate: f64,
+    lower: f64,
+    upper: f64,
+    blocks: u64,
+}
+
+/// Wilson-Hilferty approximation of the chi-square quantile with `dof` degrees of freedom
+fn chi_square_quantile(z: f64, dof: f64) -> f64 {
+    let k = 2.0 / (9.0 * dof);
+    dof * (1.0 - k + z * k.sqrt()).powi(3)
+}
+
+/// Estimates the aggregate hashrate from `{bits, timestamp}` pairs of consecutive blocks, oldest first
+///
+/// The first entry only anchors the time span. Block arrivals are modelled as a Poisson process;
+/// the span ends at a block, so it covers `n` whole inter-arrival times and both bounds of the
+/// 95% interval come from the chi-square distribution with `2n` degrees of freedom.
+#[rustler::nif]
+fn estimate_hashrate(
+    targets_and_timestamps: Vec<(u32, u64)>,
+) -> Guarded<Result<HashrateEstimate, Atom>> {
+    guard::run(|| {
+        let (first, rest) = match targets_and_timestamps.split_first() {
+            Some((first, rest)) if !rest.is_empty() => (first, rest),
+            _ => return Err(atoms::insufficient_data()),
+        };
+
+        let last = rest[rest.len() - 1].1;
+        if last <= first.1 {
+            return Err(atoms::insufficient_data());
+        }
+
+        let mut work = 0.0;
+        for &(bits, _) in rest {
+            match bits_to_target(bits) {
+                Some(target) if target != [0u8; 32] => work += expected_hashes(&target),
+                _ => return Err(atoms::invalid()),
+            }
+        }
+
+        let span = (last - first.1) as f64;
+        let blocks = rest.len() as f64;
+        let hashrate = work / span;
+        let scale = |z: f64, dof: f64| chi_square_quantile(z, dof) / (2.0 * blocks);
+
+        Ok(HashrateEstimate {
+            hashrate,
+            lower: hashrate * scale(-Z_95, 2.0 * blocks),
+            upper: hashrate * scale(Z_95, 2.0 * blocks),
+            blocks: rest.len() as u64,
+        })
+    })
+}
@@ -0,0 +1,55 @@
+use rustler::{Atom, Error, NifResult, Term};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::atoms;
+use crate::guard::{self, Guarded};
+
+/// `FIXED` while the system clock is in use
+const SYSTEM: u64 = u64::MAX;
+
+/// Unix time set with `configure(clock: seconds)`, or `SYSTEM`
+static FIXED: AtomicU64 = AtomicU64::new(SYSTEM);
+
+/// Seconds added to the clock, from `configure(clock_offset: seconds)`
+static OFFSET: AtomicI64 = AtomicI64::new(0);
+
+fn system_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// The current Unix time in seconds as verification sees it: the system clock, or the
+/// fixed time set with `configure/1`, plus the configured offset. Every expiry check uses
+/// it when a call has no `:now` of its own
+pub fn now() -> u64 {
+    let base = match FIXED.load(Ordering::Relaxed) {
+        SYSTEM => system_now(),
+        fixed => fixed,
+    };
+    base.saturating_add_signed(OFFSET.load(Ordering::Relaxed))
+}
+
+/// Sets the clock from the `:clock` option of `configure/1`: `:system`, or a Unix time in
+/// seconds that stays fixed until set again
+pub fn set(clock: Term) -> NifResult<()> {
+    let fixed = match clock.decode::<Atom>() {
+        Ok(atom) if atom == atoms::system() => SYSTEM,
+        Ok(_) => return Err(Error::BadArg),
+        Err(_) => clock.decode::<u64>()?.min(SYSTEM - 1),
+    };
+    FIXED.store(fixed, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn set_offset(seconds: i64) {
+    OFFSET.store(seconds, Ordering::Relaxed);
+}
+
+/// The Unix time in seconds that expiry checks currently use
+#[rustler::nif]
+fn clock_now() -> Guarded<u64> {
+    guard::run(now)
+}
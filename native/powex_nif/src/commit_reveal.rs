@@ -0,0 +1,129 @@
+//! Commit-reveal derivation of a challenge no single party can bias.
+//!
+//! Each party draws a contribution, publishes a commitment to it, and only reveals the
+//! opening once every commitment is in. An opening is `salt (32 bytes) || contribution`
+//! and its commitment is a BLAKE3 hash of it: the salt hides short contributions and the
+//! hash binds the party to its contribution. The challenge hashes every contribution
+//! ordered by commitment, so all parties derive the same bytes whatever order they
+//! received the openings in, and a single honest contribution makes them unpredictable.
+
+use rustler::{Atom, Binary, Env, NewBinary, NifMap, NifResult};
+
+use crate::atoms;
+use crate::entropy;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+
+/// Size of the salt heading an opening
+const SALT_LEN: usize = 32;
+
+/// Size of the contribution drawn when none is given
+const DEFAULT_CONTRIBUTION_LEN: usize = 32;
+
+/// Size of commitments and of derived challenges
+const HASH_LEN: usize = 32;
+
+/// BLAKE3 key derivation context of commitments
+const COMMIT_CONTEXT: &str = "powex 2026-10-15 challenge commitment";
+
+/// BLAKE3 key derivation context of the joint challenge
+const CHALLENGE_CONTEXT: &str = "powex 2026-10-15 commit-reveal challenge";
+
+#[derive(NifMap)]
+struct Commitment<'a> {
+    /// Published right away
+    commitment: Binary<'a>,
+    /// Kept secret until every commitment is in
+    opening: Binary<'a>,
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.into()
+}
+
+fn commit(opening: &[u8]) -> [u8; HASH_LEN] {
+    *blake3::Hasher::new_derive_key(COMMIT_CONTEXT).update(opening).finalize().as_bytes()
+}
+
+/// Whether `opening` opens `commitment`, compared in constant time
+fn opens(commitment_bytes: &[u8], opening: &[u8]) -> bool {
+    let Ok(expected) = <[u8; HASH_LEN]>::try_from(commitment_bytes) else {
+        return false;
+    };
+    opening.len() > SALT_LEN && blake3::Hash::from(commit(opening)) == expected
+}
+
+/// Commits to `:contribution`, by default 32 fresh random bytes, under a random salt
+#[rustler::nif]
+fn commit_challenge<'a>(
+    env: Env<'a>,
+    opts: Options,
+) -> NifResult<GuardedResult<Commitment<'a>>> {
+    guard::run_result(|| {
+        let contribution: Option<Binary> = opts.get(atoms::contribution())?;
+
+        let mut opening = match &contribution {
+            Some(contribution) if contribution.is_empty() => {
+                return Ok(Err((atoms::error(), "Contribution must not be empty")));
+            }
+            Some(contribution) => {
+                let mut opening = vec![0u8; SALT_LEN];
+                opening.extend_from_slice(contribution);
+                opening
+            }
+            None => vec![0u8; SALT_LEN + DEFAULT_CONTRIBUTION_LEN],
+        };
+        let random = if contribution.is_some() { SALT_LEN } else { opening.len() };
+        entropy::fill(&mut opening[..random], None);
+
+        Ok(Ok(Commitment {
+            commitment: to_binary(env, &commit(&opening)),
+            opening: to_binary(env, &opening),
+        }))
+    })
+}
+
+/// Checks that `opening` opens `commitment`
+#[rustler::nif]
+fn verify_opening(commitment: Binary, opening: Binary) -> Guarded<bool> {
+    guard::run(|| opens(&commitment, &opening))
+}
+
+/// Checks every opening against the commitment at the same position, then derives the
+/// challenge from all contributions in commitment order
+#[rustler::nif]
+fn open_challenge<'a>(
+    env: Env<'a>,
+    commitments: Vec<Binary>,
+    openings: Vec<Binary>,
+) -> Guarded<Result<Binary<'a>, Atom>> {
+    guard::run(|| {
+        if commitments.is_empty() || commitments.len() != openings.len() {
+            return Err(atoms::malformed());
+        }
+        let mut pairs: Vec<(&[u8], &[u8])> = commitments
+            .iter()
+            .zip(&openings)
+            .map(|(commitment, opening)| (commitment.as_slice(), opening.as_slice()))
+            .collect();
+        if !pairs.iter().all(|&(commitment, opening)| opens(commitment, opening)) {
+            return Err(atoms::bad_opening());
+        }
+        pairs.sort_unstable();
+        if pairs.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(atoms::duplicate());
+        }
+
+        let mut hasher = blake3::Hasher::new_derive_key(CHALLENGE_CONTEXT);
+        hasher.update(&(pairs.len() as u64).to_le_bytes());
+        for (commitment, opening) in &pairs {
+            let contribution = &opening[SALT_LEN..];
+            hasher.update(commitment);
+            hasher.update(&(contribution.len() as u64).to_le_bytes());
+            hasher.update(contribution);
+        }
+        Ok(to_binary(env, hasher.finalize().as_bytes()))
+    })
+}
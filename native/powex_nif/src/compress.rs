@@ -0,0 +1,170 @@
+use miniz_oxide::inflate::TINFLStatus;
+use rustler::{Atom, Binary, Env, NewBinary};
+use std::collections::HashMap;
+
+use crate::algorithm::write_varint;
+use crate::atoms;
+use crate::guard::{self, Guarded};
+use crate::input;
+use crate::proof;
+
+/// Version byte of the compressed batch encoding
+const BATCH_VERSION: u8 = 1;
+
+/// Deflate level: the body is mostly varints and repeated data, so higher levels gain little
+const DEFLATE_LEVEL: u8 = 6;
+
+/// Reads an unsigned LEB128 varint
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u64).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn zigzag(delta: i64) -> u64 {
+    ((delta << 1) ^ (delta >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Encodes a batch of proofs. Proofs sharing their challenge and parameters share one
+/// template, the proof without its nonce, and each proof is reduced to the index of its
+/// template and the zigzag varint difference from the previous nonce of that template.
+///
+/// Layout: `version || deflate(count || templates || proofs)`, with `count` templates of
+/// `length || bytes`, then the proof count and, per proof, the template index (only when
+/// there are several templates) and the nonce difference, all varints.
+fn compress(proofs: &[Binary]) -> Option<Vec<u8>> {
+    let mut index: HashMap<Vec<u8>, u64> = HashMap::new();
+    let mut templates: Vec<Vec<u8>> = vec![];
+    let mut entries = Vec::with_capacity(proofs.len());
+
+    for bytes in proofs {
+        let (template, nonce) = proof::split_nonce(bytes.as_slice())?;
+        let id = *index.entry(template.clone()).or_insert_with(|| {
+            templates.push(template);
+            templates.len() as u64 - 1
+        });
+        entries.push((id, nonce));
+    }
+
+    let mut body = vec![];
+    write_varint(&mut body, templates.len() as u64);
+    for template in &templates {
+        write_varint(&mut body, template.len() as u64);
+        body.extend_from_slice(template);
+    }
+
+    write_varint(&mut body, entries.len() as u64);
+    let mut previous = vec![0u64; templates.len()];
+    for (id, nonce) in entries {
+        if templates.len() > 1 {
+            write_varint(&mut body, id);
+        }
+        let previous = &mut previous[id as usize];
+        write_varint(&mut body, zigzag(nonce.wrapping_sub(*previous) as i64));
+        *previous = nonce;
+    }
+
+    let mut compressed = vec![BATCH_VERSION];
+    compressed.extend(miniz_oxide::deflate::compress_to_vec(&body, DEFLATE_LEVEL));
+    Some(compressed)
+}
+
+/// Decodes a batch from `compress`, refusing to expand past the `:max_data_size` of
+/// `configure/1` so a small batch can't blow up into gigabytes of proofs
+fn decompress(bytes: &[u8]) -> Result<Vec<Vec<u8>>, Atom> {
+    let limit = input::max_data_size();
+    let (&version, compressed) = bytes.split_first().ok_or(atoms::malformed())?;
+    if version != BATCH_VERSION {
+        return Err(atoms::malformed());
+    }
+    let body = miniz_oxide::inflate::decompress_to_vec_with_limit(compressed, limit).map_err(
+        |error| match error.status {
+            TINFLStatus::HasMoreOutput => atoms::data_too_large(),
+            _ => atoms::malformed(),
+        },
+    )?;
+
+    let mut body = body.as_slice();
+    let count = read_varint(&mut body).ok_or(atoms::malformed())?;
+    let mut templates = vec![];
+    for _ in 0..count {
+        let length = read_varint(&mut body).ok_or(atoms::malformed())?;
+        if length > body.len() as u64 {
+            return Err(atoms::malformed());
+        }
+        let (template, rest) = body.split_at(length as usize);
+        templates.push(template);
+        body = rest;
+    }
+
+    let proofs = read_varint(&mut body).ok_or(atoms::malformed())?;
+    let mut previous = vec![0u64; templates.len()];
+    let mut expanded = 0usize;
+    let mut output = vec![];
+    for _ in 0..proofs {
+        let id = match templates.len() {
+            1 => 0,
+            _ => read_varint(&mut body).ok_or(atoms::malformed())? as usize,
+        };
+        let template = templates.get(id).ok_or(atoms::malformed())?;
+        let delta = unzigzag(read_varint(&mut body).ok_or(atoms::malformed())?);
+        let nonce = previous[id].wrapping_add(delta as u64);
+        previous[id] = nonce;
+
+        expanded = expanded.saturating_add(template.len() + 8);
+        if expanded > limit {
+            return Err(atoms::data_too_large());
+        }
+        let mut proof = Vec::with_capacity(template.len() + 8);
+        if !proof::join_nonce(template, nonce, &mut proof) {
+            return Err(atoms::malformed());
+        }
+        output.push(proof);
+    }
+
+    if !body.is_empty() {
+        return Err(atoms::malformed());
+    }
+    Ok(output)
+}
+
+/// Compresses proofs from `encode_proof` for transport, in their original order
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compress_proofs<'a>(env: Env<'a>, proofs: Vec<Binary>) -> Guarded<Result<Binary<'a>, Atom>> {
+    guard::run(|| {
+        let bytes = compress(&proofs).ok_or(atoms::malformed())?;
+        let mut binary = NewBinary::new(env, bytes.len());
+        binary.as_mut_slice().copy_from_slice(&bytes);
+        Ok(binary.into())
+    })
+}
+
+/// Restores the proofs of a `compress_proofs` batch
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decompress_proofs<'a>(
+    env: Env<'a>,
+    batch: Binary,
+) -> Guarded<Result<Vec<Binary<'a>>, Atom>> {
+    guard::run(|| {
+        let proofs = decompress(batch.as_slice())?;
+        Ok(proofs
+            .iter()
+            .map(|bytes| {
+                let mut binary = NewBinary::new(env, bytes.len());
+                binary.as_mut_slice().copy_from_slice(bytes);
+                binary.into()
+            })
+            .collect())
+    })
+}
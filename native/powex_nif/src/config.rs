@@ -0,0 +1,170 @@
+use rustler::{Atom, NifResult, NifUnitEnum, Term};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+
+use crate::atoms;
+use crate::budget;
+use crate::cache;
+use crate::clock;
+use crate::entropy;
+use crate::guard::{self, Guarded};
+use crate::input;
+use crate::numa;
+use crate::options::Options;
+
+/// Stack size of background workers under the low-memory profile; search loops need little
+const LOW_MEMORY_STACK_SIZE: usize = 256 * 1024;
+
+/// Verification cache entries per shard under the low-memory profile
+const LOW_MEMORY_CACHE_SHARD_CAPACITY: usize = 64;
+
+/// Most OS threads a search or daemon may run under the mobile profile
+const MOBILE_MAX_THREADS: u32 = 2;
+
+/// Fraction of the time background workers hash under the mobile profile
+const MOBILE_DUTY_CYCLE: f64 = 0.5;
+
+/// Resource profiles selectable with `configure/1`
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// Sized for servers and desktops
+    Default,
+    /// Small caches and worker stacks for Raspberry Pi-class devices
+    LowMemory,
+    /// The low-memory sizes, at most two threads per search or daemon, and background work
+    /// held to half the time, for phones
+    Mobile,
+}
+
+impl Profile {
+    fn to_u8(self) -> u8 {
+        match self {
+            Profile::Default => 0,
+            Profile::LowMemory => 1,
+            Profile::Mobile => 2,
+        }
+    }
+
+    fn from_u8(value: u8) -> Profile {
+        match value {
+            1 => Profile::LowMemory,
+            2 => Profile::Mobile,
+            _ => Profile::Default,
+        }
+    }
+
+    fn cache_shard_capacity(self) -> usize {
+        match self {
+            Profile::Default => cache::SHARD_CAPACITY,
+            Profile::LowMemory | Profile::Mobile => LOW_MEMORY_CACHE_SHARD_CAPACITY,
+        }
+    }
+
+    fn stack_size(self) -> Option<usize> {
+        match self {
+            Profile::Default => None,
+            Profile::LowMemory | Profile::Mobile => Some(LOW_MEMORY_STACK_SIZE),
+        }
+    }
+
+    /// Caps the threads of a search or daemon
+    pub fn cap_threads(self, threads: u32) -> u32 {
+        match self {
+            Profile::Mobile => threads.min(MOBILE_MAX_THREADS),
+            _ => threads,
+        }
+    }
+
+    /// Largest fraction of the time background workers may hash
+    pub fn max_duty_cycle(self) -> f64 {
+        match self {
+            Profile::Mobile => MOBILE_DUTY_CYCLE,
+            _ => 1.0,
+        }
+    }
+}
+
+static PROFILE: AtomicU8 = AtomicU8::new(0);
+
+/// Whether background workers are pinned to NUMA nodes, set with `:numa`
+static NUMA: AtomicBool = AtomicBool::new(false);
+
+/// Workers spawned so far, dealing nodes out round-robin
+static SPAWNED: AtomicUsize = AtomicUsize::new(0);
+
+/// Gets the active resource profile
+pub fn profile() -> Profile {
+    Profile::from_u8(PROFILE.load(Ordering::Relaxed))
+}
+
+/// Spawns a background worker with the stack size of the active profile. With `:numa` on
+/// a multi-node machine, workers are pinned to the nodes in turn before they allocate
+/// anything, balancing them across nodes with their memory local
+pub fn spawn_worker<F: FnOnce() + Send + 'static>(work: F) -> JoinHandle<()> {
+    let mut builder = thread::Builder::new().name("powex-worker".into());
+    if let Some(size) = profile().stack_size() {
+        builder = builder.stack_size(size);
+    }
+
+    let nodes = numa::nodes().len();
+    let node = (NUMA.load(Ordering::Relaxed) && nodes > 1)
+        .then(|| SPAWNED.fetch_add(1, Ordering::Relaxed) % nodes);
+    builder
+        .spawn(move || {
+            if let Some(node) = node {
+                numa::bind(node);
+            }
+            work()
+        })
+        .expect("failed to spawn worker thread")
+}
+
+/// Number of workers to run: `requested`, or one per core by default. With `reserved`
+/// cores kept free for the BEAM schedulers, the count is capped to the remaining cores
+/// (at least one worker remains). The profile may cap it further.
+pub fn worker_count(requested: Option<u32>, reserved: u32) -> u32 {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    let available = cores.saturating_sub(reserved).max(1);
+    let count = match requested {
+        Some(threads) if reserved > 0 => threads.min(available),
+        Some(threads) => threads,
+        None => available,
+    };
+    profile().cap_threads(count)
+}
+
+/// Applies global settings; `:profile` switches the resource profile, `:max_data_size`
+/// caps the bytes of data accepted by the search and validation functions, and
+/// `:max_verify_bytes` / `:max_verify_memory` bound the cost of a single verification, and
+/// `:numa` pins background workers to NUMA nodes
+#[rustler::nif]
+fn configure(opts: Options) -> NifResult<Guarded<Atom>> {
+    guard::run_result(|| {
+        if let Some(profile) = opts.get::<Profile>(atoms::profile())? {
+            PROFILE.store(profile.to_u8(), Ordering::Relaxed);
+            cache::resize(profile.cache_shard_capacity());
+        }
+        if let Some(bytes) = opts.get::<usize>(atoms::max_data_size())? {
+            input::set_max_data_size(bytes);
+        }
+        if let Some(bytes) = opts.get::<u64>(atoms::max_verify_bytes())? {
+            budget::set_max_verify_bytes(bytes);
+        }
+        if let Some(bytes) = opts.get::<u64>(atoms::max_verify_memory())? {
+            budget::set_max_verify_memory(bytes);
+        }
+        if let Some(numa) = opts.get::<bool>(atoms::numa())? {
+            NUMA.store(numa, Ordering::Relaxed);
+        }
+        if let Some(source) = opts.get::<Term>(atoms::clock())? {
+            clock::set(source)?;
+        }
+        if let Some(seconds) = opts.get::<i64>(atoms::clock_offset())? {
+            clock::set_offset(seconds);
+        }
+        if let Some(source) = opts.get::<Term>(atoms::entropy())? {
+            entropy::set(source)?;
+        }
+        Ok(atoms::ok())
+    })
+}
@@ -0,0 +1,127 @@
+use rustler::{Atom, Error, NifResult, Resource, ResourceArc, Term};
+use std::sync::Mutex;
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+
+/// Most leading zero bits a 256-bit digest can have
+const MAX_ZERO_BITS: u32 = 256;
+
+/// Weight of the newest observation in the moving average
+const DEFAULT_ALPHA: f64 = 0.2;
+
+/// Bits added per doubling of the ratio between the target and the average solve time
+const DEFAULT_GAIN: f64 = 0.5;
+
+/// A closed-loop difficulty controller. Solve times are smoothed with an exponentially
+/// weighted moving average, and the difficulty, kept as a fractional number of zero bits,
+/// integrates the log2 ratio of the target to the average: one bit doubles the expected
+/// solve time, so the error is linear in bits. Clamping to the bounds keeps the integral
+/// from winding up while the difficulty is pinned
+pub struct Controller {
+    target_ms: f64,
+    min_bits: u32,
+    max_bits: u32,
+    alpha: f64,
+    gain: f64,
+    state: Mutex<ControllerState>,
+}
+
+#[rustler::resource_impl]
+impl Resource for Controller {}
+
+struct ControllerState {
+    average_ms: Option<f64>,
+    bits: f64,
+}
+
+impl Controller {
+    fn observe(&self, solve_ms: f64) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        let average = match state.average_ms {
+            Some(average) => average + self.alpha * (solve_ms - average),
+            None => solve_ms,
+        };
+        state.average_ms = Some(average);
+
+        // A zero average would mean infinitely fast solvers; treat it as a microsecond
+        let error = (self.target_ms / average.max(0.001)).log2();
+        state.bits =
+            (state.bits + self.gain * error).clamp(self.min_bits as f64, self.max_bits as f64);
+        state.bits.round() as u32
+    }
+
+    fn difficulty(&self) -> u32 {
+        self.state.lock().unwrap().bits.round() as u32
+    }
+}
+
+/// A duration in milliseconds, given as an integer or a float
+fn milliseconds(term: Term) -> NifResult<f64> {
+    let ms = match term.decode::<u64>() {
+        Ok(ms) => ms as f64,
+        Err(_) => term.decode::<f64>()?,
+    };
+    if !ms.is_finite() || ms < 0.0 {
+        return Err(Error::BadArg);
+    }
+    Ok(ms)
+}
+
+/// Creates a controller steering solve times toward `target_solve_ms` with a difficulty
+/// between `min_bits` and `max_bits` zero bits, starting at `:initial` (default: the minimum)
+#[rustler::nif]
+fn controller_new(
+    target_solve_ms: Term,
+    bounds: (u32, u32),
+    opts: Options,
+) -> NifResult<GuardedResult<ResourceArc<Controller>>> {
+    guard::run_result(|| {
+        let target_ms = milliseconds(target_solve_ms)?;
+        let (min_bits, max_bits) = bounds;
+        let initial = opts.get_or(atoms::initial(), min_bits)?;
+        let alpha = opts.get_or(atoms::alpha(), DEFAULT_ALPHA)?;
+        let gain = opts.get_or(atoms::gain(), DEFAULT_GAIN)?;
+
+        if target_ms <= 0.0 {
+            return Ok(Err((atoms::error(), "Target solve time must be positive")));
+        }
+        if min_bits > max_bits || max_bits > MAX_ZERO_BITS {
+            return Ok(Err((atoms::error(), "Invalid bounds (0-256 zero bits, min <= max)")));
+        }
+        if !(min_bits..=max_bits).contains(&initial) {
+            return Ok(Err((atoms::error(), "Initial difficulty is outside the bounds")));
+        }
+        if alpha <= 0.0 || alpha > 1.0 || gain <= 0.0 {
+            return Ok(Err((atoms::error(), "Alpha must be in (0, 1] and gain positive")));
+        }
+
+        Ok(Ok(ResourceArc::new(Controller {
+            target_ms,
+            min_bits,
+            max_bits,
+            alpha,
+            gain,
+            state: Mutex::new(ControllerState { average_ms: None, bits: initial as f64 }),
+        })))
+    })
+}
+
+/// Feeds one observed solve time to the controller and returns the updated difficulty
+#[rustler::nif]
+fn controller_observe(
+    controller: ResourceArc<Controller>,
+    solve_ms: Term,
+) -> NifResult<Guarded<(Atom, u32)>> {
+    guard::run_result(|| {
+        let bits = controller.observe(milliseconds(solve_ms)?);
+        Ok((atoms::zero_bits(), bits))
+    })
+}
+
+/// The difficulty the controller currently asks for, as `{:zero_bits, n}`
+#[rustler::nif]
+fn controller_difficulty(controller: ResourceArc<Controller>) -> Guarded<(Atom, u32)> {
+    guard::run(|| (atoms::zero_bits(), controller.difficulty()))
+}
@@ -0,0 +1,136 @@
+//! Runtime CPU feature detection. Every SIMD path is compiled into one binary and picked
+//! per CPU on first use: SHA-NI and the ARMv8 SHA-2 instructions by `sha2`, SSE4.1, AVX2
+//! and AVX-512 by `blake3`, the ARMv8.2 SHA-3 instructions by `keccak`. Builds therefore
+//! need no `target-cpu` flags, and a precompiled NIF runs at full speed on every CPU of its
+//! architecture. Detection goes through `cpufeatures`, as in `sha2` and `keccak`, so what is
+//! reported matches what they select.
+
+use rustler::{Atom, Env, NifMap, NifResult};
+use std::sync::OnceLock;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod detect {
+    cpufeatures::new!(sha_ni, "sha", "sse2", "ssse3", "sse4.1");
+    cpufeatures::new!(sse41, "sse4.1");
+    cpufeatures::new!(avx2, "avx2");
+    cpufeatures::new!(avx512, "avx512f", "avx512vl");
+
+    /// Each feature with whether the CPU has it
+    pub fn detected() -> Vec<(&'static str, bool)> {
+        vec![
+            ("sha_ni", sha_ni::get()),
+            ("sse41", sse41::get()),
+            ("avx2", avx2::get()),
+            ("avx512", avx512::get()),
+        ]
+    }
+
+    /// Each feature with whether the build assumes it, through `target-cpu` or
+    /// `target-feature` flags
+    pub fn required() -> Vec<(&'static str, bool)> {
+        vec![
+            ("sha_ni", cfg!(target_feature = "sha")),
+            ("sse41", cfg!(target_feature = "sse4.1")),
+            ("avx2", cfg!(target_feature = "avx2")),
+            ("avx512", cfg!(target_feature = "avx512f")),
+        ]
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod detect {
+    cpufeatures::new!(sha2, "sha2");
+    cpufeatures::new!(sha3, "sha3");
+
+    /// Each feature with whether the CPU has it; NEON is part of the AArch64 baseline
+    pub fn detected() -> Vec<(&'static str, bool)> {
+        vec![("neon", true), ("sha2", sha2::get()), ("sha3", sha3::get())]
+    }
+
+    /// Each feature with whether the build assumes it, through `target-cpu` or
+    /// `target-feature` flags
+    pub fn required() -> Vec<(&'static str, bool)> {
+        vec![
+            ("neon", true),
+            ("sha2", cfg!(target_feature = "sha2")),
+            ("sha3", cfg!(target_feature = "sha3")),
+        ]
+    }
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+mod detect {
+    /// No SIMD paths are selected at runtime on other architectures
+    pub fn detected() -> Vec<(&'static str, bool)> {
+        vec![]
+    }
+
+    /// The 32-bit ARM `neon` cargo feature is the only compile-time SIMD choice
+    pub fn required() -> Vec<(&'static str, bool)> {
+        vec![("neon", cfg!(feature = "neon"))]
+    }
+}
+
+fn names(features: Vec<(&'static str, bool)>) -> Vec<&'static str> {
+    features
+        .into_iter()
+        .filter_map(|(name, present)| present.then_some(name))
+        .collect()
+}
+
+/// Features the build assumes but the CPU lacks; running on such a CPU would crash with an
+/// illegal instruction, so `guard::run` refuses every call instead
+pub fn missing() -> &'static [&'static str] {
+    static MISSING: OnceLock<Vec<&'static str>> = OnceLock::new();
+    MISSING.get_or_init(|| {
+        let detected = names(detect::detected());
+        names(detect::required())
+            .into_iter()
+            .filter(|name| !detected.contains(name))
+            .collect()
+    })
+}
+
+/// The SHA-256 implementation `sha2` dispatches to on this CPU: `sha_ni`, `armv8` or `soft`
+pub fn sha256_backend() -> &'static str {
+    let detected = names(detect::detected());
+    if detected.contains(&"sha_ni") {
+        "sha_ni"
+    } else if cfg!(target_arch = "aarch64") && detected.contains(&"sha2") {
+        "armv8"
+    } else {
+        "soft"
+    }
+}
+
+/// Whether the CPU has every feature the build assumes
+pub fn supported() -> bool {
+    missing().is_empty()
+}
+
+#[derive(NifMap)]
+struct CpuFeatures {
+    arch: String,
+    detected: Vec<Atom>,
+    required: Vec<Atom>,
+    missing: Vec<Atom>,
+}
+
+/// Lists the SIMD features of the CPU, those the build was compiled to require, and the
+/// required ones the CPU lacks. Not guarded, so it still answers on such a CPU
+#[rustler::nif]
+fn cpu_features(env: Env) -> NifResult<CpuFeatures> {
+    let atoms = |names: Vec<&'static str>| {
+        names
+            .into_iter()
+            .map(|name| Atom::from_str(env, name))
+            .collect::<NifResult<Vec<Atom>>>()
+    };
+
+    Ok(CpuFeatures {
+        arch: std::env::consts::ARCH.to_string(),
+        detected: atoms(names(detect::detected()))?,
+        required: atoms(names(detect::required()))?,
+        missing: atoms(missing().to_vec())?,
+    })
+}
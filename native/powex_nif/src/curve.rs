@@ -0,0 +1,70 @@
+//! Discrete-log style puzzles on the BN254 G1 curve: a nonce is accepted when the point
+//! `H(data || nonce) * G` lands in a sparse set, the points whose x-coordinate is below
+//! `2^(254 - zero_bits)`. Each candidate costs the solver a scalar multiplication that no
+//! hash shortcut avoids, and verifying is that single multiplication.
+
+use ark_bn254::{Fr, G1Projective};
+use ark_ec::{CurveGroup, Group};
+use ark_ff::{BigInteger, PrimeField};
+use rustler::{Binary, NifResult};
+use sha2::{Digest, Sha256};
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::input;
+use crate::metrics;
+
+/// Bits of a BN254 base field element
+const FIELD_BITS: u32 = 254;
+
+/// Hardest difficulty accepted; each candidate costs far more than a hash
+const MAX_ZERO_BITS: u32 = 64;
+
+/// Candidates tried before `compute_curve` gives up
+const MAX_ATTEMPTS: u64 = 1 << 32;
+
+/// Maps data and nonce to a scalar through SHA-256
+fn scalar(data: &[u8], nonce: u64) -> Fr {
+    let digest = Sha256::new()
+        .chain_update(data)
+        .chain_update(nonce.to_le_bytes())
+        .finalize();
+    Fr::from_le_bytes_mod_order(&digest)
+}
+
+/// Whether the candidate's point is in the accepted set; the identity never is
+fn accepted(data: &[u8], nonce: u64, zero_bits: u32) -> bool {
+    let point = (G1Projective::generator() * scalar(data, nonce)).into_affine();
+    if point.infinity {
+        return false;
+    }
+    point.x.into_bigint().num_bits() <= FIELD_BITS - zero_bits
+}
+
+/// Finds the first nonce whose point has an x-coordinate with `zero_bits` leading zero bits
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_curve(data: Binary, zero_bits: u32) -> NifResult<GuardedResult<u64>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| {
+        if zero_bits > MAX_ZERO_BITS {
+            return Err((atoms::error(), "Curve difficulty must be at most 64 zero bits"));
+        }
+
+        let mut meter = metrics::Meter::new();
+        for nonce in 0..MAX_ATTEMPTS {
+            meter.tick();
+            if accepted(data.as_slice(), nonce, zero_bits) {
+                return Ok(nonce);
+            }
+        }
+
+        Err((atoms::error(), "No valid nonce found"))
+    }))
+}
+
+/// Checks a curve puzzle nonce with one scalar multiplication
+#[rustler::nif(name = "valid_curve?")]
+fn valid_curve(data: Binary, nonce: u64, zero_bits: u32) -> NifResult<Guarded<bool>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| zero_bits <= MAX_ZERO_BITS && accepted(data.as_slice(), nonce, zero_bits)))
+}
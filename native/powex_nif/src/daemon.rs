@@ -0,0 +1,323 @@
+use rustler::env::OwnedEnv;
+use rustler::{Atom, Binary, LocalPid, NifMap, NifResult, Resource, ResourceArc};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::config;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::input;
+use crate::jobs;
+use crate::lifecycle;
+use crate::log::{self, Level, Value};
+use crate::metrics;
+use crate::options::Options;
+use crate::os_limit::OsLimit;
+use crate::throttle::{DutyCycle, PowerMonitor, PowerPolicy, ThermalMonitor, TokenBucket};
+
+/// A queued search and the process its result goes to
+struct Work {
+    id: u64,
+    priority: i64,
+    algorithm: Algorithm,
+    data: Vec<u8>,
+    difficulty: Difficulty,
+    pid: LocalPid,
+}
+
+impl PartialEq for Work {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Work {}
+
+impl PartialOrd for Work {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Work {
+    /// Higher priority first, then first in, first out
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: BinaryHeap<Work>,
+    next_id: u64,
+    running: usize,
+    completed: u64,
+    stopped: bool,
+}
+
+/// State shared between a daemon handle and its workers
+struct Shared {
+    queue: Mutex<Queue>,
+    available: Condvar,
+    /// Hashrate budget shared by all workers
+    limiter: Option<TokenBucket>,
+    power: PowerMonitor,
+    thermal: Option<ThermalMonitor>,
+    /// OS enforcement of the duty cycle, for throttled daemons
+    os_limit: Option<OsLimit>,
+}
+
+impl Shared {
+    /// Blocks until work is available; once the daemon stopped, returns the work left unserved
+    fn next(&self) -> Result<Work, BinaryHeap<Work>> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if queue.stopped {
+                metrics::dequeued(queue.pending.len() as u64);
+                return Err(std::mem::take(&mut queue.pending));
+            }
+            if let Some(work) = queue.pending.pop() {
+                metrics::dequeued(1);
+                queue.running += 1;
+                return Ok(work);
+            }
+            queue = self.available.wait(queue).unwrap();
+        }
+    }
+
+    /// Wakes the workers so they exit; queued work is failed by them, since only
+    /// non-scheduler threads can send from an owned environment
+    fn stop(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        if !queue.stopped {
+            queue.stopped = true;
+            log::emit(
+                Level::Info,
+                "daemon_stopped",
+                vec![("queued", Value::U64(queue.pending.len() as u64))],
+            );
+        }
+        drop(queue);
+        self.available.notify_all();
+    }
+
+    /// Fraction of the time workers may hash under the profile, power and thermal policies
+    fn duty_cycle(&self) -> f64 {
+        let thermal = self
+            .thermal
+            .as_ref()
+            .map_or(1.0, ThermalMonitor::duty_cycle);
+        let duty_cycle = config::profile().max_duty_cycle() * self.power.duty_cycle() * thermal;
+        if let Some(os_limit) = &self.os_limit {
+            os_limit.apply(duty_cycle);
+        }
+        duty_cycle
+    }
+}
+
+/// Takes work off the queue until the daemon stops, streaming each result to its pid
+fn worker(shared: Arc<Shared>) {
+    let mut env = OwnedEnv::new();
+    let mut duty_cycle = DutyCycle::new();
+    if let Some(os_limit) = &shared.os_limit {
+        os_limit.enter();
+    }
+    let unserved = loop {
+        match shared.next() {
+            Ok(work) => {
+                log::emit(
+                    Level::Debug,
+                    "work_started",
+                    vec![
+                        ("id", Value::U64(work.id)),
+                        ("priority", Value::I64(work.priority)),
+                    ],
+                );
+                let result = guard::run(|| {
+                    jobs::search_paced(&work.algorithm, &work.data, &work.difficulty, |hashes| {
+                        lifecycle::wait_while_paused();
+                        duty_cycle.run_at(shared.duty_cycle());
+                        if let Some(limiter) = &shared.limiter {
+                            limiter.take(hashes);
+                        }
+                    })
+                });
+                let _ =
+                    env.send_and_clear(&work.pid, |_| (atoms::powex_daemon(), work.id, &result));
+                metrics::job_completed();
+                if let Guarded::Ok(result) = &result {
+                    log::search_finished(
+                        "work_finished",
+                        result,
+                        vec![
+                            ("id", Value::U64(work.id)),
+                            ("algorithm", Value::Atom(work.algorithm.name().to_string())),
+                        ],
+                    );
+                }
+
+                let mut queue = shared.queue.lock().unwrap();
+                queue.running -= 1;
+                queue.completed += 1;
+            }
+            Err(unserved) => break unserved,
+        }
+    };
+
+    for work in unserved {
+        let result = Err::<u64, _>((atoms::error(), atoms::stopped()));
+        let _ = env.send_and_clear(&work.pid, |_| (atoms::powex_daemon(), work.id, result));
+        metrics::job_completed();
+    }
+}
+
+/// A persistent worker pool with a priority queue of searches
+pub struct Daemon {
+    shared: Arc<Shared>,
+    workers: usize,
+}
+
+#[rustler::resource_impl]
+impl Resource for Daemon {}
+
+impl Drop for Daemon {
+    /// Workers would otherwise outlive the last reference to the daemon
+    fn drop(&mut self) {
+        self.shared.stop();
+    }
+}
+
+/// Starts a daemon with `:threads` workers (default: available parallelism), keeping
+/// `:reserve_cores` free for the BEAM, optionally
+/// paced to `:max_hashes_per_second` across all workers, following a `:power_policy` and
+/// backing off above `:max_temperature` degrees Celsius. Throttled daemons also have the OS
+/// enforce their duty cycle unless `:os_limit` is false
+#[rustler::nif]
+fn daemon_start(opts: Options) -> NifResult<GuardedResult<ResourceArc<Daemon>>> {
+    guard::run_result(|| {
+        let requested: Option<u32> = opts.get(atoms::threads())?;
+        let reserved = opts.get_or(atoms::reserve_cores(), 0)?;
+        let max_hashes_per_second: Option<u64> = opts.get(atoms::max_hashes_per_second())?;
+        let power_policy = opts.get_or(atoms::power_policy(), PowerPolicy::Ignore)?;
+        let max_temperature: Option<u32> = opts.get(atoms::max_temperature())?;
+        let os_limit = opts.get_or(atoms::os_limit(), true)?;
+
+        if matches!(requested, Some(threads) if threads == 0 || threads > 64) {
+            return Ok(Err((atoms::error(), "Invalid number of threads (1-64)")));
+        }
+
+        if max_hashes_per_second == Some(0) {
+            return Ok(Err((atoms::error(), "Hashrate limit must be positive")));
+        }
+
+        let threads = config::worker_count(requested, reserved).min(64);
+        let throttled = max_hashes_per_second.is_some()
+            || config::profile().max_duty_cycle() < 1.0
+            || power_policy != PowerPolicy::Ignore
+            || max_temperature.is_some();
+        let shared = Arc::new(Shared {
+            queue: Mutex::default(),
+            available: Condvar::new(),
+            limiter: max_hashes_per_second.map(TokenBucket::new),
+            power: PowerMonitor::new(power_policy),
+            thermal: max_temperature.map(|celsius| ThermalMonitor::new(celsius as f64)),
+            os_limit: (throttled && os_limit).then(|| OsLimit::new(threads as usize)),
+        });
+        for _ in 0..threads {
+            let shared = Arc::clone(&shared);
+            config::spawn_worker(move || worker(shared));
+        }
+
+        log::emit(
+            Level::Info,
+            "daemon_started",
+            vec![
+                ("workers", Value::U64(threads as u64)),
+                ("power_policy", Value::atom(power_policy)),
+                ("os_limit", Value::Bool(shared.os_limit.is_some())),
+            ],
+        );
+
+        Ok(Ok(ResourceArc::new(Daemon {
+            shared,
+            workers: threads as usize,
+        })))
+    })
+}
+
+/// Queues a search; its result is sent to `pid` as `{:powex_daemon, id, result}`.
+/// Work with a higher `:priority` runs first, equal priorities in submission order.
+#[rustler::nif]
+fn enqueue(
+    daemon: ResourceArc<Daemon>,
+    data: Binary,
+    difficulty: Difficulty,
+    pid: LocalPid,
+    opts: Options,
+) -> NifResult<Guarded<Result<u64, Atom>>> {
+    guard::run_result(|| {
+        input::check_size(data.as_slice())?;
+        let algorithm = opts.get_or(atoms::algorithm(), Algorithm::Sha256)?;
+        let priority = opts.get_or(atoms::priority(), 0)?;
+
+        let mut queue = daemon.shared.queue.lock().unwrap();
+        if queue.stopped {
+            return Ok(Err(atoms::stopped()));
+        }
+
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.pending.push(Work {
+            id,
+            priority,
+            algorithm,
+            data: data.as_slice().to_vec(),
+            difficulty,
+            pid,
+        });
+        metrics::queued(1);
+        metrics::job_started();
+        daemon.shared.available.notify_one();
+
+        Ok(Ok(id))
+    })
+}
+
+/// Stops a daemon: queued work fails with `:stopped`, running searches finish first
+/// (searches paused by the power policy finish once they resume)
+#[rustler::nif]
+fn daemon_stop(daemon: ResourceArc<Daemon>) -> Guarded<Atom> {
+    guard::run(|| {
+        daemon.shared.stop();
+        atoms::ok()
+    })
+}
+
+#[derive(NifMap)]
+struct DaemonStats {
+    workers: usize,
+    queued: usize,
+    running: usize,
+    completed: u64,
+    stopped: bool,
+}
+
+/// Gets the worker count, queue depth and progress of a daemon
+#[rustler::nif]
+fn daemon_stats(daemon: ResourceArc<Daemon>) -> Guarded<DaemonStats> {
+    guard::run(|| {
+        let queue = daemon.shared.queue.lock().unwrap();
+        DaemonStats {
+            workers: daemon.workers,
+            queued: queue.pending.len(),
+            running: queue.running,
+            completed: queue.completed,
+            stopped: queue.stopped,
+        }
+    })
+}
@@ -0,0 +1,408 @@
+use num_bigint::BigUint;
+use rustler::{Atom, Binary, Decoder, Encoder, Env, Error, NewBinary, NifResult, NifUnitEnum, Term};
+use sha2::{Digest, Sha256};
+
+use crate::atoms;
+use crate::backend::{leading_zero_bits, meets_difficulty_bytes};
+use crate::guard::{self, Guarded};
+
+/// Compact encoding of the difficulty 1 target used by pool difficulty
+pub const DIFF1_BITS: u32 = 0x1d00_ffff;
+
+/// The ways a proof-of-work target can be expressed
+///
+/// Every representation converts through a 256-bit big-endian target. Conversions between
+/// `:target`, `:hex` and `:zero_bits` are exact, `:bits` round-trips exactly for canonical
+/// compact values, and `:pool` / `:hashes` round-trip within `f64` precision.
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Representation {
+    /// 32-byte big-endian binary
+    Target,
+    /// 64-character hex string
+    Hex,
+    /// Compact nBits integer
+    Bits,
+    /// Number of leading zero bits a hash must have
+    ZeroBits,
+    /// Pool difficulty relative to the difficulty 1 target
+    Pool,
+    /// Expected number of hashes to find a solution
+    Hashes,
+}
+
+/// Difficulty argument of the search and validation functions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    /// Exact number of leading hex zeros
+    Zeros(u32),
+    /// Minimum number of leading zero bits, given as `{:zero_bits, n}`
+    ZeroBits(u32),
+    /// Minimum number of trailing hex zeros, given as `{:trailing_zeros, n}`
+    TrailingZeros(u32),
+    /// `zeros` hex zeros starting at hex digit `offset`, given as `{:zeros_at, offset, zeros}`
+    ZerosAt { offset: u32, zeros: u32 },
+    /// Big-endian target the hash must not exceed, given as a 64-character hex string
+    Target([u8; 32]),
+    /// `digest & mask == value` bytewise, given as `{mask, value}` binaries of up to 32 bytes;
+    /// shorter binaries apply to the leading bytes of the digest
+    Mask { mask: [u8; 32], value: [u8; 32] },
+}
+
+impl Difficulty {
+    /// Approximate zero count, used by the give-up heuristics of the search loops
+    pub fn zeros(&self) -> u32 {
+        match self {
+            Difficulty::Zeros(zeros) => *zeros,
+            Difficulty::ZeroBits(bits) => bits / 4,
+            Difficulty::TrailingZeros(zeros) | Difficulty::ZerosAt { zeros, .. } => *zeros,
+            Difficulty::Target(target) => leading_zero_bits(target) / 4,
+            Difficulty::Mask { mask, .. } => {
+                mask.iter().map(|byte| byte.count_ones()).sum::<u32>() / 4
+            }
+        }
+    }
+
+    /// Average number of hashes it takes to meet the difficulty
+    pub fn expected_hashes(&self) -> f64 {
+        match self {
+            Difficulty::Zeros(zeros) | Difficulty::TrailingZeros(zeros) | Difficulty::ZerosAt { zeros, .. } => {
+                16f64.powi(*zeros as i32)
+            }
+            Difficulty::ZeroBits(bits) => 2f64.powi(*bits as i32),
+            Difficulty::Target(target) => expected_hashes(target),
+            Difficulty::Mask { mask, .. } => 2f64.powi(mask.iter().map(|byte| byte.count_ones()).sum::<u32>() as i32),
+        }
+    }
+
+    /// Rejects difficulties no 256-bit digest can meet, with an error naming the mode's limit
+    pub fn validate(&self) -> Result<(), (Atom, &'static str)> {
+        match self {
+            Difficulty::Zeros(zeros) if *zeros > 64 => {
+                Err((atoms::error(), "Difficulty too high (max 64 hex zeros)"))
+            }
+            Difficulty::ZeroBits(bits) if *bits > 256 => {
+                Err((atoms::error(), "Difficulty too high (max 256 zero bits)"))
+            }
+            Difficulty::TrailingZeros(zeros) if *zeros > 64 => {
+                Err((atoms::error(), "Difficulty too high (max 64 hex zeros)"))
+            }
+            Difficulty::ZerosAt { offset, zeros } if offset.saturating_add(*zeros) > 64 => {
+                Err((atoms::error(), "Zero range must lie within the 64 hex digits of the hash"))
+            }
+            Difficulty::Target(target) if target.iter().all(|&byte| byte == 0) => {
+                Err((atoms::error(), "Target must not be zero"))
+            }
+            Difficulty::Mask { mask, value } if mask.iter().zip(value).any(|(m, v)| v & !m != 0) => {
+                Err((atoms::error(), "Mask value has bits outside the mask"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The numeric target of a threshold difficulty, where any hash at or below it meets
+    /// the difficulty; hex zeros count as a minimum here. `None` for pattern difficulties
+    pub fn threshold_target(&self) -> Option<[u8; 32]> {
+        match self {
+            Difficulty::Zeros(zeros) => zero_bits_to_target(zeros.checked_mul(4)?),
+            Difficulty::ZeroBits(bits) => zero_bits_to_target(*bits),
+            Difficulty::Target(target) => Some(*target),
+            Difficulty::TrailingZeros(_) | Difficulty::ZerosAt { .. } | Difficulty::Mask { .. } => {
+                None
+            }
+        }
+    }
+
+    /// Checks a raw digest against the difficulty. Digests shorter than 32 bytes are compared
+    /// to targets and masks as the top bytes of a 256-bit value, so a target keeps its odds.
+    pub fn is_met_by(&self, digest: &[u8]) -> bool {
+        let nibbles = digest.len() as u32 * 2;
+        let padded = || {
+            let mut padded = [0u8; 32];
+            let len = digest.len().min(32);
+            padded[..len].copy_from_slice(&digest[..len]);
+            padded
+        };
+
+        match self {
+            Difficulty::Zeros(zeros) => meets_difficulty_bytes(digest, *zeros),
+            Difficulty::ZeroBits(bits) => leading_zero_bits(digest) >= *bits,
+            Difficulty::TrailingZeros(zeros) => *zeros <= nibbles && zero_nibbles(digest, nibbles - zeros, *zeros),
+            Difficulty::ZerosAt { offset, zeros } => zero_nibbles(digest, *offset, *zeros),
+            Difficulty::Target(target) => padded() <= *target,
+            Difficulty::Mask { mask, value } => {
+                padded().iter().zip(mask).zip(value).all(|((d, m), v)| d & m == *v)
+            }
+        }
+    }
+}
+
+impl<'a> Decoder<'a> for Difficulty {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if term.is_number() {
+            if let Ok(zeros) = term.decode::<u32>() {
+                return Ok(Difficulty::Zeros(zeros));
+            }
+            return fractional_zeros(term.decode()?).ok_or(Error::BadArg);
+        }
+
+        if let Ok((tag, count)) = term.decode::<(Atom, u32)>() {
+            return match tag {
+                tag if tag == atoms::zero_bits() => Ok(Difficulty::ZeroBits(count)),
+                tag if tag == atoms::trailing_zeros() => Ok(Difficulty::TrailingZeros(count)),
+                _ => Err(Error::BadArg),
+            };
+        }
+
+        if let Ok((tag, offset, zeros)) = term.decode::<(Atom, u32, u32)>() {
+            if tag != atoms::zeros_at() {
+                return Err(Error::BadArg);
+            }
+            return Ok(Difficulty::ZerosAt { offset, zeros });
+        }
+
+        if let Ok((mask, value)) = term.decode::<(Binary, Binary)>() {
+            return decode_mask(mask.as_slice(), value.as_slice()).ok_or(Error::BadArg);
+        }
+
+        let hex: String = term.decode()?;
+        hex_to_target(&hex).map(Difficulty::Target).ok_or(Error::BadArg)
+    }
+}
+
+/// A fractional zero count as the target met by one hash in `16^zeros`, so 4.5 costs
+/// exactly 4x as much as 4.0; counts over 64 keep the integer limit's error
+fn fractional_zeros(zeros: f64) -> Option<Difficulty> {
+    if !zeros.is_finite() || zeros < 0.0 {
+        return None;
+    }
+    if zeros > 64.0 {
+        return Some(Difficulty::Zeros(zeros.ceil().min(u32::MAX as f64) as u32));
+    }
+    hashes_to_target(16f64.powf(zeros)).map(Difficulty::Target)
+}
+
+/// Whether the `count` hex digits of the digest starting at digit `start` are all zero
+fn zero_nibbles(digest: &[u8], start: u32, count: u32) -> bool {
+    let end = start.saturating_add(count);
+    end as usize <= digest.len() * 2
+        && (start..end).all(|i| {
+            let byte = digest[i as usize / 2];
+            let nibble = if i & 1 == 0 { byte >> 4 } else { byte & 0x0f };
+            nibble == 0
+        })
+}
+
+/// Pads a mask and value of equal length, at most 32 bytes, to a full digest
+fn decode_mask(mask: &[u8], value: &[u8]) -> Option<Difficulty> {
+    if mask.len() != value.len() || mask.len() > 32 {
+        return None;
+    }
+
+    let mut padded = ([0u8; 32], [0u8; 32]);
+    padded.0[..mask.len()].copy_from_slice(mask);
+    padded.1[..value.len()].copy_from_slice(value);
+    Some(Difficulty::Mask { mask: padded.0, value: padded.1 })
+}
+
+/// Expands compact nBits into a big-endian 256-bit target, rejecting negative or overflowing values
+pub fn bits_to_target(bits: u32) -> Option<[u8; 32]> {
+    let exponent = (bits >> 24) as i32;
+    let mantissa = (bits & 0x007f_ffff).to_be_bytes();
+
+    if bits & 0x0080_0000 != 0 {
+        return None;
+    }
+
+    // target = mantissa * 256^(exponent - 3): mantissa byte i lands at index 29 - (exponent - 3) + i
+    let mut target = [0u8; 32];
+    for (i, &byte) in mantissa[1..].iter().enumerate() {
+        let index = 32 - exponent + i as i32;
+        match usize::try_from(index) {
+            Ok(index) if index < 32 => target[index] = byte,
+            Ok(_) => {}
+            Err(_) if byte != 0 => return None,
+            Err(_) => {}
+        }
+    }
+
+    Some(target)
+}
+
+/// Encodes a target in canonical compact form, truncating to the top three significant bytes
+pub fn target_to_bits(target: &[u8; 32]) -> u32 {
+    let mut size = 32 - target.iter().position(|&byte| byte != 0).unwrap_or(32);
+    let mut compact = (0..3).fold(0u32, |acc, k| {
+        let byte = target.get(32 - size + k).copied().unwrap_or(0);
+        (acc << 8) | byte as u32
+    });
+
+    // The mantissa is signed, so a set high bit is moved into the next byte
+    if compact & 0x0080_0000 != 0 {
+        compact >>= 8;
+        size += 1;
+    }
+
+    ((size as u32) << 24) | compact
+}
+
+/// Parses a 64-character hex target
+pub fn hex_to_target(hex: &str) -> Option<[u8; 32]> {
+    let mut target = [0u8; 32];
+    hex::decode_to_slice(hex, &mut target).ok()?;
+    Some(target)
+}
+
+/// The largest target whose hashes all have at least `zero_bits` leading zero bits
+pub fn zero_bits_to_target(zero_bits: u32) -> Option<[u8; 32]> {
+    if zero_bits > 256 {
+        return None;
+    }
+
+    let mut target = [0u8; 32];
+    for (i, byte) in target.iter_mut().enumerate() {
+        let leading = zero_bits.saturating_sub(8 * i as u32).min(8);
+        *byte = 0xffu8.checked_shr(leading).unwrap_or(0);
+    }
+    Some(target)
+}
+
+/// Expected number of hashes to find a solution at the target: 2^256 / (target + 1)
+pub fn expected_hashes(target: &[u8; 32]) -> f64 {
+    let target = target.iter().fold(0.0, |acc, &byte| acc * 256.0 + byte as f64);
+    2f64.powi(256) / (target + 1.0)
+}
+
+/// Pool difficulty of a target, or `None` for the zero target
+pub fn pool_difficulty(target: &[u8; 32]) -> Option<f64> {
+    let target = BigUint::from_bytes_be(target);
+    if target.bits() == 0 {
+        return None;
+    }
+
+    Some(to_f64(&diff1()) / to_f64(&target))
+}
+
+/// Target for a pool difficulty, or `None` if the difficulty is not positive or the target overflows
+pub fn pool_difficulty_to_target(difficulty: f64) -> Option<[u8; 32]> {
+    to_target(divide(&diff1(), difficulty)?)
+}
+
+/// Target at which a solution takes `hashes` attempts on average
+pub fn hashes_to_target(hashes: f64) -> Option<[u8; 32]> {
+    let quotient = divide(&(BigUint::from(1u8) << 256u32), hashes)?;
+    if quotient.bits() == 0 {
+        return None;
+    }
+    to_target(quotient - 1u8)
+}
+
+fn diff1() -> BigUint {
+    BigUint::from_bytes_be(&bits_to_target(DIFF1_BITS).unwrap_or([0; 32]))
+}
+
+/// Converts a big integer to the nearest `f64` using its top 64 bits
+fn to_f64(value: &BigUint) -> f64 {
+    let shift = value.bits().saturating_sub(64);
+    let top = (value >> shift).iter_u64_digits().next().unwrap_or(0);
+    top as f64 * 2f64.powi(shift as i32)
+}
+
+/// Exact floor of `numerator / divisor` for a positive finite `divisor`
+fn divide(numerator: &BigUint, divisor: f64) -> Option<BigUint> {
+    if !divisor.is_finite() || divisor <= 0.0 {
+        return None;
+    }
+
+    // divisor = mantissa * 2^exponent exactly
+    let raw = divisor.to_bits();
+    let biased = ((raw >> 52) & 0x7ff) as i64;
+    let fraction = raw & ((1u64 << 52) - 1);
+    let (mantissa, exponent) = match biased {
+        0 => (fraction, -1074),
+        _ => (fraction | (1u64 << 52), biased - 1075),
+    };
+
+    let mantissa = BigUint::from(mantissa);
+    Some(if exponent >= 0 {
+        numerator / (mantissa << exponent as u64)
+    } else {
+        (numerator << exponent.unsigned_abs()) / mantissa
+    })
+}
+
+fn to_target(value: BigUint) -> Option<[u8; 32]> {
+    let bytes = value.to_bytes_be();
+    if bytes.len() > 32 {
+        return None;
+    }
+
+    let mut target = [0u8; 32];
+    target[32 - bytes.len()..].copy_from_slice(&bytes);
+    Some(target)
+}
+
+/// Decodes a value in the given representation into a target
+fn decode(value: Term, from: Representation) -> Option<[u8; 32]> {
+    let number = || value.decode::<f64>().ok().or_else(|| value.decode::<u64>().ok().map(|n| n as f64));
+
+    match from {
+        Representation::Target => value.decode::<Binary>().ok()?.as_slice().try_into().ok(),
+        Representation::Hex => hex_to_target(&value.decode::<String>().ok()?),
+        Representation::Bits => bits_to_target(value.decode().ok()?),
+        Representation::ZeroBits => zero_bits_to_target(value.decode().ok()?),
+        Representation::Pool => pool_difficulty_to_target(number()?),
+        Representation::Hashes => hashes_to_target(number()?),
+    }
+}
+
+/// Encodes a target in the given representation
+fn encode<'a>(env: Env<'a>, target: &[u8; 32], to: Representation) -> Option<Term<'a>> {
+    Some(match to {
+        Representation::Target => {
+            let mut binary = NewBinary::new(env, 32);
+            binary.as_mut_slice().copy_from_slice(target);
+            Binary::from(binary).encode(env)
+        }
+        Representation::Hex => hex::encode(target).encode(env),
+        Representation::Bits => target_to_bits(target).encode(env),
+        Representation::ZeroBits => leading_zero_bits(target).encode(env),
+        Representation::Pool => pool_difficulty(target)?.encode(env),
+        Representation::Hashes => expected_hashes(target).encode(env),
+    })
+}
+
+/// Converts a difficulty between representations
+#[rustler::nif]
+fn convert_difficulty<'a>(
+    env: Env<'a>,
+    value: Term<'a>,
+    from: Representation,
+    to: Representation,
+) -> Guarded<Result<Term<'a>, Atom>> {
+    guard::run(|| {
+        let target = decode(value, from).ok_or_else(atoms::invalid)?;
+        encode(env, &target, to).ok_or_else(atoms::invalid)
+    })
+}
+
+/// How many times the work of the difficulty a proof represents: `(target + 1) / (hash + 1)`,
+/// comparing the hash to the difficulty's numeric target rather than counting zeros, so a
+/// proof one hex digit over target is worth 16x and fractional multiples like 8.3x show up
+#[rustler::nif]
+fn work_multiple(data: Binary, nonce: u64, difficulty: Difficulty) -> NifResult<Guarded<Result<f64, Atom>>> {
+    guard::run_result(|| {
+        let target = difficulty.threshold_target().ok_or(Error::BadArg)?;
+        let hash: [u8; 32] = Sha256::new()
+            .chain_update(data.as_slice())
+            .chain_update(nonce.to_le_bytes())
+            .finalize()
+            .into();
+
+        if hash > target {
+            return Ok(Err(atoms::invalid()));
+        }
+        let target = BigUint::from_bytes_be(&target) + 1u8;
+        let hash = BigUint::from_bytes_be(&hash) + 1u8;
+        Ok(Ok(to_f64(&target) / to_f64(&hash)))
+    })
+}
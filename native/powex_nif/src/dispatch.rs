@@ -0,0 +1,66 @@
+use rustler::{NifResult, NifUnitEnum};
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded};
+use crate::options::Options;
+
+/// Longest a call should run on a normal scheduler, per the NIF guidelines
+const NORMAL_BUDGET_MICROS: f64 = 1_000.0;
+
+/// Data absorbed per microsecond, a conservative rate for software SHA-256
+const BYTES_PER_MICRO: f64 = 200.0;
+
+/// Scheduler a call is routed to
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheduler {
+    Normal,
+    Dirty,
+}
+
+/// Rough nanoseconds of one hash of a short input, measured without SIMD or SHA extensions
+fn hash_nanos(algorithm: &Algorithm) -> f64 {
+    match algorithm {
+        Algorithm::Sha256 | Algorithm::Blake2b(_) => 250.0,
+        Algorithm::Blake3 | Algorithm::K12 | Algorithm::TurboShake128 => 150.0,
+        Algorithm::Sha512_256 | Algorithm::Sha384 => 300.0,
+        Algorithm::Hash160 => 400.0,
+        Algorithm::Poseidon => 20_000.0,
+        #[cfg(feature = "yespower")]
+        Algorithm::Yespower(params) => 128.0 * params.n() as f64 * params.r() as f64 / 2.0,
+        Algorithm::Chain(links) => links.iter().map(hash_nanos).sum(),
+    }
+}
+
+/// Estimated microseconds to absorb `data_size` bytes and then hash `hashes` nonces
+pub fn estimate_micros(algorithm: &Algorithm, data_size: u64, hashes: f64) -> f64 {
+    data_size as f64 / BYTES_PER_MICRO + hashes * hash_nanos(algorithm) / 1_000.0
+}
+
+/// Normal for calls expected to finish within the scheduler budget, dirty for the rest
+pub fn scheduler_for_cost(micros: f64) -> Scheduler {
+    if micros <= NORMAL_BUDGET_MICROS {
+        Scheduler::Normal
+    } else {
+        Scheduler::Dirty
+    }
+}
+
+/// Picks the scheduler for a search (or, with `:verify`, a single verification) from the
+/// data size, the expected hashes of the difficulty and the algorithm
+#[rustler::nif]
+fn scheduler_for(
+    data_size: u64,
+    difficulty: Difficulty,
+    opts: Options,
+) -> NifResult<Guarded<Scheduler>> {
+    guard::run_result(|| {
+        let algorithm = opts.get_or(atoms::algorithm(), Algorithm::Sha256)?;
+        let hashes = match opts.get_or(atoms::verify(), false)? {
+            true => 1.0,
+            false => difficulty.expected_hashes(),
+        };
+        Ok(scheduler_for_cost(estimate_micros(&algorithm, data_size, hashes)))
+    })
+}
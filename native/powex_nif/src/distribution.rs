@@ -0,0 +1,64 @@
+use rand::Rng;
+use rustler::{Binary, NifMap, NifResult};
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::backend::leading_zero_bits;
+use crate::guard::{self, GuardedResult};
+use crate::metrics;
+use crate::options::Options;
+use crate::seeded;
+
+/// Most nonces one call may hash
+const MAX_SAMPLES: u64 = 100_000_000;
+
+#[derive(NifMap)]
+struct Distribution {
+    /// `counts[k]` hashes had exactly k leading zero bits
+    counts: Vec<u64>,
+    /// What an ideal hash would give: `n / 2^(k + 1)` for k leading zero bits
+    expected: Vec<f64>,
+    mean_bits: f64,
+}
+
+/// Hashes `n` random nonces and counts how many leading zero bits each hash has, so
+/// difficulty settings can be checked against the theory on real payloads
+#[rustler::nif(schedule = "DirtyCpu")]
+fn sample_distribution(data: Binary, n: u64, opts: Options) -> NifResult<GuardedResult<Distribution>> {
+    guard::run_result(|| {
+        let algorithm = opts.get_or(atoms::algorithm(), Algorithm::Sha256)?;
+        let seed: Option<u64> = opts.get(atoms::deterministic_seed())?;
+
+        if n == 0 || n > MAX_SAMPLES {
+            return Ok(Err((atoms::error(), "Invalid sample count (1-100000000)")));
+        }
+
+        let prepared = algorithm.prepare(data.as_slice());
+        let mut rng = seeded::Source::new(seed);
+        let mut meter = metrics::Meter::new();
+        let mut digest = Vec::new();
+        let mut counts: Vec<u64> = vec![];
+        let mut total_bits = 0u64;
+
+        for _ in 0..n {
+            meter.tick();
+            prepared.digest_into(rng.gen(), &mut digest);
+            let bits = leading_zero_bits(&digest) as usize;
+            if counts.len() <= bits {
+                counts.resize(bits + 1, 0);
+            }
+            counts[bits] += 1;
+            total_bits += bits as u64;
+        }
+
+        let expected = (0..counts.len())
+            .map(|bits| n as f64 / 2f64.powi(bits as i32 + 1))
+            .collect();
+
+        Ok(Ok(Distribution {
+            counts,
+            expected,
+            mean_bits: total_bits as f64 / n as f64,
+        }))
+    })
+}
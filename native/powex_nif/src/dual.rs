@@ -0,0 +1,94 @@
+use rustler::{Atom, Binary, NifResult};
+
+use crate::algorithm::{Algorithm, State};
+use crate::atoms;
+use crate::backend::meets_difficulty_bytes;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::input;
+use crate::metrics;
+
+fn check_algorithms(algorithms: &(Algorithm, Algorithm)) -> Result<(), (Atom, &'static str)> {
+    if algorithms.0 == algorithms.1 {
+        return Err((atoms::error(), "Algorithms must differ"));
+    }
+    Ok(())
+}
+
+fn prepare_both(algorithms: &(Algorithm, Algorithm), data: &[u8]) -> (State, State) {
+    (algorithms.0.prepare(data), algorithms.1.prepare(data))
+}
+
+fn meets_both(states: &(State, State), nonce: u64, difficulty: u32, digest: &mut Vec<u8>) -> bool {
+    // Evaluate the second algorithm only when the first one already passed
+    states.0.digest_into(nonce, digest);
+    if !meets_difficulty_bytes(digest, difficulty) {
+        return false;
+    }
+    states.1.digest_into(nonce, digest);
+    meets_difficulty_bytes(digest, difficulty)
+}
+
+/// Proof of Work computation requiring the difficulty under two hash algorithms at once
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_dual(
+    data: Binary,
+    difficulty: u32,
+    algorithms: (Algorithm, Algorithm),
+) -> NifResult<GuardedResult<u64>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| {
+        Difficulty::Zeros(difficulty).validate()?;
+        check_algorithms(&algorithms)?;
+
+        let states = prepare_both(&algorithms, data.as_slice());
+        let mut digest = Vec::new();
+        let mut meter = metrics::Meter::new();
+
+        for nonce in 0..u64::MAX {
+            meter.tick();
+            if meets_both(&states, nonce, difficulty, &mut digest) {
+                return Ok(nonce);
+            }
+
+            if nonce > 100_000_000 && difficulty > 10 {
+                return Err((atoms::error(), "Difficulty too high, computation aborted"));
+            }
+        }
+
+        Err((atoms::error(), "No valid nonce found"))
+    }))
+}
+
+fn verify_dual(data: &[u8], nonce: u64, difficulty: u32, algorithms: &(Algorithm, Algorithm)) -> bool {
+    if check_algorithms(algorithms).is_err() {
+        return false;
+    }
+
+    let states = prepare_both(algorithms, data);
+    meets_both(&states, nonce, difficulty, &mut Vec::new())
+}
+
+/// Validates that a nonce meets the difficulty under both hash algorithms
+#[rustler::nif(name = "valid_dual_normal?")]
+fn valid_dual(
+    data: Binary,
+    nonce: u64,
+    difficulty: u32,
+    algorithms: (Algorithm, Algorithm),
+) -> NifResult<Guarded<bool>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| verify_dual(data.as_slice(), nonce, difficulty, &algorithms)))
+}
+
+/// `valid_dual?` on a dirty CPU scheduler, for large data
+#[rustler::nif(name = "valid_dual_dirty?", schedule = "DirtyCpu")]
+fn valid_dual_dirty(
+    data: Binary,
+    nonce: u64,
+    difficulty: u32,
+    algorithms: (Algorithm, Algorithm),
+) -> NifResult<Guarded<bool>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| verify_dual(data.as_slice(), nonce, difficulty, &algorithms)))
+}
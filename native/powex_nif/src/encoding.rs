@@ -0,0 +1,35 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rustler::{Binary, Encoder, Env, NewBinary, NifUnitEnum, Term};
+
+/// Output formats for digests returned to Elixir
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Raw bytes as a binary
+    Raw,
+    /// Lowercase hex string
+    Hex,
+    /// Uppercase hex string
+    HexUpper,
+    /// Padded standard Base64
+    Base64,
+    /// Bitcoin-alphabet Base58
+    Base58,
+}
+
+impl Encoding {
+    /// Encodes a digest in this format
+    pub fn encode<'a>(self, env: Env<'a>, digest: &[u8]) -> Term<'a> {
+        match self {
+            Encoding::Raw => {
+                let mut binary = NewBinary::new(env, digest.len());
+                binary.as_mut_slice().copy_from_slice(digest);
+                Binary::from(binary).encode(env)
+            }
+            Encoding::Hex => hex::encode(digest).encode(env),
+            Encoding::HexUpper => hex::encode_upper(digest).encode(env),
+            Encoding::Base64 => STANDARD.encode(digest).encode(env),
+            Encoding::Base58 => bs58::encode(digest).into_string().encode(env),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rustler::{Atom, Binary, Env, Error, NewBinary, NifMap, NifResult, NifUnitEnum, Term};
+use std::sync::Mutex;
+
+use crate::atoms;
+use crate::guard::{self, GuardedResult};
+use crate::options::Options;
+
+/// Default `:size` of `generate_challenge`
+const DEFAULT_CHALLENGE_SIZE: usize = 32;
+
+/// Largest `:size` of `generate_challenge`
+const MAX_CHALLENGE_SIZE: usize = 1024;
+
+/// BLAKE3 key derivation context of the seeded generator's output stream
+const SEEDED_CONTEXT: &str = "powex 2026-10-15 seeded challenge DRBG";
+
+/// BLAKE3 key derivation context mixing caller entropy into random bytes
+const MIX_CONTEXT: &str = "powex 2026-10-15 challenge entropy mixing";
+
+/// BLAKE3 key derivation context of challenges derived from beacon values
+const BEACON_CONTEXT: &str = "powex 2026-10-15 beacon-anchored challenge";
+
+/// Size of challenges derived from beacon values
+const BEACON_CHALLENGE_LEN: usize = 32;
+
+/// Where challenge bytes come from, recorded in the challenge metadata
+#[derive(NifUnitEnum, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// The operating system's CSPRNG
+    Os,
+    /// A deterministic generator set with `configure(entropy: {:seeded, seed})`, for tests
+    Seeded,
+}
+
+enum Source {
+    Os,
+    /// BLAKE3 in XOF mode over the seed: the same stream on every platform and version
+    Seeded(Box<blake3::OutputReader>),
+}
+
+static SOURCE: Mutex<Source> = Mutex::new(Source::Os);
+
+/// Fills `out` from the configured source, then, if given, mixes in caller entropy such as
+/// a drand beacon round with a keyed BLAKE3 hash: the result is unpredictable as long as
+/// either input is
+pub fn fill(out: &mut [u8], mix: Option<&[u8]>) -> SourceKind {
+    let kind = match &mut *SOURCE.lock().unwrap() {
+        Source::Os => {
+            OsRng.fill_bytes(out);
+            SourceKind::Os
+        }
+        Source::Seeded(stream) => {
+            stream.fill(out);
+            SourceKind::Seeded
+        }
+    };
+
+    if let Some(mix) = mix {
+        let key = blake3::derive_key(MIX_CONTEXT, mix);
+        let mut hasher = blake3::Hasher::new_keyed(&key);
+        hasher.update(out);
+        hasher.finalize_xof().fill(out);
+    }
+    kind
+}
+
+/// Sets the source from the `:entropy` option of `configure/1`: `:os`, or `{:seeded, seed}`
+/// to restart the deterministic stream of `seed`
+pub fn set(source: Term) -> NifResult<()> {
+    let source = match source.decode::<Atom>() {
+        Ok(atom) if atom == atoms::os() => Source::Os,
+        Ok(_) => return Err(Error::BadArg),
+        Err(_) => {
+            let (tag, seed): (Atom, Binary) = source.decode()?;
+            if tag != atoms::seeded() {
+                return Err(Error::BadArg);
+            }
+            let mut hasher = blake3::Hasher::new_derive_key(SEEDED_CONTEXT);
+            hasher.update(&seed);
+            Source::Seeded(Box::new(hasher.finalize_xof()))
+        }
+    };
+    *SOURCE.lock().unwrap() = source;
+    Ok(())
+}
+
+#[derive(NifMap)]
+struct Challenge<'a> {
+    challenge: Binary<'a>,
+    source: SourceKind,
+    /// BLAKE3 hash of the caller entropy mixed in, if any
+    entropy: Option<Binary<'a>>,
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.into()
+}
+
+/// Generates `:size` random challenge bytes from the configured source, mixing in
+/// `:entropy` if given
+#[rustler::nif]
+fn generate_challenge<'a>(
+    env: Env<'a>,
+    opts: Options,
+) -> NifResult<GuardedResult<Challenge<'a>>> {
+    guard::run_result(|| {
+        let size = opts.get_or(atoms::size(), DEFAULT_CHALLENGE_SIZE)?;
+        let mix: Option<Binary> = opts.get(atoms::entropy())?;
+
+        if !(1..=MAX_CHALLENGE_SIZE).contains(&size) {
+            return Ok(Err((atoms::error(), "Invalid challenge size (1-1024 bytes)")));
+        }
+
+        let mut challenge = NewBinary::new(env, size);
+        let source = fill(challenge.as_mut_slice(), mix.as_deref());
+        Ok(Ok(Challenge {
+            challenge: challenge.into(),
+            source,
+            entropy: mix.map(|mix| to_binary(env, blake3::hash(&mix).as_bytes())),
+        }))
+    })
+}
+
+/// The challenge of a beacon value in a caller context. The context is length-prefixed, so
+/// no two (context, beacon) pairs hash the same input
+fn from_beacon(beacon: &[u8], context: &[u8]) -> [u8; BEACON_CHALLENGE_LEN] {
+    let mut hasher = blake3::Hasher::new_derive_key(BEACON_CONTEXT);
+    hasher.update(&(context.len() as u64).to_le_bytes());
+    hasher.update(context);
+    hasher.update(beacon);
+    *hasher.finalize().as_bytes()
+}
+
+/// Derives a challenge from a public randomness beacon value, e.g. a drand round, and a
+/// context naming the protocol it is for. Anyone holding both can recompute it
+#[rustler::nif]
+fn challenge_from_beacon<'a>(
+    env: Env<'a>,
+    beacon: Binary,
+    context: Binary,
+) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        if beacon.is_empty() {
+            return Err((atoms::error(), "Beacon value must not be empty"));
+        }
+        if context.is_empty() {
+            return Err((atoms::error(), "Context must not be empty"));
+        }
+        Ok(to_binary(env, &from_beacon(&beacon, &context)))
+    })
+}
@@ -0,0 +1,88 @@
+use rustler::{Binary, NifMap, NifResult, Term};
+use zeroize::Zeroizing;
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::clock;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, GuardedResult};
+use crate::options::Options;
+
+/// Default length of an epoch in seconds
+const DEFAULT_EPOCH_SECONDS: u64 = 60 * 60;
+
+/// BLAKE3 key derivation context, so epoch parameters never collide with other uses of the secret
+const KEY_CONTEXT: &str = "powex 2026-10-14 challenge epoch parameters";
+
+#[derive(NifMap)]
+struct ChallengeParams<'a> {
+    epoch: u64,
+    algorithm: Algorithm,
+    difficulty: Term<'a>,
+    seed: String,
+    starts_at: u64,
+    expires_at: u64,
+}
+
+/// Picks an entry of `choices` from eight bytes of the epoch digest
+fn pick<T: Clone>(choices: &[T], bytes: &[u8]) -> T {
+    let word = u64::from_le_bytes(bytes.try_into().unwrap_or([0; 8]));
+    choices[(word % choices.len() as u64) as usize].clone()
+}
+
+/// Derives the active algorithm, difficulty and seed of the epoch containing `:now` from
+/// a shared secret, so every node holding the secret rotates parameters in lockstep
+#[rustler::nif]
+fn current_challenge_params<'a>(
+    opts: Options<'a>,
+) -> NifResult<GuardedResult<ChallengeParams<'a>>> {
+    guard::run_result(|| {
+        let secret = opts.get::<Binary>(atoms::secret())?;
+        let epoch_seconds = opts.get_or(atoms::epoch_seconds(), DEFAULT_EPOCH_SECONDS)?;
+        let algorithms = opts.get_or(
+            atoms::algorithms(),
+            vec![Algorithm::Sha256, Algorithm::Blake3],
+        )?;
+        let difficulties = match opts.get::<Vec<Term>>(atoms::difficulties())? {
+            Some(difficulties) => difficulties,
+            None => return Ok(Err((atoms::error(), "Missing :difficulties"))),
+        };
+        let now = opts.get::<u64>(atoms::now())?.unwrap_or_else(clock::now);
+
+        let secret = match secret {
+            Some(secret) if !secret.is_empty() => secret,
+            _ => return Ok(Err((atoms::error(), "Missing or empty :secret"))),
+        };
+
+        if epoch_seconds == 0 {
+            return Ok(Err((atoms::error(), "Epoch length must be positive")));
+        }
+
+        if algorithms.is_empty() || difficulties.is_empty() {
+            return Ok(Err((
+                atoms::error(),
+                "Algorithms and difficulties must not be empty",
+            )));
+        }
+
+        for difficulty in &difficulties {
+            if let Err(reason) = difficulty.decode::<Difficulty>()?.validate() {
+                return Ok(Err(reason));
+            }
+        }
+
+        let epoch = now / epoch_seconds;
+        let key = Zeroizing::new(blake3::derive_key(KEY_CONTEXT, secret.as_slice()));
+        let digest = blake3::keyed_hash(&key, &epoch.to_le_bytes());
+        let bytes = digest.as_bytes();
+
+        Ok(Ok(ChallengeParams {
+            epoch,
+            algorithm: pick(&algorithms, &bytes[..8]),
+            difficulty: pick(&difficulties, &bytes[8..16]),
+            seed: hex::encode(&bytes[16..]),
+            starts_at: epoch * epoch_seconds,
+            expires_at: (epoch + 1).saturating_mul(epoch_seconds),
+        }))
+    })
+}
@@ -0,0 +1,77 @@
+use rustler::{Encoder, Env, Term};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded};
+
+/// How long each algorithm is benchmarked the first time it's converted
+const BENCH_DURATION: Duration = Duration::from_millis(50);
+
+/// Measured hashes per second of each algorithm, on one thread
+fn hashrates() -> &'static Mutex<HashMap<Algorithm, f64>> {
+    static HASHRATES: OnceLock<Mutex<HashMap<Algorithm, f64>>> = OnceLock::new();
+    HASHRATES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn benchmark(algorithm: &Algorithm) -> f64 {
+    let prepared = algorithm.prepare(b"powex equivalence benchmark");
+    let mut digest = Vec::new();
+    let start = Instant::now();
+    let mut hashes = 0u64;
+
+    while start.elapsed() < BENCH_DURATION {
+        prepared.digest_into(hashes, &mut digest);
+        std::hint::black_box(&digest);
+        hashes += 1;
+    }
+
+    hashes as f64 / start.elapsed().as_secs_f64()
+}
+
+/// The hashrate of an algorithm, measured once and then remembered. BLAKE2b keys don't
+/// change the speed, so they're redacted rather than kept in the table
+fn hashrate(algorithm: &Algorithm) -> f64 {
+    let algorithm = algorithm.redacted();
+    if let Some(rate) = hashrates().lock().unwrap().get(&algorithm) {
+        return *rate;
+    }
+
+    // Measured without the lock held, so conversions between other algorithms don't wait
+    let rate = benchmark(&algorithm);
+    *hashrates().lock().unwrap().entry(algorithm).or_insert(rate)
+}
+
+/// Converts a difficulty between algorithms so the expected solve time stays the same.
+/// Hex zero difficulties convert to hex zeros; every other form converts to
+/// `{:zero_bits, n}`, rounded to the nearest step and clamped to what a digest can meet
+#[rustler::nif(schedule = "DirtyCpu")]
+fn equivalent_difficulty<'a>(
+    env: Env<'a>,
+    from: Algorithm,
+    difficulty: Difficulty,
+    to: Algorithm,
+) -> Guarded<Term<'a>> {
+    guard::run(|| {
+        let hashes = difficulty.expected_hashes() * hashrate(&to) / hashrate(&from);
+        match difficulty {
+            Difficulty::Zeros(_) => {
+                let zeros = (hashes.log2() / 4.0).round().clamp(0.0, 64.0) as u32;
+                zeros.encode(env)
+            }
+            _ => {
+                let bits = hashes.log2().round().clamp(0.0, 256.0) as u32;
+                (atoms::zero_bits(), bits).encode(env)
+            }
+        }
+    })
+}
+
+/// The measured single-thread hashrate of an algorithm in hashes per second
+#[rustler::nif(schedule = "DirtyCpu")]
+fn algorithm_hashrate(algorithm: Algorithm) -> Guarded<f64> {
+    guard::run(|| hashrate(&algorithm))
+}
@@ -0,0 +1,155 @@
+//! Greylisting-style challenge sequences: after failures, a client must solve several
+//! challenges in a row, each harder than the last. The sequence is issued as one token of
+//! `token.rs` whose data is `seed (16 bytes) || steps || step`, so the server keeps no
+//! state. Step `i` has difficulty `base + i * step`; the data of step 0 is the token's data
+//! and the data of step `i + 1` is derived from the data and nonce of step `i`, so the
+//! steps can only be solved in order.
+
+use rustler::{Atom, NifResult, ResourceArc};
+use std::time::{Duration, Instant};
+
+use crate::atoms;
+use crate::entropy;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+use crate::proof::{self, Policy, Proof};
+use crate::token::{self, Opened};
+use crate::Status;
+
+/// Size of the random seed of a sequence
+const SEED_LEN: usize = 16;
+
+/// Default `:max_steps`: at most four challenges in a row
+const DEFAULT_MAX_STEPS: u8 = 4;
+
+/// Default `:step`: each challenge one level harder than the previous one
+const DEFAULT_STEP: u8 = 1;
+
+/// BLAKE3 key derivation context chaining each step to the solution of the previous one
+const CHAIN_CONTEXT: &str = "powex 2026-10-15 escalating challenge chain";
+
+/// A sequence token's parameters: its proof carries the algorithm, the base difficulty and
+/// the data of step 0
+struct Sequence<'a> {
+    proof: Proof<'a>,
+    steps: u8,
+    step: u8,
+}
+
+impl<'a> Sequence<'a> {
+    fn new(proof: Proof<'a>) -> Option<Sequence<'a>> {
+        if proof.data.len() != SEED_LEN + 2 {
+            return None;
+        }
+        let (steps, step) = (proof.data[SEED_LEN], proof.data[SEED_LEN + 1]);
+        if steps == 0 {
+            return None;
+        }
+        Some(Sequence { proof, steps, step })
+    }
+
+    fn difficulty(&self, index: u8) -> u8 {
+        self.proof.difficulty.saturating_add(index.saturating_mul(self.step))
+    }
+
+    /// The proof of step `index` for `nonce` over `data`
+    fn proof<'d>(&self, index: u8, nonce: u64, data: &'d [u8]) -> Proof<'d> {
+        let proof = &self.proof;
+        Proof::new(proof.algorithm, self.difficulty(index), nonce, proof.memory, data)
+    }
+}
+
+/// Data of the step after one solved with `nonce` over `data`
+fn next_data(data: &[u8], nonce: u64) -> Vec<u8> {
+    let mut hasher = blake3::Hasher::new_derive_key(CHAIN_CONTEXT);
+    hasher.update(data);
+    hasher.update(&nonce.to_le_bytes());
+    hasher.finalize().as_bytes().to_vec()
+}
+
+/// Issues a sequence for a client with `failures` prior failures: `failures + 1` steps, at
+/// most `:max_steps`, from the base `:difficulty` up by `:step` per step. The other options
+/// are as in `token_encode`
+#[rustler::nif]
+fn escalating_challenge(failures: u32, opts: Options) -> NifResult<GuardedResult<String>> {
+    guard::run_result(|| {
+        let Some(base) = opts.get::<u8>(atoms::difficulty())? else {
+            return Ok(Err((atoms::error(), "Missing :difficulty")));
+        };
+        let max_steps = opts.get_or(atoms::max_steps(), DEFAULT_MAX_STEPS)?;
+        let step = opts.get_or(atoms::step(), DEFAULT_STEP)?;
+
+        if max_steps == 0 {
+            return Ok(Err((atoms::error(), "Max steps must be positive")));
+        }
+
+        let steps = failures.saturating_add(1).min(max_steps as u32) as u8;
+        let mut data = [0u8; SEED_LEN + 2];
+        entropy::fill(&mut data[..SEED_LEN], None);
+        data[SEED_LEN] = steps;
+        data[SEED_LEN + 1] = step;
+        token::encode_fields(&opts, &data, base)
+    })
+}
+
+/// Solves every step of a sequence token in order, returning the nonces, or
+/// `{:error, :timeout}` once `:timeout` milliseconds have passed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn escalating_solve(token: &str, opts: Options) -> NifResult<Guarded<Result<Vec<u64>, Atom>>> {
+    guard::run_result(|| {
+        let timeout: Option<u64> = opts.get(atoms::timeout())?;
+        let deadline = timeout.map(|ms| Instant::now() + Duration::from_millis(ms));
+
+        let opened = match Opened::decode(token) {
+            Ok(opened) => opened,
+            Err(reason) => return Ok(Err(reason)),
+        };
+        let Some(sequence) = Sequence::new(opened.proof()) else {
+            return Ok(Err(atoms::malformed()));
+        };
+
+        let mut data = sequence.proof.data.to_vec();
+        let mut nonces = Vec::with_capacity(sequence.steps as usize);
+        for index in 0..sequence.steps {
+            let Some(nonce) = sequence.proof(index, 0, &data).solve(deadline) else {
+                return Ok(Err(atoms::timeout()));
+            };
+            data = next_data(&data, nonce);
+            nonces.push(nonce);
+        }
+        Ok(Ok(nonces))
+    })
+}
+
+/// Checks a sequence token's tag and timestamp as `token_verify` does, then that `nonces`
+/// solve its steps in order, each under the policy. Returns the number of steps
+#[rustler::nif(schedule = "DirtyCpu")]
+fn escalating_verify(
+    token: &str,
+    nonces: Vec<u64>,
+    policy: ResourceArc<Policy>,
+    opts: Options,
+) -> NifResult<Guarded<Result<u8, Atom>>> {
+    guard::run_result(|| {
+        let opened = match token::authenticate(token, &opts)? {
+            Ok(opened) => opened,
+            Err(reason) => return Ok(Err(reason)),
+        };
+        let Some(sequence) = Sequence::new(opened.proof()) else {
+            return Ok(Err(atoms::malformed()));
+        };
+        if nonces.len() != sequence.steps as usize {
+            return Ok(Err(atoms::incomplete()));
+        }
+
+        let mut data = sequence.proof.data.to_vec();
+        for (index, &nonce) in (0..sequence.steps).zip(&nonces) {
+            let bytes = sequence.proof(index, nonce, &data).encode();
+            if let Status::Error(reason) = proof::verify_encoded(&bytes, &policy) {
+                return Ok(Err(reason));
+            }
+            data = next_data(&data, nonce);
+        }
+        Ok(Ok(sequence.steps))
+    })
+}
@@ -0,0 +1,375 @@
+//! Ethash verification from the light cache, following the Ethash specification and geth's
+//! implementation; the full dataset is never built, each of the 128 items a hash needs is
+//! derived from the cache on demand.
+
+use lru::LruCache;
+use num_bigint::BigUint;
+use rustler::{Binary, NifMap, NifResult};
+use sha3::{Digest, Keccak256, Keccak512};
+use std::collections::HashSet;
+use std::panic;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+
+use crate::atoms;
+use crate::config;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+use crate::primes;
+
+const DATASET_INIT_BYTES: u64 = 1 << 30;
+const DATASET_GROWTH_BYTES: u64 = 1 << 23;
+const CACHE_INIT_BYTES: u64 = 1 << 24;
+const CACHE_GROWTH_BYTES: u64 = 1 << 17;
+const MIX_BYTES: u64 = 128;
+const HASH_BYTES: u64 = 64;
+const HASH_WORDS: usize = 16;
+const MIX_WORDS: usize = 32;
+const DATASET_PARENTS: u32 = 256;
+const CACHE_ROUNDS: usize = 3;
+const LOOP_ACCESSES: u32 = 64;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Epochs accepted; Ethereum's proof of work ended in epoch 521, and later epochs need
+/// caches of over 256 MiB
+const MAX_EPOCH: u64 = 2048;
+
+/// Default byte cap of the light caches: a few epochs near the end of Ethereum's proof of
+/// work, when each cache was about 80 MiB
+const DEFAULT_MAX_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+fn fnv(a: u32, b: u32) -> u32 {
+    a.wrapping_mul(FNV_PRIME) ^ b
+}
+
+/// Largest size below the epoch's nominal size whose count of `unit`-byte rows is prime
+fn prime_size(nominal: u64, unit: u64) -> u64 {
+    let mut size = nominal - unit;
+    while !primes::is_probable_prime(&BigUint::from(size / unit)) {
+        size -= 2 * unit;
+    }
+    size
+}
+
+fn cache_size(epoch: u64) -> u64 {
+    prime_size(CACHE_INIT_BYTES + CACHE_GROWTH_BYTES * epoch, HASH_BYTES)
+}
+
+fn dataset_size(epoch: u64) -> u64 {
+    prime_size(DATASET_INIT_BYTES + DATASET_GROWTH_BYTES * epoch, MIX_BYTES)
+}
+
+/// The seed is Keccak-256 applied `epoch` times to 32 zero bytes
+fn seed_hash(epoch: u64) -> [u8; 32] {
+    (0..epoch).fold([0u8; 32], |seed, _| Keccak256::digest(seed).into())
+}
+
+fn words(bytes: &[u8]) -> impl Iterator<Item = u32> + '_ {
+    bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+}
+
+/// RandMemoHash over sequential Keccak-512 rows, as little-endian words
+fn generate_cache(epoch: u64) -> Vec<u32> {
+    let size = cache_size(epoch) as usize;
+    let rows = size / HASH_BYTES as usize;
+    let mut cache = vec![0u8; size];
+
+    cache[..64].copy_from_slice(&Keccak512::digest(seed_hash(epoch)));
+    for offset in (64..size).step_by(64) {
+        let row = Keccak512::digest(&cache[offset - 64..offset]);
+        cache[offset..offset + 64].copy_from_slice(&row);
+    }
+
+    let mut temp = [0u8; 64];
+    for _ in 0..CACHE_ROUNDS {
+        for j in 0..rows {
+            let src = (j + rows - 1) % rows * 64;
+            let dst = j * 64;
+            let first = u32::from_le_bytes(cache[dst..dst + 4].try_into().unwrap_or_default());
+            let xor = first as usize % rows * 64;
+            for (k, byte) in temp.iter_mut().enumerate() {
+                *byte = cache[src + k] ^ cache[xor + k];
+            }
+            cache[dst..dst + 64].copy_from_slice(&Keccak512::digest(temp));
+        }
+    }
+
+    words(&cache).collect()
+}
+
+fn keccak512_words(mix: &[u32; HASH_WORDS]) -> [u32; HASH_WORDS] {
+    let mut bytes = [0u8; 64];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(mix) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    let mut out = [0u32; HASH_WORDS];
+    for (word, value) in out.iter_mut().zip(words(&Keccak512::digest(bytes))) {
+        *word = value;
+    }
+    out
+}
+
+/// Derives one 64-byte dataset item from 256 pseudorandomly chosen cache rows
+fn dataset_item(cache: &[u32], index: u32) -> [u32; HASH_WORDS] {
+    let rows = (cache.len() / HASH_WORDS) as u32;
+    let row = (index % rows) as usize * HASH_WORDS;
+
+    let mut mix = [0u32; HASH_WORDS];
+    mix.copy_from_slice(&cache[row..row + HASH_WORDS]);
+    mix[0] ^= index;
+    let mut mix = keccak512_words(&mix);
+
+    for i in 0..DATASET_PARENTS {
+        let parent = (fnv(index ^ i, mix[i as usize % HASH_WORDS]) % rows) as usize * HASH_WORDS;
+        for (word, data) in mix.iter_mut().zip(&cache[parent..parent + HASH_WORDS]) {
+            *word = fnv(*word, *data);
+        }
+    }
+
+    keccak512_words(&mix)
+}
+
+/// Hashimoto over dataset items derived from the cache: returns the mix digest and the
+/// final result hash
+fn hashimoto_light(
+    cache: &[u32],
+    epoch: u64,
+    header_hash: &[u8],
+    nonce: u64,
+) -> ([u8; 32], [u8; 32]) {
+    let rows = (dataset_size(epoch) / MIX_BYTES) as u32;
+
+    let mut seed_input = [0u8; 40];
+    seed_input[..32].copy_from_slice(header_hash);
+    seed_input[32..].copy_from_slice(&nonce.to_le_bytes());
+    let seed = Keccak512::digest(seed_input);
+    let seed_words: Vec<u32> = words(&seed).collect();
+
+    let mut mix = [0u32; MIX_WORDS];
+    for (i, word) in mix.iter_mut().enumerate() {
+        *word = seed_words[i % HASH_WORDS];
+    }
+
+    for i in 0..LOOP_ACCESSES {
+        let parent = fnv(i ^ seed_words[0], mix[i as usize % MIX_WORDS]) % rows;
+        for half in 0..2 {
+            let item = dataset_item(cache, 2 * parent + half);
+            let offset = half as usize * HASH_WORDS;
+            for (word, data) in mix[offset..offset + HASH_WORDS].iter_mut().zip(item) {
+                *word = fnv(*word, data);
+            }
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (chunk, lanes) in digest.chunks_exact_mut(4).zip(mix.chunks_exact(4)) {
+        let word = fnv(fnv(fnv(lanes[0], lanes[1]), lanes[2]), lanes[3]);
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+
+    let result = Keccak256::new()
+        .chain_update(seed)
+        .chain_update(digest)
+        .finalize();
+    (digest, result.into())
+}
+
+/// Light caches kept within a byte cap, least recently used evicted first
+struct EpochCaches {
+    caches: LruCache<u64, Arc<Vec<u32>>>,
+    bytes: u64,
+    max_bytes: u64,
+    /// Epochs being generated; callers needing one wait for it instead of generating it twice
+    building: HashSet<u64>,
+    prebuild: bool,
+}
+
+impl EpochCaches {
+    fn insert(&mut self, epoch: u64, cache: Arc<Vec<u32>>) {
+        self.bytes += cache_bytes(&cache);
+        if let Some((_, replaced)) = self.caches.push(epoch, cache) {
+            self.bytes -= cache_bytes(&replaced);
+        }
+        // The newest cache stays even over the cap, so a verification always has its epoch
+        while self.bytes > self.max_bytes && self.caches.len() > 1 {
+            if let Some((_, evicted)) = self.caches.pop_lru() {
+                self.bytes -= cache_bytes(&evicted);
+            }
+        }
+    }
+}
+
+fn cache_bytes(cache: &[u32]) -> u64 {
+    cache.len() as u64 * 4
+}
+
+fn epoch_caches() -> &'static (Mutex<EpochCaches>, Condvar) {
+    static CACHES: OnceLock<(Mutex<EpochCaches>, Condvar)> = OnceLock::new();
+    CACHES.get_or_init(|| {
+        let caches = EpochCaches {
+            caches: LruCache::unbounded(),
+            bytes: 0,
+            max_bytes: DEFAULT_MAX_CACHE_BYTES,
+            building: HashSet::new(),
+            prebuild: true,
+        };
+        (Mutex::new(caches), Condvar::new())
+    })
+}
+
+/// The light cache of an epoch, generated once and kept while it fits the byte cap.
+/// Generation runs without the lock held, so other epochs stay available meanwhile
+fn light_cache(epoch: u64) -> Arc<Vec<u32>> {
+    let (lock, built) = epoch_caches();
+    let mut caches = lock.lock().unwrap();
+    loop {
+        if let Some(cache) = caches.caches.get(&epoch) {
+            return Arc::clone(cache);
+        }
+        if !caches.building.contains(&epoch) {
+            break;
+        }
+        caches = built.wait(caches).unwrap();
+    }
+    caches.building.insert(epoch);
+    drop(caches);
+    build(epoch)
+}
+
+/// Generates a cache its caller marked as building, then stores it and wakes the waiters
+fn build(epoch: u64) -> Arc<Vec<u32>> {
+    let (lock, built) = epoch_caches();
+    let generated = panic::catch_unwind(|| generate_cache(epoch));
+
+    let mut caches = lock.lock().unwrap();
+    caches.building.remove(&epoch);
+    built.notify_all();
+    // After a panic, waiters retry the generation themselves rather than waiting forever
+    let cache = match generated {
+        Ok(cache) => Arc::new(cache),
+        Err(payload) => {
+            drop(caches);
+            panic::resume_unwind(payload)
+        }
+    };
+    caches.insert(epoch, Arc::clone(&cache));
+    cache
+}
+
+/// Generates the next epoch's cache on a background worker, unless it is cached, being
+/// built, or prebuilding is off, so verification doesn't stall at the epoch boundary
+fn prebuild(epoch: u64) {
+    let next = epoch + 1;
+    let mut caches = epoch_caches().0.lock().unwrap();
+    if !caches.prebuild
+        || next >= MAX_EPOCH
+        || caches.caches.contains(&next)
+        || caches.building.contains(&next)
+    {
+        return;
+    }
+    caches.building.insert(next);
+    drop(caches);
+
+    config::spawn_worker(move || {
+        let _ = guard::run(|| build(next));
+    });
+}
+
+/// Checks an Ethash seal from the light cache: the header's mix hash must match the
+/// recomputed one and, with `:difficulty`, the result must not exceed `2^256 / difficulty`.
+/// Generating an epoch's cache takes about a second the first time
+#[rustler::nif(schedule = "DirtyCpu")]
+fn ethash_verify(
+    header_hash: Binary,
+    nonce: u64,
+    mix_hash: Binary,
+    epoch: u64,
+    opts: Options,
+) -> NifResult<Guarded<bool>> {
+    guard::run_result(|| {
+        let difficulty: Option<u64> = opts.get(atoms::difficulty())?;
+
+        if header_hash.len() != 32 || mix_hash.len() != 32 || epoch >= MAX_EPOCH {
+            return Ok(false);
+        }
+        if difficulty == Some(0) {
+            return Ok(false);
+        }
+
+        let cache = light_cache(epoch);
+        prebuild(epoch);
+        let (digest, result) = hashimoto_light(&cache, epoch, header_hash.as_slice(), nonce);
+        if digest[..] != *mix_hash.as_slice() {
+            return Ok(false);
+        }
+
+        Ok(match difficulty {
+            Some(difficulty) => {
+                BigUint::from_bytes_be(&result) <= (BigUint::from(1u8) << 256) / difficulty
+            }
+            None => true,
+        })
+    })
+}
+
+/// Generates an epoch's light cache ahead of time and returns its size in bytes
+#[rustler::nif(schedule = "DirtyCpu")]
+fn ethash_cache_build(epoch: u64) -> GuardedResult<u64> {
+    guard::run(|| {
+        if epoch >= MAX_EPOCH {
+            return Err((atoms::error(), "Epoch out of range (0-2047)"));
+        }
+        Ok(cache_bytes(&light_cache(epoch)))
+    })
+}
+
+#[derive(NifMap)]
+struct CacheInfo {
+    /// Cached epochs, most recently used first
+    epochs: Vec<u64>,
+    building: Vec<u64>,
+    bytes: u64,
+    max_bytes: u64,
+    prebuild: bool,
+}
+
+/// Reports the cached and in-progress epochs and the bytes they take
+#[rustler::nif]
+fn ethash_cache_info() -> Guarded<CacheInfo> {
+    guard::run(|| {
+        let caches = epoch_caches().0.lock().unwrap();
+        let mut building: Vec<u64> = caches.building.iter().copied().collect();
+        building.sort_unstable();
+        CacheInfo {
+            epochs: caches.caches.iter().map(|(epoch, _)| *epoch).collect(),
+            building,
+            bytes: caches.bytes,
+            max_bytes: caches.max_bytes,
+            prebuild: caches.prebuild,
+        }
+    })
+}
+
+/// Sets the byte cap of the light caches (`:max_bytes`), evicting down to it, and whether
+/// verifications prebuild the next epoch (`:prebuild`)
+#[rustler::nif]
+fn ethash_cache_configure(opts: Options) -> NifResult<Guarded<rustler::Atom>> {
+    guard::run_result(|| {
+        let mut caches = epoch_caches().0.lock().unwrap();
+        if let Some(prebuild) = opts.get::<bool>(atoms::prebuild())? {
+            caches.prebuild = prebuild;
+        }
+        if let Some(max_bytes) = opts.get::<u64>(atoms::max_bytes())? {
+            caches.max_bytes = max_bytes;
+            while caches.bytes > caches.max_bytes {
+                let Some((_, evicted)) = caches.caches.pop_lru() else {
+                    break;
+                };
+                caches.bytes -= cache_bytes(&evicted);
+            }
+        }
+        Ok(atoms::ok())
+    })
+}
@@ -0,0 +1,72 @@
+use rustler::{Atom, Encoder, Env, NifResult, Term};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::atoms;
+use crate::cpu;
+use crate::log::{self, Level, Value};
+
+/// A NIF result, or the message of a panic raised while computing it
+pub enum Guarded<T> {
+    Ok(T),
+    /// Encoded as `{:error, :internal, message}`
+    Panicked(String),
+    /// Encoded as `{:error, :unsupported_cpu}`: the build requires CPU features this
+    /// machine lacks, see `cpu::missing`
+    Unsupported,
+}
+
+/// A guarded NIF result with the crate's usual `{:error, reason}` errors
+pub type GuardedResult<T> = Guarded<Result<T, (Atom, &'static str)>>;
+
+impl<T: Encoder> Encoder for Guarded<T> {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            Guarded::Ok(value) => value.encode(env),
+            Guarded::Panicked(message) => (atoms::error(), atoms::internal(), message).encode(env),
+            Guarded::Unsupported => (atoms::error(), atoms::unsupported_cpu()).encode(env),
+        }
+    }
+}
+
+/// Runs a NIF body, turning a panic into `{:error, :internal, message}` instead of
+/// letting it unwind into the VM. On a CPU missing features the build requires, the body
+/// is not run at all
+pub fn run<T>(body: impl FnOnce() -> T) -> Guarded<T> {
+    if !cpu::supported() {
+        return Guarded::Unsupported;
+    }
+
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => Guarded::Ok(value),
+        Err(payload) => {
+            let message = message(payload.as_ref());
+            log::emit(
+                Level::Error,
+                "nif_panicked",
+                vec![("message", Value::Str(message.clone()))],
+            );
+            Guarded::Panicked(message)
+        }
+    }
+}
+
+/// Like `run`, for bodies that raise on malformed arguments; the raise is kept
+pub fn run_result<T>(body: impl FnOnce() -> NifResult<T>) -> NifResult<Guarded<T>> {
+    match run(body) {
+        Guarded::Ok(result) => result.map(Guarded::Ok),
+        Guarded::Panicked(message) => Ok(Guarded::Panicked(message)),
+        Guarded::Unsupported => Ok(Guarded::Unsupported),
+    }
+}
+
+/// The text passed to `panic!`, if any
+pub fn message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "unknown panic".to_string(),
+        },
+    }
+}
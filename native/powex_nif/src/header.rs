@@ -0,0 +1,358 @@
+use rustler::{Atom, Binary, Env, NewBinary, NifMap};
+use sha2::{Digest, Sha256};
+
+use crate::atoms;
+use crate::difficulty::bits_to_target;
+use crate::guard::{self, GuardedResult};
+use crate::metrics;
+use crate::options::Options;
+
+/// Default number of extranonce increments before `compute_header` gives up
+const DEFAULT_MAX_ROLLS: u64 = 1_000;
+
+/// Size in bytes of a serialized block header
+pub const HEADER_SIZE: usize = 80;
+
+/// Bitcoin-style block header, with hashes in internal (serialized) byte order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub version: u32,
+    pub prev_hash: [u8; 32],
+    pub merkle_root: [u8; 32],
+    pub timestamp: u32,
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+impl Header {
+    pub fn to_bytes(self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&self.version.to_le_bytes());
+        bytes[4..36].copy_from_slice(&self.prev_hash);
+        bytes[36..68].copy_from_slice(&self.merkle_root);
+        bytes[68..72].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes[72..76].copy_from_slice(&self.bits.to_le_bytes());
+        bytes[76..80].copy_from_slice(&self.nonce.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Header> {
+        if bytes.len() != HEADER_SIZE {
+            return None;
+        }
+
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        Some(Header {
+            version: u32_at(0),
+            prev_hash: bytes[4..36].try_into().unwrap(),
+            merkle_root: bytes[36..68].try_into().unwrap(),
+            timestamp: u32_at(68),
+            bits: u32_at(72),
+            nonce: u32_at(76),
+        })
+    }
+
+    /// Applies the fields present in the options, leaving the others untouched
+    fn update(&mut self, fields: &Options) -> Result<(), (Atom, &'static str)> {
+        if let Some(version) = field(fields, atoms::version())? {
+            self.version = version;
+        }
+        if let Some(hash) = hash_field(fields, atoms::prev_hash())? {
+            self.prev_hash = hash;
+        }
+        if let Some(hash) = hash_field(fields, atoms::merkle_root())? {
+            self.merkle_root = hash;
+        }
+        if let Some(timestamp) = field(fields, atoms::timestamp())? {
+            self.timestamp = timestamp;
+        }
+        if let Some(bits) = field(fields, atoms::bits())? {
+            self.bits = bits;
+        }
+        if let Some(nonce) = field(fields, atoms::nonce())? {
+            self.nonce = nonce;
+        }
+        Ok(())
+    }
+}
+
+fn field<'a, T: rustler::Decoder<'a>>(
+    fields: &Options<'a>,
+    key: Atom,
+) -> Result<Option<T>, (Atom, &'static str)> {
+    fields
+        .get(key)
+        .map_err(|_| (atoms::error(), "Invalid header fields"))
+}
+
+fn hash_field(fields: &Options, key: Atom) -> Result<Option<[u8; 32]>, (Atom, &'static str)> {
+    match field::<Binary>(fields, key)? {
+        Some(binary) => binary
+            .as_slice()
+            .try_into()
+            .map(Some)
+            .map_err(|_| (atoms::error(), "Hashes must be 32 bytes")),
+        None => Ok(None),
+    }
+}
+
+/// Header fields as exposed to Elixir
+#[derive(NifMap)]
+struct HeaderFields<'a> {
+    version: u32,
+    prev_hash: Binary<'a>,
+    merkle_root: Binary<'a>,
+    timestamp: u32,
+    bits: u32,
+    nonce: u32,
+}
+
+/// Checks a hash in internal byte order (little-endian integer) against a big-endian target
+pub fn hash_meets_target(hash: &[u8; 32], target: &[u8; 32]) -> bool {
+    hash.iter().rev().cmp(target.iter()) != std::cmp::Ordering::Greater
+}
+
+pub fn double_sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.into()
+}
+
+fn parse(header: &Binary) -> Result<Header, (Atom, &'static str)> {
+    Header::from_bytes(header.as_slice()).ok_or((atoms::error(), "Header must be 80 bytes"))
+}
+
+/// Computes the merkle root for a coinbase split around its extranonce, given the merkle branch
+pub fn coinbase_merkle_root(
+    coinbase1: &[u8],
+    extranonce: &[u8],
+    coinbase2: &[u8],
+    branch: &[[u8; 32]],
+) -> [u8; 32] {
+    let coinbase = [coinbase1, extranonce, coinbase2].concat();
+    branch
+        .iter()
+        .fold(double_sha256(&coinbase), |root, sibling| {
+            double_sha256(&[root.as_slice(), sibling].concat())
+        })
+}
+
+/// Increments the extranonce as a little-endian counter, returning false on overflow
+pub fn increment_extranonce(extranonce: &mut [u8]) -> bool {
+    for byte in extranonce.iter_mut() {
+        let (value, overflow) = byte.overflowing_add(1);
+        *byte = value;
+        if !overflow {
+            return true;
+        }
+    }
+    false
+}
+
+fn decode_branch(branch: &[Binary]) -> Result<Vec<[u8; 32]>, (Atom, &'static str)> {
+    branch
+        .iter()
+        .map(|hash| {
+            hash.as_slice()
+                .try_into()
+                .map_err(|_| (atoms::error(), "Hashes must be 32 bytes"))
+        })
+        .collect()
+}
+
+/// Assembles an 80-byte header from its fields (`nonce` defaults to 0)
+#[rustler::nif]
+fn header_assemble<'a>(env: Env<'a>, fields: Options) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        let required = [
+            atoms::version(),
+            atoms::prev_hash(),
+            atoms::merkle_root(),
+            atoms::timestamp(),
+            atoms::bits(),
+        ];
+        for key in required {
+            if field::<rustler::Term>(&fields, key)?.is_none() {
+                return Err((atoms::error(), "Missing header fields"));
+            }
+        }
+
+        let mut header = Header {
+            version: 0,
+            prev_hash: [0; 32],
+            merkle_root: [0; 32],
+            timestamp: 0,
+            bits: 0,
+            nonce: 0,
+        };
+        header.update(&fields)?;
+        Ok(to_binary(env, &header.to_bytes()))
+    })
+}
+
+/// Decodes an 80-byte header into its fields
+#[rustler::nif]
+fn header_parse<'a>(env: Env<'a>, header: Binary) -> GuardedResult<HeaderFields<'a>> {
+    guard::run(|| {
+        let header = parse(&header)?;
+
+        Ok(HeaderFields {
+            version: header.version,
+            prev_hash: to_binary(env, &header.prev_hash),
+            merkle_root: to_binary(env, &header.merkle_root),
+            timestamp: header.timestamp,
+            bits: header.bits,
+            nonce: header.nonce,
+        })
+    })
+}
+
+/// Returns a copy of the header with the given fields replaced
+#[rustler::nif]
+fn header_update<'a>(env: Env<'a>, header: Binary, fields: Options) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        let mut header = parse(&header)?;
+        header.update(&fields)?;
+        Ok(to_binary(env, &header.to_bytes()))
+    })
+}
+
+/// Computes the double SHA-256 header hash, in internal byte order
+#[rustler::nif]
+fn header_hash<'a>(env: Env<'a>, header: Binary) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        let header = parse(&header)?;
+        Ok(to_binary(env, &double_sha256(&header.to_bytes())))
+    })
+}
+
+/// Rolls the timestamp forward by `seconds` and resets the nonce
+#[rustler::nif]
+fn header_roll_time<'a>(env: Env<'a>, header: Binary, seconds: u32) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        let mut header = parse(&header)?;
+        header.timestamp = header
+            .timestamp
+            .checked_add(seconds)
+            .ok_or((atoms::error(), "Timestamp overflow"))?;
+        header.nonce = 0;
+        Ok(to_binary(env, &header.to_bytes()))
+    })
+}
+
+/// Increments the extranonce, recomputes the merkle root from the coinbase and branch,
+/// and resets the nonce
+#[rustler::nif]
+fn header_roll_extranonce<'a>(
+    env: Env<'a>,
+    header: Binary,
+    coinbase1: Binary,
+    extranonce: Binary,
+    coinbase2: Binary,
+    branch: Vec<Binary>,
+) -> GuardedResult<(Binary<'a>, Binary<'a>)> {
+    guard::run(|| {
+        let mut header = parse(&header)?;
+        let branch = decode_branch(&branch)?;
+
+        let mut next = extranonce.as_slice().to_vec();
+        if !increment_extranonce(&mut next) {
+            return Err((atoms::error(), "Extranonce space exhausted"));
+        }
+
+        header.merkle_root = coinbase_merkle_root(&coinbase1, &next, &coinbase2, &branch);
+        header.nonce = 0;
+        Ok((to_binary(env, &header.to_bytes()), to_binary(env, &next)))
+    })
+}
+
+/// Result of a header search
+#[derive(NifMap)]
+struct HeaderSolution<'a> {
+    header: Binary<'a>,
+    nonce: u32,
+    extranonce: Option<Binary<'a>>,
+    hash: Binary<'a>,
+}
+
+/// Searches the nonce range of a header against its nBits target using a SHA-256 midstate
+fn search_nonces(header: &mut Header, target: &[u8; 32], nonce_end: u64) -> Option<[u8; 32]> {
+    let bytes = header.to_bytes();
+    let midstate = Sha256::new().chain_update(&bytes[..64]);
+    let mut tail = [0u8; 16];
+    tail[..12].copy_from_slice(&bytes[64..76]);
+
+    let mut meter = metrics::Meter::new();
+
+    for nonce in 0..nonce_end {
+        meter.tick();
+        tail[12..].copy_from_slice(&(nonce as u32).to_le_bytes());
+        let first = midstate.clone().chain_update(tail).finalize();
+        let hash: [u8; 32] = Sha256::digest(first).into();
+
+        if hash_meets_target(&hash, target) {
+            header.nonce = nonce as u32;
+            return Some(hash);
+        }
+    }
+
+    None
+}
+
+/// Mines a Bitcoin-style header against its nBits target.
+/// When the nonce space is exhausted and an extranonce context is given, the extranonce is
+/// incremented and the merkle root recomputed without returning to Elixir.
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_header<'a>(
+    env: Env<'a>,
+    header: Binary,
+    opts: Options,
+) -> GuardedResult<HeaderSolution<'a>> {
+    guard::run(|| {
+        let mut header = parse(&header)?;
+        let target = bits_to_target(header.bits).ok_or((atoms::error(), "Invalid nBits"))?;
+        let nonce_end = field(&opts, atoms::nonce_end())?
+            .unwrap_or(1u64 << 32)
+            .min(1u64 << 32);
+        let max_rolls: u64 = field(&opts, atoms::max_rolls())?.unwrap_or(DEFAULT_MAX_ROLLS);
+        let context: Option<(Binary, Binary, Binary, Vec<Binary>)> =
+            field(&opts, atoms::extranonce())?;
+
+        let Some((coinbase1, extranonce, coinbase2, branch)) = context else {
+            return match search_nonces(&mut header, &target, nonce_end) {
+                Some(hash) => Ok(HeaderSolution {
+                    header: to_binary(env, &header.to_bytes()),
+                    nonce: header.nonce,
+                    extranonce: None,
+                    hash: to_binary(env, &hash),
+                }),
+                None => Err((atoms::error(), "Nonce space exhausted")),
+            };
+        };
+
+        let branch = decode_branch(&branch)?;
+        let mut extranonce = extranonce.as_slice().to_vec();
+
+        for _ in 0..=max_rolls {
+            if let Some(hash) = search_nonces(&mut header, &target, nonce_end) {
+                return Ok(HeaderSolution {
+                    header: to_binary(env, &header.to_bytes()),
+                    nonce: header.nonce,
+                    extranonce: Some(to_binary(env, &extranonce)),
+                    hash: to_binary(env, &hash),
+                });
+            }
+
+            if !increment_extranonce(&mut extranonce) {
+                return Err((atoms::error(), "Extranonce space exhausted"));
+            }
+            header.merkle_root = coinbase_merkle_root(&coinbase1, &extranonce, &coinbase2, &branch);
+        }
+
+        Err((atoms::error(), "Maximum extranonce rolls reached"))
+    })
+}
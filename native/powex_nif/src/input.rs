@@ -0,0 +1,53 @@
+use rustler::{Error, NifResult};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::atoms;
+
+/// Default `:max_data_size` of `configure/1`
+pub const DEFAULT_MAX_DATA_SIZE: usize = 64 * 1024 * 1024;
+
+/// Bytes hashed between cancellation checks
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+
+static MAX_DATA_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DATA_SIZE);
+
+/// Largest data accepted by the search and validation functions
+pub fn max_data_size() -> usize {
+    MAX_DATA_SIZE.load(Ordering::Relaxed)
+}
+
+pub fn set_max_data_size(bytes: usize) {
+    MAX_DATA_SIZE.store(bytes, Ordering::Relaxed);
+}
+
+/// Raises `{:data_too_large, limit}` for data larger than the configured limit, before any
+/// hashing starts
+pub fn check_size(data: &[u8]) -> NifResult<()> {
+    let limit = max_data_size();
+    if data.len() > limit {
+        return Err(Error::RaiseTerm(Box::new((atoms::data_too_large(), limit))));
+    }
+    Ok(())
+}
+
+/// Passes `data` to `update` in `CHUNK_SIZE` pieces, calling `keep_going` before each piece
+/// but the first; returns `false` as soon as it asks to stop
+pub fn chunked(
+    data: &[u8],
+    mut keep_going: impl FnMut() -> bool,
+    mut update: impl FnMut(&[u8]),
+) -> bool {
+    for (index, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
+        if index > 0 && !keep_going() {
+            return false;
+        }
+        update(chunk);
+    }
+    true
+}
+
+/// Feeds `data` to the hasher in chunks, as by `chunked`
+pub fn absorb(hasher: &mut Sha256, data: &[u8], keep_going: impl FnMut() -> bool) -> bool {
+    chunked(data, keep_going, |chunk| hasher.update(chunk))
+}
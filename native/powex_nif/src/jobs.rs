@@ -0,0 +1,273 @@
+use rustler::env::OwnedEnv;
+use rustler::{Atom, Binary, Env, LocalPid, NifMap, NifResult, Resource, ResourceArc, Term};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::audit::{self, Outcome};
+use crate::backend;
+use crate::budget;
+use crate::config;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded};
+use crate::input;
+use crate::lifecycle;
+use crate::log::{self, Level, Value};
+use crate::memory_hard::{Hasher, MemoryHardParams};
+use crate::metrics;
+use crate::options::Options;
+use crate::throttle::DutyCycle;
+
+/// Handle of an async job; its result arrives as `{:powex_job, job, result}`
+pub struct Job;
+
+#[rustler::resource_impl]
+impl Resource for Job {}
+
+/// Identity of a search: jobs with equal keys always produce the same nonce
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct JobKey {
+    algorithm: Algorithm,
+    data: Vec<u8>,
+    difficulty: Difficulty,
+}
+
+/// A caller waiting for the result of a search
+struct Subscriber {
+    pid: LocalPid,
+    job: ResourceArc<Job>,
+}
+
+pub type JobResult = Result<u64, (Atom, &'static str)>;
+
+/// Searches in flight, each with every job waiting on it
+fn in_flight() -> &'static Mutex<HashMap<JobKey, Vec<Subscriber>>> {
+    static IN_FLIGHT: OnceLock<Mutex<HashMap<JobKey, Vec<Subscriber>>>> = OnceLock::new();
+    IN_FLIGHT.get_or_init(Default::default)
+}
+
+/// Jobs that joined an in-flight search instead of starting their own
+static COALESCED: AtomicU64 = AtomicU64::new(0);
+
+/// Nonces hashed between calls to a paced search's hook
+pub const BATCH: u64 = 1_024;
+
+/// Scans nonces from zero with the given algorithm
+pub fn search(algorithm: &Algorithm, data: &[u8], difficulty: &Difficulty) -> JobResult {
+    search_paced(algorithm, data, difficulty, |_| {})
+}
+
+/// Like `search`, but calls `pace` with the batch size before each batch of nonces,
+/// so the caller can throttle the work by blocking
+pub fn search_paced(
+    algorithm: &Algorithm,
+    data: &[u8],
+    difficulty: &Difficulty,
+    mut pace: impl FnMut(u64),
+) -> JobResult {
+    difficulty.validate()?;
+
+    let prepared = backend::prepare(data);
+    let state = algorithm.prepare(data);
+    let mut digest = Vec::new();
+    let mut meets = |nonce: u64| match algorithm {
+        Algorithm::Sha256 => prepared.meets(nonce, difficulty),
+        _ => {
+            state.digest_into(nonce, &mut digest);
+            difficulty.is_met_by(&digest)
+        }
+    };
+
+    let mut meter = metrics::Meter::new();
+
+    for nonce in 0..u64::MAX {
+        meter.tick();
+        if nonce % BATCH == 0 {
+            pace(BATCH);
+        }
+
+        if meets(nonce) {
+            return Ok(nonce);
+        }
+
+        if nonce > 100_000_000 && difficulty.zeros() > 20 {
+            return Err((atoms::error(), "Difficulty too high, computation aborted"));
+        }
+    }
+
+    Err((atoms::error(), "No valid nonce found"))
+}
+
+/// Runs the search once and delivers its result to every job that joined it meanwhile.
+/// The search pauses with background work and keeps to the profile's duty cycle
+fn execute(key: JobKey) {
+    let mut duty_cycle = DutyCycle::new();
+    let result = guard::run(|| {
+        search_paced(&key.algorithm, &key.data, &key.difficulty, |_| {
+            lifecycle::wait_while_paused();
+            duty_cycle.run_at(config::profile().max_duty_cycle());
+        })
+    });
+    let subscribers = in_flight().lock().unwrap().remove(&key).unwrap_or_default();
+    if let Guarded::Ok(result) = &result {
+        log::search_finished(
+            "job_finished",
+            result,
+            vec![
+                ("algorithm", Value::Atom(key.algorithm.name().to_string())),
+                ("jobs", Value::U64(subscribers.len() as u64)),
+            ],
+        );
+    }
+
+    let mut env = OwnedEnv::new();
+    for subscriber in subscribers {
+        let _ = env.send_and_clear(&subscriber.pid, |_| {
+            (atoms::powex_job(), subscriber.job.clone(), &result)
+        });
+        metrics::job_completed();
+    }
+}
+
+/// Starts a search in the background and returns its job handle.
+/// A job identical to one in flight (same algorithm, data and difficulty) joins it
+/// instead of searching again, and both receive the same result.
+#[rustler::nif]
+fn compute_async(
+    env: Env,
+    data: Binary,
+    difficulty: Difficulty,
+    opts: Options,
+) -> NifResult<Guarded<(Atom, ResourceArc<Job>)>> {
+    guard::run_result(|| {
+        input::check_size(data.as_slice())?;
+        let key = JobKey {
+            algorithm: opts.get_or(atoms::algorithm(), Algorithm::Sha256)?,
+            data: data.as_slice().to_vec(),
+            difficulty,
+        };
+        let job = ResourceArc::new(Job);
+        let subscriber = Subscriber {
+            pid: opts.get_or(atoms::pid(), env.pid())?,
+            job: job.clone(),
+        };
+
+        metrics::job_started();
+        let metadata = vec![
+            ("algorithm", Value::Atom(key.algorithm.name().to_string())),
+            ("data_size", Value::U64(key.data.len() as u64)),
+        ];
+        match in_flight().lock().unwrap().entry(key) {
+            Entry::Occupied(mut entry) => {
+                COALESCED.fetch_add(1, Ordering::Relaxed);
+                entry.get_mut().push(subscriber);
+                log::emit(Level::Debug, "job_coalesced", metadata);
+            }
+            Entry::Vacant(entry) => {
+                let key = entry.key().clone();
+                entry.insert(vec![subscriber]);
+                log::emit(Level::Debug, "job_started", metadata);
+                config::spawn_worker(move || execute(key));
+            }
+        }
+
+        Ok((atoms::ok(), job))
+    })
+}
+
+/// How an async verification or a session hashes: a digest algorithm, or Argon2id
+pub enum Verifier {
+    Hash(Algorithm),
+    Argon2id(MemoryHardParams),
+}
+
+impl Verifier {
+    /// Reads `:algorithm`, with `:m_cost`, `:t_cost` and `:p_cost` for `:argon2id`
+    pub fn from_options(opts: &Options) -> NifResult<Verifier> {
+        match opts.get::<Term>(atoms::algorithm())? {
+            Some(term) if term.decode::<Atom>().is_ok_and(|name| name == atoms::argon2id()) => {
+                Ok(Verifier::Argon2id(MemoryHardParams::from_options(opts)?))
+            }
+            Some(term) => Ok(Verifier::Hash(term.decode()?)),
+            None => Ok(Verifier::Hash(Algorithm::Sha256)),
+        }
+    }
+
+    /// Checks the proof like `valid?` or `valid_memory_hard?` would, without a scheduler to
+    /// yield to
+    fn verify(&self, data: &[u8], nonce: u64, difficulty: Difficulty) -> Result<bool, Atom> {
+        let valid = match self {
+            // Cached and audited like `valid?`
+            Verifier::Hash(Algorithm::Sha256) => {
+                return Ok(crate::verify(None, data, nonce, difficulty).unwrap_or(false));
+            }
+            Verifier::Hash(algorithm) => {
+                difficulty.is_met_by(&algorithm.prepare(data).digest(nonce))
+            }
+            Verifier::Argon2id(params) => {
+                budget::check(data.len() as u64 + 8, params.memory_bytes())?;
+                let mut hasher = Hasher::new(data, *params).ok_or(atoms::invalid())?;
+                difficulty.is_met_by(&hasher.digest(nonce))
+            }
+        };
+
+        let outcome = if valid { Outcome::Valid } else { Outcome::Invalid };
+        audit::record(&[data, &nonce.to_le_bytes()], difficulty.zeros(), outcome);
+        Ok(valid)
+    }
+}
+
+/// Verifies a proof on a background worker and returns a job handle; the result arrives as
+/// `{:powex_job, job, valid}`, so huge data or memory-hard parameters never hold a scheduler
+#[rustler::nif(name = "valid_async?")]
+fn valid_async(
+    env: Env,
+    data: Binary,
+    nonce: u64,
+    difficulty: Difficulty,
+    opts: Options,
+) -> NifResult<Guarded<(Atom, ResourceArc<Job>)>> {
+    guard::run_result(|| {
+        input::check_size(data.as_slice())?;
+        let verifier = Verifier::from_options(&opts)?;
+        let pid = opts.get_or(atoms::pid(), env.pid())?;
+        let data = data.as_slice().to_vec();
+        let job = ResourceArc::new(Job);
+        let handle = job.clone();
+
+        metrics::job_started();
+        config::spawn_worker(move || {
+            let result = guard::run(|| verifier.verify(&data, nonce, difficulty));
+            let _ = OwnedEnv::new().send_and_clear(&pid, |_| (atoms::powex_job(), handle, &result));
+            metrics::job_completed();
+        });
+
+        Ok((atoms::ok(), job))
+    })
+}
+
+#[derive(NifMap)]
+pub struct JobStats {
+    pub searches: usize,
+    pub jobs: usize,
+    pub coalesced: u64,
+}
+
+/// Exposes `stats` to Elixir
+#[rustler::nif]
+fn job_stats() -> Guarded<JobStats> {
+    guard::run(stats)
+}
+
+/// Counts searches in flight, the jobs waiting on them, and jobs coalesced so far
+pub fn stats() -> JobStats {
+    let in_flight = in_flight().lock().unwrap();
+    JobStats {
+        searches: in_flight.len(),
+        jobs: in_flight.values().map(Vec::len).sum(),
+        coalesced: COALESCED.load(Ordering::Relaxed),
+    }
+}
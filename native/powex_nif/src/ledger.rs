@@ -0,0 +1,239 @@
+use rustler::{Atom, Binary, Env, NifMap, NifResult, Term};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+use crate::{atoms, compute_hash, meets_difficulty, Status};
+
+/// Default number of nonces leased per `get_work` call
+const DEFAULT_CHUNK_SIZE: u64 = 1_000_000;
+
+/// Default lease lifetime in milliseconds
+const DEFAULT_LEASE_MS: u64 = 30_000;
+
+struct Lease {
+    start: u64,
+    end: u64,
+    expires: Instant,
+}
+
+struct Job {
+    id: u64,
+    challenge: Vec<u8>,
+    difficulty: u32,
+    next: u64,
+    chunk_size: u64,
+    lease_duration: Duration,
+    /// Leases keyed by the worker id in external term format
+    leases: HashMap<Vec<u8>, Lease>,
+    /// Ranges returned by expired leases, handed out before fresh ones
+    free: VecDeque<(u64, u64)>,
+    solutions: Vec<(Vec<u8>, u64)>,
+}
+
+impl Job {
+    /// Moves the ranges of expired leases back to the free queue
+    fn reclaim_expired(&mut self, now: Instant) {
+        let free = &mut self.free;
+        self.leases.retain(|_, lease| {
+            let alive = lease.expires > now;
+            if !alive && lease.start < lease.end {
+                free.push_back((lease.start, lease.end));
+            }
+            alive
+        });
+    }
+
+    fn next_range(&mut self) -> Option<(u64, u64)> {
+        if let Some(range) = self.free.pop_front() {
+            return Some(range);
+        }
+
+        if self.next == u64::MAX {
+            return None;
+        }
+
+        let start = self.next;
+        let end = start.saturating_add(self.chunk_size);
+        self.next = end;
+        Some((start, end))
+    }
+}
+
+struct Ledger {
+    last_id: u64,
+    job: Option<Job>,
+}
+
+static LEDGER: Mutex<Ledger> = Mutex::new(Ledger {
+    last_id: 0,
+    job: None,
+});
+
+#[derive(NifMap)]
+struct WorkUnit<'a> {
+    job_id: u64,
+    challenge: Binary<'a>,
+    difficulty: u32,
+    start: u64,
+    end: u64,
+}
+
+#[derive(NifMap)]
+struct WorkStatus<'a> {
+    job_id: u64,
+    difficulty: u32,
+    leases: usize,
+    solutions: Vec<(Term<'a>, u64)>,
+}
+
+fn decode_worker<'a>(env: Env<'a>, worker: &[u8]) -> Term<'a> {
+    env.binary_to_term(worker).map(|(term, _)| term).unwrap()
+}
+
+/// Publishes a new job, replacing the current one and its leases
+#[rustler::nif]
+fn put_work(challenge: Binary, difficulty: u32, opts: Options) -> NifResult<GuardedResult<u64>> {
+    guard::run_result(|| {
+        let chunk_size = opts.get_or(atoms::chunk_size(), DEFAULT_CHUNK_SIZE)?;
+        let lease_ms = opts.get_or(atoms::lease_ms(), DEFAULT_LEASE_MS)?;
+
+        if let Err(reason) = Difficulty::Zeros(difficulty).validate() {
+            return Ok(Err(reason));
+        }
+
+        if chunk_size == 0 {
+            return Ok(Err((atoms::error(), "Chunk size must be positive")));
+        }
+
+        let mut ledger = LEDGER.lock().unwrap();
+        ledger.last_id += 1;
+        let id = ledger.last_id;
+
+        ledger.job = Some(Job {
+            id,
+            challenge: challenge.as_slice().to_vec(),
+            difficulty,
+            next: 0,
+            chunk_size,
+            lease_duration: Duration::from_millis(lease_ms),
+            leases: HashMap::new(),
+            free: VecDeque::new(),
+            solutions: vec![],
+        });
+
+        Ok(Ok(id))
+    })
+}
+
+/// Leases the next nonce range of the current job to a worker.
+/// A worker asking again is assumed to have finished its previous range.
+#[rustler::nif]
+fn get_work<'a>(env: Env<'a>, worker_id: Term<'a>) -> Guarded<Result<WorkUnit<'a>, Atom>> {
+    guard::run(|| {
+        let mut ledger = LEDGER.lock().unwrap();
+        let job = ledger.job.as_mut().ok_or(atoms::no_work())?;
+
+        let now = Instant::now();
+        let worker = worker_id.to_binary().as_slice().to_vec();
+        job.leases.remove(&worker);
+        job.reclaim_expired(now);
+
+        let (start, end) = job.next_range().ok_or(atoms::exhausted())?;
+
+        job.leases.insert(
+            worker,
+            Lease {
+                start,
+                end,
+                expires: now + job.lease_duration,
+            },
+        );
+
+        let mut challenge = rustler::NewBinary::new(env, job.challenge.len());
+        challenge.as_mut_slice().copy_from_slice(&job.challenge);
+
+        Ok(WorkUnit {
+            job_id: job.id,
+            challenge: challenge.into(),
+            difficulty: job.difficulty,
+            start,
+            end,
+        })
+    })
+}
+
+/// Extends a worker's lease by the job's lease duration. With `progress`, nonces below it
+/// count as searched, so only the rest of the range returns to the pool if the lease
+/// expires later
+#[rustler::nif]
+fn heartbeat_work(worker_id: Term, progress: Option<u64>) -> Guarded<Status> {
+    guard::run(|| {
+        let mut ledger = LEDGER.lock().unwrap();
+        let Some(job) = ledger.job.as_mut() else {
+            return Status::Error(atoms::no_work());
+        };
+
+        let now = Instant::now();
+        job.reclaim_expired(now);
+
+        let worker = worker_id.to_binary();
+        let Some(lease) = job.leases.get_mut(worker.as_slice()) else {
+            return Status::Error(atoms::no_lease());
+        };
+
+        if let Some(progress) = progress {
+            lease.start = progress.clamp(lease.start, lease.end);
+        }
+        lease.expires = now + job.lease_duration;
+        Status::Ok
+    })
+}
+
+/// Validates a worker's solution for the current job and records it
+#[rustler::nif]
+fn submit_work(worker_id: Term, nonce: u64) -> Guarded<Status> {
+    guard::run(|| {
+        let mut ledger = LEDGER.lock().unwrap();
+        let Some(job) = ledger.job.as_mut() else {
+            return Status::Error(atoms::no_work());
+        };
+
+        if job.solutions.iter().any(|(_, n)| *n == nonce) {
+            return Status::Error(atoms::duplicate());
+        }
+
+        if !meets_difficulty(&compute_hash(&job.challenge, nonce), job.difficulty) {
+            return Status::Error(atoms::invalid());
+        }
+
+        let worker = worker_id.to_binary().as_slice().to_vec();
+        job.solutions.push((worker, nonce));
+        Status::Ok
+    })
+}
+
+/// Reports the current job with its active leases and recorded solutions
+#[rustler::nif]
+fn work_status(env: Env) -> Guarded<Result<WorkStatus, Atom>> {
+    guard::run(|| {
+        let mut ledger = LEDGER.lock().unwrap();
+        let job = ledger.job.as_mut().ok_or(atoms::no_work())?;
+
+        job.reclaim_expired(Instant::now());
+
+        Ok(WorkStatus {
+            job_id: job.id,
+            difficulty: job.difficulty,
+            leases: job.leases.len(),
+            solutions: job
+                .solutions
+                .iter()
+                .map(|(worker, nonce)| (decode_worker(env, worker), *nonce))
+                .collect(),
+        })
+    })
+}
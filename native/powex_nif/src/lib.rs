@@ -1,14 +1,260 @@
-use rustler::{Atom, Binary};
+use rustler::{Atom, Binary, Encoder, Env, NifMap, NifResult, NifStruct, Term};
 use sha2::{Digest, Sha256};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::ops::Range;
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::algorithm::Algorithm;
+use crate::anytime::{Stop, Stopped};
+use crate::difficulty::Difficulty;
+use crate::encoding::Encoding;
+use crate::guard::{Guarded, GuardedResult};
+use crate::options::Options;
+
+mod algorithm;
+mod anytime;
+mod audit;
+mod backend;
+mod blind;
+mod budget;
+mod bundle;
+mod cache;
+mod chain;
+mod clock;
+mod commit_reveal;
+mod compress;
+mod config;
+mod controller;
+mod cpu;
+mod curve;
+mod daemon;
+mod difficulty;
+mod dispatch;
+mod distribution;
+mod dual;
+mod encoding;
+mod entropy;
+mod epoch;
+mod equivalence;
+mod escalation;
+mod ethash;
+mod guard;
+mod header;
+mod input;
+mod jobs;
+mod ledger;
+mod lifecycle;
+mod log;
+mod lottery;
+mod memory_hard;
+mod merkle;
+mod metrics;
+mod nano;
+mod numa;
+mod options;
+mod os_limit;
+mod partition;
+mod personal;
+mod poseidon;
+mod predicate;
+mod prime_chain;
+mod pricing;
+mod primes;
+mod proof;
+mod proof_store;
+mod proof_stream;
+mod receipt;
+mod rounds;
+mod rsa_timelock;
+mod scratchpad;
+mod seeded;
+mod session;
+mod sloth;
+mod solve_times;
+mod spec;
+mod throttle;
+mod token;
+mod tor_pow;
+mod total_work;
+mod vdf;
+mod vectors;
+#[cfg(feature = "yespower")]
+mod yespower;
 
 mod atoms {
     rustler::atoms! {
         ok,
         error,
-        nif_not_loaded
+        nif_not_loaded,
+        powex_timelock_progress,
+        exhausted,
+        mode,
+        start,
+        end,
+        chunk_size,
+        lease_ms,
+        no_work,
+        duplicate,
+        invalid,
+        block,
+        share,
+        version,
+        prev_hash,
+        merkle_root,
+        timestamp,
+        bits,
+        nonce,
+        nonce_end,
+        max_rolls,
+        extranonce,
+        window,
+        max_future_drift,
+        now,
+        too_old,
+        too_far_in_future,
+        insufficient_data,
+        encoding,
+        multihash,
+        threads,
+        timeout,
+        best_so_far,
+        diagnostics,
+        m_cost,
+        t_cost,
+        p_cost,
+        max_attempts,
+        max_memory,
+        memory_limit,
+        algorithm,
+        algorithms,
+        min_difficulty,
+        max_difficulty,
+        max_nonce_width,
+        max_m_cost,
+        max_t_cost,
+        max_p_cost,
+        malformed,
+        algorithm_not_allowed,
+        difficulty_out_of_range,
+        nonce_too_wide,
+        memory_params_not_allowed,
+        powex_job,
+        pid,
+        powex_daemon,
+        priority,
+        stopped,
+        max_hashes_per_second,
+        power_policy,
+        max_temperature,
+        reserve_cores,
+        profile,
+        powex_log,
+        seed,
+        zero_bits,
+        nonce_range,
+        nonce_space_exhausted,
+        max_data_size,
+        data_too_large,
+        internal,
+        fuel,
+        trailing_zeros,
+        zeros_at,
+        k,
+        secret,
+        epoch_seconds,
+        difficulties,
+        sha256,
+        blake3,
+        poseidon,
+        hash160,
+        blake2b,
+        sha512_256,
+        sha384,
+        chain,
+        k12,
+        turboshake128,
+        yespower,
+        send,
+        receive,
+        epoch_1,
+        difficulty,
+        already_consumed,
+        no_store,
+        io_error,
+        capacity,
+        refill_per_second,
+        credit,
+        allow,
+        deny,
+        format,
+        limit,
+        max_bytes,
+        redact,
+        initial,
+        alpha,
+        gain,
+        exclude,
+        no_lease,
+        deterministic_seed,
+        max_verify_bytes,
+        max_verify_memory,
+        over_budget,
+        argon2id,
+        verify,
+        prebuild,
+        huge_pages,
+        numa,
+        os_limit,
+        key,
+        batch,
+        entries,
+        done,
+        bad_signature,
+        truncated,
+        powex_proof_stream,
+        data,
+        max_age,
+        purpose,
+        tag,
+        secret_key,
+        public_key,
+        unsupported_version,
+        wrong_purpose,
+        clock,
+        clock_offset,
+        system,
+        client_id,
+        reputation,
+        max_adjustment,
+        max_steps,
+        step,
+        incomplete,
+        consume,
+        os,
+        seeded,
+        size,
+        entropy,
+        contribution,
+        bad_opening,
+        reschedule,
+        unsupported_cpu
+    }
+}
+
+/// Result encoded as `:ok` or `{:error, reason}`
+pub enum Status {
+    Ok,
+    Error(Atom),
+}
+
+impl Encoder for Status {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            Status::Ok => atoms::ok().encode(env),
+            Status::Error(reason) => (atoms::error(), *reason).encode(env),
+        }
     }
 }
 
@@ -34,23 +280,95 @@ fn meets_difficulty(hash: &str, difficulty: u32) -> bool {
     }
 }
 
-/// Single-threaded Proof of Work computation
-#[rustler::nif]
-fn compute(data: Binary, difficulty: u32) -> Result<u64, (Atom, &'static str)> {
-    let data_bytes = data.as_slice();
+/// Single-threaded Proof of Work computation, recording the solve time
+fn solve(data: &[u8], difficulty: Difficulty) -> Result<u64, (Atom, &'static str)> {
+    let started = Instant::now();
+    let nonce = search(data, &difficulty)?;
+    solve_times::record(difficulty, started.elapsed());
+    Ok(nonce)
+}
+
+/// `solve` on a normal scheduler, for searches `dispatch` expects to finish in time
+#[rustler::nif(name = "compute_normal")]
+fn compute(data: Binary, difficulty: Difficulty) -> NifResult<GuardedResult<u64>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| solve(data.as_slice(), difficulty)))
+}
 
-    if difficulty > 64 {
-        return Err((atoms::error(), "Difficulty too high (max 64)"));
-    }
+/// `solve` on a dirty CPU scheduler, for longer searches
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_dirty(data: Binary, difficulty: Difficulty) -> NifResult<GuardedResult<u64>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| solve(data.as_slice(), difficulty)))
+}
+
+#[derive(NifMap)]
+struct Solution<'a> {
+    nonce: u64,
+    hash: Term<'a>,
+    attempts: u64,
+    algorithm: Algorithm,
+    difficulty: Term<'a>,
+}
+
+/// Single-threaded Proof of Work computation returning a self-describing map.
+/// With a `:timeout`, the search stops at the deadline; with `:best_so_far`, a stopped
+/// search returns the lowest hash seen instead of an error.
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_detailed<'a>(
+    env: Env<'a>,
+    data: Binary,
+    difficulty_term: Term<'a>,
+    opts: Options,
+) -> NifResult<Guarded<Term<'a>>> {
+    guard::run_result(|| {
+        let encoding = opts.get_or(atoms::encoding(), Encoding::Hex)?;
+        let timeout: Option<u64> = opts.get(atoms::timeout())?;
+        let best_so_far = opts.get_or(atoms::best_so_far(), false)?;
+        let difficulty: Difficulty = difficulty_term.decode()?;
+
+        input::check_size(data.as_slice())?;
+        if let Err(reason) = difficulty.validate() {
+            return Ok(Err::<(), _>(reason).encode(env));
+        }
+
+        let deadline = timeout.map(|ms| Instant::now() + Duration::from_millis(ms));
+        let solution = |nonce: u64, attempts: u64| Solution {
+            nonce,
+            hash: encoding.encode(env, &backend::prepare(data.as_slice()).digest(nonce)),
+            attempts,
+            algorithm: Algorithm::Sha256,
+            difficulty: difficulty_term,
+        };
+
+        Ok(match anytime::search(data.as_slice(), &difficulty, deadline, best_so_far) {
+            Ok(nonce) => (atoms::ok(), solution(nonce, nonce + 1)).encode(env),
+            Err(Stopped { best: Some((nonce, _)), attempts, .. }) if best_so_far => {
+                (atoms::best_so_far(), solution(nonce, attempts)).encode(env)
+            }
+            Err(Stopped { reason: Stop::Timeout, .. }) => (atoms::error(), atoms::timeout()).encode(env),
+            Err(Stopped { reason: Stop::Aborted, .. }) => {
+                Err::<(), _>((atoms::error(), "Difficulty too high, computation aborted")).encode(env)
+            }
+        })
+    })
+}
+
+/// Scans nonces from zero until one meets the difficulty
+fn search(data_bytes: &[u8], difficulty: &Difficulty) -> Result<u64, (Atom, &'static str)> {
+    difficulty.validate()?;
+
+    let prepared = backend::prepare(data_bytes);
+    let mut meter = metrics::Meter::new();
 
     for nonce in 0..u64::MAX {
-        let hash = compute_hash(data_bytes, nonce);
-        if meets_difficulty(&hash, difficulty) {
+        meter.tick();
+        if prepared.meets(nonce, difficulty) {
             return Ok(nonce);
         }
 
         // Prevent infinite loops for very high difficulties
-        if nonce > 0 && nonce % 1_000_000 == 0 && difficulty > 20 {
+        if nonce > 0 && nonce % 1_000_000 == 0 && difficulty.zeros() > 20 {
             // For very high difficulties, we might want to give up after some attempts
             if nonce > 100_000_000 {
                 return Err((atoms::error(), "Difficulty too high, computation aborted"));
@@ -61,92 +379,488 @@ fn compute(data: Binary, difficulty: u32) -> Result<u64, (Atom, &'static str)> {
     Err((atoms::error(), "No valid nonce found"))
 }
 
-/// Validates if a nonce produces a valid hash for the given difficulty
-#[rustler::nif(name = "valid?")]
-fn valid(data: Binary, nonce: u64, difficulty: u32) -> bool {
-    let data_bytes = data.as_slice();
-    let hash = compute_hash(data_bytes, nonce);
-    meets_difficulty(&hash, difficulty)
+/// Searches for a nonce satisfying any of several (data, difficulty) targets in one pass.
+/// Targets sharing the same data share a single hash per nonce.
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_multi(targets: Vec<(Binary, u32)>) -> NifResult<GuardedResult<(u64, usize)>> {
+    for (data, _) in &targets {
+        input::check_size(data.as_slice())?;
+    }
+    Ok(guard::run(|| {
+        if targets.is_empty() {
+            return Err((atoms::error(), "No targets given"));
+        }
+
+        for (_, difficulty) in &targets {
+            Difficulty::Zeros(*difficulty).validate()?;
+        }
+
+        // Group targets by data, keeping the original indices for the result
+        let mut groups: Vec<(&[u8], Vec<u32>, Vec<usize>)> = vec![];
+        for (index, (data, difficulty)) in targets.iter().enumerate() {
+            match groups.iter_mut().find(|(d, _, _)| *d == data.as_slice()) {
+                Some((_, difficulties, indices)) => {
+                    difficulties.push(*difficulty);
+                    indices.push(index);
+                }
+                None => groups.push((data.as_slice(), vec![*difficulty], vec![index])),
+            }
+        }
+
+        let prepared: Vec<_> = groups
+            .iter()
+            .map(|(data, difficulties, indices)| (backend::prepare(data), difficulties, indices))
+            .collect();
+        let easiest = targets.iter().map(|(_, d)| *d).min().unwrap_or(0);
+        let mut meter = metrics::Meter::new();
+
+        for nonce in 0..u64::MAX {
+            meter.tick();
+            let matched = prepared
+                .iter()
+                .filter_map(|(p, difficulties, indices)| {
+                    p.check_any(nonce, difficulties).map(|i| indices[i])
+                })
+                .min();
+
+            if let Some(index) = matched {
+                return Ok((nonce, index));
+            }
+
+            if nonce > 100_000_000 && easiest > 20 {
+                return Err((atoms::error(), "Difficulty too high, computation aborted"));
+            }
+        }
+
+        Err((atoms::error(), "No valid nonce found"))
+    }))
+}
+
+/// Validates if a nonce produces a valid hash for the given difficulty, or returns `None`
+/// when a normal scheduler (`env` given) used up its time slice before the data was hashed.
+/// Data fitting in one chunk goes through the cache; larger data is hashed once, in chunks,
+/// for both the proof and its audit entry.
+fn verify(env: Option<Env>, data: &[u8], nonce: u64, difficulty: Difficulty) -> Option<bool> {
+    let outcome = |valid| if valid { audit::Outcome::Valid } else { audit::Outcome::Invalid };
+
+    if data.len() <= input::CHUNK_SIZE {
+        let valid = cache::verify_cached(data, nonce, difficulty, || {
+            difficulty.is_met_by(&backend::prepare(data).digest(nonce))
+        });
+        audit::record(&[data, &nonce.to_le_bytes()], difficulty.zeros(), outcome(valid));
+        return Some(valid);
+    }
+
+    let mut hasher = Sha256::new();
+    let mut identity = audit::enabled().then(blake3::Hasher::new);
+    let keep_going = || env.is_none_or(|env| !rustler::schedule::consume_timeslice(env, 100));
+    let hashed = input::chunked(data, keep_going, |chunk| {
+        hasher.update(chunk);
+        if let Some(identity) = &mut identity {
+            identity.update(chunk);
+        }
+    });
+    if !hashed {
+        return None;
+    }
+
+    hasher.update(nonce.to_le_bytes());
+    let valid = difficulty.is_met_by(&hasher.finalize());
+    if let Some(mut identity) = identity {
+        identity.update(&nonce.to_le_bytes());
+        audit::record_hash(*identity.finalize().as_bytes(), difficulty.zeros(), outcome(valid));
+    }
+    Some(valid)
+}
+
+/// `verify` on a normal scheduler, for data `dispatch` expects to hash in time. Every chunk
+/// but the last is reported as a used time slice, and once the slice runs out the call
+/// returns `:reschedule` for `valid?` to finish on a dirty scheduler; data over the
+/// `:max_data_size` limit raises `{:data_too_large, limit}`.
+#[rustler::nif(name = "valid_normal?")]
+fn valid<'a>(
+    env: Env<'a>,
+    data: Binary,
+    nonce: u64,
+    difficulty: Difficulty,
+) -> NifResult<Guarded<Term<'a>>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| match verify(Some(env), data.as_slice(), nonce, difficulty) {
+        Some(valid) => valid.encode(env),
+        None => atoms::reschedule().encode(env),
+    }))
+}
+
+/// `verify` on a dirty CPU scheduler, for large data
+#[rustler::nif(name = "valid_dirty?", schedule = "DirtyCpu")]
+fn valid_dirty(data: Binary, nonce: u64, difficulty: Difficulty) -> NifResult<Guarded<bool>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| verify(None, data.as_slice(), nonce, difficulty).unwrap_or(false)))
+}
+
+fn classify_share(data: &[u8], nonce: u64, share_difficulty: u32, network_difficulty: u32) -> Atom {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.update(nonce.to_le_bytes());
+    let zeros = backend::leading_zero_bits(&hasher.finalize()) / 4;
+
+    if zeros >= network_difficulty {
+        atoms::block()
+    } else if zeros >= share_difficulty {
+        atoms::share()
+    } else {
+        atoms::invalid()
+    }
+}
+
+/// Classifies a pool submission against share and network difficulty with a single hash.
+/// Unlike `valid?`, a hash with more leading zeros than required still qualifies.
+#[rustler::nif(name = "validate_share_normal")]
+fn validate_share(
+    data: Binary,
+    nonce: u64,
+    share_difficulty: u32,
+    network_difficulty: u32,
+) -> NifResult<Guarded<Atom>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| classify_share(data.as_slice(), nonce, share_difficulty, network_difficulty)))
+}
+
+/// `validate_share` on a dirty CPU scheduler, for large data
+#[rustler::nif(schedule = "DirtyCpu")]
+fn validate_share_dirty(
+    data: Binary,
+    nonce: u64,
+    share_difficulty: u32,
+    network_difficulty: u32,
+) -> NifResult<Guarded<Atom>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| classify_share(data.as_slice(), nonce, share_difficulty, network_difficulty)))
+}
+
+fn achieved_difficulty(data: &[u8], nonce: u64) -> u32 {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.update(nonce.to_le_bytes());
+    backend::leading_zero_bits(&hasher.finalize())
+}
+
+/// Measures the achieved difficulty of a proof as its number of leading zero bits
+#[rustler::nif(name = "measure_difficulty_normal")]
+fn measure_difficulty(data: Binary, nonce: u64) -> NifResult<Guarded<u32>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| achieved_difficulty(data.as_slice(), nonce)))
+}
+
+/// `measure_difficulty` on a dirty CPU scheduler, for large data
+#[rustler::nif(schedule = "DirtyCpu")]
+fn measure_difficulty_dirty(data: Binary, nonce: u64) -> NifResult<Guarded<u32>> {
+    input::check_size(data.as_slice())?;
+    Ok(guard::run(|| achieved_difficulty(data.as_slice(), nonce)))
 }
 
 /// Parallel Proof of Work computation using multiple threads
-#[rustler::nif]
-fn compute_parallel(
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_parallel<'a>(
+    env: Env<'a>,
     data: Binary,
-    difficulty: u32,
-    num_threads: u32
-) -> Result<u64, (Atom, &'static str)> {
-    let data_bytes = data.as_slice().to_vec();
+    difficulty: Difficulty,
+    num_threads: u32,
+    opts: Options,
+) -> NifResult<Guarded<Term<'a>>> {
+    guard::run_result(|| {
+        input::check_size(data.as_slice())?;
+        let diagnostics = opts.get_or(atoms::diagnostics(), false)?;
+        let range: Option<NonceRange> = opts.get(atoms::nonce_range())?;
+        let exclude: Vec<NonceRange> = opts.get_or(atoms::exclude(), vec![])?;
+        let started = Instant::now();
+
+        let nonces = match range {
+            Some(NonceRange { first, last, step: 1 }) if first <= last => first..last.saturating_add(1),
+            Some(_) => return Ok(Err::<(), _>((atoms::error(), "Invalid nonce range")).encode(env)),
+            None => 0..u64::MAX,
+        };
+        let mut excluded = Vec::with_capacity(exclude.len());
+        for range in exclude {
+            match range {
+                NonceRange { first, last, step: 1 } if first <= last => {
+                    excluded.push(first..last.saturating_add(1))
+                }
+                _ => return Ok(Err::<(), _>((atoms::error(), "Invalid excluded range")).encode(env)),
+            }
+        }
 
-    if difficulty > 64 {
-        return Err((atoms::error(), "Difficulty too high (max 64)"));
+        let result =
+            search_parallel(data.as_slice().to_vec(), difficulty, num_threads, nonces, &excluded);
+        if result.is_ok() {
+            solve_times::record(difficulty, started.elapsed());
+        }
+
+        Ok(match result {
+            Ok(run) if !diagnostics => (atoms::ok(), run.nonce).encode(env),
+            Ok(run) => {
+                let diagnostics = ParallelDiagnostics {
+                    nonce: run.nonce,
+                    found_by: run.threads.iter().find(|t| t.found).map(|t| t.thread),
+                    elapsed_ms: started.elapsed().as_secs_f64() * 1_000.0,
+                    threads: run.threads,
+                };
+                (atoms::ok(), diagnostics).encode(env)
+            }
+            Err(ParallelError::Failed(reason)) => Err::<(), _>(reason).encode(env),
+            Err(ParallelError::Exhausted(searched)) => {
+                (atoms::error(), atoms::nonce_space_exhausted(), searched).encode(env)
+            }
+        })
+    })
+}
+
+/// An inclusive Elixir range of nonces
+#[derive(NifStruct)]
+#[module = "Range"]
+struct NonceRange {
+    first: u64,
+    last: u64,
+    step: i64,
+}
+
+/// What one search thread did during a parallel run
+#[derive(NifMap)]
+struct ThreadReport {
+    thread: u32,
+    start: u64,
+    end: u64,
+    attempts: u64,
+    found: bool,
+    elapsed_ms: f64,
+}
+
+#[derive(NifMap)]
+struct ParallelDiagnostics {
+    nonce: u64,
+    found_by: Option<u32>,
+    elapsed_ms: f64,
+    threads: Vec<ThreadReport>,
+}
+
+/// Result of a successful parallel search
+struct ParallelRun {
+    nonce: u64,
+    threads: Vec<ThreadReport>,
+}
+
+/// Why a parallel search ended without a solution
+enum ParallelError {
+    /// Invalid arguments, or a give-up heuristic stopped the search
+    Failed((Atom, &'static str)),
+    /// Every nonce of the range was tried
+    Exhausted(NonceRange),
+}
+
+impl ParallelError {
+    /// Collapses the error into the reason returned by the single-threaded search
+    fn reason(self) -> (Atom, &'static str) {
+        match self {
+            ParallelError::Failed(reason) => reason,
+            ParallelError::Exhausted(_) => (atoms::error(), "No valid nonce found"),
+        }
     }
+}
+
+/// The parts of `nonces` outside every excluded range, in order
+fn subtract_ranges(nonces: &Range<u64>, excluded: &[Range<u64>]) -> Vec<Range<u64>> {
+    let mut excluded = excluded.to_vec();
+    excluded.sort_by_key(|range| range.start);
+
+    let mut remaining = vec![];
+    let mut next = nonces.start;
+    for range in excluded {
+        if next >= nonces.end {
+            break;
+        }
+        if range.start > next {
+            remaining.push(next..range.start.min(nonces.end));
+        }
+        next = next.max(range.end);
+    }
+    if next < nonces.end {
+        remaining.push(next..nonces.end);
+    }
+    remaining
+}
+
+/// Splits ordered ranges into `parts` runs holding nearly equal numbers of nonces; the
+/// last run takes the remainder
+fn split_ranges(ranges: &[Range<u64>], parts: u32) -> Vec<Vec<Range<u64>>> {
+    let parts = parts as usize;
+    let share = ranges.iter().map(|range| range.end - range.start).sum::<u64>() / parts as u64;
+    let mut runs = vec![vec![]; parts];
+    let mut part = 0;
+    let mut taken = 0;
+
+    for range in ranges {
+        let mut start = range.start;
+        while start < range.end {
+            if part + 1 < parts && taken == share {
+                part += 1;
+                taken = 0;
+                continue;
+            }
+            let end = if part + 1 == parts {
+                range.end
+            } else {
+                start + (share - taken).min(range.end - start)
+            };
+            runs[part].push(start..end);
+            taken += end - start;
+            start = end;
+        }
+    }
+    runs
+}
+
+/// Splits `nonces`, less any excluded ranges, between threads and returns the first nonce
+/// found
+fn search_parallel(
+    data_bytes: Vec<u8>,
+    difficulty: Difficulty,
+    num_threads: u32,
+    nonces: Range<u64>,
+    excluded: &[Range<u64>],
+) -> Result<ParallelRun, ParallelError> {
+    difficulty.validate().map_err(ParallelError::Failed)?;
 
     if num_threads == 0 || num_threads > 64 {
-        return Err((atoms::error(), "Invalid number of threads (1-64)"));
+        return Err(ParallelError::Failed((atoms::error(), "Invalid number of threads (1-64)")));
     }
+    let num_threads = config::profile().cap_threads(num_threads);
 
     let found = Arc::new(AtomicBool::new(false));
+    let aborted = Arc::new(AtomicBool::new(false));
     let result_nonce = Arc::new(AtomicU64::new(0));
     let mut handles = vec![];
 
-    let chunk_size = u64::MAX / num_threads as u64;
+    let runs = split_ranges(&subtract_ranges(&nonces, excluded), num_threads);
 
-    for thread_id in 0..num_threads {
+    for (thread_id, run) in (0..num_threads).zip(runs) {
         let data_clone = data_bytes.clone();
         let found_clone = Arc::clone(&found);
+        let aborted_clone = Arc::clone(&aborted);
         let result_clone = Arc::clone(&result_nonce);
 
-        let start_nonce = thread_id as u64 * chunk_size;
-        let end_nonce = if thread_id == num_threads - 1 {
-            u64::MAX
-        } else {
-            (thread_id + 1) as u64 * chunk_size
-        };
+        let start_nonce = run.first().map_or(nonces.start, |range| range.start);
+        let end_nonce = run.last().map_or(nonces.start, |range| range.end);
 
         let handle = thread::spawn(move || {
-            for nonce in start_nonce..end_nonce {
+            let prepared = backend::prepare(&data_clone);
+            let started = Instant::now();
+            let mut report = ThreadReport {
+                thread: thread_id,
+                start: start_nonce,
+                end: end_nonce,
+                attempts: 0,
+                found: false,
+                elapsed_ms: 0.0,
+            };
+
+            let mut meter = metrics::Meter::new();
+
+            for nonce in run.into_iter().flatten() {
                 if found_clone.load(Ordering::Relaxed) {
                     break;
                 }
 
-                let hash = compute_hash(&data_clone, nonce);
-                if meets_difficulty(&hash, difficulty) {
-                    found_clone.store(true, Ordering::Relaxed);
-                    result_clone.store(nonce, Ordering::Relaxed);
+                meter.tick();
+                report.attempts += 1;
+                if prepared.meets(nonce, &difficulty) {
+                    // Only the first thread to flip the flag reports its nonce
+                    if !found_clone.swap(true, Ordering::Relaxed) {
+                        result_clone.store(nonce, Ordering::Relaxed);
+                        report.found = true;
+                    }
                     break;
                 }
 
                 // Check periodically for very high difficulties
-                if nonce > 0 && nonce % 1_000_000 == 0 && difficulty > 20 {
-                    if nonce - start_nonce > 100_000_000 {
-                        break;
-                    }
+                if nonce > 0
+                    && nonce % 1_000_000 == 0
+                    && difficulty.zeros() > 20
+                    && report.attempts > 100_000_000
+                {
+                    aborted_clone.store(true, Ordering::Relaxed);
+                    break;
                 }
             }
+
+            report.elapsed_ms = started.elapsed().as_secs_f64() * 1_000.0;
+            report
         });
 
         handles.push(handle);
     }
 
     // Wait for all threads to complete
-    for handle in handles {
-        handle.join().unwrap();
-    }
+    let threads: Vec<ThreadReport> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap_or_else(|payload| std::panic::resume_unwind(payload)))
+        .collect();
 
     if found.load(Ordering::Relaxed) {
-        Ok(result_nonce.load(Ordering::Relaxed))
+        Ok(ParallelRun {
+            nonce: result_nonce.load(Ordering::Relaxed),
+            threads,
+        })
+    } else if aborted.load(Ordering::Relaxed) {
+        Err(ParallelError::Failed((atoms::error(), "Difficulty too high, computation aborted")))
     } else {
-        Err((atoms::error(), "No valid nonce found"))
+        Err(ParallelError::Exhausted(NonceRange {
+            first: nonces.start,
+            last: nonces.end - 1,
+            step: 1,
+        }))
     }
 }
 
-/// Gets the hash for a given data and nonce combination
-#[rustler::nif]
-fn get_hash(data: Binary, nonce: u64) -> Result<String, (Atom, &'static str)> {
-    let data_bytes = data.as_slice();
-    let hash = compute_hash(data_bytes, nonce);
-    Ok(hash)
+fn encoded_hash<'a>(env: Env<'a>, data: &[u8], nonce: u64, opts: &Options) -> NifResult<(Atom, Term<'a>)> {
+    let encoding = opts.get_or(atoms::encoding(), Encoding::Hex)?;
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.update(nonce.to_le_bytes());
+    let mut digest = hasher.finalize().to_vec();
+
+    if opts.get_or(atoms::multihash(), false)? {
+        digest = Algorithm::Sha256.multihash(&digest);
+    }
+
+    Ok((atoms::ok(), encoding.encode(env, &digest)))
+}
+
+/// Gets the hash for a given data and nonce combination in the requested encoding
+#[rustler::nif(name = "get_hash_normal")]
+fn get_hash<'a>(
+    env: Env<'a>,
+    data: Binary,
+    nonce: u64,
+    opts: Options,
+) -> NifResult<Guarded<(Atom, Term<'a>)>> {
+    guard::run_result(|| {
+        input::check_size(data.as_slice())?;
+        encoded_hash(env, data.as_slice(), nonce, &opts)
+    })
+}
+
+/// `get_hash` on a dirty CPU scheduler, for large data
+#[rustler::nif(schedule = "DirtyCpu")]
+fn get_hash_dirty<'a>(
+    env: Env<'a>,
+    data: Binary,
+    nonce: u64,
+    opts: Options,
+) -> NifResult<Guarded<(Atom, Term<'a>)>> {
+    guard::run_result(|| {
+        input::check_size(data.as_slice())?;
+        encoded_hash(env, data.as_slice(), nonce, &opts)
+    })
 }
 
 rustler::init!("Elixir.Powex");
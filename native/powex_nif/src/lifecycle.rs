@@ -0,0 +1,54 @@
+//! Pausing background work from the host app, for mobile apps that must stop hashing while
+//! they are in the background
+
+use rustler::Atom;
+use std::sync::{Condvar, Mutex};
+
+use crate::atoms;
+use crate::guard::{self, Guarded};
+use crate::log::{self, Level};
+
+/// Whether background work is paused, and the condition paused workers wait on
+static PAUSED: (Mutex<bool>, Condvar) = (Mutex::new(false), Condvar::new());
+
+/// Blocks the calling worker while background work is paused
+pub fn wait_while_paused() {
+    let (paused, resumed) = &PAUSED;
+    let _guard = resumed
+        .wait_while(paused.lock().unwrap(), |paused| *paused)
+        .unwrap();
+}
+
+fn set_paused(value: bool) {
+    let (paused, resumed) = &PAUSED;
+    let changed = std::mem::replace(&mut *paused.lock().unwrap(), value) != value;
+    if changed {
+        let event = if value { "background_paused" } else { "background_resumed" };
+        log::emit(Level::Info, event, vec![]);
+    }
+    resumed.notify_all();
+}
+
+/// Pauses daemon workers and async jobs at their next batch of nonces
+#[rustler::nif]
+fn pause_background() -> Guarded<Atom> {
+    guard::run(|| {
+        set_paused(true);
+        atoms::ok()
+    })
+}
+
+/// Resumes paused background work
+#[rustler::nif]
+fn resume_background() -> Guarded<Atom> {
+    guard::run(|| {
+        set_paused(false);
+        atoms::ok()
+    })
+}
+
+/// Whether background work is paused
+#[rustler::nif(name = "background_paused?")]
+fn background_paused() -> Guarded<bool> {
+    guard::run(|| *PAUSED.0.lock().unwrap())
+}
@@ -0,0 +1,163 @@
+use rustler::env::OwnedEnv;
+use rustler::types::map::map_new;
+use rustler::{Atom, Encoder, Env, LocalPid, NifUnitEnum, Term};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+use crate::atoms;
+use crate::guard::{self, Guarded};
+
+/// Log levels, matching `Logger`'s
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn to_u8(self) -> u8 {
+        match self {
+            Level::Debug => 1,
+            Level::Info => 2,
+            Level::Warning => 3,
+            Level::Error => 4,
+        }
+    }
+}
+
+/// A metadata value of a log event
+pub enum Value {
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    /// An atom, by name
+    Atom(String),
+}
+
+impl Value {
+    /// Names a unit enum variant the way `NifUnitEnum` does: `PauseOnBattery` becomes `:pause_on_battery`
+    pub fn atom(variant: impl Debug) -> Value {
+        let mut name = String::new();
+        for (i, c) in format!("{variant:?}").chars().enumerate() {
+            if c.is_ascii_uppercase() && i > 0 {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        Value::Atom(name)
+    }
+
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            Value::U64(value) => value.encode(env),
+            Value::I64(value) => value.encode(env),
+            Value::F64(value) => value.encode(env),
+            Value::Bool(value) => value.encode(env),
+            Value::Str(value) => value.encode(env),
+            Value::Atom(name) => match Atom::from_str(env, name) {
+                Ok(atom) => atom.encode(env),
+                Err(_) => name.encode(env),
+            },
+        }
+    }
+}
+
+/// A structured log event, sent as `{:powex_log, level, event, metadata}`
+struct Event {
+    level: Level,
+    event: &'static str,
+    metadata: Vec<(&'static str, Value)>,
+}
+
+impl Event {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let metadata = self
+            .metadata
+            .iter()
+            .fold(map_new(env), |map, (key, value)| {
+                match Atom::from_str(env, key) {
+                    Ok(key) => map.map_put(key, value.encode(env)).unwrap_or(map),
+                    Err(_) => map,
+                }
+            });
+        let event = Atom::from_str(env, self.event).unwrap_or_else(|_| atoms::error());
+        (atoms::powex_log(), self.level, event, metadata).encode(env)
+    }
+}
+
+/// Lowest level forwarded to the sink (0 = no sink registered)
+static MIN_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+static SINK: Mutex<Option<LocalPid>> = Mutex::new(None);
+
+/// Queue to the thread delivering events; `enif_send` from an owned environment
+/// is not allowed on scheduler threads, where many events originate
+fn events() -> &'static Mutex<Sender<Event>> {
+    static EVENTS: OnceLock<Mutex<Sender<Event>>> = OnceLock::new();
+    EVENTS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Event>();
+        thread::spawn(move || {
+            let mut env = OwnedEnv::new();
+            for event in receiver {
+                if let Some(pid) = *SINK.lock().unwrap() {
+                    let _ = env.send_and_clear(&pid, |env| event.encode(env));
+                }
+            }
+        });
+        Mutex::new(sender)
+    })
+}
+
+/// Whether events at this level reach the sink
+pub fn enabled(level: Level) -> bool {
+    let min = MIN_LEVEL.load(Ordering::Relaxed);
+    min != 0 && level.to_u8() >= min
+}
+
+/// Emits a structured event to the registered sink, if its level is enabled
+pub fn emit(level: Level, event: &'static str, metadata: Vec<(&'static str, Value)>) {
+    if enabled(level) {
+        let _ = events().lock().unwrap().send(Event {
+            level,
+            event,
+            metadata,
+        });
+    }
+}
+
+/// Emits the end of a search: at debug level with the nonce, or at warning level with the reason
+pub fn search_finished(
+    event: &'static str,
+    result: &Result<u64, (Atom, &'static str)>,
+    mut metadata: Vec<(&'static str, Value)>,
+) {
+    let level = match result {
+        Ok(nonce) => {
+            metadata.push(("nonce", Value::U64(*nonce)));
+            Level::Debug
+        }
+        Err((_, reason)) => {
+            metadata.push(("reason", Value::Str(reason.to_string())));
+            Level::Warning
+        }
+    };
+    metadata.push(("ok", Value::Bool(result.is_ok())));
+    emit(level, event, metadata);
+}
+
+/// Registers the process receiving log events at `level` and above, or removes it with `nil`
+#[rustler::nif]
+fn set_log_sink(pid: Option<LocalPid>, level: Level) -> Guarded<Atom> {
+    guard::run(|| {
+        *SINK.lock().unwrap() = pid;
+        MIN_LEVEL.store(pid.map_or(0, |_| level.to_u8()), Ordering::Relaxed);
+        atoms::ok()
+    })
+}
@@ -0,0 +1,69 @@
+//! Proof-of-work lotteries: each proof is a ticket whose draw is a uniform value in
+//! `[0, 1)`, and the lowest draw wins, so a node's chance grows with the valid proofs it
+//! brings. A valid proof's SHA-256 hash starts with zeros, so the draw is taken from a
+//! BLAKE3 hash of it rather than from the hash itself. Every node holding the same proofs
+//! picks the same winner.
+
+use rustler::{Binary, NifMap, NifResult};
+use sha2::{Digest, Sha256};
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::input;
+
+/// BLAKE3 key derivation context of lottery draws
+const DRAW_CONTEXT: &str = "powex 2026-10-15 proof-of-work lottery draw";
+
+#[derive(NifMap)]
+struct Winner {
+    /// Position of the winning proof in the list
+    index: usize,
+    ticket: f64,
+}
+
+/// The draw of a proof: a BLAKE3 hash of its SHA-256 hash, ordered as a big-endian number
+fn draw(data: &[u8], nonce: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.update(nonce.to_le_bytes());
+    let digest = hasher.finalize();
+    *blake3::Hasher::new_derive_key(DRAW_CONTEXT).update(&digest).finalize().as_bytes()
+}
+
+/// The top 53 bits of a draw as a float in `[0, 1)`
+fn ticket(draw: &[u8; 32]) -> f64 {
+    let word = u64::from_be_bytes(draw[..8].try_into().unwrap_or([0; 8]));
+    (word >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Maps a proof's hash to a uniform draw in `[0, 1)`
+#[rustler::nif(name = "lottery_ticket_normal")]
+fn lottery_ticket(data: Binary, nonce: u64) -> NifResult<Guarded<f64>> {
+    input::check_size(&data)?;
+    Ok(guard::run(|| ticket(&draw(&data, nonce))))
+}
+
+/// `lottery_ticket` on a dirty CPU scheduler, for large data
+#[rustler::nif(schedule = "DirtyCpu")]
+fn lottery_ticket_dirty(data: Binary, nonce: u64) -> NifResult<Guarded<f64>> {
+    input::check_size(&data)?;
+    Ok(guard::run(|| ticket(&draw(&data, nonce))))
+}
+
+/// Picks the proof with the lowest draw, comparing all 256 bits and then positions, so ties
+/// in the float resolve the same way everywhere
+#[rustler::nif(schedule = "DirtyCpu")]
+fn select_winner(proofs: Vec<(Binary, u64)>) -> NifResult<GuardedResult<Winner>> {
+    for (data, _) in &proofs {
+        input::check_size(data)?;
+    }
+    Ok(guard::run(|| {
+        let (draw, index) = proofs
+            .iter()
+            .enumerate()
+            .map(|(index, (data, nonce))| (draw(data, *nonce), index))
+            .min()
+            .ok_or((atoms::error(), "No proofs given"))?;
+        Ok(Winner { index, ticket: ticket(&draw) })
+    }))
+}
@@ -0,0 +1,243 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use rustler::{Atom, Binary, Encoder, Env, NifMap, NifResult, Term};
+use sha2::{Digest, Sha256};
+use std::time::Instant;
+
+use crate::atoms;
+use crate::budget;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::metrics;
+use crate::options::Options;
+use crate::scratchpad::{HugePages, Memory};
+
+/// Default number of nonces `compute_memory_hard` tries before giving up
+const DEFAULT_MAX_ATTEMPTS: u64 = 1_000_000;
+
+/// Argon2id cost parameters of a memory-hard puzzle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryHardParams {
+    /// Memory in KiB
+    pub m_cost: u32,
+    /// Number of passes over the memory
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl MemoryHardParams {
+    /// Reads `:m_cost`, `:t_cost` and `:p_cost`, defaulting to the Argon2 recommendations
+    pub fn from_options(opts: &Options) -> NifResult<MemoryHardParams> {
+        Ok(MemoryHardParams {
+            m_cost: opts.get_or(atoms::m_cost(), Params::DEFAULT_M_COST)?,
+            t_cost: opts.get_or(atoms::t_cost(), Params::DEFAULT_T_COST)?,
+            p_cost: opts.get_or(atoms::p_cost(), Params::DEFAULT_P_COST)?,
+        })
+    }
+
+    /// Bytes of Argon2 memory these parameters allocate (one KiB block per unit of `m_cost`)
+    pub fn memory_bytes(&self) -> u64 {
+        self.m_cost as u64 * 1024
+    }
+}
+
+/// Rejects parameters needing more than the `:max_memory` byte limit, before anything is allocated
+fn check_memory_limit(opts: &Options, params: MemoryHardParams) -> NifResult<Result<(), Atom>> {
+    let limit: Option<u64> = opts.get(atoms::max_memory())?;
+    Ok(match limit {
+        Some(limit) if params.memory_bytes() > limit => Err(atoms::memory_limit()),
+        _ => Ok(()),
+    })
+}
+
+/// Argon2id hasher with its memory allocated once and reused for every nonce
+pub struct Hasher {
+    argon2: Argon2<'static>,
+    memory: Memory,
+    huge_pages: HugePages,
+    salt: [u8; 16],
+    input: Vec<u8>,
+}
+
+impl Hasher {
+    /// Prepares a hasher for the data, or `None` if the parameters are invalid
+    pub fn new(data: &[u8], params: MemoryHardParams) -> Option<Hasher> {
+        Hasher::with_memory(data, params, false)
+    }
+
+    /// Like `new`, but with `huge_pages` the memory is mapped on huge pages where the OS
+    /// provides them; `huge_pages()` tells which kind was obtained
+    pub fn with_memory(data: &[u8], params: MemoryHardParams, huge_pages: bool) -> Option<Hasher> {
+        let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32)).ok()?;
+        let (memory, huge_pages) = match huge_pages {
+            true => Memory::huge(params.block_count()),
+            false => (Memory::heap(params.block_count()), HugePages::None),
+        };
+        let salt: [u8; 32] = Sha256::digest(data).into();
+
+        let mut input = data.to_vec();
+        input.extend_from_slice(&[0u8; 8]);
+
+        Some(Hasher {
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+            memory,
+            huge_pages,
+            salt: salt[..16].try_into().ok()?,
+            input,
+        })
+    }
+
+    pub fn huge_pages(&self) -> HugePages {
+        self.huge_pages
+    }
+
+    /// Argon2id of `data || nonce_le` salted with the first 16 bytes of `sha256(data)`
+    pub fn digest(&mut self, nonce: u64) -> [u8; 32] {
+        let offset = self.input.len() - 8;
+        self.input[offset..].copy_from_slice(&nonce.to_le_bytes());
+
+        let mut output = [0u8; 32];
+        // Lengths are fixed and validated by `Params::new`, so hashing cannot fail
+        let _ = self.argon2.hash_password_into_with_memory(
+            &self.input,
+            &self.salt,
+            &mut output,
+            &mut self.memory,
+        );
+        output
+    }
+}
+
+#[derive(NifMap)]
+struct MemoryHardSolution {
+    nonce: u64,
+    huge_pages: HugePages,
+}
+
+/// Finds a nonce whose Argon2id hash meets the difficulty. With `:huge_pages`, the memory is
+/// mapped on huge pages where available and the result reports which kind was obtained
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_memory_hard<'a>(
+    env: Env<'a>,
+    data: Binary,
+    difficulty: Difficulty,
+    opts: Options,
+) -> NifResult<Guarded<Term<'a>>> {
+    guard::run_result(|| {
+        let params = MemoryHardParams::from_options(&opts)?;
+        let max_attempts = opts.get_or(atoms::max_attempts(), DEFAULT_MAX_ATTEMPTS)?;
+        let huge_pages = opts.get_or(atoms::huge_pages(), false)?;
+
+        if let Err(reason) = check_memory_limit(&opts, params)? {
+            return Ok((atoms::error(), reason).encode(env));
+        }
+
+        if let Err(reason) = difficulty.validate() {
+            return Ok(Err::<u64, _>(reason).encode(env));
+        }
+
+        let Some(mut hasher) = Hasher::with_memory(data.as_slice(), params, huge_pages) else {
+            return Ok(Err::<u64, _>((atoms::error(), "Invalid Argon2 parameters")).encode(env));
+        };
+
+        let mut meter = metrics::Meter::new();
+        let found = (0..max_attempts)
+            .find(|&nonce| {
+                meter.tick();
+                difficulty.is_met_by(&hasher.digest(nonce))
+            })
+            .ok_or((atoms::error(), "No valid nonce found"));
+
+        Ok(match (found, huge_pages) {
+            (Ok(nonce), true) => (
+                atoms::ok(),
+                MemoryHardSolution {
+                    nonce,
+                    huge_pages: hasher.huge_pages(),
+                },
+            )
+                .encode(env),
+            (found, _) => found.encode(env),
+        })
+    })
+}
+
+/// Validates a memory-hard proof with a single Argon2id evaluation, if it fits the
+/// verification budget of `configure/1`
+#[rustler::nif(name = "valid_memory_hard?", schedule = "DirtyCpu")]
+fn valid_memory_hard<'a>(
+    env: Env<'a>,
+    data: Binary,
+    nonce: u64,
+    difficulty: Difficulty,
+    opts: Options,
+) -> NifResult<Guarded<Term<'a>>> {
+    guard::run_result(|| {
+        let params = MemoryHardParams::from_options(&opts)?;
+
+        if let Err(reason) = check_memory_limit(&opts, params)? {
+            return Ok((atoms::error(), reason).encode(env));
+        }
+
+        if let Err(reason) = budget::check(data.len() as u64 + 8, params.memory_bytes()) {
+            return Ok((atoms::error(), reason).encode(env));
+        }
+
+        let mut hasher = Hasher::new(data.as_slice(), params).ok_or(rustler::Error::BadArg)?;
+        Ok(difficulty.is_met_by(&hasher.digest(nonce)).encode(env))
+    })
+}
+
+/// Milliseconds taken by one hash evaluation with the given parameters
+fn measure(params: MemoryHardParams) -> Option<f64> {
+    let mut hasher = Hasher::new(b"powex memory-hard tuning", params)?;
+    let start = Instant::now();
+    std::hint::black_box(hasher.digest(0));
+    Some(start.elapsed().as_secs_f64() * 1_000.0)
+}
+
+#[derive(NifMap)]
+struct TunedParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    verify_ms: f64,
+}
+
+/// Finds Argon2id parameters whose single evaluation takes about `target_ms` on this machine,
+/// using as much of the `max_memory` byte budget as the target allows
+#[rustler::nif(schedule = "DirtyCpu")]
+fn tune_memory_hard(target_ms: u64, max_memory: u64) -> GuardedResult<TunedParams> {
+    guard::run(|| {
+        let target = target_ms as f64;
+        let mut params = MemoryHardParams {
+            m_cost: (max_memory / 1024).min(u32::MAX as u64) as u32,
+            t_cost: 1,
+            p_cost: 1,
+        };
+
+        if params.m_cost < Params::MIN_M_COST {
+            return Err((atoms::error(), "Memory budget too small (min 8 KiB)"));
+        }
+
+        // Halve the memory until a single pass fits the target
+        let mut elapsed = measure(params).ok_or((atoms::error(), "Invalid Argon2 parameters"))?;
+        while elapsed > target && params.m_cost / 2 >= Params::MIN_M_COST {
+            params.m_cost /= 2;
+            elapsed = measure(params).ok_or((atoms::error(), "Invalid Argon2 parameters"))?;
+        }
+
+        // Time grows linearly with passes, so spend the remaining budget on extra passes
+        if elapsed > 0.0 && elapsed < target {
+            params.t_cost = ((target / elapsed) as u32).max(1);
+            elapsed = measure(params).ok_or((atoms::error(), "Invalid Argon2 parameters"))?;
+        }
+
+        Ok(TunedParams {
+            m_cost: params.m_cost,
+            t_cost: params.t_cost,
+            p_cost: params.p_cost,
+            verify_ms: elapsed,
+        })
+    })
+}
@@ -0,0 +1,216 @@
+use rustler::{Binary, Env, NewBinary, NifUnitEnum};
+use sha2::{Digest, Sha256};
+
+use crate::atoms;
+use crate::backend::meets_difficulty_bytes;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::metrics;
+
+pub type Hash = [u8; 32];
+
+/// Sibling hashes from a leaf up to the root, as returned to Elixir
+type Path<'a> = Vec<(Side, Binary<'a>)>;
+
+/// Position of a sibling relative to the node being proven
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Hashes a leaf with a domain separation prefix so leaves can't pose as nodes
+pub fn hash_leaf(leaf: &[u8]) -> Hash {
+    Sha256::new()
+        .chain_update([0u8])
+        .chain_update(leaf)
+        .finalize()
+        .into()
+}
+
+pub fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    Sha256::new()
+        .chain_update([1u8])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Builds every level of the tree, from the leaf hashes up to the root.
+/// Odd nodes are paired with themselves.
+pub fn build_levels(leaf_hashes: Vec<Hash>) -> Vec<Vec<Hash>> {
+    let mut levels = vec![leaf_hashes];
+
+    while levels.last().is_some_and(|level| level.len() > 1) {
+        let level = levels.last().unwrap();
+        let next = level
+            .chunks(2)
+            .map(|pair| hash_node(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+        levels.push(next);
+    }
+
+    levels
+}
+
+/// Collects the sibling path for the leaf at `index`
+pub fn path_for(levels: &[Vec<Hash>], mut index: usize) -> Vec<(Side, Hash)> {
+    let mut path = vec![];
+
+    for level in &levels[..levels.len() - 1] {
+        let sibling = if index & 1 == 0 {
+            (Side::Right, *level.get(index + 1).unwrap_or(&level[index]))
+        } else {
+            (Side::Left, level[index - 1])
+        };
+        path.push(sibling);
+        index /= 2;
+    }
+
+    path
+}
+
+/// Folds a leaf hash up the path to recompute the root
+pub fn root_from_path(leaf_hash: Hash, path: &[(Side, Hash)]) -> Hash {
+    path.iter()
+        .fold(leaf_hash, |node, (side, sibling)| match side {
+            Side::Left => hash_node(sibling, &node),
+            Side::Right => hash_node(&node, sibling),
+        })
+}
+
+/// Reads a path given by Elixir, or `None` if a sibling isn't 32 bytes
+fn decode_path(path: Path) -> Option<Vec<(Side, Hash)>> {
+    path.into_iter()
+        .map(|(side, sibling)| Hash::try_from(sibling.as_slice()).ok().map(|hash| (side, hash)))
+        .collect()
+}
+
+fn to_binary<'a>(env: Env<'a>, hash: &Hash) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, hash.len());
+    binary.as_mut_slice().copy_from_slice(hash);
+    binary.into()
+}
+
+/// Proof hash binding the root, the leaf and the nonce together
+fn proof_state(root: &Hash, leaf: &[u8]) -> Sha256 {
+    Sha256::new().chain_update(root).chain_update(leaf)
+}
+
+fn meets(state: &Sha256, nonce: u64, difficulty: u32) -> bool {
+    let digest = state.clone().chain_update(nonce.to_le_bytes()).finalize();
+    meets_difficulty_bytes(&digest, difficulty)
+}
+
+/// Computes the Merkle root of a list of leaves
+#[rustler::nif]
+fn merkle_root<'a>(env: Env<'a>, leaves: Vec<Binary>) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        if leaves.is_empty() {
+            return Err((atoms::error(), "No leaves given"));
+        }
+
+        let levels = build_levels(leaves.iter().map(|leaf| hash_leaf(leaf)).collect());
+        Ok(to_binary(env, &levels.last().unwrap()[0]))
+    })
+}
+
+/// Searches for any leaf + nonce combination meeting the difficulty under the tree's root
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_merkle<'a>(
+    env: Env<'a>,
+    leaves: Vec<Binary>,
+    difficulty: u32,
+) -> GuardedResult<(usize, u64, Path<'a>)> {
+    guard::run(|| {
+        if leaves.is_empty() {
+            return Err((atoms::error(), "No leaves given"));
+        }
+
+        Difficulty::Zeros(difficulty).validate()?;
+
+        let levels = build_levels(leaves.iter().map(|leaf| hash_leaf(leaf)).collect());
+        let root = levels.last().unwrap()[0];
+        let states: Vec<Sha256> = leaves.iter().map(|leaf| proof_state(&root, leaf)).collect();
+
+        let mut meter = metrics::Meter::new();
+
+        for nonce in 0..u64::MAX {
+            meter.tick();
+            if let Some(index) = states
+                .iter()
+                .position(|state| meets(state, nonce, difficulty))
+            {
+                let path = path_for(&levels, index)
+                    .iter()
+                    .map(|(side, hash)| (*side, to_binary(env, hash)))
+                    .collect();
+                return Ok((index, nonce, path));
+            }
+
+            if nonce > 100_000_000 && difficulty > 20 {
+                return Err((atoms::error(), "Difficulty too high, computation aborted"));
+            }
+        }
+
+        Err((atoms::error(), "No valid nonce found"))
+    })
+}
+
+/// Validates that the leaf belongs to the root and that leaf + nonce meet the difficulty
+#[rustler::nif(name = "valid_merkle?")]
+fn valid_merkle(
+    root: Binary,
+    leaf: Binary,
+    nonce: u64,
+    path: Path,
+    difficulty: u32,
+) -> Guarded<bool> {
+    guard::run(|| {
+        let Ok(root) = Hash::try_from(root.as_slice()) else {
+            return false;
+        };
+
+        let Some(siblings) = decode_path(path) else {
+            return false;
+        };
+
+        root_from_path(hash_leaf(&leaf), &siblings) == root
+            && meets(&proof_state(&root, &leaf), nonce, difficulty)
+    })
+}
+
+/// Commits to many proofs with one Merkle root, returning the root and each proof's path
+/// in the order given
+#[rustler::nif(schedule = "DirtyCpu")]
+fn aggregate_proofs<'a>(
+    env: Env<'a>,
+    proofs: Vec<Binary>,
+) -> GuardedResult<(Binary<'a>, Vec<Path<'a>>)> {
+    guard::run(|| {
+        if proofs.is_empty() {
+            return Err((atoms::error(), "No proofs given"));
+        }
+
+        let levels = build_levels(proofs.iter().map(|proof| hash_leaf(proof)).collect());
+        let paths = (0..proofs.len())
+            .map(|index| {
+                path_for(&levels, index)
+                    .iter()
+                    .map(|(side, hash)| (*side, to_binary(env, hash)))
+                    .collect()
+            })
+            .collect();
+        Ok((to_binary(env, &levels.last().unwrap()[0]), paths))
+    })
+}
+
+/// Checks that a proof is a member of an aggregate root
+#[rustler::nif]
+fn verify_aggregate(member_proof: Binary, path: Path, root: Binary) -> Guarded<bool> {
+    guard::run(|| match (decode_path(path), Hash::try_from(root.as_slice())) {
+        (Some(siblings), Ok(root)) => root_from_path(hash_leaf(&member_proof), &siblings) == root,
+        _ => false,
+    })
+}
@@ -0,0 +1,188 @@
+use rustler::NifMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::cache::{self, CacheStats};
+use crate::guard::{self, Guarded};
+use crate::jobs::{self, JobStats};
+
+/// Hashes a meter counts locally before publishing them to the global counter
+const FLUSH_INTERVAL: u64 = 4_096;
+
+/// Time constant of the hashrate moving average, in seconds
+const HASHRATE_EMA_SECONDS: f64 = 60.0;
+
+static HASHES: AtomicU64 = AtomicU64::new(0);
+static JOBS_STARTED: AtomicU64 = AtomicU64::new(0);
+static JOBS_COMPLETED: AtomicU64 = AtomicU64::new(0);
+static QUEUE_DEPTH: AtomicU64 = AtomicU64::new(0);
+
+/// Counts the hashes of one search loop, publishing them in batches to keep the loop cheap
+pub struct Meter {
+    pending: u64,
+}
+
+impl Meter {
+    pub fn new() -> Meter {
+        Meter { pending: 0 }
+    }
+
+    /// Counts one hash
+    #[inline]
+    pub fn tick(&mut self) {
+        self.pending += 1;
+        if self.pending == FLUSH_INTERVAL {
+            HASHES.fetch_add(self.pending, Ordering::Relaxed);
+            self.pending = 0;
+        }
+    }
+}
+
+impl Drop for Meter {
+    fn drop(&mut self) {
+        HASHES.fetch_add(self.pending, Ordering::Relaxed);
+    }
+}
+
+/// Counts a job handed to a background worker
+pub fn job_started() {
+    JOBS_STARTED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a job whose result was delivered
+pub fn job_completed() {
+    JOBS_COMPLETED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts work added to a daemon queue
+pub fn queued(count: u64) {
+    QUEUE_DEPTH.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Counts work taken off a daemon queue
+pub fn dequeued(count: u64) {
+    QUEUE_DEPTH.fetch_sub(count, Ordering::Relaxed);
+}
+
+/// Updates the hashrate moving average with the hashes counted since the last update.
+/// The rate is assumed constant in between, so the average decays by `exp(-dt / tau)`.
+fn hashrate_ema() -> f64 {
+    static EMA: Mutex<Option<(f64, Instant, u64)>> = Mutex::new(None);
+
+    let mut ema = EMA.lock().unwrap();
+    let now = Instant::now();
+    let hashes = HASHES.load(Ordering::Relaxed);
+    let rate = match *ema {
+        Some((rate, updated, counted)) => {
+            let elapsed = now.duration_since(updated).as_secs_f64();
+            if elapsed <= 0.0 {
+                rate
+            } else {
+                let current = (hashes - counted) as f64 / elapsed;
+                let weight = 1.0 - (-elapsed / HASHRATE_EMA_SECONDS).exp();
+                rate + weight * (current - rate)
+            }
+        }
+        None => 0.0,
+    };
+    *ema = Some((rate, now, hashes));
+    rate
+}
+
+#[derive(NifMap)]
+struct Stats {
+    hashes: u64,
+    cache: CacheStats,
+    jobs: JobStats,
+}
+
+/// Gathers the internal counters, grouped by subsystem
+#[rustler::nif]
+fn stats() -> Guarded<Stats> {
+    guard::run(|| Stats {
+        hashes: HASHES.load(Ordering::Relaxed),
+        cache: cache::stats(),
+        jobs: jobs::stats(),
+    })
+}
+
+/// Renders the internal counters in the Prometheus text exposition format
+#[rustler::nif]
+fn metrics_prometheus() -> Guarded<String> {
+    guard::run(|| {
+        let cache = cache::stats();
+        let jobs = jobs::stats();
+        let metrics: [(&str, &str, &str, String); 10] = [
+            (
+                "powex_hashes_total",
+                "counter",
+                "Hashes computed by searches.",
+                HASHES.load(Ordering::Relaxed).to_string(),
+            ),
+            (
+                "powex_hashrate_ema",
+                "gauge",
+                "Hashes per second, exponential moving average over one minute.",
+                hashrate_ema().to_string(),
+            ),
+            (
+                "powex_jobs_started_total",
+                "counter",
+                "Async and daemon jobs started.",
+                JOBS_STARTED.load(Ordering::Relaxed).to_string(),
+            ),
+            (
+                "powex_jobs_completed_total",
+                "counter",
+                "Async and daemon jobs whose result was delivered.",
+                JOBS_COMPLETED.load(Ordering::Relaxed).to_string(),
+            ),
+            (
+                "powex_jobs_coalesced_total",
+                "counter",
+                "Async jobs that joined an identical search in flight.",
+                jobs.coalesced.to_string(),
+            ),
+            (
+                "powex_searches_in_flight",
+                "gauge",
+                "Async searches currently running.",
+                jobs.searches.to_string(),
+            ),
+            (
+                "powex_queue_depth",
+                "gauge",
+                "Work waiting in daemon queues.",
+                QUEUE_DEPTH.load(Ordering::Relaxed).to_string(),
+            ),
+            (
+                "powex_cache_hits_total",
+                "counter",
+                "Verification cache hits.",
+                cache.hits.to_string(),
+            ),
+            (
+                "powex_cache_misses_total",
+                "counter",
+                "Verification cache misses.",
+                cache.misses.to_string(),
+            ),
+            (
+                "powex_cache_entries",
+                "gauge",
+                "Entries in the verification cache.",
+                cache.size.to_string(),
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(output, "# HELP {name} {help}");
+            let _ = writeln!(output, "# TYPE {name} {kind}");
+            let _ = writeln!(output, "{name} {value}");
+        }
+        output
+    })
+}
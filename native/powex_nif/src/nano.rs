@@ -0,0 +1,202 @@
+use rand::{Rng, RngCore};
+use rustler::{Atom, Binary, Decoder, Error, NifResult, Term};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::atoms;
+use crate::config;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::metrics;
+use crate::options::Options;
+use crate::seeded::SplitMix64;
+
+/// Size of a block root: the previous block hash, or the account key for open blocks
+const ROOT_SIZE: usize = 32;
+
+/// Candidates each thread tries between checks of the deadline
+const DEADLINE_CHECK_INTERVAL: u64 = 1 << 16;
+
+/// A minimum work value, given as an integer or by the name of a network threshold
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Threshold(u64);
+
+impl Threshold {
+    /// Epoch 2 threshold for send and change blocks
+    const SEND: Threshold = Threshold(0xffff_fff8_0000_0000);
+    /// Epoch 2 threshold for receive blocks, 64 times easier than send
+    const RECEIVE: Threshold = Threshold(0xffff_fe00_0000_0000);
+    /// The single threshold of epoch 1 blocks
+    const EPOCH_1: Threshold = Threshold(0xffff_ffc0_0000_0000);
+}
+
+impl<'a> Decoder<'a> for Threshold {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if let Ok(value) = term.decode::<u64>() {
+            return Ok(Threshold(value));
+        }
+
+        match term.decode::<Atom>()? {
+            name if name == atoms::send() => Ok(Threshold::SEND),
+            name if name == atoms::receive() => Ok(Threshold::RECEIVE),
+            name if name == atoms::epoch_1() => Ok(Threshold::EPOCH_1),
+            _ => Err(Error::BadArg),
+        }
+    }
+}
+
+/// Nano's work value: the 8-byte BLAKE2b of `work || root`, both halves little-endian
+fn work_value(root: &[u8], work: u64) -> u64 {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(8)
+        .to_state()
+        .update(&work.to_le_bytes())
+        .update(root)
+        .finalize();
+    let mut value = [0u8; 8];
+    value.copy_from_slice(hash.as_bytes());
+    u64::from_le_bytes(value)
+}
+
+/// Reads work in the 16-digit hex form of the node RPC and block JSON
+fn decode_work(work: &str) -> Option<u64> {
+    if work.len() != 16 || !work.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(work, 16).ok()
+}
+
+fn encode_work(work: u64) -> String {
+    format!("{:016x}", work)
+}
+
+/// Searches `start, start + 1, ...` with the offsets strided between threads, returning
+/// the work at the lowest offset that reaches the threshold. A thread stops once its next
+/// offset is past the best one found, so the result doesn't depend on thread timing
+fn generate_seeded(
+    root: &[u8],
+    threshold: Threshold,
+    threads: u32,
+    deadline: Option<Instant>,
+    seed: u64,
+) -> Option<u64> {
+    let start = SplitMix64::new(seed).next_u64();
+    let threads = threads as u64;
+    let best = AtomicU64::new(u64::MAX);
+
+    thread::scope(|scope| {
+        for thread in 0..threads {
+            let best = &best;
+            scope.spawn(move || {
+                let mut meter = metrics::Meter::new();
+                let mut offset = thread;
+                while offset < best.load(Ordering::Relaxed) {
+                    meter.tick();
+                    if work_value(root, start.wrapping_add(offset)) >= threshold.0 {
+                        best.fetch_min(offset, Ordering::Relaxed);
+                        return;
+                    }
+                    offset = offset.saturating_add(threads);
+
+                    if (offset / threads).is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                        && deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                    {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    match best.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        offset => Some(start.wrapping_add(offset)),
+    }
+}
+
+/// Generates work for a block root on `:threads` threads, each starting at its own random
+/// point as the reference node does, until a value reaches the threshold or `:timeout` passes.
+/// With `:deterministic_seed`, the threads share one seeded start instead and the same
+/// work comes back on every run
+#[rustler::nif(schedule = "DirtyCpu")]
+fn nano_work_generate(
+    root: Binary,
+    threshold: Threshold,
+    opts: Options,
+) -> NifResult<GuardedResult<String>> {
+    guard::run_result(|| {
+        let requested: Option<u32> = opts.get(atoms::threads())?;
+        let timeout: Option<u64> = opts.get(atoms::timeout())?;
+        let seed: Option<u64> = opts.get(atoms::deterministic_seed())?;
+
+        if root.len() != ROOT_SIZE {
+            return Ok(Err((atoms::error(), "Root must be 32 bytes")));
+        }
+        if matches!(requested, Some(threads) if threads == 0 || threads > 64) {
+            return Ok(Err((atoms::error(), "Invalid number of threads (1-64)")));
+        }
+
+        let threads = config::worker_count(requested, 0).min(64);
+        let deadline = timeout.map(|ms| Instant::now() + Duration::from_millis(ms));
+        let root = root.as_slice();
+
+        if let Some(seed) = seed {
+            return Ok(match generate_seeded(root, threshold, threads, deadline, seed) {
+                Some(work) => Ok(encode_work(work)),
+                None => Err((atoms::error(), "Timed out before reaching the threshold")),
+            });
+        }
+
+        let found = AtomicBool::new(false);
+        let result = AtomicU64::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    let mut work: u64 = rand::thread_rng().gen();
+                    let mut meter = metrics::Meter::new();
+                    for attempt in 1.. {
+                        meter.tick();
+                        if work_value(root, work) >= threshold.0 {
+                            if !found.swap(true, Ordering::Relaxed) {
+                                result.store(work, Ordering::Relaxed);
+                            }
+                            return;
+                        }
+                        work = work.wrapping_add(1);
+
+                        if attempt % DEADLINE_CHECK_INTERVAL == 0
+                            && (found.load(Ordering::Relaxed)
+                                || deadline.is_some_and(|deadline| Instant::now() >= deadline))
+                        {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        if !found.load(Ordering::Relaxed) {
+            return Ok(Err((atoms::error(), "Timed out before reaching the threshold")));
+        }
+        Ok(Ok(encode_work(result.load(Ordering::Relaxed))))
+    })
+}
+
+/// Checks that work for a block root reaches the threshold
+#[rustler::nif(name = "nano_work_valid?")]
+fn nano_work_valid(root: Binary, work: &str, threshold: Threshold) -> Guarded<bool> {
+    guard::run(|| match decode_work(work) {
+        Some(work) if root.len() == ROOT_SIZE => work_value(root.as_slice(), work) >= threshold.0,
+        _ => false,
+    })
+}
+
+/// The work value of a block root and work, as the node's `work_validate` reports it
+#[rustler::nif]
+fn nano_work_value(root: Binary, work: &str) -> NifResult<Guarded<u64>> {
+    guard::run_result(|| match decode_work(work) {
+        Some(work) if root.len() == ROOT_SIZE => Ok(work_value(root.as_slice(), work)),
+        _ => Err(Error::BadArg),
+    })
+}
@@ -0,0 +1,92 @@
+//! NUMA topology from sysfs, and pinning of worker threads to a node. Linux allocates a page
+//! on the node of the thread that first touches it, so a pinned worker's scratch memory
+//! (Argon2 and yespower scratchpads, digest buffers) stays local to the worker.
+
+use rustler::NifMap;
+use std::sync::OnceLock;
+
+use crate::guard::{self, Guarded};
+
+/// CPUs of each NUMA node, read once; empty where the topology is unknown
+pub fn nodes() -> &'static [Vec<usize>] {
+    static NODES: OnceLock<Vec<Vec<usize>>> = OnceLock::new();
+    NODES.get_or_init(read_nodes)
+}
+
+#[cfg(target_os = "linux")]
+fn read_nodes() -> Vec<Vec<usize>> {
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/node") else {
+        return vec![];
+    };
+
+    let mut nodes: Vec<(usize, Vec<usize>)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let node = name.to_str()?.strip_prefix("node")?.parse().ok()?;
+            let cpulist = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            Some((node, parse_cpulist(&cpulist)))
+        })
+        .filter(|(_, cpus)| !cpus.is_empty())
+        .collect();
+    nodes.sort_unstable_by_key(|(node, _)| *node);
+    nodes.into_iter().map(|(_, cpus)| cpus).collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_nodes() -> Vec<Vec<usize>> {
+    vec![]
+}
+
+/// Parses a kernel CPU list such as `0-3,8-11`
+#[cfg(target_os = "linux")]
+fn parse_cpulist(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|part| match part.split_once('-') {
+            Some((first, last)) => Some(first.parse().ok()?..=last.parse().ok()?),
+            None => part.parse().ok().map(|cpu| cpu..=cpu),
+        })
+        .flatten()
+        .collect()
+}
+
+/// Pins the calling thread to the CPUs of `node`; returns false if that isn't possible
+#[cfg(target_os = "linux")]
+pub fn bind(node: usize) -> bool {
+    let Some(cpus) = nodes().get(node) else {
+        return false;
+    };
+
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        for &cpu in cpus.iter().filter(|&&cpu| cpu < libc::CPU_SETSIZE as usize) {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn bind(_node: usize) -> bool {
+    false
+}
+
+#[derive(NifMap)]
+struct NumaNode {
+    node: usize,
+    cpus: Vec<usize>,
+}
+
+/// Lists the NUMA nodes and their CPUs
+#[rustler::nif]
+fn numa_topology() -> Guarded<Vec<NumaNode>> {
+    guard::run(|| {
+        nodes()
+            .iter()
+            .enumerate()
+            .map(|(node, cpus)| NumaNode { node, cpus: cpus.clone() })
+            .collect()
+    })
+}
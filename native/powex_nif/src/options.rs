@@ -0,0 +1,34 @@
+use rustler::types::map::MapIterator;
+use rustler::{Atom, Decoder, Error, NifResult, Term};
+
+/// Options passed from Elixir as a keyword list (`[mode: :strided]`) or a map with atom keys
+pub struct Options<'a>(Vec<(Atom, Term<'a>)>);
+
+impl<'a> Decoder<'a> for Options<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if term.is_map() {
+            let pairs = MapIterator::new(term)
+                .ok_or(Error::BadArg)?
+                .map(|(key, value)| Ok((key.decode()?, value)))
+                .collect::<NifResult<_>>()?;
+            return Ok(Options(pairs));
+        }
+
+        Ok(Options(term.decode()?))
+    }
+}
+
+impl<'a> Options<'a> {
+    /// Gets the value for a key, raising `badarg` if it has the wrong type
+    pub fn get<T: Decoder<'a>>(&self, key: Atom) -> NifResult<Option<T>> {
+        match self.0.iter().find(|(k, _)| *k == key) {
+            Some((_, value)) => Ok(Some(value.decode()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the value for a key, falling back to a default when absent
+    pub fn get_or<T: Decoder<'a>>(&self, key: Atom, default: T) -> NifResult<T> {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+}
@@ -0,0 +1,166 @@
+//! OS enforcement of a throttled daemon's duty cycle, on top of the cooperative sleeping of
+//! `throttle::DutyCycle`. On macOS, workers run at the utility QoS class, dropping to
+//! background while throttled, so the scheduler moves them to efficiency cores and yields
+//! to other work. On Windows, where job objects hold whole processes rather than threads,
+//! the VM joins a job whose CPU rate hard cap leaves idle the cores the workers give up.
+//! Elsewhere only the cooperative limiter applies.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Source of the ids under which daemons register their share of the Windows job cap
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The OS-side limit of one daemon's workers
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct OsLimit {
+    id: u64,
+    workers: usize,
+    /// Cores given up at the last update, as `f64` bits
+    idle: AtomicU64,
+}
+
+impl OsLimit {
+    pub fn new(workers: usize) -> OsLimit {
+        OsLimit {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            workers,
+            idle: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    /// Called on each worker thread before it takes any work
+    pub fn enter(&self) {
+        #[cfg(target_os = "macos")]
+        qos::set(false);
+    }
+
+    /// Called on a worker thread with the duty cycle it's about to hash at
+    pub fn apply(&self, duty_cycle: f64) {
+        #[cfg(target_os = "macos")]
+        qos::set(duty_cycle < 1.0);
+
+        #[cfg(windows)]
+        {
+            let idle = self.workers as f64 * (1.0 - duty_cycle.clamp(0.0, 1.0));
+            if self.idle.swap(idle.to_bits(), Ordering::Relaxed) != idle.to_bits() {
+                job::set_idle(self.id, idle);
+            }
+        }
+
+        #[cfg(not(any(target_os = "macos", windows)))]
+        let _ = duty_cycle;
+    }
+}
+
+impl Drop for OsLimit {
+    /// Gives this daemon's share of the job cap back once its workers are gone
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        if f64::from_bits(self.idle.load(Ordering::Relaxed)) > 0.0 {
+            job::set_idle(self.id, 0.0);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod qos {
+    use std::cell::Cell;
+
+    thread_local! {
+        /// Whether the thread runs at background QoS; `None` until its class is first set
+        static BACKGROUND: Cell<Option<bool>> = const { Cell::new(None) };
+    }
+
+    /// Moves the calling thread to the background QoS class, or the utility class
+    pub fn set(background: bool) {
+        if BACKGROUND.get() == Some(background) {
+            return;
+        }
+        let class = if background {
+            libc::qos_class_t::QOS_CLASS_BACKGROUND
+        } else {
+            libc::qos_class_t::QOS_CLASS_UTILITY
+        };
+        if unsafe { libc::pthread_set_qos_class_self_np(class, 0) } == 0 {
+            BACKGROUND.set(Some(background));
+        }
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use std::collections::HashMap;
+    use std::ffi::c_void;
+    use std::sync::{Mutex, OnceLock};
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectCpuRateControlInformation,
+        SetInformationJobObject, JOBOBJECT_CPU_RATE_CONTROL_INFORMATION,
+        JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+    };
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    /// Lowest CPU rate the cap goes down to, in hundredths of a percent
+    const MIN_CPU_RATE: u32 = 100;
+
+    /// The job holding the VM, and the cores each daemon currently gives up
+    struct Job {
+        handle: HANDLE,
+        idle: Mutex<HashMap<u64, f64>>,
+    }
+
+    static JOB: OnceLock<Option<Job>> = OnceLock::new();
+
+    /// Puts the VM in a new job object; a process can't leave a job, so this is only
+    /// done the first time a daemon actually throttles
+    fn create() -> Option<Job> {
+        unsafe {
+            let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if handle == 0 {
+                return None;
+            }
+            if AssignProcessToJobObject(handle, GetCurrentProcess()) == 0 {
+                CloseHandle(handle);
+                return None;
+            }
+            Some(Job { handle, idle: Mutex::default() })
+        }
+    }
+
+    /// Records that daemon `owner` gives up `cores` cores, and caps the VM's CPU rate so
+    /// that all daemons' idle cores stay idle; the cap is lifted when none are
+    pub fn set_idle(owner: u64, cores: f64) {
+        let job = match JOB.get() {
+            Some(job) => job.as_ref(),
+            None if cores > 0.0 => JOB.get_or_init(create).as_ref(),
+            None => None,
+        };
+        let Some(job) = job else {
+            return;
+        };
+
+        let mut idle = job.idle.lock().unwrap();
+        if cores > 0.0 {
+            idle.insert(owner, cores);
+        } else {
+            idle.remove(&owner);
+        }
+
+        let total = std::thread::available_parallelism().map_or(1, |n| n.get()) as f64;
+        let share = 1.0 - idle.values().sum::<f64>() / total;
+        let mut info: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = unsafe { std::mem::zeroed() };
+        if share < 1.0 {
+            info.ControlFlags =
+                JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+            info.Anonymous.CpuRate = ((share * 10_000.0).round() as u32).max(MIN_CPU_RATE);
+        }
+        unsafe {
+            SetInformationJobObject(
+                job.handle,
+                JobObjectCpuRateControlInformation,
+                &info as *const _ as *const c_void,
+                std::mem::size_of::<JOBOBJECT_CPU_RATE_CONTROL_INFORMATION>() as u32,
+            );
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use rustler::{Atom, NifMap, NifResult, NifUnitEnum, Resource, ResourceArc, Term};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+
+/// How the nonce space is split between workers
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    /// Each worker gets one contiguous block
+    Contiguous,
+    /// Worker i searches start + i, start + i + n, ...
+    Strided,
+}
+
+/// A worker's share of the nonce space: start, start + stride, ... below `end`
+#[derive(NifMap)]
+struct Assignment {
+    start: u64,
+    end: u64,
+    stride: u64,
+}
+
+/// Splits [start, end) deterministically between `num_workers` workers
+#[rustler::nif]
+fn partition_space(
+    num_workers: u64,
+    worker_index: u64,
+    opts: Options,
+) -> NifResult<GuardedResult<Assignment>> {
+    guard::run_result(|| {
+        let mode = opts.get_or(atoms::mode(), Mode::Contiguous)?;
+        let start = opts.get_or(atoms::start(), 0u64)?;
+        let end = opts.get_or(atoms::end(), u64::MAX)?;
+
+        if num_workers == 0 || worker_index >= num_workers {
+            return Ok(Err((atoms::error(), "Invalid worker index")));
+        }
+
+        if start >= end {
+            return Ok(Err((atoms::error(), "Empty nonce range")));
+        }
+
+        let assignment = match mode {
+            Mode::Contiguous => {
+                let size = (end - start) / num_workers;
+                let worker_start = start + worker_index * size;
+                let worker_end = if worker_index == num_workers - 1 {
+                    end
+                } else {
+                    worker_start + size
+                };
+                Assignment {
+                    start: worker_start,
+                    end: worker_end,
+                    stride: 1,
+                }
+            }
+            Mode::Strided => Assignment {
+                start: start.saturating_add(worker_index).min(end),
+                end,
+                stride: num_workers,
+            },
+        };
+
+        Ok(Ok(assignment))
+    })
+}
+
+/// Jump consistent hash (Lamping and Veach): maps a key to one of `buckets` buckets so
+/// that growing the cluster from n to n + 1 buckets moves only 1 / (n + 1) of the keys
+fn jump_hash(mut key: u64, buckets: u64) -> u64 {
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+    while next < buckets as i64 {
+        bucket = next;
+        key = key.wrapping_mul(2_862_933_555_777_941_757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    bucket as u64
+}
+
+/// Assigns a node the strided share `offset, offset + cluster_size, ...` of the nonce
+/// space, with the offset derived from the node id alone, so no coordinator is needed
+#[rustler::nif]
+fn assigned_stride(node_id: Term, cluster_size: u64) -> GuardedResult<Assignment> {
+    guard::run(|| {
+        if cluster_size == 0 || cluster_size > i64::MAX as u64 {
+            return Err((atoms::error(), "Invalid cluster size"));
+        }
+
+        let digest = blake3::hash(node_id.to_binary().as_slice());
+        let mut key = [0u8; 8];
+        key.copy_from_slice(&digest.as_bytes()[..8]);
+
+        Ok(Assignment {
+            start: jump_hash(u64::from_le_bytes(key), cluster_size),
+            end: u64::MAX,
+            stride: cluster_size,
+        })
+    })
+}
+
+struct PoolState {
+    next: u64,
+    end: u64,
+    chunk_size: u64,
+    released: VecDeque<(u64, u64)>,
+}
+
+/// A shared pool handing out chunks of the nonce space to coordinating workers
+pub struct RangePool {
+    state: Mutex<PoolState>,
+}
+
+#[rustler::resource_impl]
+impl Resource for RangePool {}
+
+/// Creates a range pool over [start, end) handing out chunks of `chunk_size` nonces
+#[rustler::nif]
+fn range_pool_new(start: u64, end: u64, chunk_size: u64) -> GuardedResult<ResourceArc<RangePool>> {
+    guard::run(|| {
+        if start >= end {
+            return Err((atoms::error(), "Empty nonce range"));
+        }
+
+        if chunk_size == 0 {
+            return Err((atoms::error(), "Chunk size must be positive"));
+        }
+
+        Ok(ResourceArc::new(RangePool {
+            state: Mutex::new(PoolState {
+                next: start,
+                end,
+                chunk_size,
+                released: VecDeque::new(),
+            }),
+        }))
+    })
+}
+
+/// Claims the next free range, preferring ranges released by other workers
+#[rustler::nif]
+fn range_claim(pool: ResourceArc<RangePool>) -> Guarded<Result<(u64, u64), Atom>> {
+    guard::run(|| {
+        let mut state = pool.state.lock().unwrap();
+
+        if let Some(range) = state.released.pop_front() {
+            return Ok(range);
+        }
+
+        if state.next >= state.end {
+            return Err(atoms::exhausted());
+        }
+
+        let start = state.next;
+        let end = start.saturating_add(state.chunk_size).min(state.end);
+        state.next = end;
+        Ok((start, end))
+    })
+}
+
+/// Returns an unfinished range to the pool so another worker can claim it
+#[rustler::nif]
+fn range_release(pool: ResourceArc<RangePool>, range: (u64, u64)) -> Guarded<Atom> {
+    guard::run(|| {
+        if range.0 < range.1 {
+            pool.state.lock().unwrap().released.push_back(range);
+        }
+        atoms::ok()
+    })
+}
@@ -0,0 +1,76 @@
+use rustler::{Binary, Error, NifResult, Term};
+use zeroize::Zeroizing;
+
+use crate::atoms;
+use crate::guard::{self, GuardedResult};
+use crate::options::Options;
+
+/// BLAKE3 key derivation context, so client jitter never collides with other uses of the secret
+const KEY_CONTEXT: &str = "powex 2026-10-15 per-client difficulty jitter";
+
+/// Default `:max_adjustment`: a score of 0 or 1 moves the difficulty by two levels
+pub const DEFAULT_MAX_ADJUSTMENT: f64 = 2.0;
+
+/// Neutral reputation score, for clients nothing is known about
+pub const NEUTRAL_REPUTATION: f64 = 0.5;
+
+/// Highest difficulty a proof policy accepts by default
+const MAX_DIFFICULTY: f64 = 64.0;
+
+/// A reputation score in `[0, 1]`, given as an integer or a float
+pub fn reputation(term: Term) -> NifResult<f64> {
+    let score = match term.decode::<u64>() {
+        Ok(score) => score as f64,
+        Err(_) => term.decode::<f64>()?,
+    };
+    if !(0.0..=1.0).contains(&score) {
+        return Err(Error::BadArg);
+    }
+    Ok(score)
+}
+
+/// The difficulty of one client: the base moved by up to `max_adjustment` levels, down for
+/// a score above neutral and up for one below, plus a jitter in `[-0.5, 0.5)` drawn from a
+/// keyed BLAKE3 hash of the client id. The jitter dithers fractional targets, so across many
+/// clients the mean difficulty follows the score exactly while each client always gets the
+/// same difficulty, and clients can't predict each other's
+pub fn difficulty(
+    secret: &[u8],
+    client_id: &[u8],
+    base: u32,
+    reputation: f64,
+    max_adjustment: f64,
+) -> u32 {
+    let key = Zeroizing::new(blake3::derive_key(KEY_CONTEXT, secret));
+    let digest = blake3::keyed_hash(&key, client_id);
+    let word = u64::from_le_bytes(digest.as_bytes()[..8].try_into().unwrap_or([0; 8]));
+    let jitter = word as f64 / 2f64.powi(64) - 0.5;
+
+    let adjustment = (1.0 - 2.0 * reputation) * max_adjustment;
+    (base as f64 + adjustment + jitter).round().clamp(0.0, MAX_DIFFICULTY) as u32
+}
+
+/// Derives the difficulty of a client from a server secret, its id, a base difficulty and
+/// a reputation score in `[0, 1]`, without storing anything per client
+#[rustler::nif]
+fn personal_difficulty(
+    secret: Binary,
+    client_id: Binary,
+    base: u32,
+    reputation_score: Term,
+    opts: Options,
+) -> NifResult<GuardedResult<u32>> {
+    guard::run_result(|| {
+        let score = reputation(reputation_score)?;
+        let max_adjustment = opts.get_or(atoms::max_adjustment(), DEFAULT_MAX_ADJUSTMENT)?;
+
+        if secret.is_empty() {
+            return Ok(Err((atoms::error(), "Secret must not be empty")));
+        }
+        if !max_adjustment.is_finite() || max_adjustment < 0.0 {
+            return Ok(Err((atoms::error(), "Max adjustment must be non-negative")));
+        }
+
+        Ok(Ok(difficulty(&secret, &client_id, base, score, max_adjustment)))
+    })
+}
@@ -0,0 +1,42 @@
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use light_poseidon::{Poseidon, PoseidonHasher};
+use std::cell::RefCell;
+
+/// Bytes per field element when absorbing data; 31 bytes always fit below the BN254 modulus
+const CHUNK_LEN: usize = 31;
+
+thread_local! {
+    /// Circom-compatible two-input Poseidon over BN254, built once per thread
+    static HASHER: RefCell<Option<Poseidon<Fr>>> = const { RefCell::new(None) };
+}
+
+/// `Poseidon(left, right)`, the circom `Poseidon(2)` template
+fn hash2(left: Fr, right: Fr) -> Fr {
+    HASHER.with(|hasher| {
+        let mut hasher = hasher.borrow_mut();
+        let hasher = hasher.get_or_insert_with(|| {
+            Poseidon::<Fr>::new_circom(2).expect("two inputs are supported")
+        });
+        hasher
+            .hash(&[left, right])
+            .expect("two inputs match the parameters")
+    })
+}
+
+/// Absorbs the data into one field element: starting from the data length, each 31-byte
+/// big-endian chunk is folded in as `state = Poseidon(state, chunk)`
+pub fn absorb(data: &[u8]) -> Fr {
+    data.chunks(CHUNK_LEN)
+        .fold(Fr::from(data.len() as u64), |state, chunk| {
+            hash2(state, Fr::from_be_bytes_mod_order(chunk))
+        })
+}
+
+/// `Poseidon(state, nonce)` as a 32-byte big-endian field element
+pub fn digest(state: Fr, nonce: u64) -> [u8; 32] {
+    let bytes = hash2(state, Fr::from(nonce)).into_bigint().to_bytes_be();
+    let mut digest = [0u8; 32];
+    digest[32 - bytes.len()..].copy_from_slice(&bytes);
+    digest
+}
@@ -0,0 +1,163 @@
+use rustler::{Binary, NifResult};
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::input;
+use crate::metrics;
+use crate::options::Options;
+
+/// Fuel each `accept` call may burn before it is stopped
+const DEFAULT_FUEL: u64 = 10_000;
+
+/// Nonces tried by `compute_with_predicate` before it gives up
+const DEFAULT_MAX_ATTEMPTS: u64 = 1_000_000;
+
+#[cfg(feature = "wasm")]
+mod sandbox {
+    use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+    use wasmi::{TrapCode, TypedFunc};
+
+    /// Linear memory a predicate module may grow to
+    const MAX_MEMORY: usize = 1024 * 1024;
+
+    /// An instantiated predicate module; no host functions are importable
+    pub struct Predicate {
+        store: Store<StoreLimits>,
+        accept: TypedFunc<(i64, i64, i64, i64), i32>,
+        fuel: u64,
+    }
+
+    impl Predicate {
+        pub fn new(wasm: &[u8], fuel: u64) -> Result<Predicate, &'static str> {
+            let mut config = Config::default();
+            config.consume_fuel(true);
+            let engine = Engine::new(&config);
+            let module = Module::new(&engine, wasm).map_err(|_| "Invalid WASM module")?;
+
+            let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build();
+            let mut store = Store::new(&engine, limits);
+            store.limiter(|limits| limits);
+            store
+                .set_fuel(fuel)
+                .map_err(|_| "Fuel metering unavailable")?;
+
+            let instance = Linker::new(&engine)
+                .instantiate_and_start(&mut store, &module)
+                .map_err(|_| {
+                    "WASM module must not import anything and must start within its fuel"
+                })?;
+            let accept = instance
+                .get_typed_func(&store, "accept")
+                .map_err(|_| "WASM module must export accept(i64, i64, i64, i64) -> i32")?;
+
+            Ok(Predicate {
+                store,
+                accept,
+                fuel,
+            })
+        }
+
+        /// Runs `accept` with a fresh fuel budget on the digest's first 32 bytes,
+        /// passed as four big-endian 64-bit words
+        pub fn accepts(&mut self, digest: &[u8]) -> Result<bool, &'static str> {
+            let mut words = [0i64; 4];
+            for (word, bytes) in words.iter_mut().zip(digest.chunks(8)) {
+                let mut buffer = [0u8; 8];
+                buffer[..bytes.len()].copy_from_slice(bytes);
+                *word = i64::from_be_bytes(buffer);
+            }
+
+            self.store
+                .set_fuel(self.fuel)
+                .map_err(|_| "Fuel metering unavailable")?;
+            match self.accept.call(&mut self.store, words.into()) {
+                Ok(accepted) => Ok(accepted != 0),
+                Err(error) if error.as_trap_code() == Some(TrapCode::OutOfFuel) => {
+                    Err("Predicate ran out of fuel")
+                }
+                Err(_) => Err("Predicate trapped"),
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "wasm"))]
+mod sandbox {
+    /// Stand-in when the crate is built without the `wasm` feature
+    pub struct Predicate;
+
+    impl Predicate {
+        pub fn new(_wasm: &[u8], _fuel: u64) -> Result<Predicate, &'static str> {
+            Err("WASM predicates need the NIF built with the wasm feature")
+        }
+
+        pub fn accepts(&mut self, _digest: &[u8]) -> Result<bool, &'static str> {
+            Err("WASM predicates need the NIF built with the wasm feature")
+        }
+    }
+}
+
+use sandbox::Predicate;
+
+/// Finds the first nonce whose digest the module's `accept` export says yes to.
+/// Each call gets `:fuel` units of fuel; running out fails the search.
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_with_predicate(
+    data: Binary,
+    wasm: Binary,
+    opts: Options,
+) -> NifResult<GuardedResult<u64>> {
+    guard::run_result(|| {
+        let algorithm = opts.get_or(atoms::algorithm(), Algorithm::Sha256)?;
+        let fuel = opts.get_or(atoms::fuel(), DEFAULT_FUEL)?;
+        let max_attempts = opts.get_or(atoms::max_attempts(), DEFAULT_MAX_ATTEMPTS)?;
+
+        input::check_size(data.as_slice())?;
+
+        let mut predicate = match Predicate::new(wasm.as_slice(), fuel) {
+            Ok(predicate) => predicate,
+            Err(reason) => return Ok(Err((atoms::error(), reason))),
+        };
+
+        let state = algorithm.prepare(data.as_slice());
+        let mut digest = Vec::new();
+        let mut meter = metrics::Meter::new();
+        for nonce in 0..max_attempts {
+            meter.tick();
+            state.digest_into(nonce, &mut digest);
+            match predicate.accepts(&digest) {
+                Ok(true) => return Ok(Ok(nonce)),
+                Ok(false) => {}
+                Err(reason) => return Ok(Err((atoms::error(), reason))),
+            }
+        }
+
+        Ok(Err((
+            atoms::error(),
+            "No nonce accepted within max_attempts",
+        )))
+    })
+}
+
+/// Checks a nonce against a predicate module, as `compute_with_predicate` does; an invalid
+/// module, a trap or running out of fuel counts as rejection
+#[rustler::nif(name = "valid_predicate?", schedule = "DirtyCpu")]
+fn valid_predicate(
+    data: Binary,
+    nonce: u64,
+    wasm: Binary,
+    opts: Options,
+) -> NifResult<Guarded<bool>> {
+    guard::run_result(|| {
+        let algorithm = opts.get_or(atoms::algorithm(), Algorithm::Sha256)?;
+        let fuel = opts.get_or(atoms::fuel(), DEFAULT_FUEL)?;
+
+        input::check_size(data.as_slice())?;
+
+        let digest = algorithm.prepare(data.as_slice()).digest(nonce);
+        let accepted = Predicate::new(wasm.as_slice(), fuel)
+            .and_then(|mut predicate| predicate.accepts(&digest));
+        Ok(accepted == Ok(true))
+    })
+}
@@ -0,0 +1,178 @@
+use rustler::{Atom, Binary, Decoder, Env, Error, NewBinary, NifMap, NifResult, Resource};
+use rustler::{ResourceArc, Term};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::atoms;
+use crate::backend::meets_difficulty_bytes;
+use crate::difficulty::Difficulty;
+use crate::entropy;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::options::Options;
+
+/// Size of the challenge a client hashes to earn tokens
+const CHALLENGE_SIZE: usize = 16;
+
+const DEFAULT_CAPACITY: f64 = 10.0;
+const DEFAULT_REFILL_PER_SECOND: f64 = 1.0;
+const DEFAULT_MIN_DIFFICULTY: u32 = 2;
+const DEFAULT_MAX_DIFFICULTY: u32 = 6;
+
+/// A token amount given as an integer or a float
+#[derive(Clone, Copy)]
+struct Tokens(f64);
+
+impl<'a> Decoder<'a> for Tokens {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let tokens = match term.decode::<i64>() {
+            Ok(tokens) => tokens as f64,
+            Err(_) => term.decode::<f64>()?,
+        };
+        Ok(Tokens(tokens))
+    }
+}
+
+/// A client's token bucket: requests spend tokens, which refill over time or are bought
+/// with proofs of work whose difficulty rises as the bucket empties
+pub struct PricingBucket {
+    capacity: f64,
+    refill_per_second: f64,
+    credit: f64,
+    min_difficulty: u32,
+    max_difficulty: u32,
+    state: Mutex<BucketState>,
+}
+
+#[rustler::resource_impl]
+impl Resource for PricingBucket {}
+
+struct BucketState {
+    tokens: f64,
+    refilled: Instant,
+    /// What the next proof must hash; replaced whenever a proof is accepted
+    challenge: [u8; CHALLENGE_SIZE],
+}
+
+fn new_challenge() -> [u8; CHALLENGE_SIZE] {
+    let mut challenge = [0u8; CHALLENGE_SIZE];
+    entropy::fill(&mut challenge, None);
+    challenge
+}
+
+impl PricingBucket {
+    /// Interpolates from the minimum difficulty when full to the maximum when empty
+    fn difficulty(&self, tokens: f64) -> u32 {
+        let fill = (tokens / self.capacity).clamp(0.0, 1.0);
+        let span = (self.max_difficulty - self.min_difficulty) as f64;
+        self.min_difficulty + ((1.0 - fill) * span).round() as u32
+    }
+
+    fn refill(&self, state: &mut BucketState) {
+        let now = Instant::now();
+        let earned = now.duration_since(state.refilled).as_secs_f64() * self.refill_per_second;
+        state.tokens = (state.tokens + earned).min(self.capacity);
+        state.refilled = now;
+    }
+}
+
+/// What a client needs to know after a request
+#[derive(NifMap)]
+struct Quote<'a> {
+    tokens: f64,
+    difficulty: u32,
+    challenge: Binary<'a>,
+}
+
+fn quote<'a>(env: Env<'a>, bucket: &PricingBucket, state: &BucketState) -> Quote<'a> {
+    let mut challenge = NewBinary::new(env, CHALLENGE_SIZE);
+    challenge.as_mut_slice().copy_from_slice(&state.challenge);
+    Quote {
+        tokens: state.tokens,
+        difficulty: bucket.difficulty(state.tokens),
+        challenge: challenge.into(),
+    }
+}
+
+/// Creates a full bucket of `:capacity` tokens refilling at `:refill_per_second`; each
+/// accepted proof credits `:credit` tokens (default: the capacity) and proofs cost from
+/// `:min_difficulty` when full to `:max_difficulty` when empty
+#[rustler::nif]
+fn pricing_bucket_new(opts: Options) -> NifResult<GuardedResult<ResourceArc<PricingBucket>>> {
+    guard::run_result(|| {
+        let Tokens(capacity) = opts.get_or(atoms::capacity(), Tokens(DEFAULT_CAPACITY))?;
+        let Tokens(refill_per_second) =
+            opts.get_or(atoms::refill_per_second(), Tokens(DEFAULT_REFILL_PER_SECOND))?;
+        let Tokens(credit) = opts.get_or(atoms::credit(), Tokens(capacity))?;
+        let min_difficulty = opts.get_or(atoms::min_difficulty(), DEFAULT_MIN_DIFFICULTY)?;
+        let max_difficulty = opts.get_or(atoms::max_difficulty(), DEFAULT_MAX_DIFFICULTY)?;
+
+        if capacity <= 0.0 || refill_per_second < 0.0 || credit <= 0.0 {
+            return Ok(Err((
+                atoms::error(),
+                "Capacity and credit must be positive and the refill rate non-negative",
+            )));
+        }
+        if min_difficulty > max_difficulty {
+            return Ok(Err((atoms::error(), "Minimum difficulty exceeds the maximum")));
+        }
+        if let Err(reason) = Difficulty::Zeros(max_difficulty).validate() {
+            return Ok(Err(reason));
+        }
+
+        Ok(Ok(ResourceArc::new(PricingBucket {
+            capacity,
+            refill_per_second,
+            credit,
+            min_difficulty,
+            max_difficulty,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                refilled: Instant::now(),
+                challenge: new_challenge(),
+            }),
+        })))
+    })
+}
+
+/// Atomically applies an optional proof and charges `cost` tokens. A proof is a nonce for
+/// the bucket's current challenge at the current difficulty, hashed as by `compute/2`; it
+/// credits tokens and rotates the challenge, so it can't be replayed. An invalid proof
+/// denies the request without charging it
+#[rustler::nif]
+fn price_request<'a>(
+    env: Env<'a>,
+    bucket: ResourceArc<PricingBucket>,
+    cost: Tokens,
+    proof: Option<u64>,
+) -> NifResult<Guarded<(Atom, Quote<'a>)>> {
+    guard::run_result(|| {
+        let Tokens(cost) = cost;
+        if cost < 0.0 {
+            return Err(Error::BadArg);
+        }
+
+        let mut state = bucket.state.lock().unwrap();
+        bucket.refill(&mut state);
+
+        if let Some(nonce) = proof {
+            let digest = Sha256::new()
+                .chain_update(state.challenge)
+                .chain_update(nonce.to_le_bytes())
+                .finalize();
+            if !meets_difficulty_bytes(&digest, bucket.difficulty(state.tokens)) {
+                return Ok((atoms::deny(), quote(env, &bucket, &state)));
+            }
+            state.tokens = (state.tokens + bucket.credit).min(bucket.capacity);
+            state.challenge = new_challenge();
+        }
+
+        let decision = if state.tokens >= cost {
+            state.tokens -= cost;
+            atoms::allow()
+        } else {
+            atoms::deny()
+        };
+        Ok((decision, quote(env, &bucket, &state)))
+    })
+}
@@ -0,0 +1,141 @@
+//! Experimental verification of Primecoin-style prime chains, following Primecoin's
+//! `CheckPrimeProofOfWork`. Chain lengths are fixed point with 24 fractional bits: the
+//! whole part counts probable primes, and the fraction measures how close the first
+//! composite came to passing its Fermat test.
+
+use num_bigint::BigUint;
+use rustler::{Binary, Decoder, Error, NifResult, NifUnitEnum, Term};
+
+use crate::guard::{self, Guarded};
+
+const FRACTIONAL_BITS: u32 = 24;
+const FRACTIONAL_MASK: u32 = (1 << FRACTIONAL_BITS) - 1;
+const LENGTH_MASK: u32 = !FRACTIONAL_MASK;
+
+/// Shortest and longest whole target lengths Primecoin accepts
+const MIN_TARGET_LENGTH: u32 = 2;
+const MAX_TARGET_LENGTH: u32 = 99;
+
+/// Longest origin accepted, in bits
+const MAX_ORIGIN_BITS: u64 = 2000;
+
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainType {
+    /// `origin - 1` and each `2p + 1` after it
+    Cunningham1,
+    /// `origin + 1` and each `2p - 1` after it
+    Cunningham2,
+    /// Alternating links of both chains, so both twins of each doubling count
+    BiTwin,
+}
+
+/// A target length given as an integer or a float with a fractional part
+pub struct TargetLength(u32);
+
+impl<'a> Decoder<'a> for TargetLength {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let length = match term.decode::<u32>() {
+            Ok(whole) => whole as f64,
+            Err(_) => term.decode::<f64>()?,
+        };
+        if !(MIN_TARGET_LENGTH as f64..(MAX_TARGET_LENGTH + 1) as f64).contains(&length) {
+            return Err(Error::BadArg);
+        }
+        let whole = length.trunc() as u32;
+        let fraction = (length.fract() * (1 << FRACTIONAL_BITS) as f64) as u32;
+        Ok(TargetLength(whole << FRACTIONAL_BITS | fraction))
+    }
+}
+
+fn whole_length(length: u32) -> u32 {
+    length >> FRACTIONAL_BITS
+}
+
+/// The fraction of a failed test: `(n - r) / n` for the Fermat remainder `r`
+fn fractional_length(n: &BigUint, remainder: &BigUint) -> u32 {
+    let fraction = ((n - remainder) << FRACTIONAL_BITS) / n;
+    fraction.iter_u32_digits().next().unwrap_or(0) & FRACTIONAL_MASK
+}
+
+/// Fermat test to base 2; on failure the fractional part of `length` is replaced
+fn fermat_test(n: &BigUint, length: &mut u32) -> bool {
+    let remainder = BigUint::from(2u8).modpow(&(n - 1u8), n);
+    if remainder == BigUint::from(1u8) {
+        return true;
+    }
+    *length = (*length & LENGTH_MASK) | fractional_length(n, &remainder);
+    false
+}
+
+/// Length of the Cunningham chain starting at `n`, with Fermat tests only as Primecoin
+/// uses for block verification
+fn cunningham_length(n: BigUint, first_kind: bool) -> u32 {
+    let mut length = 0;
+    let mut n = n;
+    if !fermat_test(&n, &mut length) {
+        return length;
+    }
+    loop {
+        length += 1 << FRACTIONAL_BITS;
+        n = if first_kind { &n + &n + 1u8 } else { &n + &n - 1u8 };
+        if !fermat_test(&n, &mut length) {
+            return length;
+        }
+    }
+}
+
+/// Chain lengths of each type from an origin
+fn chain_lengths(origin: &BigUint) -> [u32; 3] {
+    let cunningham1 = cunningham_length(origin - 1u8, true);
+    let cunningham2 = cunningham_length(origin + 1u8, false);
+    // A bi-twin chain may end with a single prime of the longer kind
+    let bi_twin = if whole_length(cunningham1) > whole_length(cunningham2) {
+        cunningham2 + ((whole_length(cunningham2) + 1) << FRACTIONAL_BITS)
+    } else {
+        cunningham1 + (whole_length(cunningham1) << FRACTIONAL_BITS)
+    };
+    [cunningham1, cunningham2, bi_twin]
+}
+
+fn length_of(lengths: [u32; 3], chain_type: ChainType) -> u32 {
+    match chain_type {
+        ChainType::Cunningham1 => lengths[0],
+        ChainType::Cunningham2 => lengths[1],
+        ChainType::BiTwin => lengths[2],
+    }
+}
+
+/// Verifies that `hash * multiplier` originates a chain of the given type reaching the
+/// target length. The hash is in internal byte order (a little-endian integer) and must be
+/// at least 2^255. As in Primecoin, the multiplier must be normalized: when an even
+/// multiplier gives an origin divisible by 4, half the origin must not give a longer chain
+#[rustler::nif(schedule = "DirtyCpu")]
+fn prime_chain_verify(
+    header_hash: Binary,
+    multiplier: u64,
+    chain_type: ChainType,
+    target: TargetLength,
+) -> Guarded<bool> {
+    guard::run(|| {
+        if header_hash.len() != 32 || header_hash.as_slice()[31] < 0x80 || multiplier == 0 {
+            return false;
+        }
+
+        let origin = BigUint::from_bytes_le(header_hash.as_slice()) * multiplier;
+        if origin.bits() > MAX_ORIGIN_BITS {
+            return false;
+        }
+
+        let lengths = chain_lengths(&origin);
+        if length_of(lengths, chain_type) < target.0 {
+            return false;
+        }
+
+        if multiplier % 2 == 1 || &origin % 4u8 != BigUint::ZERO {
+            return true;
+        }
+        let halved = chain_lengths(&(&origin >> 1));
+        let extends = halved.iter().zip(lengths).any(|(halved, length)| *halved > length);
+        !(extends && halved.iter().any(|length| *length >= target.0))
+    })
+}
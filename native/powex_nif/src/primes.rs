@@ -0,0 +1,58 @@
+use num_bigint::{BigUint, RandBigInt};
+
+/// Bases used by the Miller-Rabin test
+const WITNESSES: [u32; 20] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71,
+];
+
+/// Miller-Rabin probable prime test
+pub fn is_probable_prime(n: &BigUint) -> bool {
+    let one = BigUint::from(1u8);
+    let two = BigUint::from(2u8);
+
+    if *n < two {
+        return false;
+    }
+    for &p in WITNESSES.iter() {
+        if *n == BigUint::from(p) {
+            return true;
+        }
+        if (n % p) == BigUint::ZERO {
+            return false;
+        }
+    }
+
+    let n_minus_one = n - &one;
+    let s = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> s;
+
+    'witness: for &a in WITNESSES.iter() {
+        let mut x = BigUint::from(a).modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+
+    true
+}
+
+/// Generates a random probable prime with exactly `bits` bits and the top two bits set,
+/// so that the product of two such primes has exactly `2 * bits` bits
+pub fn random_prime<R: rand::Rng>(rng: &mut R, bits: u64) -> BigUint {
+    loop {
+        let mut candidate = rng.gen_biguint(bits);
+        candidate.set_bit(bits - 1, true);
+        candidate.set_bit(bits - 2, true);
+        candidate.set_bit(0, true);
+        if is_probable_prime(&candidate) {
+            return candidate;
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use rustler::{
+    Atom, Binary, Env, NewBinary, NifMap, NifResult, NifUnitEnum, Resource, ResourceArc,
+};
+use std::time::Instant;
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::audit::{self, Outcome};
+use crate::backend::meets_difficulty_bytes;
+use crate::budget;
+use crate::guard::{self, Guarded};
+use crate::memory_hard::{Hasher, MemoryHardParams};
+use crate::metrics;
+use crate::options::Options;
+use crate::{compute_hash, meets_difficulty, Status};
+
+/// Version byte of the proof encoding
+const PROOF_VERSION: u8 = 1;
+
+/// Size of the fixed proof header: version, algorithm, difficulty and nonce width
+const HEADER_LEN: usize = 4;
+
+/// BLAKE3 key derivation context of proof identities
+const IDENTITY_CONTEXT: &str = "powex 2026-10-15 proof identity";
+
+/// Nonces tried between deadline checks while solving
+const DEADLINE_CHECK_INTERVAL: u64 = 4096;
+
+/// Default memory-hard limits of a policy, matching the Argon2 recommendations
+const DEFAULT_MAX_M_COST: u32 = 64 * 1024;
+const DEFAULT_MAX_T_COST: u32 = 4;
+const DEFAULT_MAX_P_COST: u32 = 1;
+
+/// Splits the first `len` bytes off the front of a slice
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if bytes.len() < len {
+        return None;
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Some(head)
+}
+
+/// Bytes of the narrowest little-endian encoding of a nonce, at least one
+fn minimal_width(nonce: u64) -> u8 {
+    (8 - nonce.leading_zeros() / 8).max(1) as u8
+}
+
+/// Algorithms a self-describing proof can declare
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofAlgorithm {
+    Sha256,
+    Blake3,
+    Argon2id,
+    Poseidon,
+}
+
+impl ProofAlgorithm {
+    fn to_u8(self) -> u8 {
+        match self {
+            ProofAlgorithm::Sha256 => 0,
+            ProofAlgorithm::Blake3 => 1,
+            ProofAlgorithm::Argon2id => 2,
+            ProofAlgorithm::Poseidon => 3,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<ProofAlgorithm> {
+        match value {
+            0 => Some(ProofAlgorithm::Sha256),
+            1 => Some(ProofAlgorithm::Blake3),
+            2 => Some(ProofAlgorithm::Argon2id),
+            3 => Some(ProofAlgorithm::Poseidon),
+            _ => None,
+        }
+    }
+}
+
+/// A proof decoded from its binary form
+///
+/// Layout: `version || algorithm || difficulty || nonce_width || nonce (LE, nonce_width bytes)`,
+/// followed by `m_cost || t_cost || p_cost` (u32 LE each) for Argon2id, then the data.
+pub struct Proof<'a> {
+    pub algorithm: ProofAlgorithm,
+    pub difficulty: u8,
+    pub nonce_width: u8,
+    pub nonce: u64,
+    pub memory: Option<MemoryHardParams>,
+    pub data: &'a [u8],
+}
+
+impl<'a> Proof<'a> {
+    /// A proof with the narrowest nonce width
+    pub fn new(
+        algorithm: ProofAlgorithm,
+        difficulty: u8,
+        nonce: u64,
+        memory: Option<MemoryHardParams>,
+        data: &'a [u8],
+    ) -> Proof<'a> {
+        Proof {
+            algorithm,
+            difficulty,
+            nonce_width: minimal_width(nonce),
+            nonce,
+            memory,
+            data,
+        }
+    }
+
+    /// Parses untrusted bytes: every length is checked before it is read and nothing is
+    /// allocated, so any input either decodes or returns `None`. Only the narrowest nonce
+    /// width is accepted, so each proof has exactly one encoding
+    pub fn decode(mut bytes: &'a [u8]) -> Option<Proof<'a>> {
+        let [version, algorithm, difficulty, nonce_width]: [u8; HEADER_LEN] =
+            take(&mut bytes, HEADER_LEN)?.try_into().ok()?;
+
+        if version != PROOF_VERSION || !(1..=8).contains(&nonce_width) {
+            return None;
+        }
+
+        let algorithm = ProofAlgorithm::from_u8(algorithm)?;
+        let mut nonce = [0u8; 8];
+        nonce[..nonce_width as usize].copy_from_slice(take(&mut bytes, nonce_width as usize)?);
+        let nonce = u64::from_le_bytes(nonce);
+        if nonce_width != minimal_width(nonce) {
+            return None;
+        }
+
+        let memory = match algorithm {
+            ProofAlgorithm::Argon2id => {
+                let mut word = || Some(u32::from_le_bytes(take(&mut bytes, 4)?.try_into().ok()?));
+                Some(MemoryHardParams {
+                    m_cost: word()?,
+                    t_cost: word()?,
+                    p_cost: word()?,
+                })
+            }
+            _ => None,
+        };
+
+        Some(Proof {
+            algorithm,
+            difficulty,
+            nonce_width,
+            nonce,
+            memory,
+            data: bytes,
+        })
+    }
+
+    /// Bytes hashed and memory allocated by `verify`, known before it runs
+    fn cost(&self) -> (u64, u64) {
+        let hashed = self.data.len() as u64 + 8;
+        let memory = self.memory.map_or(0, |params| params.memory_bytes());
+        (hashed, memory)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![
+            PROOF_VERSION,
+            self.algorithm.to_u8(),
+            self.difficulty,
+            self.nonce_width,
+        ];
+        bytes.extend_from_slice(&self.nonce.to_le_bytes()[..self.nonce_width as usize]);
+        if let Some(params) = self.memory {
+            for word in [params.m_cost, params.t_cost, params.p_cost] {
+                bytes.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(self.data);
+        bytes
+    }
+
+    /// Identifies the work whatever its encoding: a hash of the algorithm, the Argon2id
+    /// parameters, the nonce and the data. The declared difficulty follows from them
+    pub fn identity(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new_derive_key(IDENTITY_CONTEXT);
+        hasher.update(&[self.algorithm.to_u8()]);
+        if let Some(params) = self.memory {
+            for word in [params.m_cost, params.t_cost, params.p_cost] {
+                hasher.update(&word.to_le_bytes());
+            }
+        }
+        hasher.update(&self.nonce.to_le_bytes());
+        hasher.update(self.data);
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Hashes the proof with its declared algorithm and checks the exact leading zero count
+    pub fn verify(&self) -> bool {
+        let difficulty = self.difficulty as u32;
+        match (self.algorithm, self.memory) {
+            (ProofAlgorithm::Sha256, _) => {
+                meets_difficulty(&compute_hash(self.data, self.nonce), difficulty)
+            }
+            (ProofAlgorithm::Blake3, _) => meets_difficulty_bytes(
+                &Algorithm::Blake3.prepare(self.data).digest(self.nonce),
+                difficulty,
+            ),
+            (ProofAlgorithm::Poseidon, _) => meets_difficulty_bytes(
+                &Algorithm::Poseidon.prepare(self.data).digest(self.nonce),
+                difficulty,
+            ),
+            (ProofAlgorithm::Argon2id, Some(params)) => {
+                Hasher::new(self.data, params).is_some_and(|mut hasher| {
+                    meets_difficulty_bytes(&hasher.digest(self.nonce), difficulty)
+                })
+            }
+            (ProofAlgorithm::Argon2id, None) => false,
+        }
+    }
+
+    /// Finds the lowest nonce meeting the declared difficulty, or None once the deadline
+    /// has passed
+    pub fn solve(&self, deadline: Option<Instant>) -> Option<u64> {
+        let mut meter = metrics::Meter::new();
+        for nonce in 0..=u64::MAX {
+            meter.tick();
+            if (Proof { nonce, ..*self }).verify() {
+                return Some(nonce);
+            }
+            if nonce.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                && deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+/// Splits an encoded proof into its nonce and the rest of its bytes, which proofs of the same
+/// challenge and parameters have in common
+pub fn split_nonce(bytes: &[u8]) -> Option<(Vec<u8>, u64)> {
+    let proof = Proof::decode(bytes)?;
+    let mut template = bytes[..HEADER_LEN].to_vec();
+    template.extend_from_slice(&bytes[HEADER_LEN + proof.nonce_width as usize..]);
+    Some((template, proof.nonce))
+}
+
+/// Inverse of `split_nonce`: appends the proof to `out`, or returns false if the template
+/// is malformed or the nonce doesn't fit its width
+pub fn join_nonce(template: &[u8], nonce: u64, out: &mut Vec<u8>) -> bool {
+    let Some(&width) = template.get(HEADER_LEN - 1) else {
+        return false;
+    };
+    let width = width as usize;
+    if !(1..=8).contains(&width) || (width < 8 && nonce >> (8 * width) != 0) {
+        return false;
+    }
+
+    let start = out.len();
+    out.extend_from_slice(&template[..HEADER_LEN]);
+    out.extend_from_slice(&nonce.to_le_bytes()[..width]);
+    out.extend_from_slice(&template[HEADER_LEN..]);
+    Proof::decode(&out[start..]).is_some()
+}
+
+/// Parameters a server is willing to verify, checked before any hashing
+pub struct Policy {
+    algorithms: Vec<ProofAlgorithm>,
+    min_difficulty: u32,
+    max_difficulty: u32,
+    max_nonce_width: u8,
+    max_m_cost: u32,
+    max_t_cost: u32,
+    max_p_cost: u32,
+}
+
+#[rustler::resource_impl]
+impl Resource for Policy {}
+
+impl Policy {
+    /// Returns the reason a proof is not allowed, if any
+    fn violation(&self, proof: &Proof) -> Option<Atom> {
+        if !self.algorithms.contains(&proof.algorithm) {
+            return Some(atoms::algorithm_not_allowed());
+        }
+
+        if !(self.min_difficulty..=self.max_difficulty).contains(&(proof.difficulty as u32)) {
+            return Some(atoms::difficulty_out_of_range());
+        }
+
+        if proof.nonce_width > self.max_nonce_width {
+            return Some(atoms::nonce_too_wide());
+        }
+
+        match proof.memory {
+            Some(params)
+                if params.m_cost > self.max_m_cost
+                    || params.t_cost > self.max_t_cost
+                    || params.p_cost > self.max_p_cost =>
+            {
+                Some(atoms::memory_params_not_allowed())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Creates a verification policy; SHA-256 and BLAKE3 are allowed by default, the slower
+/// Poseidon and Argon2id must be opted in
+#[rustler::nif]
+fn policy_new(opts: Options) -> NifResult<Guarded<ResourceArc<Policy>>> {
+    guard::run_result(|| {
+        Ok(ResourceArc::new(Policy {
+            algorithms: opts.get_or(
+                atoms::algorithms(),
+                vec![ProofAlgorithm::Sha256, ProofAlgorithm::Blake3],
+            )?,
+            min_difficulty: opts.get_or(atoms::min_difficulty(), 0)?,
+            max_difficulty: opts.get_or(atoms::max_difficulty(), 64)?,
+            max_nonce_width: opts
+                .get::<u8>(atoms::max_nonce_width())?
+                .unwrap_or(8)
+                .min(8),
+            max_m_cost: opts.get_or(atoms::max_m_cost(), DEFAULT_MAX_M_COST)?,
+            max_t_cost: opts.get_or(atoms::max_t_cost(), DEFAULT_MAX_T_COST)?,
+            max_p_cost: opts.get_or(atoms::max_p_cost(), DEFAULT_MAX_P_COST)?,
+        }))
+    })
+}
+
+/// Encodes a proof in the self-describing binary format, using the narrowest nonce width
+#[rustler::nif]
+fn encode_proof<'a>(
+    env: Env<'a>,
+    data: Binary,
+    nonce: u64,
+    difficulty: u8,
+    opts: Options,
+) -> NifResult<Guarded<Binary<'a>>> {
+    guard::run_result(|| {
+        let algorithm = opts.get_or(atoms::algorithm(), ProofAlgorithm::Sha256)?;
+        let memory = match algorithm {
+            ProofAlgorithm::Argon2id => Some(MemoryHardParams::from_options(&opts)?),
+            _ => None,
+        };
+
+        let bytes = Proof::new(algorithm, difficulty, nonce, memory, data.as_slice()).encode();
+        let mut binary = NewBinary::new(env, bytes.len());
+        binary.as_mut_slice().copy_from_slice(&bytes);
+        Ok(binary.into())
+    })
+}
+
+#[derive(NifMap)]
+struct VerifiedProof<'a> {
+    algorithm: ProofAlgorithm,
+    difficulty: u8,
+    nonce: u64,
+    data: Binary<'a>,
+}
+
+/// Argon2id parameters a proof declares
+#[derive(NifMap)]
+pub struct DeclaredParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl From<MemoryHardParams> for DeclaredParams {
+    fn from(params: MemoryHardParams) -> DeclaredParams {
+        DeclaredParams {
+            m_cost: params.m_cost,
+            t_cost: params.t_cost,
+            p_cost: params.p_cost,
+        }
+    }
+}
+
+#[derive(NifMap)]
+struct DecodedProof<'a> {
+    algorithm: ProofAlgorithm,
+    difficulty: u8,
+    nonce_width: u8,
+    nonce: u64,
+    memory: Option<DeclaredParams>,
+    data: Binary<'a>,
+}
+
+/// Decodes a proof without verifying it. Nothing is hashed, logged or cached, and the data
+/// is returned as a sub-binary of the input, so untrusted bytes can be passed straight in
+#[rustler::nif]
+fn decode_proof<'a>(proof: Binary<'a>) -> Guarded<Result<DecodedProof<'a>, Atom>> {
+    guard::run(|| {
+        let decoded = Proof::decode(proof.as_slice()).ok_or(atoms::malformed())?;
+        let offset = proof.len() - decoded.data.len();
+
+        Ok(DecodedProof {
+            algorithm: decoded.algorithm,
+            difficulty: decoded.difficulty,
+            nonce_width: decoded.nonce_width,
+            nonce: decoded.nonce,
+            memory: decoded.memory.map(DeclaredParams::from),
+            data: proof
+                .make_subbinary(offset, decoded.data.len())
+                .map_err(|_| atoms::malformed())?,
+        })
+    })
+}
+
+/// Decodes a proof, enforces the policy and the global verification budget on its declared
+/// parameters, then verifies it, recording the outcome in the audit log
+pub fn check<'a>(bytes: &'a [u8], policy: &Policy) -> Result<Proof<'a>, Atom> {
+    let record = |difficulty: u8, outcome| audit::record(&[bytes], difficulty as u32, outcome);
+
+    let Some(decoded) = Proof::decode(bytes) else {
+        record(0, Outcome::Rejected);
+        return Err(atoms::malformed());
+    };
+
+    if let Some(reason) = policy.violation(&decoded) {
+        record(decoded.difficulty, Outcome::Rejected);
+        return Err(reason);
+    }
+
+    let (hashed, memory) = decoded.cost();
+    if let Err(reason) = budget::check(hashed, memory) {
+        record(decoded.difficulty, Outcome::Rejected);
+        return Err(reason);
+    }
+
+    if !decoded.verify() {
+        record(decoded.difficulty, Outcome::Invalid);
+        return Err(atoms::invalid());
+    }
+    record(decoded.difficulty, Outcome::Valid);
+    Ok(decoded)
+}
+
+/// Checks an encoded proof like `decode_and_verify`, for callers outside a NIF call
+pub fn verify_encoded(bytes: &[u8], policy: &Policy) -> Status {
+    match check(bytes, policy) {
+        Ok(_) => Status::Ok,
+        Err(reason) => Status::Error(reason),
+    }
+}
+
+/// Decodes a proof, enforces the policy and the global verification budget on its declared
+/// parameters, then verifies it
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decode_and_verify<'a>(
+    proof: Binary<'a>,
+    policy: ResourceArc<Policy>,
+) -> Guarded<Result<VerifiedProof<'a>, Atom>> {
+    guard::run(|| {
+        let decoded = check(proof.as_slice(), &policy)?;
+        let offset = proof.len() - decoded.data.len();
+        Ok(VerifiedProof {
+            algorithm: decoded.algorithm,
+            difficulty: decoded.difficulty,
+            nonce: decoded.nonce,
+            data: proof
+                .make_subbinary(offset, decoded.data.len())
+                .map_err(|_| atoms::malformed())?,
+        })
+    })
+}
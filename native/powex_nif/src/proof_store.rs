@@ -0,0 +1,110 @@
+use rustler::{Atom, Binary};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::Mutex;
+
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::{atoms, Status};
+
+/// Each record is the BLAKE3 digest of a consumed proof
+const RECORD_SIZE: usize = 32;
+
+/// Consumed proofs, backed by an append-only file of fixed-size records
+struct ProofStore {
+    file: File,
+    consumed: HashSet<[u8; RECORD_SIZE]>,
+}
+
+impl ProofStore {
+    /// Loads every complete record; a partial record left by a crash mid-append is cut off
+    fn open(path: &str) -> std::io::Result<ProofStore> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let complete = bytes.len() - bytes.len() % RECORD_SIZE;
+        if complete < bytes.len() {
+            file.set_len(complete as u64)?;
+            file.seek(SeekFrom::End(0))?;
+        }
+
+        let consumed = bytes[..complete]
+            .chunks_exact(RECORD_SIZE)
+            .map(|record| record.try_into().unwrap_or([0; RECORD_SIZE]))
+            .collect();
+        Ok(ProofStore { file, consumed })
+    }
+
+    /// Records a proof unless it was already consumed; the record is synced to disk before
+    /// the proof counts as consumed
+    fn consume(&mut self, key: [u8; RECORD_SIZE]) -> Result<(), Atom> {
+        if self.consumed.contains(&key) {
+            return Err(atoms::already_consumed());
+        }
+        let written = self.file.write_all(&key).and_then(|_| self.file.sync_data());
+        if written.is_err() {
+            // Drop any partial record so later appends stay aligned
+            let _ = self.file.set_len((self.consumed.len() * RECORD_SIZE) as u64);
+            return Err(atoms::io_error());
+        }
+        self.consumed.insert(key);
+        Ok(())
+    }
+}
+
+static STORE: Mutex<Option<ProofStore>> = Mutex::new(None);
+
+fn key(proof: &[u8]) -> [u8; RECORD_SIZE] {
+    *blake3::hash(proof).as_bytes()
+}
+
+/// Opens (or creates) the file backing `consume_proof`, replacing any store opened before
+#[rustler::nif(schedule = "DirtyIo")]
+fn proof_store_open(path: String) -> GuardedResult<usize> {
+    guard::run(|| {
+        let store = ProofStore::open(&path)
+            .map_err(|_| (atoms::error(), "Cannot open the proof store file"))?;
+        let count = store.consumed.len();
+        *STORE.lock().unwrap() = Some(store);
+        Ok(count)
+    })
+}
+
+/// Consumes a proof like `consume_proof`, for callers outside a NIF call
+pub fn consume(proof: &[u8]) -> Status {
+    consume_key(key(proof))
+}
+
+/// Consumes a record already derived from the proof, such as `Proof::identity`
+pub fn consume_key(key: [u8; RECORD_SIZE]) -> Status {
+    let mut store = STORE.lock().unwrap();
+    let Some(store) = store.as_mut() else {
+        return Status::Error(atoms::no_store());
+    };
+    match store.consume(key) {
+        Ok(()) => Status::Ok,
+        Err(reason) => Status::Error(reason),
+    }
+}
+
+/// Atomically checks that a proof was never consumed and records it durably
+#[rustler::nif(schedule = "DirtyIo")]
+fn consume_proof(proof: Binary) -> Guarded<Status> {
+    guard::run(|| consume(proof.as_slice()))
+}
+
+/// Whether a proof was consumed; false when no store is open
+#[rustler::nif(name = "proof_consumed?")]
+fn proof_consumed(proof: Binary) -> Guarded<bool> {
+    guard::run(|| {
+        let store = STORE.lock().unwrap();
+        store
+            .as_ref()
+            .is_some_and(|store| store.consumed.contains(&key(proof.as_slice())))
+    })
+}
@@ -0,0 +1,275 @@
+//! Signed proof logs, verified as a stream in constant memory.
+//!
+//! A log is a concatenation of entries `length (u32 LE) || proof || tag`, where the proof is
+//! in the self-describing format of `proof.rs` and the tag is a keyed BLAKE3 hash of it, a
+//! MAC: signing and verifying share the key. A background worker reads one entry at a time
+//! into a reused buffer and sends results in batches, each only once the consumer asks for
+//! it, so neither the NIF nor the consumer's mailbox grows with the size of the log.
+
+use rustler::env::OwnedEnv;
+use rustler::{Atom, Binary, Env, LocalPid, NewBinary, NifResult, Resource, ResourceArc};
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read};
+use std::sync::{Arc, Condvar, Mutex};
+use zeroize::Zeroizing;
+
+use crate::atoms;
+use crate::budget;
+use crate::config;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::jobs::Job;
+use crate::options::Options;
+use crate::proof::{self, Policy};
+use crate::Status;
+
+/// Size of an entry's tag
+const TAG_LEN: usize = 32;
+
+/// BLAKE3 key derivation context, so log tags never collide with other uses of the key
+const KEY_CONTEXT: &str = "powex 2026-10-15 signed proof log entries";
+
+/// Entries per batch by default
+const DEFAULT_BATCH: usize = 1_000;
+
+/// Most entries per batch
+const MAX_BATCH: usize = 100_000;
+
+fn derive_key(secret: &[u8]) -> Zeroizing<[u8; 32]> {
+    Zeroizing::new(blake3::derive_key(KEY_CONTEXT, secret))
+}
+
+/// Demand for batches, shared between a stream handle and its worker
+#[derive(Default)]
+struct Demand {
+    batches: u64,
+    closed: bool,
+}
+
+struct Shared {
+    demand: Mutex<Demand>,
+    changed: Condvar,
+}
+
+impl Shared {
+    /// Blocks until a batch is requested and takes it; false once the stream is closed
+    fn next_batch(&self) -> bool {
+        let mut demand = self
+            .changed
+            .wait_while(self.demand.lock().unwrap(), |demand| {
+                demand.batches == 0 && !demand.closed
+            })
+            .unwrap();
+        if demand.closed {
+            return false;
+        }
+        demand.batches -= 1;
+        true
+    }
+
+    fn close(&self) {
+        self.demand.lock().unwrap().closed = true;
+        self.changed.notify_all();
+    }
+}
+
+/// Handle of a stream verification
+pub struct ProofStream {
+    shared: Arc<Shared>,
+}
+
+#[rustler::resource_impl]
+impl Resource for ProofStream {}
+
+impl Drop for ProofStream {
+    /// The worker would otherwise wait for demand forever
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+/// Fills `buf` completely; `Ok(false)` on a clean end of the log before the first byte
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(true)
+}
+
+/// Reads and checks the entry at the reader's position. Returns `None` at the end of the
+/// log, otherwise the entry's size and result; a truncated or unreadable entry ends the log
+fn next_entry(
+    reader: &mut BufReader<File>,
+    entry: &mut Vec<u8>,
+    key: &[u8; 32],
+    policy: &Policy,
+) -> Option<(u64, Status, bool)> {
+    let failed = |reason: io::Error| {
+        let reason = match reason.kind() {
+            ErrorKind::UnexpectedEof => atoms::truncated(),
+            _ => atoms::io_error(),
+        };
+        Some((0, Status::Error(reason), true))
+    };
+
+    let mut length = [0u8; 4];
+    match read_exact_or_eof(reader, &mut length) {
+        Ok(true) => {}
+        Ok(false) => return None,
+        Err(error) => return failed(error),
+    }
+    let length = u32::from_le_bytes(length) as u64;
+    let size = 4 + length + TAG_LEN as u64;
+
+    // Skipped unread, so the buffer stays within the verification budget
+    if length + 8 > budget::max_verify_bytes() {
+        return match reader.seek_relative((length + TAG_LEN as u64) as i64) {
+            Ok(()) => Some((size, Status::Error(atoms::over_budget()), false)),
+            Err(error) => failed(error),
+        };
+    }
+
+    entry.resize(length as usize + TAG_LEN, 0);
+    if let Err(error) = reader.read_exact(entry) {
+        return failed(error);
+    }
+
+    let (proof, tag) = entry.split_at(length as usize);
+    let expected = blake3::keyed_hash(key, proof);
+    // blake3::Hash compares in constant time
+    if expected != blake3::Hash::from_bytes(tag.try_into().unwrap_or([0; TAG_LEN])) {
+        return Some((size, Status::Error(atoms::bad_signature()), false));
+    }
+
+    Some((size, proof::verify_encoded(proof, policy), false))
+}
+
+/// Verifies entries batch by batch on demand, sending each batch to `pid` tagged with `job`.
+/// The worker holds the demand but not the stream handle, so it stops once the consumer
+/// closes or drops the handle
+fn run(
+    mut reader: BufReader<File>,
+    key: Zeroizing<[u8; 32]>,
+    policy: ResourceArc<Policy>,
+    batch: usize,
+    shared: Arc<Shared>,
+    pid: LocalPid,
+    job: ResourceArc<Job>,
+) {
+    let mut env = OwnedEnv::new();
+    let mut entry = Vec::new();
+    let (mut index, mut offset) = (0u64, 0u64);
+    let mut done = false;
+
+    while !done && shared.next_batch() {
+        let mut results = Vec::with_capacity(batch);
+        while results.len() < batch {
+            let Some((size, status, last)) = next_entry(&mut reader, &mut entry, &key, &policy)
+            else {
+                done = true;
+                break;
+            };
+            results.push((index, offset, status));
+            index += 1;
+            offset += size;
+            if last {
+                done = true;
+                break;
+            }
+        }
+
+        let _ = env.send_and_clear(&pid, |_| {
+            (atoms::powex_proof_stream(), &job, (atoms::entries(), &results))
+        });
+    }
+
+    if done {
+        let _ = env.send_and_clear(&pid, |_| (atoms::powex_proof_stream(), &job, atoms::done()));
+    }
+}
+
+/// Signs a proof for a log: returns the entry `length || proof || tag`
+#[rustler::nif]
+fn sign_proof<'a>(env: Env<'a>, proof: Binary, key: Binary) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        if key.is_empty() {
+            return Err((atoms::error(), "Missing or empty key"));
+        }
+        let length = u32::try_from(proof.len())
+            .map_err(|_| (atoms::error(), "Proof too large for a log entry"))?;
+
+        let tag = blake3::keyed_hash(&derive_key(key.as_slice()), proof.as_slice());
+        let mut entry = NewBinary::new(env, 4 + proof.len() + TAG_LEN);
+        let bytes = entry.as_mut_slice();
+        bytes[..4].copy_from_slice(&length.to_le_bytes());
+        bytes[4..4 + proof.len()].copy_from_slice(proof.as_slice());
+        bytes[4 + proof.len()..].copy_from_slice(tag.as_bytes());
+        Ok(entry.into())
+    })
+}
+
+/// Opens a signed proof log for verification under `policy` with the `:key` it was signed
+/// with. Results arrive as `{:powex_proof_stream, job, {:entries, [{index, offset, status}]}}`,
+/// one batch of up to `:batch` entries per `proof_stream_next` call, and
+/// `{:powex_proof_stream, job, :done}` follows the last one
+#[rustler::nif]
+fn proof_stream_open(
+    env: Env,
+    path: String,
+    policy: ResourceArc<Policy>,
+    opts: Options,
+) -> NifResult<GuardedResult<(ResourceArc<ProofStream>, ResourceArc<Job>)>> {
+    guard::run_result(|| {
+        let key = opts.get::<Binary>(atoms::key())?;
+        let batch = opts.get_or(atoms::batch(), DEFAULT_BATCH)?;
+
+        let key = match key {
+            Some(key) if !key.is_empty() => derive_key(key.as_slice()),
+            _ => return Ok(Err((atoms::error(), "Missing or empty :key"))),
+        };
+        if !(1..=MAX_BATCH).contains(&batch) {
+            return Ok(Err((atoms::error(), "Invalid batch size (1-100000)")));
+        }
+        let Ok(file) = File::open(path) else {
+            return Ok(Err((atoms::error(), "Cannot open the proof log")));
+        };
+
+        let shared = Arc::new(Shared {
+            demand: Mutex::default(),
+            changed: Condvar::new(),
+        });
+        let job = ResourceArc::new(Job);
+        let (worker_shared, worker_job, pid) = (Arc::clone(&shared), job.clone(), env.pid());
+        config::spawn_worker(move || {
+            let reader = BufReader::new(file);
+            run(reader, key, policy, batch, worker_shared, pid, worker_job)
+        });
+
+        Ok(Ok((ResourceArc::new(ProofStream { shared }), job)))
+    })
+}
+
+/// Asks for the next batch of results
+#[rustler::nif]
+fn proof_stream_next(stream: ResourceArc<ProofStream>) -> Guarded<Atom> {
+    guard::run(|| {
+        stream.shared.demand.lock().unwrap().batches += 1;
+        stream.shared.changed.notify_all();
+        atoms::ok()
+    })
+}
+
+/// Stops the verification; batches already sent stay in the mailbox
+#[rustler::nif]
+fn proof_stream_close(stream: ResourceArc<ProofStream>) -> Guarded<Atom> {
+    guard::run(|| {
+        stream.shared.close();
+        atoms::ok()
+    })
+}
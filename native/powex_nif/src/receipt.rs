@@ -0,0 +1,113 @@
+//! Work receipts: verified work converted into signed, single-use credit redeemable later.
+//!
+//! Layout: `version || value (u64 LE) || issued_at (u64 LE) || work (16 bytes) || mac
+//! (32 bytes)`, where `work` is the start of the proof's `identity`, telling receipts for
+//! different work apart, and the MAC is a keyed BLAKE3 hash of everything before it.
+//! Both the work and the receipt are spent in the store of `proof_store.rs`, so work buys
+//! one receipt however its proof is encoded, and a receipt redeems once, across restarts.
+
+use rustler::{Atom, Binary, Env, NewBinary, NifMap, NifResult, ResourceArc};
+use zeroize::Zeroizing;
+
+use crate::atoms;
+use crate::clock;
+use crate::guard::{self, Guarded};
+use crate::options::Options;
+use crate::proof::{self, Policy};
+use crate::proof_store;
+use crate::Status;
+
+/// Version byte of the receipt encoding
+const RECEIPT_VERSION: u8 = 1;
+
+/// Size of the proof identity prefix identifying the work
+const WORK_LEN: usize = 16;
+
+/// Size of the MAC
+const MAC_LEN: usize = 32;
+
+/// Bytes covered by the MAC
+const BODY_LEN: usize = 1 + 8 + 8 + WORK_LEN;
+
+/// Size of a receipt
+const RECEIPT_LEN: usize = BODY_LEN + MAC_LEN;
+
+/// BLAKE3 key derivation context, so receipt MACs never collide with other uses of the secret
+const KEY_CONTEXT: &str = "powex 2026-10-15 work receipt MAC";
+
+fn compute_mac(secret: &[u8], body: &[u8]) -> blake3::Hash {
+    let key = Zeroizing::new(blake3::derive_key(KEY_CONTEXT, secret));
+    blake3::keyed_hash(&key, body)
+}
+
+#[derive(NifMap)]
+struct Redeemed {
+    value: u64,
+    issued_at: u64,
+}
+
+/// Verifies a proof under the policy, spends it, and returns a receipt worth `value`
+#[rustler::nif(schedule = "DirtyCpu")]
+fn receipt_issue<'a>(
+    env: Env<'a>,
+    proof: Binary,
+    secret: Binary,
+    value: u64,
+    policy: ResourceArc<Policy>,
+) -> Guarded<Result<Binary<'a>, Atom>> {
+    guard::run(|| {
+        if secret.is_empty() {
+            return Err(atoms::malformed());
+        }
+        let identity = proof::check(&proof, &policy)?.identity();
+        if let Status::Error(reason) = proof_store::consume_key(identity) {
+            return Err(reason);
+        }
+
+        let mut receipt = NewBinary::new(env, RECEIPT_LEN);
+        let bytes = receipt.as_mut_slice();
+        bytes[0] = RECEIPT_VERSION;
+        bytes[1..9].copy_from_slice(&value.to_le_bytes());
+        bytes[9..17].copy_from_slice(&clock::now().to_le_bytes());
+        bytes[17..BODY_LEN].copy_from_slice(&identity[..WORK_LEN]);
+        let mac = compute_mac(&secret, &bytes[..BODY_LEN]);
+        bytes[BODY_LEN..].copy_from_slice(mac.as_bytes());
+        Ok(receipt.into())
+    })
+}
+
+/// Checks a receipt's MAC and, with `:max_age`, its age in seconds, then spends it.
+/// Returns its value and issue time
+#[rustler::nif(schedule = "DirtyIo")]
+fn redeem_receipt(
+    receipt: Binary,
+    secret: Binary,
+    opts: Options,
+) -> NifResult<Guarded<Result<Redeemed, Atom>>> {
+    guard::run_result(|| {
+        let max_age: Option<u64> = opts.get(atoms::max_age())?;
+
+        if receipt.len() != RECEIPT_LEN || receipt[0] != RECEIPT_VERSION {
+            return Ok(Err(atoms::malformed()));
+        }
+        let Ok(mac) = <[u8; MAC_LEN]>::try_from(&receipt[BODY_LEN..]) else {
+            return Ok(Err(atoms::malformed()));
+        };
+        // Compared as `blake3::Hash`, in constant time
+        if secret.is_empty() || compute_mac(&secret, &receipt[..BODY_LEN]) != mac {
+            return Ok(Err(atoms::bad_signature()));
+        }
+
+        let word = |range: std::ops::Range<usize>| {
+            u64::from_le_bytes(receipt[range].try_into().unwrap_or([0; 8]))
+        };
+        let (value, issued_at) = (word(1..9), word(9..17));
+        if max_age.is_some_and(|max_age| clock::now().saturating_sub(issued_at) > max_age) {
+            return Ok(Err(atoms::too_old()));
+        }
+        if let Status::Error(reason) = proof_store::consume(&receipt) {
+            return Ok(Err(reason));
+        }
+        Ok(Ok(Redeemed { value, issued_at }))
+    })
+}
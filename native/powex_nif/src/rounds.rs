@@ -0,0 +1,76 @@
+use rustler::{Binary, NifResult};
+use sha2::{Digest, Sha256};
+
+use crate::backend;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::input;
+use crate::options::Options;
+use crate::{atoms, search, search_parallel};
+
+/// Data hashed by the round after `data` was solved with `nonce`: `data || sha256(data || nonce)`
+fn next_round(data: &[u8], nonce: u64) -> Vec<u8> {
+    let hash = Sha256::new()
+        .chain_update(data)
+        .chain_update(nonce.to_le_bytes())
+        .finalize();
+
+    let mut next = data.to_vec();
+    next.extend_from_slice(&hash);
+    next
+}
+
+/// Solves `rounds` chained proofs, each round's data extended with the previous round's hash
+#[rustler::nif(schedule = "DirtyCpu")]
+fn compute_rounds(
+    data: Binary,
+    difficulty: Difficulty,
+    rounds: u32,
+    opts: Options,
+) -> NifResult<GuardedResult<Vec<u64>>> {
+    guard::run_result(|| {
+        input::check_size(data.as_slice())?;
+        let threads: u32 = opts.get_or(atoms::threads(), 1)?;
+        let mut round_data = data.as_slice().to_vec();
+        let mut nonces = Vec::with_capacity(rounds as usize);
+
+        for _ in 0..rounds {
+            let found = match threads {
+                1 => search(&round_data, &difficulty),
+                _ => search_parallel(round_data.clone(), difficulty, threads, 0..u64::MAX, &[])
+                    .map(|run| run.nonce)
+                    .map_err(|error| error.reason()),
+            };
+
+            match found {
+                Ok(nonce) => {
+                    round_data = next_round(&round_data, nonce);
+                    nonces.push(nonce);
+                }
+                Err(reason) => return Ok(Err(reason)),
+            }
+        }
+
+        Ok(Ok(nonces))
+    })
+}
+
+/// Validates a chain of exactly `rounds` proofs produced by `compute_rounds`
+#[rustler::nif(name = "valid_rounds?")]
+fn valid_rounds(data: Binary, nonces: Vec<u64>, difficulty: Difficulty, rounds: u32) -> Guarded<bool> {
+    guard::run(|| {
+        if nonces.len() != rounds as usize {
+            return false;
+        }
+
+        let mut round_data = data.as_slice().to_vec();
+        for nonce in nonces {
+            if !backend::prepare(&round_data).meets(nonce, &difficulty) {
+                return false;
+            }
+            round_data = next_round(&round_data, nonce);
+        }
+
+        true
+    })
+}
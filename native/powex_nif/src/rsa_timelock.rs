@@ -0,0 +1,149 @@
+use num_bigint::{BigUint, RandBigInt};
+use rustler::{Binary, Env, LocalPid, NewBinary, NifMap, OwnedBinary};
+use sha2::{Digest, Sha256};
+
+use crate::atoms;
+use crate::guard::{self, GuardedResult};
+use crate::primes::random_prime;
+
+/// Number of progress messages sent over a full solve
+const PROGRESS_STEPS: u64 = 100;
+
+/// A Rivest-Shamir-Wagner time-lock puzzle
+#[derive(NifMap)]
+struct Puzzle<'a> {
+    modulus: Binary<'a>,
+    base: Binary<'a>,
+    squarings: u64,
+    ciphertext: Binary<'a>,
+    tag: Binary<'a>,
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.into()
+}
+
+/// Derives the symmetric key from the puzzle solution b = a^(2^t) mod n
+fn derive_key(solution: &BigUint) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(b"powex-timelock-key")
+        .chain_update(solution.to_bytes_be())
+        .finalize()
+        .into()
+}
+
+/// XORs the input with a SHA-256 counter-mode keystream
+fn apply_keystream(key: &[u8; 32], input: &[u8]) -> Vec<u8> {
+    input
+        .chunks(32)
+        .enumerate()
+        .flat_map(|(counter, chunk)| {
+            let block = Sha256::new()
+                .chain_update(key)
+                .chain_update((counter as u64).to_be_bytes())
+                .finalize();
+            chunk
+                .iter()
+                .zip(block)
+                .map(|(byte, pad)| byte ^ pad)
+                .collect::<Vec<u8>>()
+        })
+        .collect()
+}
+
+fn compute_tag(key: &[u8; 32], ciphertext: &[u8]) -> [u8; 16] {
+    let digest = Sha256::new()
+        .chain_update(b"powex-timelock-tag")
+        .chain_update(key)
+        .chain_update(ciphertext)
+        .finalize();
+    digest[..16].try_into().unwrap()
+}
+
+/// Seals a secret behind `squarings` sequential modular squarings.
+/// The creator uses the factorization of n to shortcut the exponent 2^t mod phi(n).
+#[rustler::nif(schedule = "DirtyCpu")]
+fn timelock_encrypt<'a>(
+    env: Env<'a>,
+    secret: Binary,
+    squarings: u64,
+    modulus_bits: u64,
+) -> GuardedResult<Puzzle<'a>> {
+    guard::run(|| {
+        if squarings == 0 {
+            return Err((atoms::error(), "Squarings must be positive"));
+        }
+
+        if !(512..=4096).contains(&modulus_bits) || modulus_bits & 1 == 1 {
+            return Err((atoms::error(), "Invalid modulus size (even, 512-4096 bits)"));
+        }
+
+        let mut rng = rand::thread_rng();
+        let p = random_prime(&mut rng, modulus_bits / 2);
+        let q = loop {
+            let q = random_prime(&mut rng, modulus_bits / 2);
+            if q != p {
+                break q;
+            }
+        };
+
+        let n = &p * &q;
+        let phi = (&p - 1u8) * (&q - 1u8);
+        let base = rng.gen_biguint_range(&BigUint::from(2u8), &(&n - 1u8));
+        let exponent = BigUint::from(2u8).modpow(&BigUint::from(squarings), &phi);
+        let key = derive_key(&base.modpow(&exponent, &n));
+
+        let ciphertext = apply_keystream(&key, secret.as_slice());
+        let tag = compute_tag(&key, &ciphertext);
+
+        Ok(Puzzle {
+            modulus: to_binary(env, &n.to_bytes_be()),
+            base: to_binary(env, &base.to_bytes_be()),
+            squarings,
+            ciphertext: to_binary(env, &ciphertext),
+            tag: to_binary(env, &tag),
+        })
+    })
+}
+
+/// Solves a time-lock puzzle by performing the squarings sequentially,
+/// optionally reporting `{:powex_timelock_progress, done, total}` to a pid
+#[rustler::nif(schedule = "DirtyCpu")]
+fn timelock_solve<'a>(
+    env: Env<'a>,
+    puzzle: Puzzle,
+    progress: Option<LocalPid>,
+) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        let n = BigUint::from_bytes_be(puzzle.modulus.as_slice());
+        if n < BigUint::from(3u8) {
+            return Err((atoms::error(), "Invalid puzzle modulus"));
+        }
+
+        let interval = (puzzle.squarings / PROGRESS_STEPS).max(1);
+        let mut value = BigUint::from_bytes_be(puzzle.base.as_slice()) % &n;
+
+        for done in 1..=puzzle.squarings {
+            value = (&value * &value) % &n;
+
+            if let Some(pid) = progress.as_ref() {
+                if done % interval == 0 || done == puzzle.squarings {
+                    let message = (atoms::powex_timelock_progress(), done, puzzle.squarings);
+                    let _ = env.send(pid, message);
+                }
+            }
+        }
+
+        let key = derive_key(&value);
+        if compute_tag(&key, puzzle.ciphertext.as_slice()) != puzzle.tag.as_slice() {
+            return Err((atoms::error(), "Puzzle integrity check failed"));
+        }
+
+        let plaintext = apply_keystream(&key, puzzle.ciphertext.as_slice());
+        let mut secret = OwnedBinary::new(plaintext.len()).unwrap();
+        secret.as_mut_slice().copy_from_slice(&plaintext);
+        Ok(secret.release(env))
+    })
+}
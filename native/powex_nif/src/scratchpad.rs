@@ -0,0 +1,137 @@
+//! Argon2 memory backed by huge pages where the OS provides them. Argon2 walks its memory
+//! pseudorandomly, so with 4 KiB pages nearly every block access misses the TLB; 2 MiB
+//! pages cover the same memory with 512 times fewer entries.
+
+use argon2::Block;
+use rustler::NifUnitEnum;
+
+/// Size and alignment of an x86-64 / AArch64 huge page
+#[cfg(target_os = "linux")]
+const HUGE_PAGE: usize = 2 * 1024 * 1024;
+
+/// How a scratchpad's memory is backed
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HugePages {
+    /// Reserved huge pages (`MAP_HUGETLB`), guaranteed for the whole scratchpad
+    Explicit,
+    /// Ordinary pages the kernel was advised to back with transparent huge pages
+    Transparent,
+    /// Ordinary pages only
+    None,
+}
+
+/// Memory for Argon2 blocks, from the heap or mapped for huge pages
+pub enum Memory {
+    Heap(Vec<Block>),
+    #[cfg(target_os = "linux")]
+    Mapped(Mapping),
+}
+
+impl Memory {
+    /// `blocks` zeroed blocks on the heap
+    pub fn heap(blocks: usize) -> Memory {
+        Memory::Heap(vec![Block::default(); blocks])
+    }
+
+    /// `blocks` zeroed blocks on huge pages where possible, falling back to the heap
+    pub fn huge(blocks: usize) -> (Memory, HugePages) {
+        #[cfg(target_os = "linux")]
+        if let Some((mapping, huge_pages)) = Mapping::new(blocks) {
+            return (Memory::Mapped(mapping), huge_pages);
+        }
+        (Memory::heap(blocks), HugePages::None)
+    }
+}
+
+impl AsMut<[Block]> for Memory {
+    fn as_mut(&mut self) -> &mut [Block] {
+        match self {
+            Memory::Heap(blocks) => blocks,
+            #[cfg(target_os = "linux")]
+            Memory::Mapped(mapping) => mapping.as_mut(),
+        }
+    }
+}
+
+/// An anonymous mapping aligned to a huge page, unmapped on drop
+#[cfg(target_os = "linux")]
+pub struct Mapping {
+    ptr: std::ptr::NonNull<Block>,
+    blocks: usize,
+    len: usize,
+}
+
+// The mapping is owned exclusively, like the `Vec` it replaces
+#[cfg(target_os = "linux")]
+unsafe impl Send for Mapping {}
+
+#[cfg(target_os = "linux")]
+impl Mapping {
+    fn new(blocks: usize) -> Option<(Mapping, HugePages)> {
+        let len = (blocks * std::mem::size_of::<Block>()).div_ceil(HUGE_PAGE) * HUGE_PAGE;
+        if len == 0 {
+            return None;
+        }
+
+        if let Some(ptr) = map(len, libc::MAP_HUGETLB) {
+            return Some((Mapping { ptr, blocks, len }, HugePages::Explicit));
+        }
+
+        // Over-map by one huge page and trim both ends, so the kernel can back every
+        // 2 MiB of the scratchpad with a transparent huge page
+        let ptr = map(len + HUGE_PAGE, 0)?;
+        let start = ptr.as_ptr() as usize;
+        let aligned = start.next_multiple_of(HUGE_PAGE);
+        unsafe {
+            if aligned > start {
+                libc::munmap(start as *mut libc::c_void, aligned - start);
+            }
+            let tail = start + len + HUGE_PAGE - (aligned + len);
+            if tail > 0 {
+                libc::munmap((aligned + len) as *mut libc::c_void, tail);
+            }
+        }
+
+        let advised =
+            unsafe { libc::madvise(aligned as *mut libc::c_void, len, libc::MADV_HUGEPAGE) } == 0;
+        let ptr = std::ptr::NonNull::new(aligned as *mut Block)?;
+        let huge_pages = if advised { HugePages::Transparent } else { HugePages::None };
+        Some((Mapping { ptr, blocks, len }, huge_pages))
+    }
+}
+
+/// Maps `len` bytes of zeroed, private memory
+#[cfg(target_os = "linux")]
+fn map(len: usize, flags: libc::c_int) -> Option<std::ptr::NonNull<Block>> {
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return None;
+    }
+    std::ptr::NonNull::new(ptr as *mut Block)
+}
+
+#[cfg(target_os = "linux")]
+impl AsMut<[Block]> for Mapping {
+    fn as_mut(&mut self) -> &mut [Block] {
+        // Anonymous mappings are zero-filled, and an all-zero block is a valid `Block`
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.blocks) }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use rand::rngs::ThreadRng;
+use rand::RngCore;
+
+/// SplitMix64, whose output only depends on the seed: the same on every platform and
+/// with every version of the `rand` crate
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64(seed)
+    }
+}
+
+impl RngCore for SplitMix64 {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The random source of a search: reproducible with a `:deterministic_seed`, otherwise
+/// the thread's entropy-seeded generator
+pub enum Source {
+    Seeded(SplitMix64),
+    Entropy(ThreadRng),
+}
+
+impl Source {
+    pub fn new(seed: Option<u64>) -> Source {
+        match seed {
+            Some(seed) => Source::Seeded(SplitMix64::new(seed)),
+            None => Source::Entropy(rand::thread_rng()),
+        }
+    }
+}
+
+impl RngCore for Source {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            Source::Seeded(rng) => rng.next_u32(),
+            Source::Entropy(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            Source::Seeded(rng) => rng.next_u64(),
+            Source::Entropy(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            Source::Seeded(rng) => rng.fill_bytes(dest),
+            Source::Entropy(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            Source::Seeded(rng) => rng.try_fill_bytes(dest),
+            Source::Entropy(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
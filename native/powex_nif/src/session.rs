@@ -0,0 +1,118 @@
+use rustler::{Binary, NifResult, Resource, ResourceArc};
+use std::sync::Mutex;
+
+use crate::algorithm::State;
+use crate::atoms;
+use crate::budget;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::input;
+use crate::jobs::Verifier;
+use crate::memory_hard::Hasher;
+use crate::metrics;
+use crate::options::Options;
+
+/// Default nonces `session_mine` tries with Argon2id, as in `compute_memory_hard`
+const DEFAULT_MAX_ARGON2_ATTEMPTS: u64 = 1_000_000;
+
+/// The per-data setup a session keeps between calls
+enum Engine {
+    /// Hasher state with the data absorbed, cloned per nonce
+    Hash(Box<State>),
+    /// Argon2id with its memory allocated once; calls on one session take turns
+    Argon2id(Mutex<Hasher>),
+}
+
+/// Data bound to an algorithm, prepared once for any number of searches and verifications
+pub struct Session {
+    engine: Engine,
+}
+
+#[rustler::resource_impl]
+impl Resource for Session {}
+
+impl Session {
+    fn digest_into(&self, nonce: u64, out: &mut Vec<u8>) {
+        match &self.engine {
+            Engine::Hash(state) => state.digest_into(nonce, out),
+            Engine::Argon2id(hasher) => {
+                out.clear();
+                out.extend_from_slice(&hasher.lock().unwrap().digest(nonce));
+            }
+        }
+    }
+}
+
+/// Prepares a session for the data: absorbs it into the hasher state, or allocates the
+/// Argon2id memory, so later calls only hash nonces
+#[rustler::nif(schedule = "DirtyCpu")]
+fn session_new(data: Binary, opts: Options) -> NifResult<GuardedResult<ResourceArc<Session>>> {
+    guard::run_result(|| {
+        let verifier = Verifier::from_options(&opts)?;
+        let huge_pages = opts.get_or(atoms::huge_pages(), false)?;
+
+        input::check_size(data.as_slice())?;
+
+        let engine = match verifier {
+            Verifier::Hash(algorithm) => Engine::Hash(Box::new(algorithm.prepare(data.as_slice()))),
+            Verifier::Argon2id(params) => {
+                if budget::check(0, params.memory_bytes()).is_err() {
+                    return Ok(Err((atoms::error(), "Argon2 memory exceeds the budget")));
+                }
+                match Hasher::with_memory(data.as_slice(), params, huge_pages) {
+                    Some(hasher) => Engine::Argon2id(Mutex::new(hasher)),
+                    None => return Ok(Err((atoms::error(), "Invalid Argon2 parameters"))),
+                }
+            }
+        };
+
+        Ok(Ok(ResourceArc::new(Session { engine })))
+    })
+}
+
+/// Searches the session's data for a nonce meeting the difficulty, from `:start` (default 0)
+/// for at most `:max_attempts` nonces (default: unbounded, or 1000000 with Argon2id)
+#[rustler::nif(schedule = "DirtyCpu")]
+fn session_mine(
+    session: ResourceArc<Session>,
+    difficulty: Difficulty,
+    opts: Options,
+) -> NifResult<GuardedResult<u64>> {
+    guard::run_result(|| {
+        let start = opts.get_or(atoms::start(), 0u64)?;
+        let default_attempts = match session.engine {
+            Engine::Hash(_) => u64::MAX,
+            Engine::Argon2id(_) => DEFAULT_MAX_ARGON2_ATTEMPTS,
+        };
+        let max_attempts = opts.get_or(atoms::max_attempts(), default_attempts)?;
+
+        if let Err(reason) = difficulty.validate() {
+            return Ok(Err(reason));
+        }
+
+        let mut digest = Vec::new();
+        let mut meter = metrics::Meter::new();
+        Ok((0..max_attempts)
+            .map_while(|attempt| start.checked_add(attempt))
+            .find(|&nonce| {
+                meter.tick();
+                session.digest_into(nonce, &mut digest);
+                difficulty.is_met_by(&digest)
+            })
+            .ok_or((atoms::error(), "No valid nonce found")))
+    })
+}
+
+/// Checks a nonce against the session's data with a single hash
+#[rustler::nif(schedule = "DirtyCpu")]
+fn session_verify(
+    session: ResourceArc<Session>,
+    nonce: u64,
+    difficulty: Difficulty,
+) -> Guarded<bool> {
+    guard::run(|| {
+        let mut digest = Vec::new();
+        session.digest_into(nonce, &mut digest);
+        difficulty.is_met_by(&digest)
+    })
+}
@@ -0,0 +1,99 @@
+use num_bigint::BigUint;
+use rustler::{Binary, Env, NewBinary};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+
+/// Size in bytes of sloth witnesses (field elements)
+const WITNESS_SIZE: usize = 32;
+
+/// Field prime p = 2^256 - 2^32 - 977, with p ≡ 3 (mod 4) so square roots are a single exponentiation
+fn prime() -> &'static BigUint {
+    static PRIME: OnceLock<BigUint> = OnceLock::new();
+    PRIME.get_or_init(|| {
+        (BigUint::from(1u8) << 256) - (BigUint::from(1u8) << 32) - BigUint::from(977u32)
+    })
+}
+
+/// Exponent (p + 1) / 4 used for square roots modulo p
+fn sqrt_exponent() -> &'static BigUint {
+    static EXPONENT: OnceLock<BigUint> = OnceLock::new();
+    EXPONENT.get_or_init(|| (prime() + 1u8) >> 2)
+}
+
+/// Maps the data to the starting field element
+fn seed(data: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(&Sha256::digest(data)) % prime()
+}
+
+/// One slow step: x -> sqrt(x + 1), taking the even root for residues and the odd root of -x otherwise
+fn forward(x: &BigUint) -> BigUint {
+    let p = prime();
+    let x = (x + 1u8) % p;
+    let root = x.modpow(sqrt_exponent(), p);
+    let is_residue = (&root * &root) % p == x;
+    let is_even = !root.bit(0);
+
+    if is_residue == is_even {
+        root
+    } else {
+        p - root
+    }
+}
+
+/// One fast step undoing `forward` with a single squaring
+fn backward(y: &BigUint) -> BigUint {
+    let p = prime();
+    let square = (y * y) % p;
+    let x = if y.bit(0) { (p - square) % p } else { square };
+    (x + p - 1u8) % p
+}
+
+fn encode(x: &BigUint) -> [u8; WITNESS_SIZE] {
+    let bytes = x.to_bytes_be();
+    let mut out = [0u8; WITNESS_SIZE];
+    out[WITNESS_SIZE - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+/// Creates a sequential time-lock witness by chaining `iterations` modular square roots
+#[rustler::nif(schedule = "DirtyCpu")]
+fn timelock_create<'a>(env: Env<'a>, data: Binary, iterations: u64) -> GuardedResult<Binary<'a>> {
+    guard::run(|| {
+        if iterations == 0 {
+            return Err((atoms::error(), "Iterations must be positive"));
+        }
+
+        let mut x = seed(data.as_slice());
+        for _ in 0..iterations {
+            x = forward(&x);
+        }
+
+        let mut witness = NewBinary::new(env, WITNESS_SIZE);
+        witness.as_mut_slice().copy_from_slice(&encode(&x));
+        Ok(witness.into())
+    })
+}
+
+/// Verifies a time-lock witness by unwinding the chain with one squaring per iteration
+#[rustler::nif(schedule = "DirtyCpu")]
+fn timelock_verify(data: Binary, iterations: u64, witness: Binary) -> Guarded<bool> {
+    guard::run(|| {
+        if iterations == 0 || witness.len() != WITNESS_SIZE {
+            return false;
+        }
+
+        let mut y = BigUint::from_bytes_be(witness.as_slice());
+        if &y >= prime() {
+            return false;
+        }
+
+        for _ in 0..iterations {
+            y = backward(&y);
+        }
+
+        y == seed(data.as_slice())
+    })
+}
@@ -0,0 +1,98 @@
+use lru::LruCache;
+use rustler::{Atom, Error, NifMap, NifResult, Term};
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::atoms;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, Guarded};
+
+/// Most recent solves kept per difficulty
+const WINDOW: usize = 512;
+
+/// Difficulties tracked at once; the least recently solved one is forgotten first
+const TRACKED_DIFFICULTIES: usize = 64;
+
+type Windows = LruCache<Difficulty, VecDeque<f64>>;
+
+fn windows() -> &'static Mutex<Windows> {
+    static WINDOWS: OnceLock<Mutex<Windows>> = OnceLock::new();
+    WINDOWS.get_or_init(|| {
+        Mutex::new(LruCache::new(NonZeroUsize::new(TRACKED_DIFFICULTIES).unwrap()))
+    })
+}
+
+fn push(difficulty: Difficulty, solve_ms: f64) {
+    let mut windows = windows().lock().unwrap();
+    let window = windows.get_or_insert_mut(difficulty, || VecDeque::with_capacity(WINDOW));
+    if window.len() == WINDOW {
+        window.pop_front();
+    }
+    window.push_back(solve_ms);
+}
+
+/// Records how long a search took to meet a difficulty
+pub fn record(difficulty: Difficulty, elapsed: Duration) {
+    push(difficulty, elapsed.as_secs_f64() * 1_000.0);
+}
+
+#[derive(NifMap)]
+struct SolveTimeStats {
+    count: usize,
+    min_ms: f64,
+    max_ms: f64,
+    mean_ms: f64,
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Records a solve measured outside the NIF, such as the time between issuing a
+/// challenge and receiving its proof
+#[rustler::nif]
+fn solve_time_record(difficulty: Difficulty, solve_ms: Term) -> NifResult<Guarded<Atom>> {
+    guard::run_result(|| {
+        let solve_ms = match solve_ms.decode::<u64>() {
+            Ok(ms) => ms as f64,
+            Err(_) => solve_ms.decode::<f64>()?,
+        };
+        if !solve_ms.is_finite() || solve_ms < 0.0 {
+            return Err(Error::BadArg);
+        }
+        push(difficulty, solve_ms);
+        Ok(atoms::ok())
+    })
+}
+
+/// Summarizes the recent solve times of a difficulty, or returns nil if none were recorded
+#[rustler::nif]
+fn solve_time_stats(difficulty: Difficulty) -> Guarded<Option<SolveTimeStats>> {
+    guard::run(|| {
+        let mut sorted: Vec<f64> = {
+            let mut windows = windows().lock().unwrap();
+            windows.get(&difficulty)?.iter().copied().collect()
+        };
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(f64::total_cmp);
+
+        Some(SolveTimeStats {
+            count: sorted.len(),
+            min_ms: sorted[0],
+            max_ms: sorted[sorted.len() - 1],
+            mean_ms: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50_ms: percentile(&sorted, 50.0),
+            p90_ms: percentile(&sorted, 90.0),
+            p99_ms: percentile(&sorted, 99.0),
+        })
+    })
+}
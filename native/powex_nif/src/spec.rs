@@ -0,0 +1,317 @@
+use crate::algorithm::TURBOSHAKE_DOMAIN;
+use crate::guard::{self, Guarded};
+use argon2::Params;
+use rustler::types::map::map_new;
+use rustler::{Atom, Encoder, Env, NifResult, NifUnitEnum, Term};
+
+/// Hashing modes whose encoding rules `encoding_spec` describes
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// `compute`, `valid?`, `compute_parallel`, `get_hash`
+    Sha256,
+    /// The `:blake3` algorithm option
+    Blake3,
+    /// The `:poseidon` algorithm option
+    Poseidon,
+    /// The `:sha512_256` algorithm option
+    Sha512_256,
+    /// The `:sha384` algorithm option
+    Sha384,
+    /// The `:hash160` algorithm option
+    Hash160,
+    /// The `:k12` algorithm option
+    K12,
+    /// The `:turboshake128` algorithm option
+    Turboshake128,
+    /// The `:yespower` algorithm option, with its cost parameters and personalization
+    Yespower,
+    /// The `:blake2b` algorithm option, with its length and key parameters
+    Blake2b,
+    /// `compute_memory_hard` and `valid_memory_hard?`
+    Argon2id,
+    /// Bitcoin-style header templates
+    Header,
+    /// Self-describing proofs of `encode_proof` and `decode_and_verify`
+    Proof,
+}
+
+/// Builds a map with atom keys
+fn map<'a>(env: Env<'a>, pairs: &[(&str, Term<'a>)]) -> NifResult<Term<'a>> {
+    pairs.iter().try_fold(map_new(env), |map, (key, value)| {
+        map.map_put(Atom::from_str(env, key)?, *value)
+    })
+}
+
+fn atom<'a>(env: Env<'a>, name: &str) -> NifResult<Term<'a>> {
+    Ok(Atom::from_str(env, name)?.encode(env))
+}
+
+/// How `data || nonce` is hashed and compared by the data-and-nonce modes
+fn data_nonce<'a>(
+    env: Env<'a>,
+    function: &str,
+    size: u32,
+    parameters: Term<'a>,
+) -> NifResult<Term<'a>> {
+    map(
+        env,
+        &[
+            (
+                "input",
+                vec![atom(env, "data")?, atom(env, "nonce")?].encode(env),
+            ),
+            (
+                "nonce",
+                map(
+                    env,
+                    &[
+                        ("width", 8u32.encode(env)),
+                        ("byte_order", atom(env, "little")?),
+                        ("signed", false.encode(env)),
+                    ],
+                )?,
+            ),
+            (
+                "digest",
+                map(
+                    env,
+                    &[
+                        ("function", atom(env, function)?),
+                        ("size", size.encode(env)),
+                        ("encoding", atom(env, "hex_lowercase")?),
+                    ],
+                )?,
+            ),
+            (
+                "difficulty",
+                map(
+                    env,
+                    &[
+                        ("integer", atom(env, "exact_leading_hex_zeros")?),
+                        ("zero_bits", atom(env, "minimum_leading_zero_bits")?),
+                        ("trailing_zeros", atom(env, "minimum_trailing_hex_zeros")?),
+                        ("zeros_at", atom(env, "hex_zeros_from_hex_digit_offset")?),
+                        ("target", atom(env, "digest_big_endian_at_most_target")?),
+                        ("mask", atom(env, "digest_and_mask_equals_value")?),
+                    ],
+                )?,
+            ),
+            ("parameters", parameters),
+        ],
+    )
+}
+
+/// Describes exactly how a mode hashes its input, for cross-language solvers
+#[rustler::nif]
+fn encoding_spec<'a>(env: Env<'a>, mode: Mode) -> NifResult<Guarded<Term<'a>>> {
+    guard::run_result(|| {
+        let nil = rustler::types::atom::nil().encode(env);
+        let spec = match mode {
+            Mode::Sha256 => data_nonce(env, "sha256", 32, nil)?,
+            Mode::Blake3 => data_nonce(env, "blake3", 32, nil)?,
+            Mode::Sha512_256 => data_nonce(env, "sha512_256", 32, nil)?,
+            Mode::Sha384 => data_nonce(env, "sha384", 48, nil)?,
+            Mode::Hash160 => data_nonce(env, "ripemd160_of_sha256", 20, nil)?,
+            Mode::K12 => {
+                let parameters = map(env, &[("customization", "".encode(env))])?;
+                data_nonce(env, "kangarootwelve", 32, parameters)?
+            }
+            Mode::Turboshake128 => {
+                let parameters = map(env, &[("domain", TURBOSHAKE_DOMAIN.encode(env))])?;
+                data_nonce(env, "turboshake128", 32, parameters)?
+            }
+            Mode::Yespower => {
+                let parameters = map(
+                    env,
+                    &[
+                        ("version", "1.0".encode(env)),
+                        ("prehash", atom(env, "sha256")?),
+                        ("n", atom(env, "from_algorithm_default_2048")?),
+                        ("r", atom(env, "from_algorithm_default_8")?),
+                        ("personalization", atom(env, "from_algorithm_default_empty")?),
+                    ],
+                )?;
+                data_nonce(env, "yespower", 32, parameters)?
+            }
+            Mode::Blake2b => {
+                let parameters = map(
+                    env,
+                    &[
+                        ("size", atom(env, "from_algorithm_1_to_64")?),
+                        ("key", atom(env, "from_algorithm_up_to_64_bytes")?),
+                    ],
+                )?;
+                data_nonce(env, "blake2b", 64, parameters)?
+            }
+            Mode::Poseidon => {
+                let parameters = map(
+                    env,
+                    &[
+                        ("field", atom(env, "bn254_scalar")?),
+                        ("permutation", atom(env, "circom_poseidon_2")?),
+                        ("initial_state", atom(env, "data_byte_length")?),
+                        ("chunk_size", 31u32.encode(env)),
+                        ("chunk_byte_order", atom(env, "big")?),
+                        ("absorb", atom(env, "state_equals_poseidon_state_chunk")?),
+                        (
+                            "finalize",
+                            atom(env, "poseidon_state_nonce_as_field_element")?,
+                        ),
+                        ("digest_byte_order", atom(env, "big")?),
+                    ],
+                )?;
+                data_nonce(env, "poseidon", 32, parameters)?
+            }
+            Mode::Argon2id => {
+                let parameters = map(
+                    env,
+                    &[
+                        ("version", 0x13u32.encode(env)),
+                        (
+                            "password",
+                            vec![atom(env, "data")?, atom(env, "nonce")?].encode(env),
+                        ),
+                        ("salt", atom(env, "sha256_of_data_first_16_bytes")?),
+                        ("output_size", 32u32.encode(env)),
+                        ("m_cost", Params::DEFAULT_M_COST.encode(env)),
+                        ("t_cost", Params::DEFAULT_T_COST.encode(env)),
+                        ("p_cost", Params::DEFAULT_P_COST.encode(env)),
+                    ],
+                )?;
+                data_nonce(env, "argon2id", 32, parameters)?
+            }
+            Mode::Header => {
+                let field = |name: &str, offset: u32, size: u32| {
+                    map(
+                        env,
+                        &[
+                            ("field", atom(env, name)?),
+                            ("offset", offset.encode(env)),
+                            ("size", size.encode(env)),
+                            ("byte_order", atom(env, "little")?),
+                        ],
+                    )
+                };
+                map(
+                    env,
+                    &[
+                        (
+                            "input",
+                            vec![
+                                field("version", 0, 4)?,
+                                field("prev_hash", 4, 32)?,
+                                field("merkle_root", 36, 32)?,
+                                field("timestamp", 68, 4)?,
+                                field("bits", 72, 4)?,
+                                field("nonce", 76, 4)?,
+                            ]
+                            .encode(env),
+                        ),
+                        (
+                            "nonce",
+                            map(
+                                env,
+                                &[
+                                    ("width", 4u32.encode(env)),
+                                    ("byte_order", atom(env, "little")?),
+                                    ("offset", 76u32.encode(env)),
+                                ],
+                            )?,
+                        ),
+                        (
+                            "digest",
+                            map(
+                                env,
+                                &[
+                                    ("function", atom(env, "double_sha256")?),
+                                    ("size", 32u32.encode(env)),
+                                    ("encoding", atom(env, "binary")?),
+                                    ("byte_order", atom(env, "internal")?),
+                                ],
+                            )?,
+                        ),
+                        (
+                            "difficulty",
+                            map(
+                                env,
+                                &[(
+                                    "target",
+                                    atom(env, "digest_little_endian_at_most_bits_target")?,
+                                )],
+                            )?,
+                        ),
+                        ("parameters", nil),
+                    ],
+                )?
+            }
+            Mode::Proof => {
+                let field = |name: &str, size: Term<'a>| {
+                    map(env, &[("field", atom(env, name)?), ("size", size)])
+                };
+                map(
+                    env,
+                    &[
+                        (
+                            "layout",
+                            vec![
+                                field("version", 1u32.encode(env))?,
+                                field("algorithm", 1u32.encode(env))?,
+                                field("difficulty", 1u32.encode(env))?,
+                                field("nonce_width", 1u32.encode(env))?,
+                                field("nonce", atom(env, "nonce_width")?)?,
+                                field("memory_params", atom(env, "argon2id_only_12")?)?,
+                                field("data", atom(env, "rest")?)?,
+                            ]
+                            .encode(env),
+                        ),
+                        ("version", 1u32.encode(env)),
+                        (
+                            "algorithms",
+                            map(
+                                env,
+                                &[
+                                    ("sha256", 0u32.encode(env)),
+                                    ("blake3", 1u32.encode(env)),
+                                    ("argon2id", 2u32.encode(env)),
+                                    ("poseidon", 3u32.encode(env)),
+                                ],
+                            )?,
+                        ),
+                        (
+                            "nonce",
+                            map(
+                                env,
+                                &[
+                                    ("width", atom(env, "nonce_width")?),
+                                    ("byte_order", atom(env, "little")?),
+                                ],
+                            )?,
+                        ),
+                        (
+                            "memory_params",
+                            map(
+                                env,
+                                &[
+                                    (
+                                        "fields",
+                                        vec![
+                                            atom(env, "m_cost")?,
+                                            atom(env, "t_cost")?,
+                                            atom(env, "p_cost")?,
+                                        ]
+                                        .encode(env),
+                                    ),
+                                    ("width", 4u32.encode(env)),
+                                    ("byte_order", atom(env, "little")?),
+                                ],
+                            )?,
+                        ),
+                        ("hashing", atom(env, "as_algorithm_mode_with_u64_nonce")?),
+                    ],
+                )?
+            }
+        };
+
+        Ok(spec)
+    })
+}
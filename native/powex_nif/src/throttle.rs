@@ -0,0 +1,288 @@
+use rustler::NifUnitEnum;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::guard::{self, Guarded};
+use crate::log::{self, Level, Value};
+
+/// Longest burst a token bucket allows after idling, as a fraction of a second of work
+const BURST_SECONDS: f64 = 0.1;
+
+/// How long a power source reading is reused before the OS is asked again
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fraction of the time workers hash under `:throttle_on_battery`
+const BATTERY_DUTY_CYCLE: f64 = 0.25;
+
+/// How long a temperature reading is reused, and how often the thermal duty cycle adapts
+const THERMAL_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Lowest duty cycle thermal backoff goes down to, so work always progresses
+const MIN_THERMAL_DUTY_CYCLE: f64 = 0.05;
+
+/// Duty cycle regained per check once the temperature is back under the threshold
+const THERMAL_RECOVERY_STEP: f64 = 0.1;
+
+/// Token-bucket limiter shared by all workers of a pool
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    /// Available tokens, negative while reservations are outstanding, and the last refill
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    /// Creates a bucket refilling at `rate` tokens per second, starting full
+    pub fn new(rate: u64) -> TokenBucket {
+        let rate = rate as f64;
+        let capacity = rate * BURST_SECONDS;
+        TokenBucket {
+            rate,
+            capacity,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Takes `tokens` from the bucket, sleeping until they have been earned.
+    /// Tokens are reserved before sleeping, so concurrent takers queue up fairly.
+    pub fn take(&self, tokens: u64) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let (available, refilled) = &mut *state;
+            let now = Instant::now();
+
+            let earned = now.duration_since(*refilled).as_secs_f64() * self.rate;
+            *available = (*available + earned).min(self.capacity) - tokens as f64;
+            *refilled = now;
+
+            if *available < 0.0 {
+                -*available / self.rate
+            } else {
+                0.0
+            }
+        };
+
+        if wait > 0.0 {
+            thread::sleep(Duration::from_secs_f64(wait));
+        }
+    }
+}
+
+/// Where the machine currently draws its power from
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    /// No power supply information, as on most desktops, servers and non-Linux systems
+    Unknown,
+}
+
+/// Reads the power source from `/sys/class/power_supply`
+#[cfg(target_os = "linux")]
+pub fn power_source() -> PowerSource {
+    let read = |path: std::path::PathBuf| std::fs::read_to_string(path).unwrap_or_default();
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+        return PowerSource::Unknown;
+    };
+
+    let (mut mains, mut mains_online, mut discharging) = (false, false, false);
+    for supply in supplies.flatten() {
+        let path = supply.path();
+        match read(path.join("type")).trim() {
+            "Mains" | "USB" => {
+                mains = true;
+                mains_online |= read(path.join("online")).trim() == "1";
+            }
+            "Battery" => discharging |= read(path.join("status")).trim() == "Discharging",
+            _ => {}
+        }
+    }
+
+    match (mains, mains_online, discharging) {
+        (_, true, _) => PowerSource::Ac,
+        (_, false, true) => PowerSource::Battery,
+        (true, false, false) => PowerSource::Battery,
+        (false, false, false) => PowerSource::Unknown,
+    }
+}
+
+/// Reads the power source; only Linux is supported so far
+#[cfg(not(target_os = "linux"))]
+pub fn power_source() -> PowerSource {
+    PowerSource::Unknown
+}
+
+/// What workers do while the machine runs on battery
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerPolicy {
+    /// Keep hashing at full speed
+    Ignore,
+    /// Stop hashing until AC power returns
+    PauseOnBattery,
+    /// Hash at a reduced duty cycle until AC power returns
+    ThrottleOnBattery,
+}
+
+/// Applies a power policy, caching the power source between checks
+pub struct PowerMonitor {
+    policy: PowerPolicy,
+    reading: Mutex<(PowerSource, Option<Instant>)>,
+}
+
+impl PowerMonitor {
+    pub fn new(policy: PowerPolicy) -> PowerMonitor {
+        PowerMonitor {
+            policy,
+            reading: Mutex::new((PowerSource::Unknown, None)),
+        }
+    }
+
+    fn on_battery(&self) -> bool {
+        let mut reading = self.reading.lock().unwrap();
+        let (source, checked) = &mut *reading;
+        let stale = match checked {
+            Some(at) => at.elapsed() >= POWER_CHECK_INTERVAL,
+            None => true,
+        };
+        if stale {
+            let previous = std::mem::replace(source, power_source());
+            if checked.is_some() && previous != *source {
+                log::emit(
+                    Level::Info,
+                    "power_source_changed",
+                    vec![
+                        ("source", Value::atom(*source)),
+                        ("policy", Value::atom(self.policy)),
+                    ],
+                );
+            }
+            *checked = Some(Instant::now());
+        }
+        *source == PowerSource::Battery
+    }
+
+    /// Blocks while the policy pauses work, then returns the duty cycle to hash at
+    pub fn duty_cycle(&self) -> f64 {
+        match self.policy {
+            PowerPolicy::Ignore => 1.0,
+            PowerPolicy::PauseOnBattery => {
+                while self.on_battery() {
+                    thread::sleep(POWER_CHECK_INTERVAL);
+                }
+                1.0
+            }
+            PowerPolicy::ThrottleOnBattery if self.on_battery() => BATTERY_DUTY_CYCLE,
+            PowerPolicy::ThrottleOnBattery => 1.0,
+        }
+    }
+}
+
+/// Hottest reading of `/sys/class/thermal`, in degrees Celsius
+#[cfg(target_os = "linux")]
+pub fn cpu_temperature() -> Option<f64> {
+    std::fs::read_dir("/sys/class/thermal")
+        .ok()?
+        .flatten()
+        .filter(|zone| {
+            zone.file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|zone| std::fs::read_to_string(zone.path().join("temp")).ok())
+        .filter_map(|millidegrees| millidegrees.trim().parse::<f64>().ok())
+        .map(|millidegrees| millidegrees / 1_000.0)
+        .reduce(f64::max)
+}
+
+/// Reads the CPU temperature; only Linux is supported so far
+#[cfg(not(target_os = "linux"))]
+pub fn cpu_temperature() -> Option<f64> {
+    None
+}
+
+/// Adapts the duty cycle to the CPU temperature: halved on every check above the
+/// threshold, then regained step by step once below it
+pub struct ThermalMonitor {
+    max_temperature: f64,
+    /// Current duty cycle and when it last adapted
+    state: Mutex<(f64, Option<Instant>)>,
+}
+
+impl ThermalMonitor {
+    pub fn new(max_temperature: f64) -> ThermalMonitor {
+        ThermalMonitor {
+            max_temperature,
+            state: Mutex::new((1.0, None)),
+        }
+    }
+
+    /// Returns the duty cycle to hash at, adapting it at most once per check interval
+    pub fn duty_cycle(&self) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        let (duty_cycle, checked) = &mut *state;
+        let stale = match checked {
+            Some(at) => at.elapsed() >= THERMAL_CHECK_INTERVAL,
+            None => true,
+        };
+
+        if stale {
+            *checked = Some(Instant::now());
+            match cpu_temperature() {
+                Some(celsius) if celsius > self.max_temperature => {
+                    *duty_cycle = (*duty_cycle / 2.0).max(MIN_THERMAL_DUTY_CYCLE);
+                    log::emit(
+                        Level::Warning,
+                        "thermal_backoff",
+                        vec![
+                            ("temperature", Value::F64(celsius)),
+                            ("duty_cycle", Value::F64(*duty_cycle)),
+                        ],
+                    );
+                }
+                _ if *duty_cycle < 1.0 => {
+                    *duty_cycle = (*duty_cycle + THERMAL_RECOVERY_STEP).min(1.0);
+                    if *duty_cycle == 1.0 {
+                        log::emit(Level::Info, "thermal_recovered", vec![]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        *duty_cycle
+    }
+}
+
+/// Per-worker duty cycling: sleeps in proportion to the time spent hashing since the last call
+pub struct DutyCycle {
+    resumed: Instant,
+}
+
+impl DutyCycle {
+    pub fn new() -> DutyCycle {
+        DutyCycle {
+            resumed: Instant::now(),
+        }
+    }
+
+    /// Idles long enough that hashing takes up `fraction` of the time
+    pub fn run_at(&mut self, fraction: f64) {
+        if fraction < 1.0 && fraction > 0.0 {
+            let busy = self.resumed.elapsed().as_secs_f64();
+            thread::sleep(Duration::from_secs_f64(busy * (1.0 - fraction) / fraction));
+        }
+        self.resumed = Instant::now();
+    }
+}
+
+/// Reports where the machine draws its power from
+#[rustler::nif(name = "power_source")]
+fn power_source_nif() -> Guarded<PowerSource> {
+    guard::run(power_source)
+}
+
+/// Reports the hottest CPU temperature sensor in degrees Celsius, if readable
+#[rustler::nif(name = "cpu_temperature")]
+fn cpu_temperature_nif() -> Guarded<Option<f64>> {
+    guard::run(cpu_temperature)
+}
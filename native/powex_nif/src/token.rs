@@ -0,0 +1,425 @@
+//! Proof tokens for HTTP headers such as `X-PoW`, in a versioned envelope modeled on PASETO:
+//! `<version>.<purpose>.<base64url(payload)>` without padding. The header names the whole
+//! cryptographic suite, so a new suite is a new version verifiers opt into, and tokens of
+//! an unknown version are refused rather than parsed as something they aren't.
+//!
+//! Version 1 has two purposes:
+//! - `v1.local`: authenticated with a shared key, by a keyed BLAKE3 MAC
+//! - `v1.public`: signed with an Ed25519 secret key, checked by anyone with the public key
+//!
+//! The payload is `timestamp (u64 LE) || tag || proof`, where the proof is in the
+//! self-describing format of `proof.rs`. The tag covers the header, the timestamp and the
+//! proof but not the nonce, so a server can issue a signed challenge as a token and stay
+//! stateless: the client solves it, puts its nonce in, and the tag still holds.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::RngCore;
+use rustler::{
+    Atom, Binary, Env, Error, NewBinary, NifMap, NifResult, NifUnitEnum, ResourceArc, Term,
+};
+use zeroize::Zeroizing;
+
+use crate::atoms;
+use crate::clock;
+use crate::entropy;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::memory_hard::MemoryHardParams;
+use crate::options::Options;
+use crate::personal;
+use crate::proof::{self, DeclaredParams, Policy, Proof, ProofAlgorithm};
+use crate::Status;
+
+/// Size of a `v1.local` MAC
+const MAC_LEN: usize = 32;
+
+/// Size of a `v1.public` Ed25519 signature
+const SIGNATURE_LEN: usize = 64;
+
+/// Size of Ed25519 secret and public keys
+const KEY_LEN: usize = 32;
+
+/// Longest token in characters, under the 8 KB per header many HTTP servers allow
+const MAX_TOKEN_LEN: usize = 8 * 1024 - 1;
+
+/// BLAKE3 key derivation context, so token MACs never collide with other uses of the key
+const KEY_CONTEXT: &str = "powex 2026-10-15 proof token MAC";
+
+/// Size of the random data of a challenge from `issue_challenge`
+const CHALLENGE_LEN: usize = 16;
+
+/// Default `:max_age` of a token, in seconds
+const DEFAULT_MAX_AGE: u64 = 300;
+
+/// Default `:max_future_drift` of a token timestamp, in seconds
+const DEFAULT_MAX_FUTURE_DRIFT: u64 = 60;
+
+#[derive(NifUnitEnum, Clone, Copy, PartialEq, Eq)]
+enum Version {
+    V1,
+}
+
+#[derive(NifUnitEnum, Clone, Copy, PartialEq, Eq)]
+enum Purpose {
+    Local,
+    Public,
+}
+
+impl Purpose {
+    fn header(self) -> &'static str {
+        match self {
+            Purpose::Local => "v1.local.",
+            Purpose::Public => "v1.public.",
+        }
+    }
+
+    fn tag_len(self) -> usize {
+        match self {
+            Purpose::Local => MAC_LEN,
+            Purpose::Public => SIGNATURE_LEN,
+        }
+    }
+}
+
+/// What a token's tag covers: the header, the timestamp and the proof with its nonce and
+/// nonce width zeroed. The proof comes last, so the layout is unambiguous
+fn signed_message(purpose: Purpose, timestamp: u64, proof: &Proof) -> Vec<u8> {
+    let unsolved = Proof { nonce: 0, nonce_width: 1, ..*proof };
+    let mut message = purpose.header().as_bytes().to_vec();
+    message.extend_from_slice(&timestamp.to_le_bytes());
+    message.extend_from_slice(&unsolved.encode());
+    message
+}
+
+fn compute_mac(key: &[u8], message: &[u8]) -> [u8; MAC_LEN] {
+    let key = Zeroizing::new(blake3::derive_key(KEY_CONTEXT, key));
+    *blake3::keyed_hash(&key, message).as_bytes()
+}
+
+/// A token split into its parts
+struct Token<'a> {
+    purpose: Purpose,
+    timestamp: u64,
+    tag: &'a [u8],
+    proof: Proof<'a>,
+}
+
+impl<'a> Token<'a> {
+    fn decode(purpose: Purpose, bytes: &'a [u8]) -> Option<Token<'a>> {
+        let tag_end = 8 + purpose.tag_len();
+        if bytes.len() < tag_end {
+            return None;
+        }
+        Some(Token {
+            purpose,
+            timestamp: u64::from_le_bytes(bytes[..8].try_into().ok()?),
+            tag: &bytes[8..tag_end],
+            proof: Proof::decode(&bytes[tag_end..])?,
+        })
+    }
+
+    fn encode(&self) -> String {
+        let mut bytes = self.timestamp.to_le_bytes().to_vec();
+        bytes.extend_from_slice(self.tag);
+        bytes.extend_from_slice(&self.proof.encode());
+        format!("{}{}", self.purpose.header(), URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    fn message(&self) -> Vec<u8> {
+        signed_message(self.purpose, self.timestamp, &self.proof)
+    }
+
+    /// Compares the MAC in constant time
+    fn mac_matches(&self, key: &[u8]) -> bool {
+        let Ok(mac) = <[u8; MAC_LEN]>::try_from(self.tag) else {
+            return false;
+        };
+        blake3::Hash::from_bytes(mac) == compute_mac(key, &self.message())
+    }
+
+    /// Checks the signature strictly, refusing weak public keys and malleable signatures
+    fn signature_matches(&self, public_key: &[u8]) -> bool {
+        let (Ok(public_key), Ok(signature)) =
+            (<[u8; KEY_LEN]>::try_from(public_key), Signature::from_slice(self.tag))
+        else {
+            return false;
+        };
+        VerifyingKey::from_bytes(&public_key)
+            .and_then(|key| key.verify_strict(&self.message(), &signature))
+            .is_ok()
+    }
+}
+
+/// Splits the text of a token into its purpose and decoded payload, refusing oversized
+/// input before decoding it. Tokens of another version are `:unsupported_version`
+fn decode_text(token: &str) -> Result<(Purpose, Vec<u8>), Atom> {
+    if token.len() > MAX_TOKEN_LEN {
+        return Err(atoms::malformed());
+    }
+    let mut parts = token.splitn(3, '.');
+    let (Some(version), Some(purpose), Some(payload)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(atoms::malformed());
+    };
+    let purpose = match (version, purpose) {
+        ("v1", "local") => Purpose::Local,
+        ("v1", "public") => Purpose::Public,
+        _ => return Err(atoms::unsupported_version()),
+    };
+    let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| atoms::malformed())?;
+    Ok((purpose, payload))
+}
+
+#[derive(NifMap)]
+struct TokenFields<'a> {
+    version: Version,
+    purpose: Purpose,
+    algorithm: ProofAlgorithm,
+    difficulty: u8,
+    nonce: u64,
+    memory: Option<DeclaredParams>,
+    data: Binary<'a>,
+    timestamp: u64,
+    tag: Binary<'a>,
+}
+
+fn binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(bytes);
+    Binary::from(binary)
+}
+
+impl<'a> TokenFields<'a> {
+    fn new(env: Env<'a>, token: &Token) -> TokenFields<'a> {
+        TokenFields {
+            version: Version::V1,
+            purpose: token.purpose,
+            algorithm: token.proof.algorithm,
+            difficulty: token.proof.difficulty,
+            nonce: token.proof.nonce,
+            memory: token.proof.memory.map(DeclaredParams::from),
+            data: binary(env, token.proof.data),
+            timestamp: token.timestamp,
+            tag: binary(env, token.tag),
+        }
+    }
+}
+
+/// Encodes a token for `data` and `difficulty` from the remaining fields
+pub fn encode_fields(
+    fields: &Options,
+    data: &[u8],
+    difficulty: u8,
+) -> NifResult<Result<String, (Atom, &'static str)>> {
+    let purpose = fields.get_or(atoms::purpose(), Purpose::Local)?;
+    let algorithm = fields.get_or(atoms::algorithm(), ProofAlgorithm::Sha256)?;
+    let nonce = fields.get_or(atoms::nonce(), 0)?;
+    let timestamp = fields.get::<u64>(atoms::timestamp())?.unwrap_or_else(clock::now);
+    let memory = match algorithm {
+        ProofAlgorithm::Argon2id => Some(MemoryHardParams::from_options(fields)?),
+        _ => None,
+    };
+    let key = match purpose {
+        Purpose::Local => fields.get::<Binary>(atoms::key())?,
+        Purpose::Public => fields.get::<Binary>(atoms::secret_key())?,
+    };
+    let given_tag: Option<Binary> = fields.get(atoms::tag())?;
+
+    let proof = Proof::new(algorithm, difficulty, nonce, memory, data);
+    let message = signed_message(purpose, timestamp, &proof);
+    let tag = match (purpose, key, given_tag) {
+        (Purpose::Local, Some(key), _) if !key.is_empty() => {
+            compute_mac(key.as_slice(), &message).to_vec()
+        }
+        (Purpose::Public, Some(key), _) => match <[u8; KEY_LEN]>::try_from(key.as_slice()) {
+            Ok(seed) => SigningKey::from_bytes(&seed).sign(&message).to_bytes().to_vec(),
+            Err(_) => return Ok(Err((atoms::error(), "An Ed25519 secret key is 32 bytes"))),
+        },
+        (_, None, Some(given)) if given.len() == purpose.tag_len() => given.as_slice().to_vec(),
+        (_, None, Some(_)) => {
+            return Ok(Err((atoms::error(), "Tag length doesn't match the purpose")))
+        }
+        (Purpose::Local, _, _) => return Ok(Err((atoms::error(), "Missing :key or :tag"))),
+        (Purpose::Public, _, _) => {
+            return Ok(Err((atoms::error(), "Missing :secret_key or :tag")))
+        }
+    };
+
+    let token = Token { purpose, timestamp, tag: &tag, proof }.encode();
+    if token.len() > MAX_TOKEN_LEN {
+        return Ok(Err((atoms::error(), "Token too large for an HTTP header")));
+    }
+    Ok(Ok(token))
+}
+
+/// Encodes a token from its fields: `:data` and `:difficulty`, with `:algorithm` (default
+/// SHA-256, Argon2id parameters as in `encode_proof`), `:nonce` (default 0) and `:timestamp`
+/// (default now). A `:local` token (the default `:purpose`) is authenticated with `:key`,
+/// a `:public` one signed with `:secret_key`; either may instead carry the `:tag` of a token
+/// issued earlier
+#[rustler::nif]
+fn token_encode(fields: Options) -> NifResult<GuardedResult<String>> {
+    guard::run_result(|| {
+        let Some(data) = fields.get::<Binary>(atoms::data())? else {
+            return Ok(Err((atoms::error(), "Missing :data")));
+        };
+        let Some(difficulty) = fields.get::<u8>(atoms::difficulty())? else {
+            return Ok(Err((atoms::error(), "Missing :difficulty")));
+        };
+        encode_fields(&fields, data.as_slice(), difficulty)
+    })
+}
+
+/// Issues a challenge token for `:client_id`: fresh random data, at the base `:difficulty`
+/// personalized by `:reputation` as in `personal_difficulty`. The personalization secret is
+/// `:secret`, or else the signing key; the other fields are as in `token_encode`
+#[rustler::nif]
+fn issue_challenge(opts: Options) -> NifResult<GuardedResult<String>> {
+    guard::run_result(|| {
+        let Some(client_id) = opts.get::<Binary>(atoms::client_id())? else {
+            return Ok(Err((atoms::error(), "Missing :client_id")));
+        };
+        let Some(base) = opts.get::<u32>(atoms::difficulty())? else {
+            return Ok(Err((atoms::error(), "Missing :difficulty")));
+        };
+        let score = match opts.get::<Term>(atoms::reputation())? {
+            Some(score) => personal::reputation(score)?,
+            None => personal::NEUTRAL_REPUTATION,
+        };
+        let max_adjustment =
+            opts.get_or(atoms::max_adjustment(), personal::DEFAULT_MAX_ADJUSTMENT)?;
+        let secret = match opts.get::<Binary>(atoms::secret())? {
+            Some(secret) => Some(secret),
+            None => opts.get::<Binary>(atoms::key())?.or(opts.get(atoms::secret_key())?),
+        };
+
+        let Some(secret) = secret.filter(|secret| !secret.is_empty()) else {
+            return Ok(Err((atoms::error(), "Missing :secret, :key or :secret_key")));
+        };
+        if !max_adjustment.is_finite() || max_adjustment < 0.0 {
+            return Ok(Err((atoms::error(), "Max adjustment must be non-negative")));
+        }
+
+        let difficulty = personal::difficulty(&secret, &client_id, base, score, max_adjustment);
+        let mut data = [0u8; CHALLENGE_LEN];
+        entropy::fill(&mut data, None);
+        encode_fields(&opts, &data, difficulty as u8)
+    })
+}
+
+/// A token whose text decoded, owning its payload so its parts can borrow from it
+pub struct Opened {
+    purpose: Purpose,
+    payload: Vec<u8>,
+}
+
+impl Opened {
+    /// Decodes the text of a token without checking its tag, timestamp or proof
+    pub fn decode(token: &str) -> Result<Opened, Atom> {
+        let (purpose, payload) = decode_text(token)?;
+        Token::decode(purpose, &payload).ok_or(atoms::malformed())?;
+        Ok(Opened { purpose, payload })
+    }
+
+    fn token(&self) -> Token<'_> {
+        Token::decode(self.purpose, &self.payload).expect("decoded when opened")
+    }
+
+    pub fn proof(&self) -> Proof<'_> {
+        self.token().proof
+    }
+
+    pub fn proof_bytes(&self) -> &[u8] {
+        &self.payload[8 + self.purpose.tag_len()..]
+    }
+}
+
+/// Decodes a token and checks its tag and timestamp under the options of `token_verify`,
+/// leaving its proof to the caller
+pub fn authenticate(token: &str, opts: &Options) -> NifResult<Result<Opened, Atom>> {
+    let key: Option<Binary> = opts.get(atoms::key())?;
+    let public_key: Option<Binary> = opts.get(atoms::public_key())?;
+    let max_age = opts.get_or(atoms::max_age(), DEFAULT_MAX_AGE)?;
+    let max_drift = opts.get_or(atoms::max_future_drift(), DEFAULT_MAX_FUTURE_DRIFT)?;
+    let now = opts.get::<u64>(atoms::now())?.unwrap_or_else(clock::now);
+    if key.is_none() && public_key.is_none() {
+        return Err(Error::BadArg);
+    }
+
+    let opened = match Opened::decode(token) {
+        Ok(opened) => opened,
+        Err(reason) => return Ok(Err(reason)),
+    };
+    let decoded = opened.token();
+
+    let signed = match (decoded.purpose, key, public_key) {
+        (Purpose::Local, Some(key), _) => !key.is_empty() && decoded.mac_matches(key.as_slice()),
+        (Purpose::Public, _, Some(public_key)) => {
+            decoded.signature_matches(public_key.as_slice())
+        }
+        _ => return Ok(Err(atoms::wrong_purpose())),
+    };
+    if !signed {
+        return Ok(Err(atoms::bad_signature()));
+    }
+    if now.saturating_sub(decoded.timestamp) > max_age {
+        return Ok(Err(atoms::too_old()));
+    }
+    if decoded.timestamp > now.saturating_add(max_drift) {
+        return Ok(Err(atoms::too_far_in_future()));
+    }
+    Ok(Ok(opened))
+}
+
+/// Decodes a token without checking its tag, timestamp or proof
+#[rustler::nif]
+fn token_decode<'a>(env: Env<'a>, token: &str) -> Guarded<Result<TokenFields<'a>, Atom>> {
+    guard::run(|| {
+        let opened = Opened::decode(token)?;
+        Ok(TokenFields::new(env, &opened.token()))
+    })
+}
+
+/// Checks a token's tag under `:key` (`v1.local`) or `:public_key` (`v1.public`), its age
+/// against `:max_age` and `:max_future_drift` seconds from `:now`, then its proof under the
+/// policy. A token whose purpose has no key among the options is `:wrong_purpose`
+#[rustler::nif(schedule = "DirtyCpu")]
+fn token_verify<'a>(
+    env: Env<'a>,
+    token: &str,
+    policy: ResourceArc<Policy>,
+    opts: Options,
+) -> NifResult<Guarded<Result<TokenFields<'a>, Atom>>> {
+    guard::run_result(|| {
+        let opened = match authenticate(token, &opts)? {
+            Ok(opened) => opened,
+            Err(reason) => return Ok(Err(reason)),
+        };
+        if let Status::Error(reason) = proof::verify_encoded(opened.proof_bytes(), &policy) {
+            return Ok(Err(reason));
+        }
+        Ok(Ok(TokenFields::new(env, &opened.token())))
+    })
+}
+
+#[derive(NifMap)]
+struct Keypair<'a> {
+    secret_key: Binary<'a>,
+    public_key: Binary<'a>,
+}
+
+/// Generates an Ed25519 key pair for `v1.public` tokens
+#[rustler::nif]
+fn token_keypair(env: Env) -> Guarded<Keypair> {
+    guard::run(|| {
+        let mut seed = Zeroizing::new([0u8; KEY_LEN]);
+        rand::thread_rng().fill_bytes(seed.as_mut());
+        let signing_key = SigningKey::from_bytes(&seed);
+
+        Keypair {
+            secret_key: binary(env, &signing_key.to_bytes()),
+            public_key: binary(env, signing_key.verifying_key().as_bytes()),
+        }
+    })
+}
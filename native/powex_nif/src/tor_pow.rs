@@ -0,0 +1,185 @@
+use equix::{EquiX, Solution, SolutionByteArray};
+use rand::RngCore;
+use rustler::{Binary, Env, NewBinary, NifMap, NifResult};
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::metrics;
+use crate::options::Options;
+use crate::seeded;
+
+/// Personalization string starting every Tor onion service PoW v1 challenge
+const PERSONALIZATION: &[u8; 16] = b"Tor hs intro v1\0";
+
+/// Blinded service identity key, seed and client nonce sizes of the v1 scheme
+const ID_SIZE: usize = 32;
+const SEED_SIZE: usize = 32;
+const NONCE_SIZE: usize = 16;
+
+/// `P || ID || C || N || INT_32(E)`
+const CHALLENGE_SIZE: usize = PERSONALIZATION.len() + ID_SIZE + SEED_SIZE + NONCE_SIZE + 4;
+
+/// Leading seed bytes the client echoes so the service knows which seed it used
+const SEED_HEAD_SIZE: usize = 4;
+
+/// Nonces tried by `onion_pow_solve` before it gives up
+const DEFAULT_MAX_ATTEMPTS: u64 = 1_000_000;
+
+/// Builds the EquiX challenge of an INTRODUCE2 proof-of-work extension
+fn challenge(id: &[u8], seed: &[u8], nonce: &[u8; NONCE_SIZE], effort: u32) -> Vec<u8> {
+    let mut challenge = Vec::with_capacity(CHALLENGE_SIZE);
+    challenge.extend_from_slice(PERSONALIZATION);
+    challenge.extend_from_slice(id);
+    challenge.extend_from_slice(seed);
+    challenge.extend_from_slice(nonce);
+    challenge.extend_from_slice(&effort.to_be_bytes());
+    challenge
+}
+
+/// Tor's effort check: `R * E <= UINT32_MAX` with `R` the big-endian 32-bit
+/// BLAKE2b of the challenge and solution
+fn meets_effort(challenge: &[u8], solution: &SolutionByteArray, effort: u32) -> bool {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(4)
+        .to_state()
+        .update(challenge)
+        .update(solution)
+        .finalize();
+    let mut r = [0u8; 4];
+    r.copy_from_slice(hash.as_bytes());
+    u32::from_be_bytes(r) as u64 * effort as u64 <= u32::MAX as u64
+}
+
+/// Increments the nonce as a little-endian 128-bit integer, as Tor's solver does
+fn increment(nonce: &mut [u8; NONCE_SIZE]) {
+    for byte in nonce.iter_mut() {
+        *byte = byte.wrapping_add(1);
+        if *byte != 0 {
+            break;
+        }
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.into()
+}
+
+/// Finds every EquiX solution for a challenge; some challenges have none, and a few
+/// make HashX generate an unusable program, which is reported as an error
+#[rustler::nif(schedule = "DirtyCpu")]
+fn equix_solve<'a>(env: Env<'a>, challenge: Binary) -> GuardedResult<Vec<Binary<'a>>> {
+    guard::run(|| {
+        let equix = EquiX::new(challenge.as_slice())
+            .map_err(|_| (atoms::error(), "Challenge yields no usable HashX program"))?;
+        Ok(equix
+            .solve()
+            .iter()
+            .map(|solution| to_binary(env, &solution.to_bytes()))
+            .collect())
+    })
+}
+
+/// Checks a 16-byte EquiX solution against its challenge
+#[rustler::nif(name = "equix_verify?")]
+fn equix_verify(challenge: Binary, solution: Binary) -> Guarded<bool> {
+    guard::run(|| {
+        let Ok(bytes) = SolutionByteArray::try_from(solution.as_slice()) else {
+            return false;
+        };
+        equix::verify_bytes(challenge.as_slice(), &bytes).is_ok()
+    })
+}
+
+/// The fields a client puts in the INTRODUCE2 proof-of-work extension
+#[derive(NifMap)]
+struct OnionPow<'a> {
+    nonce: Binary<'a>,
+    effort: u32,
+    seed_head: Binary<'a>,
+    solution: Binary<'a>,
+}
+
+/// Solves Tor's onion service PoW v1 for a blinded service key and seed: starting from a
+/// random nonce (drawn from `:deterministic_seed` if given), solves EquiX for each
+/// challenge until a solution meets the effort
+#[rustler::nif(schedule = "DirtyCpu")]
+fn onion_pow_solve<'a>(
+    env: Env<'a>,
+    blinded_id: Binary,
+    seed: Binary,
+    effort: u32,
+    opts: Options,
+) -> NifResult<GuardedResult<OnionPow<'a>>> {
+    guard::run_result(|| {
+        let max_attempts = opts.get_or(atoms::max_attempts(), DEFAULT_MAX_ATTEMPTS)?;
+        let deterministic_seed: Option<u64> = opts.get(atoms::deterministic_seed())?;
+
+        if blinded_id.len() != ID_SIZE || seed.len() != SEED_SIZE {
+            return Ok(Err((
+                atoms::error(),
+                "Blinded id and seed must be 32 bytes each",
+            )));
+        }
+
+        let mut nonce = [0u8; NONCE_SIZE];
+        seeded::Source::new(deterministic_seed).fill_bytes(&mut nonce);
+
+        let mut meter = metrics::Meter::new();
+        for _ in 0..max_attempts {
+            meter.tick();
+            let challenge = challenge(blinded_id.as_slice(), seed.as_slice(), &nonce, effort);
+            // Challenges whose HashX program is unusable have no solutions
+            if let Ok(equix) = EquiX::new(&challenge) {
+                let solutions = equix.solve();
+                let found = solutions
+                    .iter()
+                    .map(Solution::to_bytes)
+                    .find(|solution| meets_effort(&challenge, solution, effort));
+                if let Some(solution) = found {
+                    return Ok(Ok(OnionPow {
+                        nonce: to_binary(env, &nonce),
+                        effort,
+                        seed_head: to_binary(env, &seed.as_slice()[..SEED_HEAD_SIZE]),
+                        solution: to_binary(env, &solution),
+                    }));
+                }
+            }
+            increment(&mut nonce);
+        }
+
+        Ok(Err((
+            atoms::error(),
+            "No solution met the effort within max_attempts",
+        )))
+    })
+}
+
+/// Verifies an onion service PoW v1 proof against the seed it names: the EquiX solution
+/// must be valid for the challenge and meet the claimed effort. Matching the seed head,
+/// enforcing a minimum effort and rejecting replayed nonces are left to the caller
+#[rustler::nif(name = "onion_pow_valid?")]
+fn onion_pow_valid(
+    blinded_id: Binary,
+    seed: Binary,
+    nonce: Binary,
+    effort: u32,
+    solution: Binary,
+) -> Guarded<bool> {
+    guard::run(|| {
+        let (Ok(nonce), Ok(solution)) = (
+            <[u8; NONCE_SIZE]>::try_from(nonce.as_slice()),
+            SolutionByteArray::try_from(solution.as_slice()),
+        ) else {
+            return false;
+        };
+        if blinded_id.len() != ID_SIZE || seed.len() != SEED_SIZE {
+            return false;
+        }
+
+        let challenge = challenge(blinded_id.as_slice(), seed.as_slice(), &nonce, effort);
+        meets_effort(&challenge, &solution, effort)
+            && equix::verify_bytes(&challenge, &solution).is_ok()
+    })
+}
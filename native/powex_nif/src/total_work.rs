@@ -0,0 +1,39 @@
+use rustler::{Binary, Error, NifResult, Term};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use crate::backend::leading_zero_bits;
+use crate::guard::{self, Guarded};
+
+/// Expected hashes behind a proof: 2^n for a hash with n leading zero bits
+fn achieved_work(data: &[u8], nonce: u64) -> f64 {
+    let digest = Sha256::new()
+        .chain_update(data)
+        .chain_update(nonce.to_le_bytes())
+        .finalize();
+    2f64.powi(leading_zero_bits(&digest) as i32)
+}
+
+/// Sums the work achieved by `{data, nonce}` proofs, counting repeated proofs once, and
+/// accepts if the total reaches `2^min_total_bits` expected hashes
+#[rustler::nif(schedule = "DirtyCpu")]
+fn verify_total_work(proofs: Vec<(Binary, u64)>, min_total_bits: Term) -> NifResult<Guarded<bool>> {
+    guard::run_result(|| {
+        let min_total_bits = match min_total_bits.decode::<u32>() {
+            Ok(bits) => bits as f64,
+            Err(_) => min_total_bits.decode::<f64>()?,
+        };
+        if !min_total_bits.is_finite() {
+            return Err(Error::BadArg);
+        }
+
+        let mut seen = HashSet::with_capacity(proofs.len());
+        let total: f64 = proofs
+            .iter()
+            .filter(|(data, nonce)| seen.insert((data.as_slice(), *nonce)))
+            .map(|(data, nonce)| achieved_work(data.as_slice(), *nonce))
+            .sum();
+
+        Ok(total > 0.0 && total.log2() >= min_total_bits)
+    })
+}
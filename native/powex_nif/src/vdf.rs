@@ -0,0 +1,151 @@
+use num_bigint::BigUint;
+use rustler::{Binary, Env, NewBinary};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+use crate::atoms;
+use crate::guard::{self, Guarded, GuardedResult};
+use crate::primes::is_probable_prime;
+
+/// RSA-2048 challenge modulus, whose factorization is unknown
+const MODULUS_HEX: &str = concat!(
+    "c7970ceedcc3b0754490201a7aa613cd73911081c790f5f1a8726f463550bb5b",
+    "7ff0db8e1ea1189ec72f93d1650011bd721aeeacc2acde32a04107f0648c2813",
+    "a31f5b0b7765ff8b44b4b6ffc93384b646eb09c7cf5e8592d40ea33c80039f35",
+    "b4f14a04b51f7bfd781be4d1673164ba8eb991c2c4d730bbbe35f592bdef524a",
+    "f7e8daefd26c66fc02c479af89d64d373f442709439de66ceb955f3ea37d5159",
+    "f6135809f85334b5cb1813addc80cd05609f10ac6a95ad65872c909525bdad32",
+    "bc729592642920f24c61dc5b3c3b7923e56b16a4d9d373d8721f24a3fc0f1b31",
+    "31f55615172866bccc30f95054c824e733a5eb6817f7bc16399d48c6361cc7e5",
+);
+
+/// Size in bytes of group elements (output and proof)
+const ELEMENT_SIZE: usize = 256;
+
+fn modulus() -> &'static BigUint {
+    static MODULUS: OnceLock<BigUint> = OnceLock::new();
+    MODULUS.get_or_init(|| BigUint::parse_bytes(MODULUS_HEX.as_bytes(), 16).unwrap())
+}
+
+/// Maps an element to its class in the quotient group Z_N* / {±1}
+fn normalize(x: BigUint) -> BigUint {
+    let n = modulus();
+    let negated = n - &x;
+    x.min(negated)
+}
+
+/// Hashes the data into the group by expanding SHA-256 beyond the modulus size
+fn hash_to_group(data: &[u8]) -> BigUint {
+    let mut wide = Vec::with_capacity(ELEMENT_SIZE + 32);
+    let mut counter = 0u32;
+    while wide.len() < ELEMENT_SIZE + 16 {
+        let block = Sha256::new()
+            .chain_update(b"powex-vdf-group")
+            .chain_update(counter.to_be_bytes())
+            .chain_update(data)
+            .finalize();
+        wide.extend_from_slice(&block);
+        counter += 1;
+    }
+    normalize(BigUint::from_bytes_be(&wide) % modulus())
+}
+
+/// Derives the 128-bit Fiat-Shamir challenge prime from the input and output
+fn hash_to_prime(g: &BigUint, y: &BigUint) -> BigUint {
+    for counter in 0u64.. {
+        let digest = Sha256::new()
+            .chain_update(b"powex-vdf-prime")
+            .chain_update(counter.to_be_bytes())
+            .chain_update(encode(g))
+            .chain_update(encode(y))
+            .finalize();
+        let mut candidate = BigUint::from_bytes_be(&digest[..16]);
+        candidate.set_bit(127, true);
+        candidate.set_bit(0, true);
+        if is_probable_prime(&candidate) {
+            return candidate;
+        }
+    }
+    unreachable!("prime search exhausted")
+}
+
+fn encode(x: &BigUint) -> [u8; ELEMENT_SIZE] {
+    let bytes = x.to_bytes_be();
+    let mut out = [0u8; ELEMENT_SIZE];
+    out[ELEMENT_SIZE - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+fn decode(bytes: &[u8]) -> Option<BigUint> {
+    if bytes.len() != ELEMENT_SIZE {
+        return None;
+    }
+    let x = BigUint::from_bytes_be(bytes);
+    (x > BigUint::ZERO && &x < modulus()).then_some(x)
+}
+
+fn to_binary<'a>(env: Env<'a>, x: &BigUint) -> Binary<'a> {
+    let mut binary = NewBinary::new(env, ELEMENT_SIZE);
+    binary.as_mut_slice().copy_from_slice(&encode(x));
+    binary.into()
+}
+
+/// Evaluates the VDF: y = g^(2^T) with a Wesolowski proof pi = g^floor(2^T / l)
+#[rustler::nif(schedule = "DirtyCpu")]
+fn vdf_eval<'a>(
+    env: Env<'a>,
+    data: Binary,
+    iterations: u64,
+) -> GuardedResult<(Binary<'a>, Binary<'a>)> {
+    guard::run(|| {
+        if iterations == 0 {
+            return Err((atoms::error(), "Iterations must be positive"));
+        }
+
+        let n = modulus();
+        let g = hash_to_group(data.as_slice());
+
+        let mut y = g.clone();
+        for _ in 0..iterations {
+            y = (&y * &y) % n;
+        }
+        let y = normalize(y);
+        let l = hash_to_prime(&g, &y);
+
+        // Long division of 2^T by l, one bit of the quotient per squaring
+        let mut proof = BigUint::from(1u8);
+        let mut remainder = BigUint::from(1u8);
+        for _ in 0..iterations {
+            remainder <<= 1;
+            proof = (&proof * &proof) % n;
+            if remainder >= l {
+                remainder -= &l;
+                proof = (&proof * &g) % n;
+            }
+        }
+        let proof = normalize(proof);
+
+        Ok((to_binary(env, &y), to_binary(env, &proof)))
+    })
+}
+
+/// Verifies a VDF output and proof with two small exponentiations
+#[rustler::nif]
+fn vdf_verify(data: Binary, iterations: u64, output: (Binary, Binary)) -> Guarded<bool> {
+    guard::run(|| {
+        let (Some(y), Some(proof)) = (decode(output.0.as_slice()), decode(output.1.as_slice()))
+        else {
+            return false;
+        };
+        if iterations == 0 {
+            return false;
+        }
+
+        let n = modulus();
+        let g = hash_to_group(data.as_slice());
+        let l = hash_to_prime(&g, &y);
+        let r = BigUint::from(2u8).modpow(&BigUint::from(iterations), &l);
+
+        normalize((proof.modpow(&l, n) * g.modpow(&r, n)) % n) == y
+    })
+}
@@ -0,0 +1,76 @@
+use rustler::{Binary, Env, NewBinary, NifMap, NifResult};
+use sha2::{Digest, Sha256};
+
+use crate::algorithm::Algorithm;
+use crate::atoms;
+use crate::difficulty::Difficulty;
+use crate::guard::{self, GuardedResult};
+use crate::jobs;
+use crate::options::Options;
+
+/// Seed used when none is given, so published vectors can be regenerated anywhere
+const DEFAULT_SEED: &[u8] = b"powex test vectors";
+
+/// Upper bound on the number of vectors per call
+const MAX_VECTORS: usize = 10_000;
+
+/// One known-answer test vector
+#[derive(NifMap)]
+struct TestVector<'a> {
+    data: Binary<'a>,
+    nonce: u64,
+    hash: String,
+    difficulty: u32,
+}
+
+/// Derives the data of vector `index`: `sha256(seed || index_be)` repeated and cut to
+/// `index * 7 % 160` bytes, so lengths cover empty, partial and multi-block inputs
+fn vector_data(seed: &[u8], index: u64) -> Vec<u8> {
+    let block: [u8; 32] = Sha256::new()
+        .chain_update(seed)
+        .chain_update(index.to_be_bytes())
+        .finalize()
+        .into();
+    let len = (index * 7 % 160) as usize;
+    block.iter().copied().cycle().take(len).collect()
+}
+
+/// Generates deterministic known-answer vectors: vector `i` requires exactly `i % 4`
+/// leading hex zeros and carries the lowest nonce meeting it
+#[rustler::nif(schedule = "DirtyCpu")]
+fn test_vectors<'a>(
+    env: Env<'a>,
+    algorithm: Algorithm,
+    count: usize,
+    opts: Options,
+) -> NifResult<GuardedResult<Vec<TestVector<'a>>>> {
+    guard::run_result(|| {
+        let seed: Option<Binary> = opts.get(atoms::seed())?;
+        let seed = seed.as_ref().map_or(DEFAULT_SEED, |seed| seed.as_slice());
+
+        if count > MAX_VECTORS {
+            return Ok(Err((atoms::error(), "Too many vectors (max 10000)")));
+        }
+
+        let mut vectors = Vec::with_capacity(count);
+        for index in 0..count as u64 {
+            let data = vector_data(seed, index);
+            let zeros = (index % 4) as u32;
+            let nonce = match jobs::search(&algorithm, &data, &Difficulty::Zeros(zeros)) {
+                Ok(nonce) => nonce,
+                Err(reason) => return Ok(Err(reason)),
+            };
+
+            let mut binary = NewBinary::new(env, data.len());
+            binary.as_mut_slice().copy_from_slice(&data);
+            vectors.push(TestVector {
+                data: binary.into(),
+                nonce,
+                hash: hex::encode(algorithm.prepare(&data).digest(nonce)),
+                difficulty: zeros,
+            });
+        }
+
+        Ok(Ok(vectors))
+    })
+}
@@ -0,0 +1,325 @@
+//! yespower 1.0, the CPU-friendly and GPU-unfriendly scrypt successor from
+//! <https://www.openwall.com/yespower/>, ported from the reference implementation.
+//!
+//! Blocks are kept in the SIMD-shuffled word order of the reference code, where word `k`
+//! of a 64-byte block holds little-endian word `5k mod 16` of its serialized form.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::cell::RefCell;
+
+/// A 64-byte Salsa20 block in shuffled word order
+type Block = [u32; 16];
+
+/// log2 of the number of 16-byte entries in each of the three pwxform S-boxes
+const SWIDTH: u32 = 11;
+
+/// 64-bit lanes in each S-box
+const SBOX_LANES: usize = (1 << SWIDTH) * 2;
+
+/// Mask selecting a 16-byte-aligned byte offset within an S-box
+const SMASK: u64 = (((1 << SWIDTH) - 1) * 2 * 8) as u64;
+
+/// pwxform rounds per 64-byte block
+const PWX_ROUNDS: usize = 3;
+
+/// Longest personalization string accepted
+const MAX_PERS_LEN: usize = 256;
+
+/// Cost parameters and personalization of a yespower 1.0 hash
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Params {
+    n: u32,
+    r: u32,
+    pers: Vec<u8>,
+}
+
+impl Params {
+    /// `N = 2048`, `r = 8` and no personalization: 2 MiB per hash
+    pub const DEFAULT: Params = Params {
+        n: 2048,
+        r: 8,
+        pers: Vec::new(),
+    };
+
+    /// Checks the ranges of the reference implementation: `N` a power of two from 1024 to
+    /// 524288, `r` from 8 to 32, and a personalization string of at most 256 bytes
+    pub fn new(n: u32, r: u32, pers: &[u8]) -> Option<Params> {
+        let valid = (1024..=512 * 1024).contains(&n)
+            && n.is_power_of_two()
+            && (8..=32).contains(&r)
+            && pers.len() <= MAX_PERS_LEN;
+        valid.then(|| Params {
+            n,
+            r,
+            pers: pers.to_vec(),
+        })
+    }
+
+    pub fn n(&self) -> u32 {
+        self.n
+    }
+
+    pub fn r(&self) -> u32 {
+        self.r
+    }
+
+    pub fn pers(&self) -> &[u8] {
+        &self.pers
+    }
+
+    /// Finishes yespower for an input whose SHA-256 digest is `sha256`, so callers can
+    /// absorb a fixed prefix once and only hash the nonce per candidate
+    pub fn hash(&self, sha256: &[u8]) -> [u8; 32] {
+        SCRATCH.with(|scratch| self.hash_with(&mut scratch.borrow_mut(), sha256))
+    }
+
+    fn hash_with(&self, scratch: &mut Scratch, sha256: &[u8]) -> [u8; 32] {
+        let r = self.r as usize;
+        let n = self.n as usize;
+
+        // B is the full 128r-byte PBKDF2 output, as in the reference `yespower`
+        let mut initial = vec![0u8; 128 * r];
+        pbkdf2::pbkdf2_hmac::<Sha256>(sha256, &self.pers, 1, &mut initial);
+        let mut b: Vec<Block> = initial.chunks(64).map(shuffle).collect();
+
+        scratch.v.resize(2 * r * n, [0; 16]);
+        smix(&mut b, r, n, scratch);
+
+        let key = unshuffle(&b[2 * r - 1]);
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key).expect("HMAC takes any key length");
+        mac.update(&initial[..32]);
+        mac.finalize().into_bytes().into()
+    }
+}
+
+/// Memory reused across hashes on the same thread, like the reference `yespower_tls`
+struct Scratch {
+    v: Vec<Block>,
+    sbox: Vec<Block>,
+}
+
+thread_local! {
+    static SCRATCH: RefCell<Scratch> = const {
+        RefCell::new(Scratch {
+            v: Vec::new(),
+            sbox: Vec::new(),
+        })
+    };
+}
+
+/// Reads 64 serialized bytes into shuffled word order
+fn shuffle(bytes: &[u8]) -> Block {
+    let mut block = [0u32; 16];
+    for (k, word) in block.iter_mut().enumerate() {
+        let i = k * 5 % 16;
+        *word = u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap_or_default());
+    }
+    block
+}
+
+/// Serializes a shuffled block back to 64 bytes
+fn unshuffle(block: &Block) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    for (k, word) in block.iter().enumerate() {
+        let i = k * 5 % 16;
+        bytes[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    bytes
+}
+
+fn xor(x: &mut Block, y: &Block) {
+    for (x, y) in x.iter_mut().zip(y) {
+        *x ^= y;
+    }
+}
+
+/// Salsa20 with a single double round, yespower 1.0's `H`
+fn salsa20_2(b: &mut Block) {
+    let mut x = [0u32; 16];
+    for (i, word) in b.iter().enumerate() {
+        x[i * 5 % 16] = *word;
+    }
+
+    let mut quarter = |a: usize, b: usize, c: usize, d: usize| {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    };
+    quarter(0, 4, 8, 12);
+    quarter(5, 9, 13, 1);
+    quarter(10, 14, 2, 6);
+    quarter(15, 3, 7, 11);
+    quarter(0, 1, 2, 3);
+    quarter(5, 6, 7, 4);
+    quarter(10, 11, 8, 9);
+    quarter(15, 12, 13, 14);
+
+    for (i, word) in b.iter_mut().enumerate() {
+        *word = word.wrapping_add(x[i * 5 % 16]);
+    }
+}
+
+/// scrypt's BlockMix with `r = 1`, used to fill the S-boxes
+fn blockmix_salsa(b: &mut [Block]) {
+    let mut x = b[1];
+    for block in b.iter_mut() {
+        xor(&mut x, block);
+        salsa20_2(&mut x);
+        *block = x;
+    }
+}
+
+/// The three pwxform S-boxes as 64-bit lanes, rotated after every block, and the write cursor
+struct Sboxes {
+    lanes: Vec<u64>,
+    s0: usize,
+    s1: usize,
+    s2: usize,
+    w: usize,
+}
+
+impl Sboxes {
+    /// Takes the S-boxes from the blocks filled by the first `smix1`
+    fn new(blocks: &[Block]) -> Sboxes {
+        let lanes = blocks
+            .iter()
+            .flat_map(|block| block.chunks(2))
+            .map(|pair| pair[0] as u64 | (pair[1] as u64) << 32)
+            .collect();
+        Sboxes {
+            lanes,
+            s0: 0,
+            s1: SBOX_LANES,
+            s2: 2 * SBOX_LANES,
+            w: 0,
+        }
+    }
+
+    /// Applies pwxform to one 64-byte block, writing some of its state into the S-boxes
+    fn pwxform(&mut self, block: &mut Block) {
+        let mut x = [0u64; 8];
+        for (lane, pair) in x.iter_mut().zip(block.chunks(2)) {
+            *lane = pair[0] as u64 | (pair[1] as u64) << 32;
+        }
+
+        for round in 0..PWX_ROUNDS {
+            for gather in 0..4 {
+                let lo = x[2 * gather];
+                let p0 = self.s0 + ((lo & SMASK) >> 3) as usize;
+                let p1 = self.s1 + (((lo >> 32) & SMASK) >> 3) as usize;
+
+                for k in 0..2 {
+                    let lane = x[2 * gather + k];
+                    x[2 * gather + k] = ((lane >> 32) * (lane & 0xffff_ffff))
+                        .wrapping_add(self.lanes[p0 + k])
+                        ^ self.lanes[p1 + k];
+                }
+
+                if round == 0 || gather < 2 {
+                    let sbox = if gather & 1 == 0 { self.s0 } else { self.s1 };
+                    self.lanes[sbox + self.w] = x[2 * gather];
+                    self.lanes[sbox + self.w + 1] = x[2 * gather + 1];
+                    if gather & 1 == 1 {
+                        self.w += 2;
+                    }
+                }
+            }
+        }
+
+        self.w &= SBOX_LANES - 1;
+        (self.s0, self.s1, self.s2) = (self.s2, self.s0, self.s1);
+
+        for (pair, lane) in block.chunks_mut(2).zip(x) {
+            pair[0] = lane as u32;
+            pair[1] = (lane >> 32) as u32;
+        }
+    }
+
+    /// BlockMix_pwxform over `2r` 64-byte blocks
+    fn blockmix(&mut self, b: &mut [Block]) {
+        let last = b.len() - 1;
+        let mut x = b[last];
+        for (i, block) in b.iter_mut().enumerate() {
+            xor(&mut x, block);
+            self.pwxform(&mut x);
+            if i == last {
+                salsa20_2(&mut x);
+            }
+            *block = x;
+        }
+    }
+}
+
+/// Reads the last block's first word as the little-endian integer of the reference code
+fn integerify(x: &[Block]) -> usize {
+    x[x.len() - 1][0] as usize
+}
+
+/// Maps `x` into the `i` blocks written so far, preferring the most recent power of two of them
+fn wrap(x: usize, i: usize) -> usize {
+    let n = 1 << (usize::BITS - 1 - i.leading_zeros());
+    (x & (n - 1)) + (i - n)
+}
+
+/// Mixing function `H`: pwxform once the S-boxes exist, plain Salsa20 while filling them
+fn mix(x: &mut [Block], sboxes: &mut Option<Sboxes>) {
+    match sboxes {
+        Some(sboxes) => sboxes.blockmix(x),
+        None => blockmix_salsa(x),
+    }
+}
+
+/// First SMix loop: fills `v` with `n` sequential states of `2r` blocks
+fn smix1(b: &mut [Block], r: usize, n: usize, v: &mut [Block], sboxes: &mut Option<Sboxes>) {
+    let s = 2 * r;
+    let x = &mut b[..s];
+
+    if let Some(sboxes) = sboxes {
+        for k in 1..r {
+            let (done, next) = x.split_at_mut(2 * k);
+            next[..2].copy_from_slice(&done[2 * k - 2..]);
+            sboxes.blockmix(&mut next[..2]);
+        }
+    }
+
+    for i in 0..n {
+        v[i * s..(i + 1) * s].copy_from_slice(x);
+        if i > 1 {
+            let j = wrap(integerify(x), i);
+            for (x, y) in x.iter_mut().zip(&v[j * s..(j + 1) * s]) {
+                xor(x, y);
+            }
+        }
+        mix(x, sboxes);
+    }
+}
+
+/// Second SMix loop: `nloop` data-dependent reads and writes of `v`
+fn smix2(b: &mut [Block], r: usize, n: usize, nloop: usize, v: &mut [Block], sboxes: &mut Sboxes) {
+    let s = 2 * r;
+    for _ in 0..nloop {
+        let j = integerify(b) & (n - 1);
+        let v_j = &mut v[j * s..(j + 1) * s];
+        for (x, y) in b.iter_mut().zip(v_j.iter_mut()) {
+            xor(x, y);
+            *y = *x;
+        }
+        sboxes.blockmix(b);
+    }
+}
+
+fn smix(b: &mut [Block], r: usize, n: usize, scratch: &mut Scratch) {
+    let sbox_blocks = 3 * SBOX_LANES / 8;
+    scratch.sbox.resize(sbox_blocks, [0; 16]);
+    smix1(&mut b[..2], 1, sbox_blocks / 2, &mut scratch.sbox, &mut None);
+
+    let mut sboxes = Some(Sboxes::new(&scratch.sbox));
+    smix1(b, r, n, &mut scratch.v, &mut sboxes);
+
+    let nloop = (n.div_ceil(3) + 1) & !1;
+    if let Some(sboxes) = &mut sboxes {
+        smix2(b, r, n, nloop, &mut scratch.v, sboxes);
+    }
+}
@@ -2,6 +2,8 @@ defmodule POWTest do
   use ExUnit.Case
   doctest Powex
 
+  @genesis_merkle_root Base.decode16!("3BA3EDFD7A7B12B27AC72C3E67768F617FC81BC3888A51323A9FB8AA4B1E5E4A")
+
   describe "compute/2" do
     test "computes valid nonce for difficulty 0" do
       assert {:ok, nonce} = Powex.compute("test data", 0)
@@ -43,6 +45,129 @@ defmodule POWTest do
       assert {:ok, nonce} = Powex.compute("", 1)
       assert Powex.valid?("", nonce, 1)
     end
+
+    test "compute_detailed/3 returns a self-describing map" do
+      assert {:ok, result} = Powex.compute_detailed("detailed", 3)
+      assert %{nonce: nonce, hash: hash, attempts: attempts, algorithm: :sha256, difficulty: 3} = result
+      assert {:ok, ^nonce} = Powex.compute("detailed", 3)
+      assert {:ok, ^hash} = Powex.get_hash("detailed", nonce)
+      assert attempts == nonce + 1
+
+      assert {:ok, %{hash: <<_::binary-size(32)>>}} = Powex.compute_detailed("detailed", 3, encoding: :raw)
+      assert {:error, _reason} = Powex.compute_detailed("detailed", 65)
+    end
+
+    test "compute_detailed/3 stops at the deadline" do
+      assert {:error, :timeout} = Powex.compute_detailed("deadline", 20, timeout: 50)
+    end
+
+    test "compute_detailed/3 returns the best hash so far on timeout" do
+      assert {:best_so_far, %{nonce: nonce, hash: hash, attempts: attempts}} =
+               Powex.compute_detailed("deadline", 20, timeout: 50, best_so_far: true)
+
+      assert {:ok, ^hash} = Powex.get_hash("deadline", nonce)
+      assert nonce < attempts
+
+      # No earlier nonce hashed lower
+      for earlier <- Enum.take_every(0..(attempts - 1), max(div(attempts, 200), 1)) do
+        {:ok, other} = Powex.get_hash("deadline", earlier)
+        assert hash <= other
+      end
+    end
+
+    test "compute_detailed/3 still returns solutions found before the deadline" do
+      assert {:ok, %{nonce: nonce}} = Powex.compute_detailed("deadline", 2, timeout: 5_000, best_so_far: true)
+      assert Powex.valid?("deadline", nonce, 2)
+    end
+
+    test "accepts a hex target" do
+      target = "000fff" <> String.duplicate("f", 58)
+
+      assert {:ok, nonce} = Powex.compute("hex target", target)
+      assert {:ok, hash} = Powex.get_hash("hex target", nonce)
+      assert hash <= target
+    end
+
+    test "accepts a minimum number of leading zero bits" do
+      assert {:ok, nonce} = Powex.compute("zero bits", {:zero_bits, 10})
+      assert {:ok, hash} = Powex.get_hash("zero bits", nonce, encoding: :raw)
+      assert leading_zero_bits(hash) >= 10
+      assert Powex.valid?("zero bits", nonce, {:zero_bits, 10})
+    end
+
+    test "treats a float difficulty as a precise target" do
+      # 16^2.5 = 1024 expected hashes: target 2^246 - 1
+      target = "003" <> String.duplicate("f", 61)
+
+      assert {:ok, nonce} = Powex.compute("fractional", 2.5)
+      assert {:ok, hash} = Powex.get_hash("fractional", nonce)
+      assert hash <= target
+      assert Powex.valid?("fractional", nonce, 2.5)
+      assert Powex.valid?("fractional", nonce, target)
+      assert {:error, {:error, "Difficulty too high (max 64 hex zeros)"}} = Powex.compute("fractional", 64.5)
+    end
+
+    test "accepts trailing and infix zeros" do
+      assert {:ok, nonce} = Powex.compute("suffix", {:trailing_zeros, 3})
+      assert {:ok, hash} = Powex.get_hash("suffix", nonce)
+      assert String.ends_with?(hash, "000")
+      assert Powex.valid?("suffix", nonce, {:trailing_zeros, 3})
+
+      assert {:ok, nonce} = Powex.compute("infix", {:zeros_at, 10, 3})
+      assert {:ok, hash} = Powex.get_hash("infix", nonce)
+      assert binary_part(hash, 10, 3) == "000"
+      assert Powex.valid?("infix", nonce, {:zeros_at, 10, 3})
+
+      assert {:error, {:error, "Zero range must lie within the 64 hex digits of the hash"}} =
+               Powex.compute("infix", {:zeros_at, 62, 3})
+    end
+
+    test "accepts a byte mask and value" do
+      difficulty = {<<0xF0, 0, 0xFF>>, <<0, 0, 0xAB>>}
+
+      assert {:ok, nonce} = Powex.compute("mask", difficulty)
+      assert {:ok, <<top::4, _::12, 0xAB, _::binary>>} = Powex.get_hash("mask", nonce, encoding: :raw)
+      assert top == 0
+      assert Powex.valid?("mask", nonce, difficulty)
+      assert {:error, {:error, "Mask value has bits outside the mask"}} = Powex.compute("mask", {<<0xF0>>, <<0x01>>})
+      assert_raise ArgumentError, fn -> Powex.compute("mask", {<<0xF0>>, <<>>}) end
+    end
+
+    test "rejects difficulties beyond each mode's limit" do
+      assert {:error, {:error, "Difficulty too high (max 64 hex zeros)"}} = Powex.compute("limits", 65)
+      assert {:error, {:error, "Difficulty too high (max 256 zero bits)"}} = Powex.compute("limits", {:zero_bits, 257})
+      assert {:error, {:error, "Target must not be zero"}} = Powex.compute("limits", String.duplicate("0", 64))
+      assert_raise ArgumentError, fn -> Powex.compute("limits", {:one_bits, 1}) end
+    end
+  end
+
+  describe "compute_multi/2" do
+    test "finds a nonce valid for one of several data values" do
+      challenges = ["rotation 1", "rotation 2", "rotation 3"]
+
+      assert {:ok, {nonce, index}} = Powex.compute_multi(challenges, 3)
+      assert Powex.valid?(Enum.at(challenges, index), nonce, 3)
+    end
+
+    test "finds a nonce valid for one of several difficulties" do
+      difficulties = [4, 2]
+
+      assert {:ok, {nonce, index}} = Powex.compute_multi("multi difficulty", difficulties)
+      assert Powex.valid?("multi difficulty", nonce, Enum.at(difficulties, index))
+    end
+
+    test "returns the first nonce matching any target" do
+      {:ok, nonce_a} = Powex.compute("target a", 2)
+      {:ok, nonce_b} = Powex.compute("target b", 2)
+
+      assert {:ok, {nonce, _index}} = Powex.compute_multi([{"target a", 2}, {"target b", 2}])
+      assert nonce == min(nonce_a, nonce_b)
+    end
+
+    test "returns error for empty targets and high difficulty" do
+      assert {:error, _reason} = Powex.compute_multi([])
+      assert {:error, _reason} = Powex.compute_multi("test", [2, 65])
+    end
   end
 
   describe "valid?/3" do
@@ -80,103 +205,2656 @@ defmodule POWTest do
       {:ok, nonce} = Powex.compute(data, difficulty)
       assert Powex.valid?(data, nonce, difficulty)
     end
+
+    test "compares hex targets as integers rather than exact zero counts" do
+      {:ok, nonce} = Powex.compute("hex validation", 4)
+      {:ok, hash} = Powex.get_hash("hex validation", nonce)
+
+      # The hash itself is the tightest target it meets
+      assert Powex.valid?("hex validation", nonce, hash)
+      assert Powex.valid?("hex validation", nonce, String.upcase(hash))
+      assert Powex.valid?("hex validation", nonce, "0000" <> String.duplicate("f", 60))
+      refute Powex.valid?("hex validation", nonce, String.duplicate("0", 64))
+    end
+
+    test "hashes multi-chunk data like a single pass" do
+      data = :binary.copy("chunked", 500_000)
+      {:ok, nonce} = Powex.compute(data, 1)
+
+      digest = :crypto.hash(:sha256, data <> <<nonce::64-little>>)
+      assert <<0::4, first::4, _::binary>> = digest
+      assert first != 0
+      assert Powex.valid?(data, nonce, 1)
+    end
+
+    test "raises on malformed hex targets" do
+      assert_raise ArgumentError, fn -> Powex.valid?("data", 0, "ffff") end
+      assert_raise ArgumentError, fn -> Powex.valid?("data", 0, String.duplicate("g", 64)) end
+    end
   end
 
-  describe "compute_parallel/3" do
-    test "computes valid nonce using parallel processing" do
-      data = "parallel test"
-      difficulty = 3
-      threads = 4
+  describe "verification cache" do
+    test "repeated verifications hit the cache" do
+      :ok = Powex.clear_cache()
+      {:ok, nonce} = Powex.compute("cached", 3)
 
-      assert {:ok, nonce} = Powex.compute_parallel(data, difficulty, threads)
-      assert is_integer(nonce)
-      assert Powex.valid?(data, nonce, difficulty)
+      assert Powex.valid?("cached", nonce, 3)
+      assert %{hits: 0, misses: 1, size: 1} = Powex.cache_stats()
+
+      assert Powex.valid?("cached", nonce, 3)
+      refute Powex.valid?("cached", nonce, 2)
+      assert %{hits: 1, misses: 2, size: 2} = Powex.cache_stats()
     end
 
-    test "returns error for invalid thread count" do
-      assert {:error, _reason} = Powex.compute_parallel("test", 2, 0)
-      assert {:error, _reason} = Powex.compute_parallel("test", 2, 100)
+    test "clear_cache/0 empties the cache and resets counters" do
+      Powex.valid?("cached", 1, 1)
+      assert :ok = Powex.clear_cache()
+      assert %{hits: 0, misses: 0, size: 0, capacity: capacity} = Powex.cache_stats()
+      assert capacity > 0
     end
 
-    test "returns error for extremely high difficulty" do
-      assert {:error, _reason} = Powex.compute_parallel("test", 65, 4)
+    test "the cache is bounded" do
+      :ok = Powex.clear_cache()
+      %{capacity: capacity} = Powex.cache_stats()
+
+      for nonce <- 0..(capacity * 2), do: Powex.valid?("bounded", nonce, 1)
+      assert %{size: size} = Powex.cache_stats()
+      assert size <= capacity
     end
+  end
 
-    test "works with single thread" do
-      data = "single thread"
-      difficulty = 2
+  describe "test_vectors/3" do
+    test "matches independently computed answers" do
+      {:ok, [_, second | _]} = Powex.test_vectors(:sha256, 2)
 
-      assert {:ok, nonce} = Powex.compute_parallel(data, difficulty, 1)
-      assert Powex.valid?(data, nonce, difficulty)
+      assert second == %{
+               data: Base.decode16!("76CBF1551C6798"),
+               nonce: 33,
+               difficulty: 1,
+               hash: "0d6fa9e111fe4ea757729ce6a43e4afc0e6a0b7d7622707b2e3fc8bd92116462"
+             }
+    end
+
+    test "vectors verify with valid?/3 and get_hash/3" do
+      {:ok, vectors} = Powex.test_vectors(:sha256, 12)
+
+      for {%{data: data, nonce: nonce, hash: hash, difficulty: difficulty}, i} <- Enum.with_index(vectors) do
+        assert byte_size(data) == rem(i * 7, 160)
+        assert difficulty == rem(i, 4)
+        assert Powex.valid?(data, nonce, difficulty)
+        assert {:ok, ^hash} = Powex.get_hash(data, nonce)
+      end
+    end
+
+    test "are deterministic per seed" do
+      assert Powex.test_vectors(:blake3, 6) == Powex.test_vectors(:blake3, 6)
+      assert Powex.test_vectors(:sha256, 6, seed: "other") != Powex.test_vectors(:sha256, 6)
+      assert {:ok, vectors} = Powex.test_vectors(:blake3, 6)
+      assert Enum.all?(vectors, &(byte_size(&1.hash) == 64))
+    end
+
+    test "poseidon matches the circom permutation" do
+      # Empty data absorbs to the length 0, and circom's Poseidon(0, 0) starts with a nonzero digit
+      assert {:ok, [%{data: "", nonce: 0, hash: hash}]} = Powex.test_vectors(:poseidon, 1)
+      assert hash == "2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864"
+    end
+
+    test "hash160 is RIPEMD-160 of SHA-256" do
+      {:ok, vectors} = Powex.test_vectors(:hash160, 4)
+
+      for %{data: data, nonce: nonce, hash: hash, difficulty: difficulty} <- vectors do
+        expected = :crypto.hash(:ripemd160, :crypto.hash(:sha256, data <> <<nonce::64-little>>))
+        assert hash == Base.encode16(expected, case: :lower)
+        assert String.starts_with?(hash, String.duplicate("0", difficulty))
+      end
+    end
+
+    test "wide SHA-2 variants match reference digests" do
+      # SHA-512/256 of <<0::64>>, from an independent implementation
+      assert {:ok, [%{data: "", nonce: 0, hash: hash} | _]} = Powex.test_vectors(:sha512_256, 2)
+      assert hash == "45ac134ffa7a54f7c40eeface107be5788b603621774295cd06e0b327a2baf95"
+
+      {:ok, vectors} = Powex.test_vectors(:sha384, 4)
+
+      for %{data: data, nonce: nonce, hash: hash} <- vectors do
+        assert hash == Base.encode16(:crypto.hash(:sha384, data <> <<nonce::64-little>>), case: :lower)
+      end
+    end
+
+    test "k12 and turboshake128 match reference digests" do
+      # K12 and TurboSHAKE128 of <<0::64>>, from the reference implementations
+      assert {:ok, [%{data: "", nonce: 0, hash: k12} | _]} = Powex.test_vectors(:k12, 2)
+      assert k12 == "973ba36b2d2e123a80161d34b483f20847e31944f2025bc3aaee02a62211f469"
+
+      assert {:ok, [%{data: "", nonce: 0, hash: turboshake} | _]} =
+               Powex.test_vectors(:turboshake128, 2)
+
+      assert turboshake == "b62e8fc1cf2fdb071e1c99e6ccb4d12029097abed5319b5a373029c8849bbdb4"
+    end
+
+    test "chains apply each algorithm to the previous digest" do
+      {:ok, vectors} = Powex.test_vectors({:chain, [:sha256, :blake2b]}, 4)
+
+      for %{data: data, nonce: nonce, hash: hash} <- vectors do
+        expected = :crypto.hash(:blake2b, :crypto.hash(:sha256, data <> <<nonce::64-little>>))
+        assert hash == Base.encode16(expected, case: :lower)
+      end
+
+      {:ok, doubles} = Powex.test_vectors({:chain, [:sha256, :sha256]}, 4)
+
+      for %{data: data, nonce: nonce, hash: hash} <- doubles do
+        expected = :crypto.hash(:sha256, :crypto.hash(:sha256, data <> <<nonce::64-little>>))
+        assert hash == Base.encode16(expected, case: :lower)
+      end
+
+      assert_raise ArgumentError, fn -> Powex.test_vectors({:chain, [:sha256, :poseidon]}, 1) end
+      assert_raise ArgumentError, fn -> Powex.test_vectors({:chain, [{:chain, [:sha256]}]}, 1) end
+      assert_raise ArgumentError, fn -> Powex.test_vectors({:chain, []}, 1) end
+    end
+
+    test "blake2b matches :crypto and takes a length and key" do
+      {:ok, vectors} = Powex.test_vectors(:blake2b, 4)
+
+      for %{data: data, nonce: nonce, hash: hash} <- vectors do
+        assert hash == Base.encode16(:crypto.hash(:blake2b, data <> <<nonce::64-little>>), case: :lower)
+      end
+
+      {:ok, short} = Powex.test_vectors({:blake2b, 32}, 4)
+      {:ok, keyed} = Powex.test_vectors({:blake2b, 32, "key"}, 4)
+      assert Enum.all?(short ++ keyed, &(byte_size(&1.hash) == 64))
+      assert Enum.map(short, & &1.hash) != Enum.map(keyed, & &1.hash)
+      assert_raise ArgumentError, fn -> Powex.test_vectors({:blake2b, 65}, 1) end
+    end
+
+    @tag :yespower
+    test "yespower takes cost parameters and a personalization" do
+      {:ok, default} = Powex.test_vectors(:yespower, 2)
+      {:ok, explicit} = Powex.test_vectors({:yespower, 2048, 8}, 2)
+      {:ok, personalized} = Powex.test_vectors({:yespower, 2048, 8, "powex"}, 2)
+      assert default == explicit
+      assert Enum.all?(default ++ personalized, &(byte_size(&1.hash) == 64))
+      assert Enum.map(default, & &1.hash) != Enum.map(personalized, & &1.hash)
+      assert_raise ArgumentError, fn -> Powex.test_vectors({:yespower, 1000, 8}, 1) end
+      assert_raise ArgumentError, fn -> Powex.test_vectors({:yespower, 2048, 4}, 1) end
+    end
+
+    test "rejects oversized requests" do
+      assert {:error, _reason} = Powex.test_vectors(:sha256, 10_001)
     end
   end
 
-  describe "get_hash/2" do
-    test "returns hash for given data and nonce" do
-      data = "test data"
-      nonce = 12345
+  describe "compute_with_predicate/3" do
+    # accept(w0, _, _, _) = w0 >>> 52 == 0: the top 12 bits of the digest are zero
+    @top_12_bits_zero <<0, 97, 115, 109, 1, 0, 0, 0, 1, 9, 1, 96, 4, 126, 126, 126, 126, 1, 127,
+                        3, 2, 1, 0, 7, 10, 1, 6, 97, 99, 99, 101, 112, 116, 0, 0, 10, 10, 1, 8, 0,
+                        32, 0, 66, 52, 136, 80, 11>>
+    # accept/4 loops forever
+    @spin <<0, 97, 115, 109, 1, 0, 0, 0, 1, 9, 1, 96, 4, 126, 126, 126, 126, 1, 127, 3, 2, 1, 0,
+            7, 10, 1, 6, 97, 99, 99, 101, 112, 116, 0, 0, 10, 11, 1, 9, 0, 3, 64, 12, 0, 11, 65,
+            0, 11>>
 
-      assert {:ok, hash} = Powex.get_hash(data, nonce)
-      assert is_binary(hash)
-      assert String.length(hash) == 64  # SHA-256 hex string length
-      assert String.match?(hash, ~r/^[0-9a-f]+$/)
+    test "rejects invalid modules" do
+      assert {:error, _reason} = Powex.compute_with_predicate("wasm", "not wasm")
+      refute Powex.valid_predicate?("wasm", 0, "not wasm")
     end
 
-    test "returns consistent hash for same inputs" do
-      data = "consistent test"
-      nonce = 98765
+    @tag :wasm
+    test "finds a nonce the predicate accepts" do
+      assert {:ok, nonce} = Powex.compute_with_predicate("wasm", @top_12_bits_zero)
+      assert {:ok, hash} = Powex.get_hash("wasm", nonce, encoding: :raw)
+      assert leading_zero_bits(hash) >= 12
+      assert Powex.valid_predicate?("wasm", nonce, @top_12_bits_zero)
+      refute Powex.valid_predicate?("wasm", nonce - 1, @top_12_bits_zero)
+    end
 
-      {:ok, hash1} = Powex.get_hash(data, nonce)
-      {:ok, hash2} = Powex.get_hash(data, nonce)
+    @tag :wasm
+    test "stops predicates that exceed their fuel" do
+      assert {:error, {:error, "Predicate ran out of fuel"}} = Powex.compute_with_predicate("wasm", @spin)
+      refute Powex.valid_predicate?("wasm", 0, @spin)
+    end
+  end
 
-      assert hash1 == hash2
+  describe "EquiX and onion service PoW" do
+    test "every solution of a challenge verifies" do
+      {challenge, solutions} =
+        Enum.find_value(0..9, fn i ->
+          case Powex.equix_solve("equix #{i}") do
+            {:ok, [_ | _] = solutions} -> {"equix #{i}", solutions}
+            _ -> nil
+          end
+        end)
+
+      for <<first, rest::binary>> = solution <- solutions do
+        assert byte_size(solution) == 16
+        assert Powex.equix_verify?(challenge, solution)
+        refute Powex.equix_verify?("other " <> challenge, solution)
+        refute Powex.equix_verify?(challenge, <<Bitwise.bxor(first, 1), rest::binary>>)
+      end
     end
 
-    test "returns different hash for different inputs" do
-      nonce = 555
+    test "onion proofs verify only for their seed and effort" do
+      blinded_id = :crypto.strong_rand_bytes(32)
+      seed = :crypto.strong_rand_bytes(32)
 
-      {:ok, hash1} = Powex.get_hash("data1", nonce)
-      {:ok, hash2} = Powex.get_hash("data2", nonce)
+      assert {:ok, pow} = Powex.onion_pow_solve(blinded_id, seed, 20)
+      assert %{effort: 20, seed_head: <<head::binary-4>>} = pow
+      assert head == binary_part(seed, 0, 4)
+      assert byte_size(pow.nonce) == 16 and byte_size(pow.solution) == 16
 
-      assert hash1 != hash2
+      assert Powex.onion_pow_valid?(blinded_id, seed, pow.nonce, 20, pow.solution)
+      refute Powex.onion_pow_valid?(blinded_id, :crypto.strong_rand_bytes(32), pow.nonce, 20, pow.solution)
+      refute Powex.onion_pow_valid?(blinded_id, seed, pow.nonce, 21, pow.solution)
     end
 
-    test "works with binary data" do
-      data = <<1, 2, 3>>
-      nonce = 777
+    test "rejects malformed onion inputs" do
+      assert {:error, _reason} = Powex.onion_pow_solve("short", <<0::256>>, 1)
+      refute Powex.onion_pow_valid?(<<0::256>>, <<0::256>>, "short", 1, <<0::128>>)
+    end
+  end
 
-      assert {:ok, hash} = Powex.get_hash(data, nonce)
-      assert String.length(hash) == 64
+  describe "Nano work" do
+    test "generated work reaches the threshold" do
+      root = :crypto.strong_rand_bytes(32)
+      threshold = 0xFFF0000000000000
+
+      assert {:ok, work} = Powex.nano_work_generate(root, threshold, threads: 2)
+      assert work =~ ~r/^[0-9a-f]{16}$/
+      assert Powex.nano_work_valid?(root, work, threshold)
+
+      value = Powex.nano_work_value(root, work)
+      assert value >= threshold
+      assert Powex.nano_work_valid?(root, work, value)
+      refute Powex.nano_work_valid?(root, work, value + 1)
+      refute Powex.nano_work_valid?(:crypto.strong_rand_bytes(32), work, value)
+    end
+
+    test "named thresholds match their values" do
+      root = <<0::256>>
+      value = Powex.nano_work_value(root, "0000000000000000")
+
+      assert Powex.nano_work_valid?(root, "0000000000000000") == value >= 0xFFFFFFF800000000
+      assert Powex.nano_work_valid?(root, "0000000000000000", :receive) == value >= 0xFFFFFE0000000000
+      assert Powex.nano_work_valid?(root, "0000000000000000", :epoch_1) == value >= 0xFFFFFFC000000000
+    end
+
+    test "rejects malformed input" do
+      refute Powex.nano_work_valid?(<<0::128>>, "0000000000000000", 0)
+      refute Powex.nano_work_valid?(<<0::256>>, "+000000000000000", 0)
+      assert {:error, _reason} = Powex.nano_work_generate("short", :send)
+      assert {:error, _reason} = Powex.nano_work_generate(<<0::256>>, 0xFFFFFFFFFFFFFFFF, timeout: 10)
+      assert_raise ArgumentError, fn -> Powex.nano_work_value(<<0::256>>, "xyz") end
+      assert_raise ArgumentError, fn -> Powex.nano_work_valid?(<<0::256>>, "0000000000000000", :other) end
     end
   end
 
-  describe "integration tests" do
-    test "complete workflow: compute -> validate -> get_hash" do
-      data = "integration test data"
-      difficulty = 3
+  describe "ethash light caches" do
+    test "are built once, prebuilt ahead and evicted to the cap" do
+      :ok = Powex.ethash_cache_configure(prebuild: false)
 
-      # Compute a valid nonce
-      {:ok, nonce} = Powex.compute(data, difficulty)
+      try do
+        assert {:ok, bytes} = Powex.ethash_cache_build(0)
+        assert bytes > 16_000_000
+        assert %{epochs: [0 | _], building: []} = Powex.ethash_cache_info()
 
-      # Validate the nonce
-      assert Powex.valid?(data, nonce, difficulty)
+        :ok = Powex.ethash_cache_configure(max_bytes: bytes)
+        assert {:ok, _} = Powex.ethash_cache_build(1)
+        assert %{epochs: [1], bytes: size} = Powex.ethash_cache_info()
+        assert size > bytes
 
-      # Get the hash and verify it meets difficulty
-      {:ok, hash} = Powex.get_hash(data, nonce)
-      leading_zeros = String.duplicate("0", difficulty)
-      assert String.starts_with?(hash, leading_zeros)
+        :ok = Powex.ethash_cache_configure(prebuild: true, max_bytes: 256 * 1024 * 1024)
+        refute Powex.ethash_verify(<<0::256>>, 0, <<0::256>>, 1)
+        assert Enum.any?([:building, :epochs], &(2 in Map.fetch!(Powex.ethash_cache_info(), &1)))
+        assert {:error, _} = Powex.ethash_cache_build(2048)
+      after
+        Powex.ethash_cache_configure(prebuild: true, max_bytes: 256 * 1024 * 1024)
+      end
     end
+  end
 
-    test "parallel vs sequential computation produces valid results" do
-      data = "comparison test"
-      difficulty = 2
+  describe "ethash_verify/5" do
+    # Mainnet block 1 (0x88e96d45...); the header hash is Keccak-256 of its RLP header without the seal
+    @block_1_header_hash Base.decode16!("85913A3057EA8BEC78CD916871CA73802E77724E014DDA65ADD3405D02240EB7")
+    @block_1_nonce 0x539BD4979FEF1EC4
+    @block_1_mix_hash Base.decode16!("969B900DE27B6AC6A67742365DD65F55A0526C41FD18E1B16F1A1215C2E66F59")
+    @block_1_difficulty 17_171_480_576
 
-      {:ok, nonce1} = Powex.compute(data, difficulty)
-      {:ok, nonce2} = Powex.compute_parallel(data, difficulty, 2)
+    test "accepts the seal of a mainnet block" do
+      assert Powex.ethash_verify(@block_1_header_hash, @block_1_nonce, @block_1_mix_hash, 0)
 
-      # Both should be valid (though potentially different)
-      assert Powex.valid?(data, nonce1, difficulty)
-      assert Powex.valid?(data, nonce2, difficulty)
+      assert Powex.ethash_verify(@block_1_header_hash, @block_1_nonce, @block_1_mix_hash, 0,
+               difficulty: @block_1_difficulty
+             )
+
+      refute Powex.ethash_verify(@block_1_header_hash, @block_1_nonce, @block_1_mix_hash, 0,
+               difficulty: Bitwise.bsl(1, 40)
+             )
+    end
+
+    test "rejects a tampered seal of a mainnet block" do
+      <<first, rest::binary>> = @block_1_mix_hash
+      tampered = <<Bitwise.bxor(first, 1), rest::binary>>
+
+      refute Powex.ethash_verify(@block_1_header_hash, @block_1_nonce, tampered, 0)
+      refute Powex.ethash_verify(@block_1_header_hash, @block_1_nonce + 1, @block_1_mix_hash, 0)
+    end
+
+    test "rejects a mix hash that doesn't match the header" do
+      header_hash = :crypto.strong_rand_bytes(32)
+      refute Powex.ethash_verify(header_hash, 42, <<0::256>>, 0)
+      refute Powex.ethash_verify(header_hash, 42, <<0::256>>, 0, difficulty: 1)
+    end
+
+    test "rejects malformed input" do
+      refute Powex.ethash_verify("short", 0, <<0::256>>, 0)
+      refute Powex.ethash_verify(<<0::256>>, 0, <<0::256>>, 2048)
+      refute Powex.ethash_verify(<<0::256>>, 0, <<0::256>>, 0, difficulty: 0)
+    end
+  end
+
+  describe "prime_chain_verify/4" do
+    test "accepts a multiplier that originates a chain" do
+      header_hash = <<:crypto.strong_rand_bytes(31)::binary, 0xC0>>
+      multiplier = Enum.find(1..200_000, &Powex.prime_chain_verify(header_hash, &1, :cunningham1, 2))
+
+      assert multiplier
+      assert Powex.prime_chain_verify(header_hash, multiplier, :cunningham1, 2.0)
+      refute Powex.prime_chain_verify(header_hash, multiplier, :cunningham1, 12)
+    end
+
+    test "rejects small hashes and invalid targets" do
+      refute Powex.prime_chain_verify(<<0::256>>, 1, :cunningham1, 2)
+      refute Powex.prime_chain_verify(<<0::248, 0xC0>>, 0, :bi_twin, 2)
+      assert_raise ArgumentError, fn -> Powex.prime_chain_verify(<<0::256>>, 1, :cunningham1, 1) end
+      assert_raise ArgumentError, fn -> Powex.prime_chain_verify(<<0::256>>, 1, :twin, 2) end
+    end
+  end
+
+  describe "compute_curve/2 and valid_curve?/3" do
+    test "finds a nonce whose point is in the sparse set" do
+      assert {:ok, nonce} = Powex.compute_curve("curve data", 6)
+      assert Powex.valid_curve?("curve data", nonce, 6)
+      assert Powex.valid_curve?("curve data", nonce, 0)
+
+      for earlier <- 0..(nonce - 1)//1 do
+        refute Powex.valid_curve?("curve data", earlier, 6)
+      end
+    end
+
+    test "rejects out-of-range difficulties" do
+      assert {:error, _reason} = Powex.compute_curve("curve data", 65)
+      refute Powex.valid_curve?("curve data", 0, 65)
+    end
+  end
+
+  describe "consume_proof/1" do
+    test "spends each proof once, across reopening" do
+      path = Path.join(System.tmp_dir!(), "powex_proofs_#{System.unique_integer([:positive])}")
+      on_exit(fn -> File.rm(path) end)
+
+      assert {:ok, 0} = Powex.proof_store_open(path)
+      refute Powex.proof_consumed?("proof a")
+      assert :ok = Powex.consume_proof("proof a")
+      assert {:error, :already_consumed} = Powex.consume_proof("proof a")
+      assert Powex.proof_consumed?("proof a")
+
+      # A torn record from a crash mid-append is dropped on open
+      File.write!(path, <<1, 2, 3>>, [:append])
+      assert {:ok, 1} = Powex.proof_store_open(path)
+      assert {:error, :already_consumed} = Powex.consume_proof("proof a")
+      assert :ok = Powex.consume_proof("proof b")
+      assert File.stat!(path).size == 64
+    end
+  end
+
+  describe "price_request/3" do
+    test "denies once the bucket is empty and sells tokens for proofs" do
+      {:ok, bucket} =
+        Powex.pricing_bucket_new(capacity: 4, refill_per_second: 0, credit: 2, max_difficulty: 3)
+
+      assert {:allow, %{tokens: 3.0, difficulty: 2}} = Powex.price_request(bucket, 1)
+      assert {:allow, _} = Powex.price_request(bucket, 3)
+      assert {:deny, quote} = Powex.price_request(bucket, 1)
+      assert quote.difficulty == 3
+
+      {:ok, nonce} = Powex.compute(quote.challenge, quote.difficulty)
+      assert {:allow, %{tokens: 1.0} = next} = Powex.price_request(bucket, 1, nonce)
+      assert next.challenge != quote.challenge
+
+      # The challenge rotated, so the same nonce doesn't buy tokens again
+      assert {:deny, %{tokens: 1.0}} = Powex.price_request(bucket, 2, nonce)
+    end
+
+    test "rejects inconsistent options" do
+      assert {:error, _reason} = Powex.pricing_bucket_new(capacity: 0)
+      assert {:error, _reason} = Powex.pricing_bucket_new(min_difficulty: 5, max_difficulty: 3)
+    end
+  end
+
+  describe "audit_export/1" do
+    setup do
+      on_exit(fn -> Powex.audit_disable() end)
+    end
+
+    test "records verifications while enabled" do
+      {:ok, nonce} = Powex.compute("audit", 1)
+      Powex.valid?("audit", nonce, 1)
+      assert :ok = Powex.audit_enable(capacity: 10)
+
+      assert Powex.valid?("audit", nonce, 1)
+      refute Powex.valid?("audit", nonce, 3)
+
+      assert ["hash,difficulty,timestamp_ms,outcome", valid, invalid, ""] =
+               String.split(Powex.audit_export(), "\n")
+
+      assert [hash, "1", _timestamp, "valid"] = String.split(valid, ",")
+      assert [^hash, "3", _timestamp, "invalid"] = String.split(invalid, ",")
+      assert byte_size(hash) == 64
+
+      assert <<_::binary-32, 3::32, _::64, 1>> = Powex.audit_export(format: :binary, limit: 1)
+      assert byte_size(Powex.audit_export(format: :binary, max_bytes: 89)) == 45
+    end
+
+    test "drops the oldest entries and redacts fields" do
+      assert :ok = Powex.audit_enable(capacity: 2)
+      for nonce <- 1..3, do: Powex.valid?("audit", nonce, 8)
+
+      assert <<0::256, 8::32, 0::64, 1, 0::256, 8::32, 0::64, 1>> =
+               Powex.audit_export(format: :binary, redact: [:hash, :timestamp])
+    end
+
+    test "rejects invalid capacities" do
+      assert {:error, _reason} = Powex.audit_enable(capacity: 0)
+    end
+  end
+
+  describe "solve_time_stats/1" do
+    test "summarizes recorded solve times" do
+      difficulty = {:zeros_at, 7, 1}
+      assert Powex.solve_time_stats(difficulty) == nil
+
+      for ms <- 1..100, do: :ok = Powex.solve_time_record(difficulty, ms)
+
+      assert %{count: 100, min_ms: 1.0, max_ms: 100.0, mean_ms: 50.5} =
+               stats = Powex.solve_time_stats(difficulty)
+
+      assert %{p50_ms: 50.0, p90_ms: 90.0, p99_ms: 99.0} = stats
+    end
+
+    test "times successful searches" do
+      {:ok, _nonce} = Powex.compute("solve times", {:zeros_at, 9, 1})
+      assert %{count: 1} = Powex.solve_time_stats({:zeros_at, 9, 1})
+    end
+  end
+
+  describe "controller_observe/2" do
+    test "raises the difficulty for fast solves and lowers it for slow ones" do
+      {:ok, controller} = Powex.controller_new(1_000, {8, 24})
+
+      assert {:zero_bits, bits} = Powex.controller_observe(controller, 10)
+      assert bits > 8
+
+      for _ <- 1..20, do: Powex.controller_observe(controller, 10)
+      assert Powex.controller_difficulty(controller) == {:zero_bits, 24}
+
+      for _ <- 1..40, do: Powex.controller_observe(controller, 100_000.0)
+      assert Powex.controller_difficulty(controller) == {:zero_bits, 8}
+    end
+
+    test "holds steady at the target" do
+      {:ok, controller} = Powex.controller_new(500, {0, 64}, initial: 20)
+      for _ <- 1..10, do: Powex.controller_observe(controller, 500)
+      assert Powex.controller_difficulty(controller) == {:zero_bits, 20}
+    end
+
+    test "rejects invalid bounds" do
+      assert {:error, _reason} = Powex.controller_new(1_000, {24, 8})
+      assert {:error, _reason} = Powex.controller_new(1_000, {8, 300})
+      assert {:error, _reason} = Powex.controller_new(1_000, {8, 24}, initial: 4)
+    end
+  end
+
+  describe "equivalent_difficulty/3" do
+    test "scales by the measured hashrates" do
+      assert Powex.equivalent_difficulty(:blake3, {:zero_bits, 20}, :blake3) == {:zero_bits, 20}
+
+      ratio = Powex.algorithm_hashrate(:sha256) / Powex.algorithm_hashrate(:blake3)
+      expected = round(20 + :math.log2(ratio))
+      assert Powex.equivalent_difficulty(:blake3, {:zero_bits, 20}, :sha256) == {:zero_bits, expected}
+    end
+
+    test "converts slow algorithms to easier difficulties" do
+      {:zero_bits, bits} = Powex.equivalent_difficulty(:sha256, {:zero_bits, 24}, :poseidon)
+      assert bits < 24
+      assert {:zero_bits, 0} = Powex.equivalent_difficulty(:sha256, {:zero_bits, 0}, :poseidon)
+    end
+
+    test "converts keyed BLAKE2b without telling keys apart" do
+      one = Powex.equivalent_difficulty({:blake2b, 32, "key one"}, {:zero_bits, 20}, :sha256)
+      assert {:zero_bits, _bits} = one
+      assert Powex.equivalent_difficulty({:blake2b, 32, "key two"}, {:zero_bits, 20}, :sha256) == one
+    end
+  end
+
+  describe "deterministic_seed" do
+    test "makes nano work generation reproducible across thread counts" do
+      root = :binary.copy(<<7>>, 32)
+      threshold = 0xFFF0_0000_0000_0000
+
+      {:ok, work} = Powex.nano_work_generate(root, threshold, deterministic_seed: 42, threads: 1)
+      assert {:ok, ^work} = Powex.nano_work_generate(root, threshold, deterministic_seed: 42, threads: 4)
+      assert Powex.nano_work_valid?(root, work, threshold)
+    end
+
+    test "makes sampling reproducible" do
+      sample = fn seed -> Powex.sample_distribution("seeded", 1_000, deterministic_seed: seed) end
+      assert sample.(1) == sample.(1)
+      assert sample.(1) != sample.(2)
+    end
+  end
+
+  describe "encoding_spec/1" do
+    test "sha256 spec reproduces get_hash/3" do
+      spec = Powex.encoding_spec(:sha256)
+      assert spec.input == [:data, :nonce]
+      assert %{width: 8, byte_order: :little} = spec.nonce
+      assert %{function: :sha256, size: 32, encoding: :hex_lowercase} = spec.digest
+
+      data = "spec check"
+      expected = :crypto.hash(:sha256, data <> <<42::64-little>>) |> Base.encode16(case: :lower)
+      assert {:ok, ^expected} = Powex.get_hash(data, 42)
+    end
+
+    test "describes every mode" do
+      for mode <- [:sha256, :blake3, :sha512_256, :sha384, :poseidon, :hash160, :k12, :turboshake128, :yespower, :blake2b, :argon2id, :header, :proof] do
+        assert is_map(Powex.encoding_spec(mode))
+      end
+
+      assert Powex.encoding_spec(:header).nonce.offset == 76
+      assert Powex.encoding_spec(:argon2id).parameters.version == 0x13
+    end
+  end
+
+  describe "metrics_prometheus/0" do
+    test "renders every metric with help and type lines" do
+      text = Powex.metrics_prometheus()
+
+      for name <- ~w(powex_hashes_total powex_hashrate_ema powex_jobs_started_total
+                     powex_jobs_completed_total powex_jobs_coalesced_total
+                     powex_searches_in_flight powex_queue_depth powex_cache_hits_total
+                     powex_cache_misses_total powex_cache_entries) do
+        assert text =~ "# HELP #{name} "
+        assert text =~ ~r/^# TYPE #{name} (counter|gauge)$/m
+        assert Map.has_key?(prometheus_samples(text), name)
+      end
+    end
+
+    test "counts hashes and jobs" do
+      before = prometheus_samples(Powex.metrics_prometheus())
+      {:ok, nonce} = Powex.compute("metered", 3)
+      {:ok, job} = Powex.compute_async("metered async", 1)
+      assert_receive {:powex_job, ^job, {:ok, _}}, 5_000
+
+      samples = prometheus_samples(Powex.metrics_prometheus())
+      assert samples["powex_hashes_total"] >= before["powex_hashes_total"] + nonce + 1
+      assert samples["powex_jobs_started_total"] >= before["powex_jobs_started_total"] + 1
+      assert samples["powex_jobs_completed_total"] >= before["powex_jobs_completed_total"] + 1
+      assert samples["powex_hashrate_ema"] >= 0
     end
   end
+
+  describe "structured logging" do
+    setup do
+      on_exit(fn -> Powex.set_log_sink(nil, :info) end)
+    end
+
+    test "emits job lifecycle events to the sink" do
+      :ok = Powex.set_log_sink(self(), :debug)
+
+      {:ok, daemon} = Powex.daemon_start(threads: 1)
+      assert_receive {:powex_log, :info, :daemon_started, %{workers: 1, power_policy: :ignore}}, 1_000
+
+      {:ok, id} = Powex.enqueue(daemon, "logged", 2, self())
+      assert_receive {:powex_daemon, ^id, {:ok, nonce}}, 5_000
+      assert_receive {:powex_log, :debug, :work_started, %{id: ^id, priority: 0}}, 1_000
+      assert_receive {:powex_log, :debug, :work_finished, %{id: ^id, ok: true, nonce: ^nonce}}, 1_000
+
+      Powex.daemon_stop(daemon)
+      assert_receive {:powex_log, :info, :daemon_stopped, %{queued: 0}}, 1_000
+    end
+
+    test "filters events below the sink level and reports failures as warnings" do
+      :ok = Powex.set_log_sink(self(), :warning)
+
+      {:ok, job} = Powex.compute_async("logged failure", 65)
+      assert_receive {:powex_job, ^job, {:error, _}}, 5_000
+      assert_receive {:powex_log, :warning, :job_finished, %{ok: false, reason: reason}}, 1_000
+      assert is_binary(reason)
+      refute_received {:powex_log, :debug, :job_started, _}
+    end
+
+    test "a nil sink stops events" do
+      :ok = Powex.set_log_sink(self(), :debug)
+      :ok = Powex.set_log_sink(nil, :debug)
+
+      {:ok, _daemon} = Powex.daemon_start(threads: 1)
+      refute_receive {:powex_log, _, _, _}, 100
+    end
+
+    test "Powex.LogSink forwards events to Logger" do
+      start_supervised!({Powex.LogSink, level: :info})
+
+      log =
+        ExUnit.CaptureLog.capture_log(fn ->
+          {:ok, _daemon} = Powex.daemon_start(threads: 1)
+          Process.sleep(100)
+        end)
+
+      assert log =~ "powex daemon_started"
+      assert log =~ "workers=1"
+    end
+  end
+
+  describe "configure/1" do
+    setup do
+      on_exit(fn -> Powex.configure(profile: :default, max_data_size: 64 * 1024 * 1024) end)
+    end
+
+    test "the low-memory profile shrinks the verification cache" do
+      assert %{capacity: default} = Powex.cache_stats()
+
+      assert :ok = Powex.configure(profile: :low_memory)
+      assert %{capacity: small} = Powex.cache_stats()
+      assert small < default
+
+      assert :ok = Powex.configure(profile: :default)
+      assert %{capacity: ^default} = Powex.cache_stats()
+    end
+
+    test "background work still runs under the low-memory profile" do
+      :ok = Powex.configure(profile: :low_memory)
+      {:ok, daemon} = Powex.daemon_start(threads: 1)
+      {:ok, id} = Powex.enqueue(daemon, "embedded", 2, self())
+      assert_receive {:powex_daemon, ^id, {:ok, nonce}}, 5_000
+      assert Powex.valid?("embedded", nonce, 2)
+    end
+
+    test "the mobile profile caps threads and still mines" do
+      :ok = Powex.configure(profile: :mobile)
+      {:ok, daemon} = Powex.daemon_start(threads: 8)
+      assert %{workers: 2} = Powex.daemon_stats(daemon)
+
+      {:ok, id} = Powex.enqueue(daemon, "phone", 2, self())
+      assert_receive {:powex_daemon, ^id, {:ok, nonce}}, 5_000
+      assert Powex.valid?("phone", nonce, 2)
+
+      assert {:ok, %{nonce: nonce, threads: threads}} =
+               Powex.compute_parallel("phone", 2, 8, diagnostics: true)
+
+      assert length(threads) == 2
+      assert Powex.valid?("phone", nonce, 2)
+    end
+
+    test "background work waits while paused" do
+      try do
+        assert :ok = Powex.pause_background()
+        assert Powex.background_paused?()
+        {:ok, job} = Powex.compute_async("backgrounded", 1)
+        refute_receive {:powex_job, ^job, _result}, 200
+
+        assert :ok = Powex.resume_background()
+        refute Powex.background_paused?()
+        assert_receive {:powex_job, ^job, {:ok, nonce}}, 5_000
+        assert Powex.valid?("backgrounded", nonce, 1)
+      after
+        Powex.resume_background()
+      end
+    end
+
+    test "rejects unknown profiles" do
+      assert_raise ArgumentError, fn -> Powex.configure(profile: :tiny) end
+    end
+
+    test "max_data_size rejects larger data with a clear error" do
+      :ok = Powex.configure(max_data_size: 1024)
+      big = :binary.copy("x", 1025)
+
+      for fun <- [
+            fn -> Powex.compute(big, 1) end,
+            fn -> Powex.compute_parallel(big, 1, 2) end,
+            fn -> Powex.compute_async(big, 1) end,
+            fn -> Powex.valid?(big, 0, 1) end,
+            fn -> Powex.validate_share(big, 0, 1, 2) end,
+            fn -> Powex.measure_difficulty(big, 0) end,
+            fn -> Powex.get_hash(big, 0) end,
+            fn -> Powex.compute_dual(big, 1) end,
+            fn -> Powex.valid_dual?(big, 0, 1) end,
+            fn -> Powex.lottery_ticket(big, 0) end
+          ] do
+        error = assert_raise ErlangError, fun
+        assert error.original == {:data_too_large, 1024}
+      end
+
+      assert {:ok, _nonce} = Powex.compute(:binary.copy("x", 1024), 1)
+    end
+  end
+
+  describe "validate_share/4" do
+    test "classifies submissions by leading zeros" do
+      data = "share data"
+      {:ok, nonce} = Powex.compute(data, 2)
+
+      assert Powex.validate_share(data, nonce, 1, 2) == :block
+      assert Powex.validate_share(data, nonce, 2, 5) == :share
+      assert Powex.validate_share(data, nonce, 3, 5) == :invalid
+    end
+
+    test "agrees with get_hash/2" do
+      data = "share data"
+      {:ok, hash} = Powex.get_hash(data, 42)
+      zeros = String.length(hash) - String.length(String.trim_leading(hash, "0"))
+
+      assert Powex.validate_share(data, 42, zeros, zeros) == :block
+      assert Powex.validate_share(data, 42, zeros + 1, zeros + 1) == :invalid
+    end
+  end
+
+  describe "compute_rounds/4 and valid_rounds?/4" do
+    test "chains each round's data with the previous hash" do
+      assert {:ok, [n1, n2, n3] = nonces} = Powex.compute_rounds("rounds", 2, 3)
+      assert Powex.valid_rounds?("rounds", nonces, 2, 3)
+
+      assert Powex.valid?("rounds", n1, 2)
+      {:ok, h1} = Powex.get_hash("rounds", n1, encoding: :raw)
+      assert Powex.valid?("rounds" <> h1, n2, 2)
+      {:ok, h2} = Powex.get_hash("rounds" <> h1, n2, encoding: :raw)
+      assert Powex.valid?("rounds" <> h1 <> h2, n3, 2)
+    end
+
+    test "parallel rounds produce valid chains" do
+      assert {:ok, nonces} = Powex.compute_rounds("rounds", 3, 2, threads: 4)
+      assert Powex.valid_rounds?("rounds", nonces, 3, 2)
+    end
+
+    test "rejects truncated, reordered or foreign chains" do
+      {:ok, [n1, n2, n3]} = Powex.compute_rounds("rounds", 2, 3)
+
+      refute Powex.valid_rounds?("rounds", [n1, n2], 2, 3)
+      refute Powex.valid_rounds?("rounds", [n3, n2, n1], 2, 3)
+      refute Powex.valid_rounds?("other", [n1, n2, n3], 2, 3)
+    end
+
+    test "returns error for extremely high difficulty" do
+      assert {:error, _reason} = Powex.compute_rounds("rounds", 65, 2)
+    end
+  end
+
+  describe "memory-hard puzzles" do
+    @memory_params [m_cost: 64, t_cost: 1]
+
+    test "compute_memory_hard/3 returns the first valid nonce" do
+      assert {:ok, nonce} = Powex.compute_memory_hard("argon", 1, @memory_params)
+      assert Powex.valid_memory_hard?("argon", nonce, 1, @memory_params)
+
+      for earlier <- 0..(nonce - 1)//1 do
+        refute Powex.valid_memory_hard?("argon", earlier, 1, @memory_params)
+      end
+    end
+
+    test "compute_memory_hard/3 reports invalid parameters and exhaustion" do
+      assert {:error, _reason} = Powex.compute_memory_hard("argon", 1, m_cost: 1)
+      assert {:error, _reason} = Powex.compute_memory_hard("argon", 65, @memory_params)
+      assert {:error, _reason} = Powex.compute_memory_hard("argon", 8, [max_attempts: 3] ++ @memory_params)
+      assert_raise ArgumentError, fn -> Powex.valid_memory_hard?("argon", 0, 1, t_cost: 0) end
+    end
+
+    test "enforces the caller's memory limit before allocating" do
+      huge = [m_cost: 64 * 1024 * 1024, t_cost: 1, max_memory: 1024 * 1024]
+
+      assert {:error, :memory_limit} = Powex.compute_memory_hard("argon", 1, huge)
+      assert {:error, :memory_limit} = Powex.valid_memory_hard?("argon", 0, 1, huge)
+
+      assert {:ok, nonce} = Powex.compute_memory_hard("argon", 1, [max_memory: 64 * 1024] ++ @memory_params)
+      assert Powex.valid_memory_hard?("argon", nonce, 1, [max_memory: 64 * 1024] ++ @memory_params)
+    end
+
+    test "tune_memory_hard/2 stays within the memory budget" do
+      assert {:ok, %{m_cost: m_cost, t_cost: t_cost, p_cost: 1, verify_ms: verify_ms}} =
+               Powex.tune_memory_hard(20, 4 * 1024 * 1024)
+
+      assert m_cost * 1024 <= 4 * 1024 * 1024
+      assert t_cost >= 1
+      assert verify_ms > 0
+      assert {:error, _reason} = Powex.tune_memory_hard(20, 1024)
+    end
+  end
+
+  describe "issue_bundle/3" do
+    test "accepts any k solved sub-challenges" do
+      assert {:ok, bundle} = Powex.issue_bundle(6, 3, k: 3)
+      %{target: target, challenges: challenges} = Powex.decode_bundle(bundle)
+      assert length(challenges) == 6
+
+      solutions =
+        for index <- [1, 4, 5] do
+          {:ok, nonce} = Powex.compute(Enum.at(challenges, index), target)
+          {index, nonce}
+        end
+
+      assert Powex.verify_bundle({bundle, solutions}, 3)
+      refute Powex.verify_bundle({bundle, solutions}, 4)
+      refute Powex.verify_bundle({bundle, List.duplicate(hd(solutions), 3)}, 3)
+    end
+
+    test "splits the difficulty k ways" do
+      {:ok, single} = Powex.issue_bundle(1, 4)
+      {:ok, split} = Powex.issue_bundle(4, 4, k: 4)
+
+      # 16^4 expected hashes split four ways: 16^4 / 4 = 2^14
+      assert Powex.decode_bundle(single).target == "0000" <> String.duplicate("f", 60)
+      assert Powex.decode_bundle(split).target == "0003" <> String.duplicate("f", 60)
+    end
+
+    test "rejects invalid parameters and bundles" do
+      assert {:error, _reason} = Powex.issue_bundle(0, 2)
+      assert {:error, _reason} = Powex.issue_bundle(2, 2, k: 3)
+      assert_raise ArgumentError, fn -> Powex.decode_bundle(<<1, 2>>) end
+      assert_raise ArgumentError, fn -> Powex.verify_bundle({<<>>, []}, 1) end
+    end
+  end
+
+  describe "current_challenge_params/1" do
+    test "is the same for every node within an epoch" do
+      opts = [secret: "cluster", difficulties: [2, 3, {:zero_bits, 10}], epoch_seconds: 60]
+
+      assert {:ok, params} = Powex.current_challenge_params([now: 120] ++ opts)
+      assert {:ok, ^params} = Powex.current_challenge_params([now: 179] ++ opts)
+      assert %{epoch: 2, starts_at: 120, expires_at: 180} = params
+      assert params.algorithm in [:sha256, :blake3]
+      assert params.difficulty in [2, 3, {:zero_bits, 10}]
+      assert byte_size(params.seed) == 32
+
+      keyed = {:blake2b, 32, "shared key"}
+      assert {:ok, %{algorithm: ^keyed}} = Powex.current_challenge_params([algorithms: [keyed], now: 0] ++ opts)
+    end
+
+    test "rotates with the epoch and the secret" do
+      opts = [difficulties: [2], epoch_seconds: 60]
+      seeds =
+        for secret <- ["a", "b"], now <- [0, 60, 120] do
+          {:ok, %{seed: seed}} = Powex.current_challenge_params([secret: secret, now: now] ++ opts)
+          seed
+        end
+
+      assert length(Enum.uniq(seeds)) == 6
+    end
+
+    test "rejects missing or invalid options" do
+      assert {:error, _reason} = Powex.current_challenge_params(difficulties: [2])
+      assert {:error, _reason} = Powex.current_challenge_params(secret: "s")
+      assert {:error, _reason} = Powex.current_challenge_params(secret: "s", difficulties: [2], epoch_seconds: 0)
+      assert {:error, _reason} = Powex.current_challenge_params(secret: "s", difficulties: [65])
+    end
+  end
+
+  describe "huge pages" do
+    test "give the same proofs and report the backing" do
+      opts = [m_cost: 64, t_cost: 1]
+      {:ok, nonce} = Powex.compute_memory_hard("huge", 1, opts)
+
+      assert {:ok, %{nonce: ^nonce, huge_pages: huge_pages}} =
+               Powex.compute_memory_hard("huge", 1, [huge_pages: true] ++ opts)
+
+      assert huge_pages in [:explicit, :transparent, :none]
+
+      {:ok, session} = Powex.session_new("huge", [algorithm: :argon2id, huge_pages: true] ++ opts)
+      assert Powex.session_verify(session, nonce, 1)
+    end
+  end
+
+  describe "mining sessions" do
+    test "match the one-shot functions" do
+      {:ok, session} = Powex.session_new("session")
+      {:ok, nonce} = Powex.session_mine(session, 2)
+      assert {:ok, ^nonce} = Powex.compute("session", 2)
+      assert Powex.session_verify(session, nonce, 2)
+      refute Powex.session_verify(session, nonce, 3)
+
+      assert {:ok, later} = Powex.session_mine(session, 2, start: nonce + 1)
+      assert later > nonce and Powex.valid?("session", later, 2)
+
+      {:ok, session} = Powex.session_new("session", algorithm: :blake3)
+      {:ok, nonce} = Powex.session_mine(session, 1)
+      assert Powex.session_verify(session, nonce, 1)
+    end
+
+    test "reuse the Argon2id memory" do
+      opts = [algorithm: :argon2id, m_cost: 64, t_cost: 1]
+      {:ok, session} = Powex.session_new("session", opts)
+      {:ok, nonce} = Powex.session_mine(session, 1)
+
+      assert Powex.valid_memory_hard?("session", nonce, 1, m_cost: 64, t_cost: 1)
+      assert Powex.session_verify(session, nonce, 1)
+      assert {:error, _} = Powex.session_mine(session, 1, start: nonce + 1, max_attempts: 0)
+      assert {:error, _} = Powex.session_new("session", algorithm: :argon2id, m_cost: 1)
+    end
+  end
+
+  describe "scheduler dispatch" do
+    test "routes by estimated cost" do
+      assert Powex.scheduler_for(64, 1) == :normal
+      assert Powex.scheduler_for(64, {:zero_bits, 40}) == :dirty
+      assert Powex.scheduler_for(16 * 1024 * 1024, 0, verify: true) == :dirty
+      assert Powex.scheduler_for(64, 3, algorithm: :poseidon) == :dirty
+    end
+
+    test "gives the same results on either scheduler" do
+      {:ok, nonce} = Powex.compute("dispatch", 2, schedule: :dirty)
+      assert {:ok, ^nonce} = Powex.compute("dispatch", 2, schedule: :normal)
+      assert Powex.valid?("dispatch", nonce, 2, schedule: :dirty)
+      assert Powex.valid?("dispatch", nonce, 2, schedule: :normal)
+    end
+
+    test "moves large verifications forced on a normal scheduler to a dirty one" do
+      data = :binary.copy("large", 1024 * 1024)
+      {:ok, nonce} = Powex.compute(data, 1, schedule: :dirty)
+      assert Powex.valid?(data, nonce, 1, schedule: :normal)
+      assert Powex.valid?(data, nonce + 1, 1, schedule: :normal) ==
+               Powex.valid?(data, nonce + 1, 1, schedule: :dirty)
+    end
+  end
+
+  describe "valid_async?/4" do
+    test "replies with the verification result" do
+      {:ok, nonce} = Powex.compute("async verify", 2)
+      {:ok, job} = Powex.valid_async?("async verify", nonce, 2)
+      assert_receive {:powex_job, ^job, {:ok, true}}, 5_000
+
+      {:ok, job} = Powex.valid_async?("async verify", nonce + 1, 64, pid: self())
+      assert_receive {:powex_job, ^job, {:ok, false}}, 5_000
+    end
+
+    test "verifies memory-hard proofs within the budget" do
+      {:ok, nonce} = Powex.compute_memory_hard("async verify", 1, m_cost: 64, t_cost: 1)
+      {:ok, job} = Powex.valid_async?("async verify", nonce, 1, algorithm: :argon2id, m_cost: 64, t_cost: 1)
+      assert_receive {:powex_job, ^job, {:ok, true}}, 5_000
+
+      {:ok, job} = Powex.valid_async?("async verify", nonce, 1, algorithm: :argon2id, m_cost: 4_194_304)
+      assert_receive {:powex_job, ^job, {:error, :over_budget}}, 5_000
+    end
+  end
+
+  describe "verification budget" do
+    test "rejects proofs whose declared cost exceeds the budget" do
+      proof = Powex.encode_proof(String.duplicate("x", 2048), 0, 1)
+      argon_proof = Powex.encode_proof("budget", 0, 1, algorithm: :argon2id, m_cost: 1024, t_cost: 1)
+      policy = Powex.policy_new(algorithms: [:sha256, :argon2id])
+
+      try do
+        Powex.configure(max_verify_bytes: 1024, max_verify_memory: 512 * 1024)
+        assert {:error, :over_budget} = Powex.decode_and_verify(proof, policy)
+        assert {:error, :over_budget} = Powex.decode_and_verify(argon_proof, policy)
+        assert {:error, :over_budget} = Powex.valid_memory_hard?("budget", 0, 1, m_cost: 1024, t_cost: 1)
+        assert is_boolean(Powex.valid_memory_hard?("budget", 0, 1, m_cost: 64, t_cost: 1))
+      after
+        Powex.configure(max_verify_bytes: 64 * 1024 * 1024, max_verify_memory: 256 * 1024 * 1024)
+      end
+
+      refute Powex.decode_and_verify(proof, policy) == {:error, :over_budget}
+    end
+  end
+
+  describe "compress_proofs/1" do
+    test "round-trips mixed challenges, parameters and nonce widths in order" do
+      proofs = [
+        Powex.encode_proof("a", 5, 2),
+        Powex.encode_proof("b", 1_000_000, 3, algorithm: :blake3),
+        Powex.encode_proof("a", 3, 2),
+        Powex.encode_proof("a", 0xFFFF_FFFF_FFFF_FFFF, 2),
+        Powex.encode_proof("m", 9, 1, algorithm: :argon2id, m_cost: 64, t_cost: 1)
+      ]
+
+      assert {:ok, batch} = Powex.compress_proofs(proofs)
+      assert Powex.decompress_proofs(batch) == {:ok, proofs}
+      assert {:ok, empty} = Powex.compress_proofs([])
+      assert Powex.decompress_proofs(empty) == {:ok, []}
+    end
+
+    test "shrinks proofs of one challenge to a few bytes each" do
+      data = :crypto.strong_rand_bytes(64)
+      proofs = for nonce <- Enum.sort(Enum.take_random(1..1_000_000, 1_000)), do: Powex.encode_proof(data, nonce, 4)
+
+      {:ok, batch} = Powex.compress_proofs(proofs)
+      assert byte_size(batch) < 4 * length(proofs)
+    end
+
+    test "rejects malformed proofs and batches" do
+      assert Powex.compress_proofs(["not a proof"]) == {:error, :malformed}
+      assert Powex.decompress_proofs(<<>>) == {:error, :malformed}
+      assert Powex.decompress_proofs(<<2, 0>>) == {:error, :malformed}
+
+      {:ok, batch} = Powex.compress_proofs([Powex.encode_proof("x", 1, 1)])
+      assert {:error, :malformed} = Powex.decompress_proofs(binary_part(batch, 0, byte_size(batch) - 2))
+    end
+
+    test "refuses batches expanding past max_data_size" do
+      proofs = List.duplicate(Powex.encode_proof(:binary.copy("x", 1024), 1, 1), 100)
+      {:ok, batch} = Powex.compress_proofs(proofs)
+
+      try do
+        :ok = Powex.configure(max_data_size: 16 * 1024)
+        assert Powex.decompress_proofs(batch) == {:error, :data_too_large}
+      after
+        Powex.configure(max_data_size: 64 * 1024 * 1024)
+      end
+    end
+  end
+
+  describe "token_encode/1" do
+    test "round-trips a challenge solved by the client under the server's MAC" do
+      policy = Powex.policy_new()
+      {:ok, challenge} = Powex.token_encode(data: "token", difficulty: 2, key: "server key")
+      {:ok, %{data: "token", nonce: 0, timestamp: timestamp, tag: tag}} = Powex.token_decode(challenge)
+      assert byte_size(tag) == 32
+      refute challenge =~ "="
+
+      {:ok, nonce} = Powex.compute("token", 2)
+      {:ok, token} = Powex.token_encode(%{data: "token", difficulty: 2, nonce: nonce, timestamp: timestamp, tag: tag})
+
+      assert {:ok, %{data: "token", difficulty: 2, nonce: ^nonce, algorithm: :sha256}} =
+               Powex.token_verify(token, policy, key: "server key")
+      assert Powex.token_verify(token, policy, key: "other key") == {:error, :bad_signature}
+    end
+
+    test "rejects stale, future-dated and unsolved tokens" do
+      policy = Powex.policy_new()
+      {:ok, nonce} = Powex.compute("token", 2)
+      fields = [data: "token", difficulty: 2, nonce: nonce, key: "k", timestamp: 1_000]
+      {:ok, token} = Powex.token_encode(fields)
+
+      assert {:ok, _} = Powex.token_verify(token, policy, key: "k", now: 1_100)
+      assert Powex.token_verify(token, policy, key: "k", now: 2_000) == {:error, :too_old}
+      assert Powex.token_verify(token, policy, key: "k", now: 2_000, max_age: 1_000) |> elem(0) == :ok
+      assert Powex.token_verify(token, policy, key: "k", now: 900) == {:error, :too_far_in_future}
+
+      {:ok, unsolved} = Powex.token_encode(Keyword.put(fields, :difficulty, 8))
+      assert Powex.token_verify(unsolved, policy, key: "k", now: 1_000) == {:error, :invalid}
+
+      strict = Powex.policy_new(min_difficulty: 4)
+      assert Powex.token_verify(token, strict, key: "k", now: 1_000) == {:error, :difficulty_out_of_range}
+    end
+
+    test "signs public tokens verifiable with the public key alone" do
+      %{secret_key: secret_key, public_key: public_key} = Powex.token_keypair()
+      policy = Powex.policy_new()
+      {:ok, challenge} = Powex.token_encode(data: "public", difficulty: 2, purpose: :public, secret_key: secret_key)
+      assert "v1.public." <> _ = challenge
+      {:ok, %{version: :v1, purpose: :public, timestamp: timestamp, tag: tag}} = Powex.token_decode(challenge)
+      assert byte_size(tag) == 64
+
+      {:ok, nonce} = Powex.compute("public", 2)
+      fields = [data: "public", difficulty: 2, purpose: :public, nonce: nonce, timestamp: timestamp]
+      {:ok, token} = Powex.token_encode([tag: tag] ++ fields)
+
+      assert {:ok, %{purpose: :public, nonce: ^nonce}} = Powex.token_verify(token, policy, public_key: public_key)
+      assert Powex.token_verify(token, policy, key: secret_key) == {:error, :wrong_purpose}
+      %{public_key: other} = Powex.token_keypair()
+      assert Powex.token_verify(token, policy, public_key: other) == {:error, :bad_signature}
+
+      {:ok, local} = Powex.token_encode(data: "public", difficulty: 2, nonce: nonce, key: secret_key)
+      assert Powex.token_verify(local, policy, public_key: public_key) == {:error, :wrong_purpose}
+      assert {:error, _} = Powex.token_encode([tag: binary_part(tag, 0, 32)] ++ fields)
+    end
+
+    test "refuses tokens of unknown versions and purposes" do
+      {:ok, "v1.local." <> payload} = Powex.token_encode(data: "x", difficulty: 1, key: "k")
+
+      for header <- ["v2.local.", "v1.secret.", "v2.public."] do
+        assert Powex.token_decode(header <> payload) == {:error, :unsupported_version}
+        assert Powex.token_verify(header <> payload, Powex.policy_new(), key: "k") == {:error, :unsupported_version}
+      end
+    end
+
+    test "rejects malformed and oversized tokens" do
+      assert Powex.token_decode("not base64!") == {:error, :malformed}
+      assert Powex.token_decode(Base.url_encode64(<<2, 0>>, padding: false)) == {:error, :malformed}
+      assert Powex.token_verify("", Powex.policy_new(), key: "k") == {:error, :malformed}
+
+      assert {:error, _} = Powex.token_encode(data: "x", difficulty: 1)
+      assert {:error, _} = Powex.token_encode(data: "x", difficulty: 1, tag: "short")
+      assert {:error, _} = Powex.token_encode(data: :binary.copy("x", 8192), difficulty: 1, key: "k")
+      assert Powex.token_decode(:binary.copy("A", 8192)) == {:error, :malformed}
+    end
+  end
+
+  describe "personal_difficulty/5" do
+    test "scales with reputation and averages to the fractional target" do
+      difficulties =
+        for i <- 1..2_000 do
+          {:ok, d} = Powex.personal_difficulty("secret", "client #{i}", 8, 0.6)
+          d
+        end
+
+      assert Enum.all?(difficulties, &(&1 in 7..8))
+      assert_in_delta Enum.sum(difficulties) / length(difficulties), 7.6, 0.06
+
+      assert Powex.personal_difficulty("secret", "client", 8, 0.5, max_adjustment: 0) == {:ok, 8}
+      assert Powex.personal_difficulty("secret", "client", 0, 1) == {:ok, 0}
+      assert Powex.personal_difficulty("secret", "client", 64, 0) == {:ok, 64}
+    end
+
+    test "depends on the secret and the client only" do
+      {:ok, a} = Powex.personal_difficulty("secret", "client", 8, 0.5)
+      assert Powex.personal_difficulty("secret", "client", 8, 0.5) == {:ok, a}
+
+      spread =
+        for i <- 1..64, uniq: true do
+          Powex.personal_difficulty("secret #{i}", "client", 8, 0.3)
+        end
+
+      assert length(spread) > 1
+    end
+
+    test "rejects invalid arguments" do
+      assert {:error, _} = Powex.personal_difficulty("", "client", 8, 0.5)
+      assert {:error, _} = Powex.personal_difficulty("secret", "client", 8, 0.5, max_adjustment: -1.0)
+      assert_raise ArgumentError, fn -> Powex.personal_difficulty("secret", "client", 8, 1.5) end
+    end
+  end
+
+  describe "issue_challenge/1" do
+    test "embeds the client's difficulty in a verifiable token" do
+      {:ok, token} = Powex.issue_challenge(client_id: "good", difficulty: 3, reputation: 1.0, key: "k")
+      {:ok, %{data: data, difficulty: difficulty, timestamp: timestamp, tag: tag}} = Powex.token_decode(token)
+      assert {:ok, ^difficulty} = Powex.personal_difficulty("k", "good", 3, 1.0)
+      assert byte_size(data) == 16
+
+      {:ok, nonce} = Powex.compute(data, difficulty)
+      fields = [data: data, nonce: nonce, timestamp: timestamp, tag: tag]
+      {:ok, solved} = Powex.token_encode([difficulty: difficulty] ++ fields)
+      assert {:ok, _} = Powex.token_verify(solved, Powex.policy_new(), key: "k")
+
+      {:ok, easier} = Powex.token_encode([difficulty: difficulty - 1] ++ fields)
+      assert Powex.token_verify(easier, Powex.policy_new(), key: "k") == {:error, :bad_signature}
+
+      {:ok, other} = Powex.issue_challenge(client_id: "good", difficulty: 3, reputation: 1.0, key: "k")
+      refute other == token
+      assert {:error, _} = Powex.issue_challenge(difficulty: 3, key: "k")
+      assert {:error, _} = Powex.issue_challenge(client_id: "good", difficulty: 3)
+    end
+  end
+
+  describe "escalating_challenge/2" do
+    test "escalates with failures up to max_steps" do
+      for {failures, steps} <- [{0, 1}, {1, 2}, {5, 3}] do
+        {:ok, token} = Powex.escalating_challenge(failures, difficulty: 1, max_steps: 3, key: "k")
+        assert {:ok, nonces} = Powex.escalating_solve(token)
+        assert length(nonces) == steps
+        assert Powex.escalating_verify(token, nonces, Powex.policy_new(), key: "k") == {:ok, steps}
+      end
+    end
+
+    test "requires every step, solved in order" do
+      {:ok, token} = Powex.escalating_challenge(2, difficulty: 2, step: 0, key: "k")
+      {:ok, [a, b, c] = nonces} = Powex.escalating_solve(token)
+      policy = Powex.policy_new()
+
+      assert Powex.escalating_verify(token, [a, b], policy, key: "k") == {:error, :incomplete}
+      assert Powex.escalating_verify(token, nonces, policy, key: "other") == {:error, :bad_signature}
+
+      unless [b, a, c] == nonces do
+        assert Powex.escalating_verify(token, [b, a, c], policy, key: "k") == {:error, :invalid}
+      end
+
+      {:ok, plain} = Powex.token_encode(data: "not a sequence", difficulty: 1, key: "k")
+      assert Powex.escalating_solve(plain) == {:error, :malformed}
+      assert Powex.escalating_verify(plain, [0], policy, key: "k") == {:error, :malformed}
+    end
+
+    test "checks every step against the policy" do
+      {:ok, token} = Powex.escalating_challenge(1, difficulty: 1, step: 1, key: "k")
+      {:ok, nonces} = Powex.escalating_solve(token)
+      strict = Powex.policy_new(max_difficulty: 1)
+      assert Powex.escalating_verify(token, nonces, strict, key: "k") == {:error, :difficulty_out_of_range}
+    end
+
+    test "gives up at the timeout" do
+      {:ok, token} = Powex.escalating_challenge(0, difficulty: 60, key: "k")
+      assert Powex.escalating_solve(token, timeout: 10) == {:error, :timeout}
+      assert {:error, _} = Powex.escalating_challenge(0, difficulty: 1, max_steps: 0, key: "k")
+    end
+  end
+
+  describe "blind_mint/4" do
+    setup_all do
+      {:ok, keys} = Powex.blind_keypair(bits: 1024)
+      keys
+    end
+
+    test "mints tokens that redeem once and don't reveal the request", %{public_key: pk, secret_key: sk} do
+      path = Path.join(System.tmp_dir!(), "powex_blind_#{System.unique_integer([:positive])}")
+      on_exit(fn -> File.rm(path) end)
+      {:ok, 0} = Powex.proof_store_open(path)
+      policy = Powex.policy_new()
+
+      {:ok, request} = Powex.blind_request(pk, 2)
+      assert {:ok, %{data: blinded}} = Powex.decode_and_verify(request.proof, policy)
+      assert byte_size(blinded) == 128
+      assert :binary.match(blinded, request.token) == :nomatch
+
+      {:ok, blind_signature} = Powex.blind_mint(request.proof, sk, policy, consume: true)
+      assert Powex.blind_mint(request.proof, sk, policy, consume: true) == {:error, :already_consumed}
+
+      {:ok, signature} = Powex.blind_finalize(pk, request, blind_signature)
+      refute signature == blind_signature
+      assert Powex.blind_redeem(pk, request.token, signature, consume: true) == :ok
+      assert Powex.blind_redeem(pk, request.token, signature, consume: true) == {:error, :already_consumed}
+    end
+
+    test "refuses bad proofs, signatures and keys", %{public_key: pk, secret_key: sk} do
+      policy = Powex.policy_new()
+      {:ok, request} = Powex.blind_request(pk, 1)
+      {:ok, blind_signature} = Powex.blind_mint(request.proof, sk, policy)
+      {:ok, signature} = Powex.blind_finalize(pk, request, blind_signature)
+
+      strict = Powex.policy_new(min_difficulty: 2)
+      assert Powex.blind_mint(request.proof, sk, strict) == {:error, :difficulty_out_of_range}
+      assert Powex.blind_mint(Powex.encode_proof("not blinded", 0, 0), sk, policy) == {:error, :malformed}
+
+      {:ok, other} = Powex.blind_request(pk, 1)
+      assert Powex.blind_redeem(pk, other.token, signature) == {:error, :bad_signature}
+      assert Powex.blind_finalize(pk, other, blind_signature) == {:error, :bad_signature}
+      assert Powex.blind_redeem(%{pk | n: <<1, 2, 3>>}, request.token, signature) == {:error, :malformed}
+      assert Powex.blind_request(%{pk | e: <<2>>}, 1) == {:error, :malformed}
+      assert {:error, _} = Powex.blind_keypair(bits: 512)
+    end
+  end
+
+  describe "issue_receipt/4" do
+    setup do
+      path = Path.join(System.tmp_dir!(), "powex_receipts_#{System.unique_integer([:positive])}")
+      on_exit(fn -> File.rm(path) end)
+      {:ok, 0} = Powex.proof_store_open(path)
+      {:ok, nonce} = Powex.compute("receipt", 2)
+      %{path: path, proof: Powex.encode_proof("receipt", nonce, 2)}
+    end
+
+    test "turns work into a receipt that redeems once, across restarts", %{path: path, proof: proof} do
+      {:ok, receipt} = Powex.issue_receipt(proof, "secret", 10)
+      assert byte_size(receipt) == 65
+      assert Powex.issue_receipt(proof, "secret", 10) == {:error, :already_consumed}
+
+      {:ok, _} = Powex.proof_store_open(path)
+      assert Powex.redeem_receipt(receipt, "other") == {:error, :bad_signature}
+      assert {:ok, %{value: 10, issued_at: issued_at}} = Powex.redeem_receipt(receipt, "secret")
+      assert_in_delta issued_at, Powex.clock_now(), 2
+
+      {:ok, _} = Powex.proof_store_open(path)
+      assert Powex.redeem_receipt(receipt, "secret") == {:error, :already_consumed}
+    end
+
+    test "spends the work, not the encoding of its proof", %{proof: proof} do
+      <<header::binary-size(3), width, nonce::binary-size(width), rest::binary>> = proof
+      padded = nonce <> :binary.copy(<<0>>, 8 - width)
+      reencoded = header <> <<8>> <> padded <> rest
+
+      {:ok, _receipt} = Powex.issue_receipt(proof, "secret", 10)
+      # Padded nonces no longer decode, and work is spent under its identity regardless
+      assert Powex.issue_receipt(reencoded, "secret", 10) == {:error, :malformed}
+      assert Powex.issue_receipt(proof, "secret", 10) == {:error, :already_consumed}
+    end
+
+    test "rejects bad work, tampered and expired receipts", %{proof: proof} do
+      assert Powex.issue_receipt(proof, "secret", 1, policy: Powex.policy_new(min_difficulty: 3)) ==
+               {:error, :difficulty_out_of_range}
+
+      try do
+        :ok = Powex.configure(clock: 1_000)
+        {:ok, receipt} = Powex.issue_receipt(proof, "secret", 7)
+        <<head::binary-size(1), _value::binary-size(8), rest::binary>> = receipt
+        assert Powex.redeem_receipt(head <> <<1_000::64-little>> <> rest, "secret") == {:error, :bad_signature}
+        assert Powex.redeem_receipt(binary_part(receipt, 0, 64), "secret") == {:error, :malformed}
+
+        :ok = Powex.configure(clock: 2_000)
+        assert Powex.redeem_receipt(receipt, "secret", max_age: 999) == {:error, :too_old}
+        assert {:ok, %{value: 7, issued_at: 1_000}} = Powex.redeem_receipt(receipt, "secret", max_age: 1_000)
+      after
+        Powex.configure(clock: :system)
+      end
+    end
+  end
+
+  describe "generate_challenge/1" do
+    setup do
+      on_exit(fn -> Powex.configure(entropy: :os) end)
+    end
+
+    test "draws from the OS by default" do
+      assert {:ok, %{challenge: a, source: :os, entropy: nil}} = Powex.generate_challenge()
+      assert {:ok, %{challenge: b}} = Powex.generate_challenge()
+      assert byte_size(a) == 32
+      assert a != b
+    end
+
+    test "repeats a seeded stream" do
+      :ok = Powex.configure(entropy: {:seeded, "seed"})
+      assert {:ok, %{challenge: a, source: :seeded}} = Powex.generate_challenge()
+      assert {:ok, %{challenge: b}} = Powex.generate_challenge()
+      assert a != b
+
+      :ok = Powex.configure(entropy: {:seeded, "seed"})
+      assert {:ok, %{challenge: ^a}} = Powex.generate_challenge()
+      assert {:ok, %{challenge: ^b}} = Powex.generate_challenge()
+
+      :ok = Powex.configure(entropy: {:seeded, "other"})
+      assert {:ok, %{challenge: c}} = Powex.generate_challenge()
+      assert c != a
+    end
+
+    test "mixes in caller entropy and records its hash" do
+      beacon = :crypto.strong_rand_bytes(32)
+
+      :ok = Powex.configure(entropy: {:seeded, "seed"})
+      {:ok, %{challenge: plain}} = Powex.generate_challenge()
+      :ok = Powex.configure(entropy: {:seeded, "seed"})
+
+      assert {:ok, %{challenge: mixed, source: :seeded, entropy: hash}} =
+               Powex.generate_challenge(entropy: beacon)
+
+      assert mixed != plain
+      assert byte_size(hash) == 32
+    end
+
+    test "rejects invalid sizes and sources" do
+      assert {:ok, %{challenge: <<_>>}} = Powex.generate_challenge(size: 1)
+      assert {:error, _} = Powex.generate_challenge(size: 0)
+      assert {:error, _} = Powex.generate_challenge(size: 1025)
+      assert_raise ArgumentError, fn -> Powex.configure(entropy: :dev_random) end
+    end
+  end
+
+  describe "challenge_from_beacon/2" do
+    test "is deterministic and separated by context" do
+      beacon = :crypto.strong_rand_bytes(32)
+      assert {:ok, challenge} = Powex.challenge_from_beacon(beacon, "ctx")
+      assert byte_size(challenge) == 32
+      assert {:ok, ^challenge} = Powex.challenge_from_beacon(beacon, "ctx")
+
+      assert {:ok, other} = Powex.challenge_from_beacon(beacon, "ctx2")
+      assert other != challenge
+      assert {:ok, next} = Powex.challenge_from_beacon(:crypto.strong_rand_bytes(32), "ctx")
+      assert next != challenge
+
+      # The context is length-prefixed, so moving bytes across the boundary changes the result
+      {:ok, a} = Powex.challenge_from_beacon("bc", "a")
+      {:ok, b} = Powex.challenge_from_beacon("c", "ab")
+      assert a != b
+    end
+
+    test "rejects empty inputs" do
+      assert {:error, _} = Powex.challenge_from_beacon("", "ctx")
+      assert {:error, _} = Powex.challenge_from_beacon("beacon", "")
+    end
+  end
+
+  describe "commit_challenge/1 and open_challenge/2" do
+    test "derives the same challenge for every party" do
+      {:ok, a} = Powex.commit_challenge()
+      {:ok, b} = Powex.commit_challenge(contribution: "node b")
+      {:ok, c} = Powex.commit_challenge()
+
+      assert byte_size(a.commitment) == 32
+      assert Powex.verify_opening(b.commitment, b.opening)
+      refute Powex.verify_opening(a.commitment, b.opening)
+
+      assert {:ok, challenge} =
+               Powex.open_challenge([a.commitment, b.commitment, c.commitment], [
+                 a.opening,
+                 b.opening,
+                 c.opening
+               ])
+
+      assert byte_size(challenge) == 32
+
+      assert Powex.open_challenge([c.commitment, a.commitment, b.commitment], [
+               c.opening,
+               a.opening,
+               b.opening
+             ]) == {:ok, challenge}
+
+      assert {:ok, other} = Powex.open_challenge([a.commitment, b.commitment], [a.opening, b.opening])
+      assert other != challenge
+    end
+
+    test "binds each party to its contribution" do
+      {:ok, a} = Powex.commit_challenge(contribution: "a")
+      {:ok, b} = Powex.commit_challenge(contribution: "b")
+      <<salt::binary-size(32), _::binary>> = b.opening
+
+      assert Powex.open_challenge([a.commitment, b.commitment], [a.opening, salt <> "c"]) ==
+               {:error, :bad_opening}
+
+      assert Powex.open_challenge([a.commitment, b.commitment], [b.opening, a.opening]) ==
+               {:error, :bad_opening}
+
+      assert Powex.open_challenge([a.commitment, a.commitment], [a.opening, a.opening]) ==
+               {:error, :duplicate}
+
+      assert Powex.open_challenge([a.commitment], [a.opening, b.opening]) == {:error, :malformed}
+      assert Powex.open_challenge([], []) == {:error, :malformed}
+      assert {:error, _} = Powex.commit_challenge(contribution: "")
+    end
+  end
+
+  describe "lottery_ticket/2 and select_winner/1" do
+    test "picks the lowest draw deterministically" do
+      proofs =
+        for i <- 1..8 do
+          data = "node #{i}"
+          {:ok, nonce} = Powex.compute(data, 1)
+          {data, nonce}
+        end
+
+      tickets = Enum.map(proofs, fn {data, nonce} -> Powex.lottery_ticket(data, nonce) end)
+      assert Enum.all?(tickets, &(&1 >= 0.0 and &1 < 1.0))
+      assert tickets == Enum.map(proofs, fn {data, nonce} -> Powex.lottery_ticket(data, nonce) end)
+
+      assert {:ok, %{index: index, ticket: ticket}} = Powex.select_winner(proofs)
+      assert ticket == Enum.min(tickets)
+      assert Enum.at(tickets, index) == ticket
+
+      {winner, _} = List.pop_at(proofs, index)
+      reversed = Enum.reverse(proofs)
+      assert {:ok, %{index: reversed_index}} = Powex.select_winner(reversed)
+      assert Enum.at(reversed, reversed_index) == winner
+    end
+
+    test "draws are roughly uniform" do
+      tickets = for nonce <- 1..2000, do: Powex.lottery_ticket("uniform", nonce)
+      mean = Enum.sum(tickets) / length(tickets)
+      assert_in_delta mean, 0.5, 0.05
+      assert Enum.count(tickets, &(&1 < 0.25)) in 400..600
+    end
+
+    test "breaks ties on position and rejects empty lists" do
+      {:ok, nonce} = Powex.compute("tie", 1)
+      assert {:ok, %{index: 0}} = Powex.select_winner([{"tie", nonce}, {"tie", nonce}])
+      assert {:error, _} = Powex.select_winner([])
+    end
+  end
+
+  describe "configure/1 clock" do
+    test "drives token expiry deterministically" do
+      {:ok, nonce} = Powex.compute("clock", 1)
+      policy = Powex.policy_new()
+
+      try do
+        :ok = Powex.configure(clock: 1_000_000)
+        assert Powex.clock_now() == 1_000_000
+        {:ok, token} = Powex.token_encode(data: "clock", difficulty: 1, nonce: nonce, key: "k")
+        assert {:ok, %{timestamp: 1_000_000}} = Powex.token_decode(token)
+        assert {:ok, _} = Powex.token_verify(token, policy, key: "k")
+
+        :ok = Powex.configure(clock: 1_000_301)
+        assert Powex.token_verify(token, policy, key: "k") == {:error, :too_old}
+        assert {:ok, _} = Powex.token_verify(token, policy, key: "k", now: 1_000_100)
+
+        :ok = Powex.configure(clock_offset: -400)
+        assert Powex.clock_now() == 999_901
+        assert Powex.token_verify(token, policy, key: "k") == {:error, :too_far_in_future}
+        assert Powex.validate_timestamp([], 1_000_000 + 7200, []) == {:error, :too_far_in_future}
+      after
+        Powex.configure(clock: :system, clock_offset: 0)
+      end
+
+      assert_in_delta Powex.clock_now(), System.os_time(:second), 2
+      assert_raise ArgumentError, fn -> Powex.configure(clock: :atomic) end
+    end
+  end
+
+  describe "verify_proof_stream/3" do
+    setup do
+      path = Path.join(System.tmp_dir!(), "powex_log_#{System.unique_integer([:positive])}")
+      on_exit(fn -> File.rm(path) end)
+      %{path: path}
+    end
+
+    test "reports each entry of a signed log in order", %{path: path} do
+      {:ok, nonce} = Powex.compute("audited", 2)
+      good = Powex.encode_proof("audited", nonce, 2)
+      wrong = Powex.encode_proof("audited", nonce, 3)
+      {:ok, signed} = Powex.sign_proof(good, "log key")
+      {:ok, unsigned} = Powex.sign_proof(good, "other key")
+      {:ok, invalid} = Powex.sign_proof(wrong, "log key")
+
+      entries = List.duplicate(signed, 5) ++ [unsigned, invalid]
+      File.write!(path, entries)
+
+      results = Powex.verify_proof_stream(path, Powex.policy_new(), key: "log key", batch: 2)
+      offsets = Enum.scan(entries, 0, &(&2 + byte_size(&1))) |> List.insert_at(0, 0)
+
+      assert Enum.to_list(results) ==
+               Enum.map(0..4, &{&1, Enum.at(offsets, &1), :ok}) ++
+                 [{5, Enum.at(offsets, 5), {:error, :bad_signature}},
+                  {6, Enum.at(offsets, 6), {:error, :invalid}}]
+    end
+
+    test "ends a log cut mid-entry with :truncated", %{path: path} do
+      {:ok, nonce} = Powex.compute("cut", 1)
+      {:ok, entry} = Powex.sign_proof(Powex.encode_proof("cut", nonce, 1), "key")
+      File.write!(path, [entry, binary_part(entry, 0, 10)])
+
+      assert [{0, 0, :ok}, {1, offset, {:error, :truncated}}] =
+               Enum.to_list(Powex.verify_proof_stream(path, Powex.policy_new(), key: "key"))
+
+      assert offset == byte_size(entry)
+    end
+
+    test "is lazy and can be halted early", %{path: path} do
+      {:ok, nonce} = Powex.compute("lazy", 1)
+      {:ok, entry} = Powex.sign_proof(Powex.encode_proof("lazy", nonce, 1), "key")
+      File.write!(path, List.duplicate(entry, 100))
+
+      assert [{0, 0, :ok}, {1, _, :ok}] =
+               Powex.verify_proof_stream(path, Powex.policy_new(), key: "key", batch: 10)
+               |> Enum.take(2)
+
+      refute_received {:powex_proof_stream, _job, _message}
+    end
+
+    test "raises on a missing log or key", %{path: path} do
+      assert_raise ArgumentError, fn ->
+        Powex.verify_proof_stream(path, Powex.policy_new(), key: "key") |> Enum.to_list()
+      end
+
+      File.write!(path, "")
+
+      assert_raise ArgumentError, fn ->
+        Powex.verify_proof_stream(path, Powex.policy_new(), []) |> Enum.to_list()
+      end
+    end
+  end
+
+  describe "decode_proof/1" do
+    test "decodes declared parameters without verifying" do
+      argon_proof = Powex.encode_proof("policy", 7, 9, algorithm: :argon2id, m_cost: 64, t_cost: 1)
+
+      assert {:ok, %{algorithm: :argon2id, difficulty: 9, nonce: 7, data: "policy"} = decoded} =
+               Powex.decode_proof(argon_proof)
+
+      assert decoded.memory == %{m_cost: 64, t_cost: 1, p_cost: 1}
+      assert {:ok, %{memory: nil, nonce_width: 5}} = Powex.decode_proof(Powex.encode_proof("", Integer.pow(2, 32), 1))
+    end
+
+    test "rejects malformed input without raising" do
+      proof = Powex.encode_proof("data", 300, 2, algorithm: :argon2id, m_cost: 64, t_cost: 1)
+
+      for len <- 0..(byte_size(proof) - 5) do
+        assert {:error, :malformed} = Powex.decode_proof(binary_part(proof, 0, len))
+      end
+
+      assert {:error, :malformed} = Powex.decode_proof(<<2, 0, 2, 2, 44, 1>>)
+      assert {:error, :malformed} = Powex.decode_proof(<<1, 9, 2, 2, 44, 1>>)
+      assert {:error, :malformed} = Powex.decode_proof(<<1, 0, 2, 0>>)
+      assert {:error, :malformed} = Powex.decode_proof(<<1, 0, 2, 9, 0::64>>)
+
+      for _ <- 1..1_000 do
+        bytes = :crypto.strong_rand_bytes(:rand.uniform(64))
+        assert Powex.decode_proof(bytes) |> elem(0) in [:ok, :error]
+      end
+    end
+  end
+
+  describe "decode_and_verify/2" do
+    test "verifies proofs for every algorithm" do
+      {:ok, sha_nonce} = Powex.compute("policy", 2)
+      {:ok, dual_nonce} = Powex.compute_dual("policy", 1, {:sha256, :blake3})
+      {:ok, argon_nonce} = Powex.compute_memory_hard("policy", 1, m_cost: 64, t_cost: 1)
+      policy = Powex.policy_new(algorithms: [:sha256, :blake3, :argon2id])
+
+      assert {:ok, %{algorithm: :sha256, difficulty: 2, nonce: ^sha_nonce, data: "policy"}} =
+               Powex.decode_and_verify(Powex.encode_proof("policy", sha_nonce, 2), policy)
+
+      blake3_proof = Powex.encode_proof("policy", dual_nonce, 1, algorithm: :blake3)
+      assert {:ok, %{algorithm: :blake3}} = Powex.decode_and_verify(blake3_proof, policy)
+
+      argon_proof = Powex.encode_proof("policy", argon_nonce, 1, algorithm: :argon2id, m_cost: 64, t_cost: 1)
+      assert {:ok, %{algorithm: :argon2id, nonce: ^argon_nonce}} = Powex.decode_and_verify(argon_proof, policy)
+    end
+
+    test "verifies poseidon proofs once allowed" do
+      {:ok, job} = Powex.compute_async("policy", 1, algorithm: :poseidon)
+      assert_receive {:powex_job, ^job, {:ok, nonce}}, 10_000
+      proof = Powex.encode_proof("policy", nonce, 1, algorithm: :poseidon)
+
+      assert {:error, :algorithm_not_allowed} = Powex.decode_and_verify(proof, Powex.policy_new())
+      assert {:ok, %{algorithm: :poseidon, nonce: ^nonce}} =
+               Powex.decode_and_verify(proof, Powex.policy_new(algorithms: [:poseidon]))
+    end
+
+    test "enforces the policy before verifying" do
+      argon_proof = Powex.encode_proof("policy", 0, 1, algorithm: :argon2id, m_cost: 4_194_304, t_cost: 1)
+      wide_proof = Powex.encode_proof("policy", Integer.pow(2, 40), 1)
+
+      assert {:error, :algorithm_not_allowed} = Powex.decode_and_verify(argon_proof, Powex.policy_new())
+
+      assert {:error, :memory_params_not_allowed} =
+               Powex.decode_and_verify(argon_proof, Powex.policy_new(algorithms: [:argon2id]))
+
+      assert {:error, :nonce_too_wide} = Powex.decode_and_verify(wide_proof, Powex.policy_new(max_nonce_width: 4))
+
+      assert {:error, :difficulty_out_of_range} =
+               Powex.decode_and_verify(Powex.encode_proof("policy", 0, 10), Powex.policy_new(max_difficulty: 8))
+    end
+
+    test "rejects malformed and invalid proofs" do
+      {:ok, nonce} = Powex.compute("policy", 3)
+      policy = Powex.policy_new()
+
+      assert {:error, :malformed} = Powex.decode_and_verify(<<>>, policy)
+      assert {:error, :malformed} = Powex.decode_and_verify(<<2, 0, 3, 1, 0>>, policy)
+      assert {:error, :malformed} = Powex.decode_and_verify(<<1, 9, 3, 1, 0>>, policy)
+      assert {:error, :malformed} = Powex.decode_and_verify(<<1, 0, 3, 4, 0>>, policy)
+
+      <<header::binary-size(3), width, encoded::binary-size(width), data::binary>> =
+        Powex.encode_proof("policy", nonce, 3)
+
+      padded = header <> <<width + 1>> <> encoded <> <<0>> <> data
+      assert {:error, :malformed} = Powex.decode_and_verify(padded, policy)
+      assert {:error, :invalid} = Powex.decode_and_verify(Powex.encode_proof("other", nonce, 3), policy)
+    end
+  end
+
+  describe "measure_difficulty/2" do
+    test "returns the leading zero bits of the hash" do
+      for nonce <- 0..50 do
+        {:ok, hash} = Powex.get_hash("measure", nonce, encoding: :raw)
+        assert Powex.measure_difficulty("measure", nonce) == leading_zero_bits(hash)
+      end
+    end
+
+    test "grows with the difficulty of the proof" do
+      {:ok, weak} = Powex.compute("measure", 1)
+      {:ok, strong} = Powex.compute("measure", 4)
+
+      assert Powex.measure_difficulty("measure", weak) in 4..7
+      assert Powex.measure_difficulty("measure", strong) in 16..19
+    end
+  end
+
+  describe "work_multiple/3" do
+    test "compares the hash to the numeric target" do
+      for nonce <- 0..200 do
+        {:ok, hash} = Powex.get_hash("multiple", nonce, encoding: :raw)
+        <<value::256>> = hash
+        expected = :math.pow(2, 248) / (value + 1)
+
+        case Powex.work_multiple("multiple", nonce, {:zero_bits, 8}) do
+          {:ok, multiple} ->
+            assert leading_zero_bits(hash) >= 8
+            assert_in_delta multiple / expected, 1.0, 1.0e-9
+
+          {:error, :invalid} ->
+            assert leading_zero_bits(hash) < 8
+        end
+      end
+    end
+
+    test "treats integer difficulties as minimum hex zeros" do
+      {:ok, nonce} = Powex.compute("multiple", 3)
+      {:ok, multiple} = Powex.work_multiple("multiple", nonce, 2)
+      assert multiple >= 16.0
+    end
+  end
+
+  describe "sample_distribution/3" do
+    test "follows the geometric distribution of an ideal hash" do
+      {:ok, %{counts: counts, expected: expected, mean_bits: mean}} =
+        Powex.sample_distribution("distribution", 100_000, algorithm: :blake3)
+
+      assert Enum.sum(counts) == 100_000
+      assert length(counts) == length(expected)
+      assert_in_delta mean, 1.0, 0.05
+
+      for {count, ideal} <- Enum.zip(Enum.take(counts, 4), expected) do
+        assert_in_delta count / ideal, 1.0, 0.1
+      end
+    end
+
+    test "rejects invalid sample counts" do
+      assert {:error, _reason} = Powex.sample_distribution("distribution", 0)
+    end
+  end
+
+  describe "compute_parallel/3" do
+    test "computes valid nonce using parallel processing" do
+      data = "parallel test"
+      difficulty = 3
+      threads = 4
+
+      assert {:ok, nonce} = Powex.compute_parallel(data, difficulty, threads)
+      assert is_integer(nonce)
+      assert Powex.valid?(data, nonce, difficulty)
+    end
+
+    test "returns error for invalid thread count" do
+      assert {:error, _reason} = Powex.compute_parallel("test", 2, 0)
+      assert {:error, _reason} = Powex.compute_parallel("test", 2, 100)
+    end
+
+    test "returns error for extremely high difficulty" do
+      assert {:error, _reason} = Powex.compute_parallel("test", 65, 4)
+    end
+
+    test "works with single thread" do
+      data = "single thread"
+      difficulty = 2
+
+      assert {:ok, nonce} = Powex.compute_parallel(data, difficulty, 1)
+      assert Powex.valid?(data, nonce, difficulty)
+    end
+
+    test "reports per-thread diagnostics on request" do
+      assert {:ok, %{nonce: nonce, found_by: found_by, elapsed_ms: elapsed, threads: reports}} =
+               Powex.compute_parallel("diagnostics", 3, 4, diagnostics: true)
+
+      assert Powex.valid?("diagnostics", nonce, 3)
+      assert is_float(elapsed)
+      assert Enum.map(reports, & &1.thread) == [0, 1, 2, 3]
+      assert [%{thread: ^found_by}] = Enum.filter(reports, & &1.found)
+
+      winner = Enum.at(reports, found_by)
+      assert nonce == winner.start + winner.attempts - 1
+
+      for %{start: start, end: stop, attempts: attempts} <- reports do
+        assert start < stop
+        assert attempts >= 0
+      end
+    end
+
+    test "searches only the given nonce range" do
+      {:ok, nonce} = Powex.compute("ranged", 2)
+      assert {:ok, ^nonce} = Powex.compute_parallel("ranged", 2, 1, nonce_range: nonce..(nonce + 10))
+    end
+
+    test "reports an exhausted nonce range" do
+      assert {:error, :nonce_space_exhausted, 100..163//1} =
+               Powex.compute_parallel("exhausted", {:zero_bits, 48}, 4, nonce_range: 100..163)
+    end
+
+    test "rejects invalid nonce ranges" do
+      assert {:error, _reason} = Powex.compute_parallel("ranged", 2, 2, nonce_range: 10..1//-1)
+    end
+
+    test "skips excluded nonce ranges" do
+      {:ok, nonce} = Powex.compute("excluded", 2)
+      range = nonce..(nonce + 100_000)
+
+      {:ok, other} = Powex.compute_parallel("excluded", 2, 2, nonce_range: range, exclude: [nonce..nonce])
+      assert other > nonce
+
+      assert {:ok, %{threads: reports}} =
+               Powex.compute_parallel("excluded", 2, 2,
+                 nonce_range: 0..99_999,
+                 exclude: [0..49_999, 40_000..79_999],
+                 diagnostics: true
+               )
+
+      assert Enum.all?(reports, &(&1.start >= 80_000))
+    end
+
+    test "reports an exhausted range once every nonce is excluded" do
+      assert {:error, :nonce_space_exhausted, 0..9//1} =
+               Powex.compute_parallel("excluded", 2, 2, nonce_range: 0..9, exclude: [0..4, 5..9])
+
+      assert {:error, _reason} = Powex.compute_parallel("excluded", 2, 2, exclude: [5..1//-1])
+    end
+  end
+
+  describe "compute_async/3" do
+    test "delivers the result to the caller" do
+      {:ok, job} = Powex.compute_async("async data", 2)
+      assert_receive {:powex_job, ^job, {:ok, nonce}}, 5_000
+      assert {:ok, ^nonce} = Powex.compute("async data", 2)
+    end
+
+    test "delivers errors like compute/2" do
+      {:ok, job} = Powex.compute_async("async data", 65)
+      assert_receive {:powex_job, ^job, {:error, _reason}}, 5_000
+    end
+
+    test "sends the result to the :pid option" do
+      parent = self()
+      receiver = spawn(fn -> receive do: (message -> send(parent, {:forwarded, message})) end)
+
+      {:ok, job} = Powex.compute_async("async data", 1, pid: receiver)
+      assert_receive {:forwarded, {:powex_job, ^job, {:ok, _nonce}}}, 5_000
+    end
+
+    test "supports blake3" do
+      {:ok, job} = Powex.compute_async("async data", 2, algorithm: :blake3)
+      assert_receive {:powex_job, ^job, {:ok, nonce}}, 5_000
+      assert is_integer(nonce)
+    end
+
+    test "coalesces identical jobs into one search" do
+      %{coalesced: before} = Powex.job_stats()
+      data = "coalesced #{System.unique_integer()}"
+
+      {:ok, first} = Powex.compute_async(data, 6)
+      {:ok, second} = Powex.compute_async(data, 6)
+      {:ok, other} = Powex.compute_async(data, 6, algorithm: :blake3)
+      assert first != second
+
+      assert_receive {:powex_job, ^first, {:ok, nonce}}, 60_000
+      assert_receive {:powex_job, ^second, {:ok, ^nonce}}, 60_000
+      assert_receive {:powex_job, ^other, {:ok, _}}, 60_000
+      assert Powex.valid?(data, nonce, 6)
+
+      assert %{coalesced: coalesced} = Powex.job_stats()
+      assert coalesced == before + 1
+    end
+  end
+
+  describe "stats/0" do
+    test "counts the hashes of non-SHA-256 searches" do
+      calls = :erlang.system_info(:dirty_cpu_schedulers) + 1
+
+      for i <- 1..calls do
+        assert {:ok, _} = Powex.compute_dual("dual #{i}", 1, {:sha256, :blake3})
+      end
+
+      stats = Powex.stats()
+      assert stats |> Map.keys() |> Enum.sort() == [:cache, :hashes, :jobs]
+      assert stats.hashes >= calls
+    end
+
+    test "groups the cache and job counters" do
+      assert %{cache: cache, jobs: jobs} = Powex.stats()
+      assert Map.keys(cache) == Map.keys(Powex.cache_stats())
+      assert Map.keys(jobs) == Map.keys(Powex.job_stats())
+    end
+  end
+
+  describe "numa_topology/0" do
+    test "lists each node with its CPUs" do
+      for %{node: node, cpus: cpus} <- Powex.numa_topology() do
+        assert is_integer(node) and node >= 0
+        assert cpus != [] and Enum.all?(cpus, &(is_integer(&1) and &1 >= 0))
+      end
+    end
+
+    test "daemon workers pinned to nodes still mine" do
+      try do
+        assert :ok = Powex.configure(numa: true)
+        {:ok, daemon} = Powex.daemon_start(threads: 2)
+        {:ok, id} = Powex.enqueue(daemon, "numa", 1, self())
+        assert_receive {:powex_daemon, ^id, {:ok, nonce}}, 5_000
+        assert Powex.valid?("numa", nonce, 1)
+        Powex.daemon_stop(daemon)
+      after
+        Powex.configure(numa: false)
+      end
+    end
+  end
+
+  describe "mining daemon" do
+    test "streams results back as messages" do
+      {:ok, daemon} = Powex.daemon_start(threads: 2)
+
+      ids =
+        for i <- 1..5 do
+          {:ok, id} = Powex.enqueue(daemon, "daemon #{i}", 2, self())
+          {id, "daemon #{i}"}
+        end
+
+      for {id, data} <- ids do
+        assert_receive {:powex_daemon, ^id, {:ok, nonce}}, 5_000
+        assert Powex.valid?(data, nonce, 2)
+      end
+
+      assert %{workers: 2, queued: 0, completed: 5, stopped: false} = Powex.daemon_stats(daemon)
+    end
+
+    test "runs higher priorities first and equal priorities in order" do
+      {:ok, daemon} = Powex.daemon_start(threads: 1)
+
+      # Occupy the only worker so the rest of the work queues up
+      {:ok, blocker} = Powex.enqueue(daemon, "blocker", 5, self())
+      wait_until_running(daemon)
+      {:ok, low} = Powex.enqueue(daemon, "low", 0, self())
+      {:ok, first} = Powex.enqueue(daemon, "first", 0, self(), priority: 5)
+      {:ok, second} = Powex.enqueue(daemon, "second", 0, self(), priority: 5)
+
+      assert_receive {:powex_daemon, ^blocker, {:ok, _}}, 30_000
+      assert_receive {:powex_daemon, id1, {:ok, _}}, 5_000
+      assert_receive {:powex_daemon, id2, {:ok, _}}, 5_000
+      assert_receive {:powex_daemon, id3, {:ok, _}}, 5_000
+      assert [id1, id2, id3] == [first, second, low]
+    end
+
+    test "reports errors like compute/2" do
+      {:ok, daemon} = Powex.daemon_start(threads: 1)
+      {:ok, id} = Powex.enqueue(daemon, "daemon", 65, self())
+      assert_receive {:powex_daemon, ^id, {:error, _reason}}, 5_000
+    end
+
+    test "stopping fails queued work and rejects new work" do
+      {:ok, daemon} = Powex.daemon_start(threads: 1)
+      {:ok, running} = Powex.enqueue(daemon, "stop running", 5, self())
+      wait_until_running(daemon)
+      {:ok, queued} = Powex.enqueue(daemon, "stop queued", 5, self())
+
+      assert :ok = Powex.daemon_stop(daemon)
+      assert {:error, :stopped} = Powex.enqueue(daemon, "late", 1, self())
+      assert_receive {:powex_daemon, ^queued, {:error, :stopped}}, 30_000
+      assert_receive {:powex_daemon, ^running, {:ok, _}}, 30_000
+      assert %{stopped: true, queued: 0} = Powex.daemon_stats(daemon)
+    end
+
+    test "leaves :reserve_cores free for the BEAM" do
+      {:ok, daemon} = Powex.daemon_start(reserve_cores: 1_000)
+      assert %{workers: 1} = Powex.daemon_stats(daemon)
+
+      {:ok, all_cores} = Powex.daemon_start()
+      %{workers: cores} = Powex.daemon_stats(all_cores)
+
+      {:ok, daemon} = Powex.daemon_start(threads: 64, reserve_cores: 1)
+      assert %{workers: workers} = Powex.daemon_stats(daemon)
+      assert workers == max(cores - 1, 1)
+    end
+
+    test "rejects invalid thread counts" do
+      assert {:error, _reason} = Powex.daemon_start(threads: 0)
+      assert {:error, _reason} = Powex.daemon_start(threads: 65)
+    end
+
+    test "paces workers to :max_hashes_per_second" do
+      {:ok, nonce} = Powex.compute("paced", 4)
+      {:ok, daemon} = Powex.daemon_start(threads: 2, max_hashes_per_second: 50_000)
+
+      {elapsed_us, _} =
+        :timer.tc(fn ->
+          {:ok, id} = Powex.enqueue(daemon, "paced", 4, self())
+          assert_receive {:powex_daemon, ^id, {:ok, ^nonce}}, 60_000
+        end)
+
+      # Hashes beyond the initial burst and the first batch are paced at 50 per millisecond
+      assert elapsed_us / 1_000 >= (nonce - 5_000 - 1_024) / 50
+    end
+
+    test "rejects a zero hashrate limit" do
+      assert {:error, _reason} = Powex.daemon_start(max_hashes_per_second: 0)
+    end
+
+    test "accepts power policies" do
+      for policy <- [:ignore, :pause_on_battery, :throttle_on_battery] do
+        assert {:ok, _daemon} = Powex.daemon_start(threads: 1, power_policy: policy)
+      end
+
+      assert_raise ArgumentError, fn -> Powex.daemon_start(power_policy: :sometimes) end
+    end
+
+    test "power policies do not pause work when not on battery" do
+      if Powex.power_source() != :battery do
+        {:ok, daemon} = Powex.daemon_start(threads: 1, power_policy: :pause_on_battery)
+        {:ok, id} = Powex.enqueue(daemon, "plugged in", 2, self())
+        assert_receive {:powex_daemon, ^id, {:ok, _nonce}}, 5_000
+      end
+    end
+
+    test "power_source/0 reports a known source" do
+      assert Powex.power_source() in [:ac, :battery, :unknown]
+    end
+
+    test "cpu_temperature/0 reports degrees Celsius or nil" do
+      assert Powex.cpu_temperature() == nil or is_float(Powex.cpu_temperature())
+    end
+
+    test "thermal limits still let work progress" do
+      # A limit below any real temperature keeps the workers at their minimum duty cycle
+      {:ok, daemon} = Powex.daemon_start(threads: 1, max_temperature: 0)
+      {:ok, id} = Powex.enqueue(daemon, "hot", 1, self())
+      assert_receive {:powex_daemon, ^id, {:ok, nonce}}, 30_000
+      assert Powex.valid?("hot", nonce, 1)
+    end
+
+    test "throttled daemons mine with or without OS enforcement" do
+      for os_limit <- [true, false] do
+        {:ok, daemon} =
+          Powex.daemon_start(threads: 1, max_temperature: 0, os_limit: os_limit)
+
+        {:ok, id} = Powex.enqueue(daemon, "os limit", 1, self())
+        assert_receive {:powex_daemon, ^id, {:ok, nonce}}, 30_000
+        assert Powex.valid?("os limit", nonce, 1)
+        Powex.daemon_stop(daemon)
+      end
+    end
+  end
+
+  describe "compute_dual/3" do
+    test "computes nonce valid under both algorithms" do
+      data = "dual algorithm"
+      difficulty = 2
+
+      assert {:ok, nonce} = Powex.compute_dual(data, difficulty)
+      assert Powex.valid_dual?(data, nonce, difficulty)
+      assert Powex.valid?(data, nonce, difficulty)
+    end
+
+    test "accepts algorithms in either order" do
+      assert {:ok, nonce} = Powex.compute_dual("order", 1, {:blake3, :sha256})
+      assert Powex.valid_dual?("order", nonce, 1, {:sha256, :blake3})
+    end
+
+    test "returns error for identical algorithms and high difficulty" do
+      assert {:error, _reason} = Powex.compute_dual("test", 1, {:sha256, :sha256})
+      assert {:error, _reason} = Powex.compute_dual("test", 65)
+      refute Powex.valid_dual?("test", 0, 0, {:blake3, :blake3})
+    end
+  end
+
+  describe "timelock_create/2 and timelock_verify/3" do
+    test "verifies a created witness" do
+      assert {:ok, witness} = Powex.timelock_create("sequential", 500)
+      assert byte_size(witness) == 32
+      assert Powex.timelock_verify("sequential", 500, witness)
+    end
+
+    test "rejects witness for different data or iterations" do
+      {:ok, witness} = Powex.timelock_create("sequential", 50)
+
+      refute Powex.timelock_verify("other data", 50, witness)
+      refute Powex.timelock_verify("sequential", 49, witness)
+      refute Powex.timelock_verify("sequential", 50, binary_part(witness, 0, 31))
+    end
+
+    test "returns error for zero iterations" do
+      assert {:error, _reason} = Powex.timelock_create("sequential", 0)
+    end
+  end
+
+  describe "vdf_eval/2 and vdf_verify/3" do
+    test "verifies an evaluated output and proof" do
+      assert {:ok, {output, proof}} = Powex.vdf_eval("vdf", 2_000)
+      assert byte_size(output) == 256
+      assert byte_size(proof) == 256
+      assert Powex.vdf_verify("vdf", 2_000, {output, proof})
+    end
+
+    test "rejects tampered outputs" do
+      {:ok, {output, proof}} = Powex.vdf_eval("vdf", 500)
+
+      refute Powex.vdf_verify("other", 500, {output, proof})
+      refute Powex.vdf_verify("vdf", 501, {output, proof})
+      refute Powex.vdf_verify("vdf", 500, {proof, output})
+      refute Powex.vdf_verify("vdf", 500, {output, <<0::2048>>})
+    end
+
+    test "returns error for zero iterations" do
+      assert {:error, _reason} = Powex.vdf_eval("vdf", 0)
+    end
+  end
+
+  describe "timelock_encrypt/3 and timelock_solve/2" do
+    test "solving recovers the sealed secret" do
+      assert {:ok, puzzle} = Powex.timelock_encrypt("time capsule", 2_000, 512)
+      assert puzzle.squarings == 2_000
+      assert {:ok, "time capsule"} = Powex.timelock_solve(puzzle)
+    end
+
+    test "reports progress to the given pid" do
+      {:ok, puzzle} = Powex.timelock_encrypt("progress", 500, 512)
+
+      assert {:ok, "progress"} = Powex.timelock_solve(puzzle, self())
+      assert_received {:powex_timelock_progress, 5, 500}
+      assert_received {:powex_timelock_progress, 500, 500}
+    end
+
+    test "detects tampered puzzles" do
+      {:ok, puzzle} = Powex.timelock_encrypt("tamper", 100, 512)
+
+      assert {:error, _reason} = Powex.timelock_solve(%{puzzle | squarings: 99})
+    end
+
+    test "returns error for invalid parameters" do
+      assert {:error, _reason} = Powex.timelock_encrypt("secret", 0, 512)
+      assert {:error, _reason} = Powex.timelock_encrypt("secret", 10, 256)
+    end
+  end
+
+  describe "compute_merkle/2 and valid_merkle?/5" do
+    test "computes a proof for one of the leaves" do
+      leaves = Enum.map(1..5, &"leaf #{&1}")
+      {:ok, root} = Powex.merkle_root(leaves)
+
+      assert {:ok, {index, nonce, path}} = Powex.compute_merkle(leaves, 3)
+      assert index in 0..4
+      assert Powex.valid_merkle?(root, Enum.at(leaves, index), nonce, path, 3)
+    end
+
+    test "rejects proofs for another leaf or root" do
+      leaves = ["a", "b", "c", "d"]
+      {:ok, root} = Powex.merkle_root(leaves)
+      {:ok, other_root} = Powex.merkle_root(["a", "b", "c", "e"])
+      {:ok, {index, nonce, path}} = Powex.compute_merkle(leaves, 2)
+      leaf = Enum.at(leaves, index)
+
+      refute Powex.valid_merkle?(other_root, leaf, nonce, path, 2)
+      refute Powex.valid_merkle?(root, "not a leaf", nonce, path, 2)
+      refute Powex.valid_merkle?(root, leaf, nonce, Enum.drop(path, 1), 2)
+    end
+
+    test "works with a single leaf" do
+      {:ok, root} = Powex.merkle_root(["only"])
+
+      assert {:ok, {0, nonce, []}} = Powex.compute_merkle(["only"], 2)
+      assert Powex.valid_merkle?(root, "only", nonce, [], 2)
+    end
+
+    test "returns error for empty leaves" do
+      assert {:error, _reason} = Powex.merkle_root([])
+      assert {:error, _reason} = Powex.compute_merkle([], 1)
+    end
+  end
+
+  describe "aggregate_proofs/1" do
+    test "commits to every proof" do
+      proofs = for i <- 1..100, do: Powex.encode_proof("aggregate #{i}", i, 0)
+      {:ok, {root, paths}} = Powex.aggregate_proofs(proofs)
+
+      assert {:ok, ^root} = Powex.merkle_root(proofs)
+      assert length(paths) == 100
+
+      for {proof, path} <- Enum.zip(proofs, paths) do
+        assert Powex.verify_aggregate(proof, path, root)
+      end
+
+      refute Powex.verify_aggregate("forged", hd(paths), root)
+      refute Powex.verify_aggregate(hd(proofs), Enum.at(paths, 1), root)
+      refute Powex.verify_aggregate(hd(proofs), [{:left, "short"}], root)
+    end
+
+    test "rejects an empty list" do
+      assert {:error, _reason} = Powex.aggregate_proofs([])
+    end
+  end
+
+  describe "verify_total_work/2" do
+    test "sums the work of every distinct proof" do
+      proofs =
+        for i <- 1..4 do
+          data = "total #{i}"
+          {:ok, nonce} = Powex.compute(data, {:zero_bits, 10})
+          {data, nonce}
+        end
+
+      bits = for {data, nonce} <- proofs, do: Powex.measure_difficulty(data, nonce)
+      total = bits |> Enum.map(&:math.pow(2, &1)) |> Enum.sum() |> :math.log2()
+
+      assert Powex.verify_total_work(proofs, total - 0.01)
+      refute Powex.verify_total_work(proofs, total + 0.01)
+      assert Powex.verify_total_work(proofs, 12)
+      refute Powex.verify_total_work(proofs ++ proofs, total + 0.01)
+      refute Powex.verify_total_work([], 0)
+    end
+  end
+
+  describe "partition_space/3" do
+    test "contiguous assignments cover the space without overlap" do
+      ranges =
+        for i <- 0..2 do
+          {:ok, %{start: s, end: e, stride: 1}} = Powex.partition_space(3, i, start: 10, end: 110)
+          {s, e}
+        end
+
+      assert ranges == [{10, 43}, {43, 76}, {76, 110}]
+    end
+
+    test "strided assignments interleave workers" do
+      assert {:ok, %{start: 7, stride: 3}} = Powex.partition_space(3, 2, mode: :strided, start: 5)
+    end
+
+    test "returns error for invalid worker index" do
+      assert {:error, _reason} = Powex.partition_space(0, 0)
+      assert {:error, _reason} = Powex.partition_space(2, 2)
+    end
+  end
+
+  describe "assigned_stride/2" do
+    test "is stable and moves few nodes when the cluster grows" do
+      offsets = fn size ->
+        for id <- 1..200 do
+          {:ok, %{start: offset, stride: ^size}} = Powex.assigned_stride({:node, id}, size)
+          offset
+        end
+      end
+
+      assert offsets.(8) == offsets.(8)
+      assert Enum.all?(offsets.(8), &(&1 in 0..7))
+
+      moved = Enum.zip(offsets.(8), offsets.(9)) |> Enum.reject(fn {a, b} -> a == b end)
+      assert Enum.all?(moved, fn {_, b} -> b == 8 end)
+      assert length(moved) < 50
+    end
+
+    test "rejects an empty cluster" do
+      assert {:error, _reason} = Powex.assigned_stride(:node, 0)
+    end
+  end
+
+  describe "range_pool_new/3" do
+    test "hands out consecutive chunks until exhausted" do
+      {:ok, pool} = Powex.range_pool_new(0, 250, 100)
+
+      assert {:ok, {0, 100}} = Powex.range_claim(pool)
+      assert {:ok, {100, 200}} = Powex.range_claim(pool)
+      assert {:ok, {200, 250}} = Powex.range_claim(pool)
+      assert {:error, :exhausted} = Powex.range_claim(pool)
+    end
+
+    test "released ranges are claimed again" do
+      {:ok, pool} = Powex.range_pool_new(0, 100, 100)
+      {:ok, range} = Powex.range_claim(pool)
+
+      assert :ok = Powex.range_release(pool, range)
+      assert {:ok, ^range} = Powex.range_claim(pool)
+    end
+
+    test "is shared between processes" do
+      {:ok, pool} = Powex.range_pool_new(0, 1_000, 10)
+
+      ranges =
+        1..20
+        |> Task.async_stream(fn _ -> Powex.range_claim(pool) end)
+        |> Enum.map(fn {:ok, {:ok, range}} -> range end)
+
+      assert length(Enum.uniq(ranges)) == 20
+    end
+  end
+
+  describe "work ledger" do
+    test "leases ranges and records valid solutions" do
+      {:ok, job_id} = Powex.put_work("pool job", 2, chunk_size: 100_000)
+
+      assert {:ok, %{job_id: ^job_id, challenge: "pool job", start: 0, end: 100_000}} =
+               Powex.get_work(:worker_a)
+
+      assert {:ok, %{start: 100_000, end: 200_000}} = Powex.get_work(:worker_b)
+
+      {:ok, nonce} = Powex.compute("pool job", 2)
+      assert :ok = Powex.submit_work(:worker_a, nonce)
+      assert {:error, :duplicate} = Powex.submit_work(:worker_b, nonce)
+
+      assert {:ok, %{job_id: ^job_id, leases: 2, solutions: [{:worker_a, ^nonce}]}} =
+               Powex.work_status()
+    end
+
+    test "rejects invalid solutions" do
+      {:ok, _job_id} = Powex.put_work("pool job", 10)
+      assert {:error, :invalid} = Powex.submit_work(:worker, 1)
+    end
+
+    test "expired leases return their range to the pool" do
+      {:ok, _job_id} = Powex.put_work("expiring", 2, chunk_size: 10, lease_ms: 10)
+      {:ok, %{start: 0}} = Powex.get_work(:crashed)
+
+      Process.sleep(20)
+
+      assert {:ok, %{start: 0, end: 10}} = Powex.get_work(:replacement)
+    end
+
+    test "heartbeats keep leases alive and return only unsearched nonces" do
+      {:ok, _job_id} = Powex.put_work("heartbeat", 2, chunk_size: 10, lease_ms: 50)
+      {:ok, %{start: 0}} = Powex.get_work(:alive)
+
+      Process.sleep(30)
+      assert :ok = Powex.heartbeat_work(:alive, 4)
+      Process.sleep(30)
+      assert {:ok, %{leases: 1}} = Powex.work_status()
+
+      Process.sleep(40)
+      assert {:error, :no_lease} = Powex.heartbeat_work(:alive)
+      assert {:ok, %{start: 4, end: 10}} = Powex.get_work(:replacement)
+    end
+  end
+
+  describe "block headers" do
+    test "assembles and hashes the genesis header" do
+      assert {:ok, header} = Powex.header_assemble(genesis_fields())
+      assert {:ok, hash} = Powex.header_hash(header)
+
+      assert Base.encode16(reverse(hash), case: :lower) ==
+               "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+
+      assert {:ok, fields} = Powex.header_parse(header)
+      assert fields == genesis_fields()
+    end
+
+    test "updates and rolls the timestamp" do
+      {:ok, header} = Powex.header_assemble(genesis_fields())
+
+      assert {:ok, updated} = Powex.header_update(header, bits: 0x1B0404CB)
+      assert {:ok, %{bits: 0x1B0404CB, nonce: 2_083_236_893}} = Powex.header_parse(updated)
+
+      assert {:ok, rolled} = Powex.header_roll_time(header)
+      assert {:ok, %{timestamp: 1_231_006_506, nonce: 0}} = Powex.header_parse(rolled)
+    end
+
+    test "rolls the extranonce and recomputes the merkle root" do
+      {:ok, header} = Powex.header_assemble(genesis_fields())
+      branch = [:crypto.strong_rand_bytes(32)]
+
+      assert {:ok, {rolled, <<1, 0, 0, 0>>}} =
+               Powex.header_roll_extranonce(header, "cb1", <<0, 0, 0, 0>>, "cb2", branch)
+
+      expected_root = double_sha256(double_sha256("cb1" <> <<1, 0, 0, 0>> <> "cb2") <> hd(branch))
+      assert {:ok, %{merkle_root: ^expected_root, nonce: 0}} = Powex.header_parse(rolled)
+
+      assert {:error, _reason} =
+               Powex.header_roll_extranonce(header, "cb1", <<255, 255>>, "cb2", branch)
+    end
+
+    test "compute_header/2 rolls the extranonce when the nonce range is exhausted" do
+      branch = [double_sha256("tx1"), double_sha256("tx2")]
+      root = Enum.reduce(branch, double_sha256("cb1" <> <<0, 0>> <> "cb2"), &double_sha256(&2 <> &1))
+
+      {:ok, header} =
+        Powex.header_assemble(%{genesis_fields() | merkle_root: root, bits: 0x1F00FFFF, nonce: 0})
+
+      assert {:ok, %{header: solved, nonce: nonce, extranonce: extranonce, hash: hash}} =
+               Powex.compute_header(header,
+                 extranonce: {"cb1", <<0, 0>>, "cb2", branch},
+                 nonce_end: 1_000,
+                 max_rolls: 10_000
+               )
+
+      assert nonce < 1_000
+      assert {:ok, ^hash} = Powex.header_hash(solved)
+      assert <<_::binary-size(30), 0, 0>> = hash
+
+      expected_root = Enum.reduce(branch, double_sha256("cb1" <> extranonce <> "cb2"), &double_sha256(&2 <> &1))
+      assert {:ok, %{merkle_root: ^expected_root}} = Powex.header_parse(solved)
+    end
+
+    test "compute_header/2 reports exhaustion without an extranonce context" do
+      {:ok, header} = Powex.header_assemble(genesis_fields())
+      assert {:error, _reason} = Powex.compute_header(header, nonce_end: 1_000)
+    end
+
+    test "returns errors for invalid input" do
+      assert {:error, _reason} = Powex.header_assemble(version: 1)
+      assert {:error, _reason} = Powex.header_assemble(%{genesis_fields() | prev_hash: "short"})
+      assert {:error, _reason} = Powex.header_parse(<<0::640, 0>>)
+    end
+  end
+
+  describe "chain rules" do
+    test "validate_timestamp/3 applies median-time-past over the window" do
+      timestamps = Enum.to_list(1..20) |> Enum.map(&(&1 * 600))
+
+      # Median of the last 11 timestamps (6000..12000) is 9000
+      assert {:error, :too_old} = Powex.validate_timestamp(timestamps, 9_000, now: 20_000)
+      assert :ok = Powex.validate_timestamp(timestamps, 9_001, now: 20_000)
+      assert :ok = Powex.validate_timestamp(timestamps, 12_001, window: 1, now: 20_000)
+      assert {:error, :too_old} = Powex.validate_timestamp(timestamps, 12_000, window: 1, now: 20_000)
+    end
+
+    test "validate_timestamp/3 rejects timestamps too far in the future" do
+      now = System.os_time(:second)
+
+      assert :ok = Powex.validate_timestamp([], now + 7_200)
+      assert {:error, :too_far_in_future} = Powex.validate_timestamp([], now + 7_300)
+      assert :ok = Powex.validate_timestamp([], 1_060, now: 1_000, max_future_drift: 60)
+    end
+
+    test "estimate_hashrate/1 derives hashrate and interval from block intervals" do
+      blocks = for i <- 0..20, do: {0x1d00ffff, i * 600}
+
+      assert {:ok, %{hashrate: rate, lower: lower, upper: upper, blocks: 20}} =
+               Powex.estimate_hashrate(blocks)
+
+      # Difficulty 1 needs ~2^32 hashes per block
+      assert_in_delta rate, 4_295_032_833 / 600, 1.0
+      assert lower < rate and rate < upper
+    end
+
+    test "estimate_hashrate/1 matches the exact Poisson interval" do
+      blocks = for i <- 0..10, do: {0x1d00ffff, i * 600}
+      {:ok, %{hashrate: rate, lower: lower, upper: upper, blocks: 10}} = Powex.estimate_hashrate(blocks)
+
+      # Chi-square quantiles for 10 blocks, both with 20 degrees of freedom: 2.5% and 97.5%
+      assert_in_delta lower / rate, 9.590777 / 20, 0.005 * 9.590777 / 20
+      assert_in_delta upper / rate, 34.169607 / 20, 0.005 * 34.169607 / 20
+    end
+
+    test "estimate_hashrate/1 rejects insufficient or invalid data" do
+      assert {:error, :insufficient_data} = Powex.estimate_hashrate([])
+      assert {:error, :insufficient_data} = Powex.estimate_hashrate([{0x1d00ffff, 0}])
+      assert {:error, :insufficient_data} = Powex.estimate_hashrate([{0x1d00ffff, 5}, {0x1d00ffff, 5}])
+      assert {:error, :invalid} = Powex.estimate_hashrate([{0x1d00ffff, 0}, {0x1d80ffff, 600}])
+    end
+  end
+
+  describe "convert_difficulty/3" do
+    @diff1_hex "00000000ffff0000000000000000000000000000000000000000000000000000"
+
+    test "converts nBits to every representation" do
+      assert {:ok, @diff1_hex} = Powex.convert_difficulty(0x1d00ffff, :bits, :hex)
+      assert {:ok, <<0::32, 0xFF, 0xFF, 0::208>>} = Powex.convert_difficulty(0x1d00ffff, :bits, :target)
+      assert {:ok, 32} = Powex.convert_difficulty(0x1d00ffff, :bits, :zero_bits)
+      assert {:ok, 1.0} = Powex.convert_difficulty(0x1d00ffff, :bits, :pool)
+      assert {:ok, hashes} = Powex.convert_difficulty(0x1d00ffff, :bits, :hashes)
+      assert_in_delta hashes, 4_295_032_833, 1.0
+    end
+
+    test "round-trips exact representations" do
+      for bits <- [0x1d00ffff, 0x1b0404cb, 0x170331db, 0x03123456, 0x207fffff] do
+        {:ok, hex} = Powex.convert_difficulty(bits, :bits, :hex)
+        assert {:ok, ^bits} = Powex.convert_difficulty(hex, :hex, :bits)
+      end
+
+      for zero_bits <- [0, 1, 8, 20, 255, 256] do
+        {:ok, target} = Powex.convert_difficulty(zero_bits, :zero_bits, :target)
+        assert {:ok, ^zero_bits} = Powex.convert_difficulty(target, :target, :zero_bits)
+      end
+    end
+
+    test "round-trips floating-point representations within precision" do
+      {:ok, target} = Powex.convert_difficulty(16_307.420938523983, :pool, :target)
+      assert {:ok, 0x1b0404cb} = Powex.convert_difficulty(target, :target, :bits)
+
+      {:ok, target} = Powex.convert_difficulty(2.0, :pool, :target)
+      assert {:ok, pool} = Powex.convert_difficulty(target, :target, :pool)
+      assert_in_delta pool, 2.0, 1.0e-12
+
+      assert {:ok, "0000" <> _} = Powex.convert_difficulty(65_536, :hashes, :hex)
+    end
+
+    test "rejects invalid values" do
+      assert {:error, :invalid} = Powex.convert_difficulty(0x1d80ffff, :bits, :hex)
+      assert {:error, :invalid} = Powex.convert_difficulty("xyz", :hex, :bits)
+      assert {:error, :invalid} = Powex.convert_difficulty(257, :zero_bits, :hex)
+      assert {:error, :invalid} = Powex.convert_difficulty(0.0, :pool, :hex)
+      assert {:error, :invalid} = Powex.convert_difficulty(256, :zero_bits, :pool)
+    end
+  end
+
+  describe "get_hash/2" do
+    test "returns hash for given data and nonce" do
+      data = "test data"
+      nonce = 12345
+
+      assert {:ok, hash} = Powex.get_hash(data, nonce)
+      assert is_binary(hash)
+      assert String.length(hash) == 64  # SHA-256 hex string length
+      assert String.match?(hash, ~r/^[0-9a-f]+$/)
+    end
+
+    test "returns consistent hash for same inputs" do
+      data = "consistent test"
+      nonce = 98765
+
+      {:ok, hash1} = Powex.get_hash(data, nonce)
+      {:ok, hash2} = Powex.get_hash(data, nonce)
+
+      assert hash1 == hash2
+    end
+
+    test "returns different hash for different inputs" do
+      nonce = 555
+
+      {:ok, hash1} = Powex.get_hash("data1", nonce)
+      {:ok, hash2} = Powex.get_hash("data2", nonce)
+
+      assert hash1 != hash2
+    end
+
+    test "works with binary data" do
+      data = <<1, 2, 3>>
+      nonce = 777
+
+      assert {:ok, hash} = Powex.get_hash(data, nonce)
+      assert String.length(hash) == 64
+    end
+
+    test "supports output encodings" do
+      {:ok, hex} = Powex.get_hash("encoded", 42)
+      raw = Base.decode16!(hex, case: :lower)
+
+      assert {:ok, ^hex} = Powex.get_hash("encoded", 42, encoding: :hex)
+      assert {:ok, ^raw} = Powex.get_hash("encoded", 42, encoding: :raw)
+      assert {:ok, upper} = Powex.get_hash("encoded", 42, encoding: :hex_upper)
+      assert upper == String.upcase(hex)
+      assert {:ok, base64} = Powex.get_hash("encoded", 42, encoding: :base64)
+      assert Base.decode64!(base64) == raw
+      assert {:ok, base58} = Powex.get_hash("encoded", 42, encoding: :base58)
+      assert String.match?(base58, ~r/^[1-9A-HJ-NP-Za-km-z]+$/)
+    end
+
+    test "emits multihash-formatted digests" do
+      {:ok, raw} = Powex.get_hash("multihash", 7, encoding: :raw)
+
+      assert {:ok, <<0x12, 32, ^raw::binary>>} =
+               Powex.get_hash("multihash", 7, encoding: :raw, multihash: true)
+
+      assert {:ok, "1220" <> hex} = Powex.get_hash("multihash", 7, multihash: true)
+      assert Base.decode16!(hex, case: :lower) == raw
+      assert {:ok, "Qm" <> _} = Powex.get_hash("multihash", 7, encoding: :base58, multihash: true)
+    end
+
+    test "raises on an unknown encoding" do
+      assert_raise ArgumentError, fn -> Powex.get_hash("encoded", 42, encoding: :base32) end
+    end
+  end
+
+  describe "cpu_features/0" do
+    test "reports the architecture and features the build can run with" do
+      %{arch: arch, detected: detected, required: required, missing: missing} = Powex.cpu_features()
+
+      assert is_binary(arch) and arch != ""
+      assert Enum.all?(detected ++ required, &is_atom/1)
+      assert required -- detected == []
+      assert missing == []
+    end
+  end
+
+  describe "backend/0" do
+    test "reports the SHA-256 instructions detected on this CPU" do
+      %{detected: detected} = Powex.cpu_features()
+
+      expected =
+        cond do
+          :sha_ni in detected -> :sha_ni
+          :sha2 in detected -> :armv8
+          true -> :soft
+        end
+
+      assert Powex.backend() == expected
+    end
+  end
+
+  describe "bench_scaling/2" do
+    test "returns one measurement per thread count" do
+      assert {:ok, points} = Powex.bench_scaling(3, 20)
+      assert Enum.map(points, & &1.threads) == [1, 2, 3]
+      assert [%{speedup: 1.0, efficiency: 1.0} | _] = points
+
+      for %{threads: threads, hashrate: hashrate, speedup: speedup, efficiency: efficiency} <- points do
+        assert hashrate > 0
+        assert_in_delta efficiency, speedup / threads, 1.0e-9
+      end
+    end
+
+    test "returns error for invalid thread count" do
+      assert {:error, _reason} = Powex.bench_scaling(0, 10)
+      assert {:error, _reason} = Powex.bench_scaling(65, 10)
+    end
+  end
+
+  describe "integration tests" do
+    test "complete workflow: compute -> validate -> get_hash" do
+      data = "integration test data"
+      difficulty = 3
+
+      # Compute a valid nonce
+      {:ok, nonce} = Powex.compute(data, difficulty)
+
+      # Validate the nonce
+      assert Powex.valid?(data, nonce, difficulty)
+
+      # Get the hash and verify it meets difficulty
+      {:ok, hash} = Powex.get_hash(data, nonce)
+      leading_zeros = String.duplicate("0", difficulty)
+      assert String.starts_with?(hash, leading_zeros)
+    end
+
+    test "parallel vs sequential computation produces valid results" do
+      data = "comparison test"
+      difficulty = 2
+
+      {:ok, nonce1} = Powex.compute(data, difficulty)
+      {:ok, nonce2} = Powex.compute_parallel(data, difficulty, 2)
+
+      # Both should be valid (though potentially different)
+      assert Powex.valid?(data, nonce1, difficulty)
+      assert Powex.valid?(data, nonce2, difficulty)
+    end
+  end
+
+  defp genesis_fields do
+    %{
+      version: 1,
+      prev_hash: <<0::256>>,
+      merkle_root: @genesis_merkle_root,
+      timestamp: 1_231_006_505,
+      bits: 0x1D00FFFF,
+      nonce: 2_083_236_893
+    }
+  end
+
+  defp double_sha256(data), do: :crypto.hash(:sha256, :crypto.hash(:sha256, data))
+
+  defp reverse(binary), do: binary |> :binary.bin_to_list() |> Enum.reverse() |> :binary.list_to_bin()
+
+  defp prometheus_samples(text) do
+    for line <- String.split(text, "\n", trim: true), not String.starts_with?(line, "#"), into: %{} do
+      [name, value] = String.split(line, " ")
+      {number, ""} = Float.parse(value)
+      {name, number}
+    end
+  end
+
+  defp wait_until_running(daemon) do
+    case Powex.daemon_stats(daemon) do
+      %{running: 0} ->
+        Process.sleep(1)
+        wait_until_running(daemon)
+
+      _ ->
+        :ok
+    end
+  end
+
+  defp leading_zero_bits(<<0::1, rest::bitstring>>), do: 1 + leading_zero_bits(rest)
+  defp leading_zero_bits(_bits), do: 0
 end
@@ -3,6 +3,10 @@ ExUnit.start()
 # Configure test timeout for potentially long-running PoW computations
 ExUnit.configure(timeout: 60_000)
 
+# WASM predicate and yespower tests need the NIF built with the `wasm` and `yespower` cargo
+# features: mix test --include wasm --include yespower
+ExUnit.configure(exclude: [:wasm, :yespower])
+
 defmodule POWTestHelper do
   @moduledoc """
   Helper functions for Powex tests